@@ -6,10 +6,20 @@ import { _owner, initSdkWithoutOwner } from "./config";
 import { Raydium } from "@raydium-io/raydium-sdk-v2";
 
 import { PoolParty } from "../target/types/pool_party";
-import { NATIVE_MINT, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import {
+  NATIVE_MINT,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
 
 import { confirmTransaction } from "@solana-developers/helpers";
-import { LOOKUP_TABLE, NFT, POOL_NAME } from "./constants";
+import {
+  CLMM_PROGRAM_ID,
+  LIQUIDITY_ACCOUNTS_LAYOUT,
+  LOOKUP_TABLE,
+  NFT,
+  POOL_NAME,
+} from "./constants";
 import { pools, protocolPDAs, raydiumPDAs, tokenVaults } from "./helpers";
 
 dotenv.config();
@@ -89,11 +99,13 @@ export const collectFees = async (
   console.log();
 
   let collectFeesTx = await program.methods
-    .collectFees()
+    .collectFees(LIQUIDITY_ACCOUNTS_LAYOUT)
     .accounts({
       investor,
+      feesRecipient: investor,
       poolPositionConfig,
       positionNftAccount,
+      clmmProgram: CLMM_PROGRAM_ID,
       poolState: poolMintAWithMintB,
       personalPosition,
       protocolPosition,
@@ -103,6 +115,8 @@ export const collectFees = async (
       tokenVault1: openPositionTokenVault1,
       poolVaultTokenAMint: mintA,
       poolVaultTokenBMint: mintB,
+      tokenProgram0: TOKEN_PROGRAM_ID,
+      tokenProgram1: TOKEN_PROGRAM_ID,
     })
     .remainingAccounts([
       { pubkey: bitmapExtension, isSigner: false, isWritable: true },
@@ -35,3 +35,14 @@ export const LOOKUP_TABLE = new PublicKey(
 export const POOL_ID = new PublicKey(
   "2uU5pCFzJzpGXkFvf21FJNYFuugTwfcBYf9uG2AGbpXk"
 );
+
+// Max allowed move of the pool sqrt price between quote and increase_liquidity
+export const MAX_PRICE_DEVIATION_BPS = 100;
+
+// Remaining accounts of the liquidity instructions: the bitmap extension of the position pool only
+export const LIQUIDITY_ACCOUNTS_LAYOUT = {
+  bitmapExtensions: 1,
+  leg0TickArrays: 0,
+  leg1TickArrays: 0,
+  liquidityTickArrays: 0,
+};
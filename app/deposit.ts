@@ -15,11 +15,22 @@ import {
 } from "@raydium-io/raydium-sdk-v2";
 
 import { PoolParty } from "../target/types/pool_party";
-import { NATIVE_MINT, getAssociatedTokenAddressSync } from "@solana/spl-token";
+import {
+  NATIVE_MINT,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
 
 import { confirmTransaction } from "@solana-developers/helpers";
 import { airdrop } from "./utils";
-import { AMM_CONFIG, LOOKUP_TABLE, NFT, POOL_NAME } from "./constants";
+import {
+  AMM_CONFIG,
+  LOOKUP_TABLE,
+  LIQUIDITY_ACCOUNTS_LAYOUT,
+  MAX_PRICE_DEVIATION_BPS,
+  NFT,
+  POOL_NAME,
+} from "./constants";
 import { pools, tokenVaults, raydiumPDAs, protocolPDAs } from "./helpers";
 
 dotenv.config();
@@ -143,12 +154,14 @@ export const deposit = async (
   console.log(`positionNftAccount`, positionNftAccount.toBase58());
 
   let createInvestorPositionTx = await program.methods
-    .createInvestorPosition()
+    .createInvestorPosition([])
     .accounts({
       investor,
       poolPositionConfig,
       poolVaultToken0Mint: mintA,
       poolVaultToken1Mint: mintB,
+      tokenProgram0: TOKEN_PROGRAM_ID,
+      tokenProgram1: TOKEN_PROGRAM_ID,
     })
     .instruction();
 
@@ -172,7 +185,16 @@ export const deposit = async (
   console.log();
 
   let swapToRatioDepositTx = await program.methods
-    .swapToRatioDeposit(new anchor.BN(0), new anchor.BN(0), baseIn)
+    .swapToRatioDeposit(
+      { hopAccountCounts: Buffer.from([]), minimumAmountOut: new anchor.BN(0) },
+      { hopAccountCounts: Buffer.from([]), minimumAmountOut: new anchor.BN(0) },
+      {
+        bitmapExtensions: 2,
+        leg0TickArrays: remainingAccountsA.length,
+        leg1TickArrays: remainingAccountsB.length,
+        liquidityTickArrays: 0,
+      }
+    )
     .accounts({
       investor,
       poolPositionConfig,
@@ -194,15 +216,12 @@ export const deposit = async (
     })
     .remainingAccounts([
       { pubkey: bitmapExtensionA, isSigner: false, isWritable: true },
+      { pubkey: bitmapExtensionB, isSigner: false, isWritable: true },
       ...remainingAccountsA.map((i) => ({
         pubkey: i,
         isSigner: false,
         isWritable: true,
       })),
-
-      { pubkey: PublicKey.default, isSigner: false, isWritable: false },
-
-      { pubkey: bitmapExtensionB, isSigner: false, isWritable: true },
       ...remainingAccountsB.map((i) => ({
         pubkey: i,
         isSigner: false,
@@ -211,8 +230,16 @@ export const deposit = async (
     ])
     .instruction();
 
+  const targetPool = await raydium.clmm.getPoolInfoFromRpc(poolId);
+  const expectedSqrtPriceX64 = targetPool.computePoolInfo.sqrtPriceX64;
+
   let increaseLiquidityTx = await program.methods
-    .increaseLiquidity()
+    .increaseLiquidity(
+      new anchor.BN(0),
+      expectedSqrtPriceX64,
+      MAX_PRICE_DEVIATION_BPS,
+      LIQUIDITY_ACCOUNTS_LAYOUT
+    )
     .accounts({
       investor,
       poolPositionConfig,
@@ -226,6 +253,8 @@ export const deposit = async (
       tokenVault1: openPositionTokenVault1,
       poolVaultTokenAMint: mintA,
       poolVaultTokenBMint: mintB,
+      tokenProgram0: TOKEN_PROGRAM_ID,
+      tokenProgram1: TOKEN_PROGRAM_ID,
     })
     .remainingAccounts([
       { pubkey: bitmapExtension, isSigner: false, isWritable: true },
@@ -26,6 +26,7 @@ import {
 import {
   AMM_CONFIG,
   CLMM_PROGRAM_ID,
+  LIQUIDITY_ACCOUNTS_LAYOUT,
   MINT_A,
   MINT_B,
   POOL_NAME,
@@ -132,7 +133,8 @@ const run = async () => {
       depositTokenVault0,
       depositTokenVault1,
       mintA,
-      mintB
+      mintB,
+      { raydiumClmm: {} }
     )
     .accounts({
       manager: manager.publicKey,
@@ -160,6 +162,8 @@ const run = async () => {
       poolPosition: poolPosition,
       vault0Mint: mintA,
       vault1Mint: mintB,
+      tokenProgram0: splToken.TOKEN_PROGRAM_ID,
+      tokenProgram1: splToken.TOKEN_PROGRAM_ID,
     })
     .signers([manager])
     .rpc({ commitment: "confirmed" });
@@ -184,7 +188,8 @@ const run = async () => {
       TICK_LOWER,
       TICK_UPPER,
       tickArrayLowerStartIndex,
-      tickArrayUpperStartIndex
+      tickArrayUpperStartIndex,
+      LIQUIDITY_ACCOUNTS_LAYOUT
     )
     .accounts({
       manager: manager.publicKey,
@@ -192,6 +197,7 @@ const run = async () => {
       positionNftMint: NFT.publicKey,
       positionNftAccount,
       metadataAccount,
+      clmmProgram: CLMM_PROGRAM_ID,
       poolState: poolMintAWithMintB,
       protocolPosition,
       tickArrayLower: tickLowerArrayAddress.toBase58(),
@@ -0,0 +1,873 @@
+//! Subcommands: each builds its instructions with `pool-party-client`, sends them and returns a
+//! JSON summary of the transaction and the accounts it touched.
+
+use std::mem::size_of;
+use std::path::{ Path, PathBuf };
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{ AccountDeserialize, Discriminator, ZeroCopy };
+use anyhow::{ anyhow, Context as _, Result };
+use clap::Args;
+use pool_party::state::{
+    ConfigChange,
+    Investor,
+    LadderRange,
+    PendingConfigChange,
+    PoolPosition,
+    PoolPositionConfig,
+    PositionRoles,
+};
+use pool_party_client::pda::{ self, InvestorAddresses, PoolPositionAddresses };
+use pool_party_client::{
+    self as client,
+    AllowlistTree,
+    ClmmPool,
+    LadderRangeKeys,
+    LiquidityBounds,
+    PositionAccounts,
+    SwapLegAccounts,
+};
+use raydium_clmm_cpi::states::PoolState;
+use serde_json::{ json, Value };
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{ read_keypair_file, Keypair, Signature, Signer };
+use solana_sdk::transaction::Transaction;
+
+use crate::config::Config;
+
+/// Compute units requested by every transaction, swaps and liquidity CPIs are expensive
+const COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Tick arrays looked up in the swap direction for every swap leg, uninitialized ones are skipped
+const SWAP_TICK_ARRAYS: usize = 5;
+
+#[derive(Args, Clone, Copy, Debug)]
+pub struct SlippageArgs {
+    /// Minimum amount of token 0 received from the swap of leg 0
+    #[arg(long, default_value_t = 0)]
+    pub min_out_0: u64,
+    /// Minimum amount of token 1 received from the swap of leg 1
+    #[arg(long, default_value_t = 0)]
+    pub min_out_1: u64,
+    #[arg(long, default_value_t = 0)]
+    pub min_liquidity_out: u128,
+    /// Maximum move of the pool price from the current one, in basis points
+    #[arg(long, default_value_t = 100)]
+    pub max_price_deviation: u16,
+}
+
+pub struct Context {
+    config: Config,
+    client: RpcClient,
+    payer: Keypair,
+}
+
+impl Context {
+    pub fn new(config: Config) -> Result<Self> {
+        let payer = read_keypair_file(&config.keypair).map_err(|err|
+            anyhow!("reading keypair {}: {err}", config.keypair.display())
+        )?;
+        let client = RpcClient::new_with_commitment(
+            config.cluster_url.clone(),
+            CommitmentConfig::confirmed()
+        );
+        Ok(Context { config, client, payer })
+    }
+
+    pub fn init_protocol_config(&self, clmm_programs: &[String]) -> Result<Value> {
+        let clmm_programs = parse_pubkeys(clmm_programs)?;
+        let signature = self.send(
+            &[client::initialize_protocol_config(&self.payer.pubkey(), clmm_programs.clone())],
+            &[]
+        )?;
+        Ok(protocol_config_json(signature, &clmm_programs))
+    }
+
+    pub fn set_clmm_programs(&self, clmm_programs: &[String]) -> Result<Value> {
+        let clmm_programs = parse_pubkeys(clmm_programs)?;
+        let signature = self.send(
+            &[client::set_clmm_programs(&self.payer.pubkey(), clmm_programs.clone())],
+            &[]
+        )?;
+        Ok(protocol_config_json(signature, &clmm_programs))
+    }
+
+    pub fn create_position(&self) -> Result<Value> {
+        let position = self.configured_position(Pubkey::default())?;
+        let signature = self.send(
+            &[client::create_position(&self.payer.pubkey(), &position)],
+            &[]
+        )?;
+        let addresses = position.addresses();
+        Ok(json!({
+            "signature": signature.to_string(),
+            "pool_position_config": addresses.pool_position_config.to_string(),
+            "pool_position": addresses.pool_position.to_string(),
+        }))
+    }
+
+    pub fn create_vaults(&self) -> Result<Value> {
+        let position = self.position()?;
+        let signature = self.send(
+            &[client::create_position_vaults(&self.payer.pubkey(), &position)],
+            &[]
+        )?;
+        let addresses = position.addresses();
+        Ok(json!({
+            "signature": signature.to_string(),
+            "pool_position_vault_0": addresses.pool_position_vault_0.to_string(),
+            "pool_position_vault_1": addresses.pool_position_vault_1.to_string(),
+            "fees_vault_0": addresses.fees_vault_0.to_string(),
+            "fees_vault_1": addresses.fees_vault_1.to_string(),
+        }))
+    }
+
+    pub fn open_position(
+        &self,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        token_account_0: Option<String>,
+        token_account_1: Option<String>
+    ) -> Result<Value> {
+        let position_nft_mint = Keypair::new();
+        let mut position = self.position()?;
+        position.position_nft_mint = position_nft_mint.pubkey();
+
+        let manager = self.payer.pubkey();
+        let (token_account_0, token_account_1) = self.manager_token_accounts(
+            &position,
+            token_account_0,
+            token_account_1
+        )?;
+        let signature = self.send(
+            &[
+                client::open_position(
+                    &manager,
+                    &position,
+                    &token_account_0,
+                    &token_account_1,
+                    amount_0_max,
+                    amount_1_max
+                ),
+            ],
+            &[&position_nft_mint]
+        )?;
+        let raydium = position.raydium();
+        Ok(json!({
+            "signature": signature.to_string(),
+            "position_nft_mint": position.position_nft_mint.to_string(),
+            "position_nft_account": position.position_nft_account().to_string(),
+            "personal_position": raydium.personal_position.to_string(),
+            "protocol_position": raydium.protocol_position.to_string(),
+        }))
+    }
+
+    /// Opens the next ladder range of the position over `lower_tick..upper_tick`
+    pub fn open_ladder_range(
+        &self,
+        lower_tick: i32,
+        upper_tick: i32,
+        weight_bps: u32,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        token_account_0: Option<String>,
+        token_account_1: Option<String>
+    ) -> Result<Value> {
+        if lower_tick >= upper_tick {
+            return Err(anyhow!("lower_tick must be below upper_tick"));
+        }
+        let position_nft_mint = Keypair::new();
+        let position = self.position()?;
+        let range = LadderRangeKeys {
+            range: client::PositionRange {
+                tick_lower_index: lower_tick,
+                tick_upper_index: upper_tick,
+            },
+            position_nft_mint: position_nft_mint.pubkey(),
+        };
+        let manager = self.payer.pubkey();
+        let (token_account_0, token_account_1) = self.manager_token_accounts(
+            &position,
+            token_account_0,
+            token_account_1
+        )?;
+        let signature = self.send(
+            &[
+                client::open_ladder_range(
+                    &manager,
+                    &position,
+                    &range,
+                    weight_bps,
+                    &token_account_0,
+                    &token_account_1,
+                    amount_0_max,
+                    amount_1_max
+                ),
+            ],
+            &[&position_nft_mint]
+        )?;
+        let (ladder_range, _) = pda::ladder_range(
+            &position.addresses().pool_position,
+            position.ladder.len() as u8
+        );
+        Ok(json!({
+            "signature": signature.to_string(),
+            "index": position.ladder.len(),
+            "ladder_range": ladder_range.to_string(),
+            "position_nft_mint": range.position_nft_mint.to_string(),
+        }))
+    }
+
+    /// `allowlist` is the allowlist file of a permissioned position, the proof of the keypair is
+    /// built from it
+    pub fn create_investor(&self, allowlist: Option<PathBuf>) -> Result<Value> {
+        let position = self.position()?;
+        let proof = match allowlist {
+            Some(path) => {
+                let tree = read_allowlist(&path)?;
+                tree.proof(&self.payer.pubkey()).ok_or_else(||
+                    anyhow!("{} is not in {}", self.payer.pubkey(), path.display())
+                )?
+            }
+            None => Vec::new(),
+        };
+        let signature = self.send(
+            &[client::create_investor_position(&self.payer.pubkey(), &position, &proof)],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "investor": self.investor_json(&position, &self.payer.pubkey())?,
+        }))
+    }
+
+    pub fn deposit(&self, amount: u64) -> Result<Value> {
+        let position = self.position()?;
+        let signature = self.send(
+            &[client::deposit(&self.payer.pubkey(), &position, &self.config.stable_mint, amount)],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "investor": self.investor_json(&position, &self.payer.pubkey())?,
+        }))
+    }
+
+    pub fn zap(&self, amount: u64, slippage: SlippageArgs) -> Result<Value> {
+        let position = self.position()?;
+        let (leg_0, leg_1) = self.swap_legs(&position, slippage)?;
+        let signature = self.send(
+            &[
+                client::zap_in(
+                    &self.payer.pubkey(),
+                    &position,
+                    &self.config.stable_mint,
+                    amount,
+                    &leg_0,
+                    &leg_1,
+                    self.liquidity_bounds(&position, slippage)?
+                ),
+            ],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "investor": self.investor_json(&position, &self.payer.pubkey())?,
+        }))
+    }
+
+    pub fn increase_liquidity(&self, slippage: SlippageArgs) -> Result<Value> {
+        let position = self.position()?;
+        let signature = self.send(
+            &[
+                client::increase_liquidity(
+                    &self.payer.pubkey(),
+                    &position,
+                    self.liquidity_bounds(&position, slippage)?
+                ),
+            ],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "investor": self.investor_json(&position, &self.payer.pubkey())?,
+        }))
+    }
+
+    pub fn collect_fees(&self) -> Result<Value> {
+        let position = self.position()?;
+        let config: PoolPositionConfig = self.load_account(
+            &position.addresses().pool_position_config
+        )?;
+        // The fees of the manager go to the fee recipient of the position
+        let investor = self.payer.pubkey();
+        let fees_recipient = if config.manager_key == investor {
+            config.roles.fee_recipient
+        } else {
+            investor
+        };
+        let signature = self.send(
+            &[client::collect_fees_to(&investor, &position, &fees_recipient)],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "investor": self.investor_json(&position, &self.payer.pubkey())?,
+        }))
+    }
+
+    pub fn decrease_liquidity(
+        &self,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64
+    ) -> Result<Value> {
+        let position = self.position()?;
+        let signature = self.send(
+            &[
+                client::decrease_liquidity(
+                    &self.payer.pubkey(),
+                    &position,
+                    liquidity,
+                    amount_0_min,
+                    amount_1_min
+                ),
+            ],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "investor": self.investor_json(&position, &self.payer.pubkey())?,
+        }))
+    }
+
+    /// Queues the liquidity caps and the minimum deposit of the position
+    pub fn set_deposit_limits(
+        &self,
+        max_total_liquidity: u128,
+        max_investor_liquidity: u128,
+        min_deposit: u64
+    ) -> Result<Value> {
+        self.queue_change(ConfigChange::DepositLimits {
+            max_total_liquidity,
+            max_investor_liquidity,
+            min_deposit,
+        })
+    }
+
+    pub fn set_lockup(&self, lockup_seconds: i64, early_exit_penalty_bps: u16) -> Result<Value> {
+        self.queue_change(ConfigChange::Lockup { lockup_seconds, early_exit_penalty_bps })
+    }
+
+    /// Queues the restriction of the position to the investors of the `allowlist` file, or its
+    /// opening to anyone
+    pub fn set_allowlist(&self, allowlist: Option<PathBuf>) -> Result<Value> {
+        let root = allowlist.as_deref().map(read_allowlist).transpose()?.map(|tree| tree.root());
+        self.queue_change(ConfigChange::AllowlistRoot { allowlist_root: root })
+    }
+
+    pub fn propose_manager(&self, new_manager: &str) -> Result<Value> {
+        let new_manager = parse_pubkey(new_manager)?;
+        self.queue_change(ConfigChange::PendingManager { new_manager })
+    }
+
+    pub fn set_timelock(&self, timelock_seconds: i64) -> Result<Value> {
+        self.queue_change(ConfigChange::Timelock { timelock_seconds })
+    }
+
+    /// Applies the queued change `change_id`, signed by anyone once its timelock has elapsed
+    pub fn execute_change(&self, change_id: u64) -> Result<Value> {
+        let position = self.position()?;
+        let pending = self.pending_change(&position, change_id)?;
+        let signature = self.send(
+            &[
+                client::execute_config_change(
+                    &self.payer.pubkey(),
+                    &position,
+                    change_id,
+                    &pending.proposer
+                ),
+            ],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "change_id": change_id,
+            "change": format!("{:?}", pending.change),
+        }))
+    }
+
+    /// Drops the queued change `change_id`, signed by its proposer or the guardian
+    pub fn cancel_change(&self, change_id: u64) -> Result<Value> {
+        let position = self.position()?;
+        let pending = self.pending_change(&position, change_id)?;
+        let signature = self.send(
+            &[
+                client::cancel_config_change(
+                    &self.payer.pubkey(),
+                    &position,
+                    change_id,
+                    &pending.proposer
+                ),
+            ],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "change_id": change_id,
+        }))
+    }
+
+    /// Accepts the manager role proposed to the keypair
+    pub fn accept_manager(&self) -> Result<Value> {
+        let position = self.position()?;
+        let addresses = position.addresses();
+        let config: PoolPositionConfig = self.load_account(&addresses.pool_position_config)?;
+        let signature = self.send(
+            &[client::accept_manager(&self.payer.pubkey(), &position, &config.manager_key)],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "previous_manager": config.manager_key.to_string(),
+            "manager": self.payer.pubkey().to_string(),
+        }))
+    }
+
+    /// Queues the roles given, signed by the guardian; the others keep their current key
+    pub fn set_roles(
+        &self,
+        strategist: Option<String>,
+        guardian: Option<String>,
+        fee_recipient: Option<String>
+    ) -> Result<Value> {
+        let position = self.position()?;
+        let config: PoolPositionConfig = self.load_account(
+            &position.addresses().pool_position_config
+        )?;
+        let parse_or = |value: Option<String>, current: Pubkey| {
+            value.as_deref().map(parse_pubkey).transpose().map(|key| key.unwrap_or(current))
+        };
+        let roles = PositionRoles {
+            strategist: parse_or(strategist, config.roles.strategist)?,
+            guardian: parse_or(guardian, config.roles.guardian)?,
+            fee_recipient: parse_or(fee_recipient, config.roles.fee_recipient)?,
+        };
+        self.queue_change(ConfigChange::Roles { roles })
+    }
+
+    pub fn set_paused(&self, paused: bool) -> Result<Value> {
+        let position = self.position()?;
+        let signature = self.send(
+            &[client::set_paused(&self.payer.pubkey(), &position, paused)],
+            &[]
+        )?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "paused": paused,
+        }))
+    }
+
+    /// Sends the migrations of the accounts older than the current layout, shorter than it
+    pub fn migrate(&self, investors: &[String]) -> Result<Value> {
+        let addresses = PoolPositionAddresses::new(self.config.name()?);
+        let payer = self.payer.pubkey();
+        let mut instructions = Vec::new();
+        let mut migrated = Vec::new();
+        if self.is_legacy(&addresses.pool_position_config, PoolPositionConfig::LEN)? {
+            instructions.push(client::migrate_pool_position_config(&payer, &addresses));
+            migrated.push(addresses.pool_position_config.to_string());
+        }
+        if self.is_legacy(&addresses.pool_position, PoolPosition::LEN)? {
+            instructions.push(client::migrate_pool_position(&payer, &addresses));
+            migrated.push(addresses.pool_position.to_string());
+        }
+        for authority in parse_pubkeys(investors)? {
+            let investor_addresses = InvestorAddresses::new(
+                &addresses.pool_position_config,
+                &authority
+            );
+            if self.is_legacy(&investor_addresses.investor_account, Investor::LEN)? {
+                instructions.push(client::migrate_investor(&payer, &addresses, &authority));
+                migrated.push(investor_addresses.investor_account.to_string());
+            }
+        }
+        if instructions.is_empty() {
+            return Ok(json!({ "migrated": migrated }));
+        }
+        let signature = self.send(&instructions, &[])?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "migrated": migrated,
+        }))
+    }
+
+    pub fn show(&self, investor: Option<String>) -> Result<Value> {
+        let addresses = PoolPositionAddresses::new(self.config.name()?);
+        let config: PoolPositionConfig = self.load_account(&addresses.pool_position_config)?;
+        let pool_position: PoolPosition = self.load_zero_copy(&addresses.pool_position)?;
+        let position = self.position()?;
+        let authority = match investor {
+            Some(key) => parse_pubkey(&key)?,
+            None => self.payer.pubkey(),
+        };
+        Ok(json!({
+            "pool_position_config": {
+                "address": addresses.pool_position_config.to_string(),
+                "name": config.name,
+                "pool": config.pool_key.to_string(),
+                "manager": config.manager_key.to_string(),
+                "pending_manager": config.pending_manager_key.map(|key| key.to_string()),
+                "tick_lower_index": config.tick_lower_index,
+                "tick_upper_index": config.tick_upper_index,
+                "vault_0_mint": config.vault_0_mint_key.to_string(),
+                "vault_1_mint": config.vault_1_mint_key.to_string(),
+                "max_total_liquidity": config.max_total_liquidity.to_string(),
+                "max_investor_liquidity": config.max_investor_liquidity.to_string(),
+                "min_deposit": config.min_deposit,
+                "allowlist_root": config.allowlist_root.map(|root|
+                    Pubkey::new_from_array(root).to_string()
+                ),
+                "lockup_seconds": config.lockup_seconds,
+                "early_exit_penalty_bps": config.early_exit_penalty_bps,
+                "roles": roles_json(&config.roles),
+                "paused": config.paused,
+                "timelock_seconds": config.timelock_seconds,
+                "next_config_change_id": config.next_config_change_id,
+            },
+            "pool_position": {
+                "address": addresses.pool_position.to_string(),
+                "position_nft_mint": pool_position.position_nft_mint_key.to_string(),
+                "liquidity": pool_position.liquidity.to_string(),
+                "fees_index0": pool_position.fees_index0.to_string(),
+                "fees_index1": pool_position.fees_index1.to_string(),
+                "vaults_initialized": pool_position.vaults_initialized != 0,
+                "created_at": pool_position.created_at,
+                "ladder": position.ladder
+                    .iter()
+                    .map(|range| json!({
+                        "tick_lower_index": range.range.tick_lower_index,
+                        "tick_upper_index": range.range.tick_upper_index,
+                        "position_nft_mint": range.position_nft_mint.to_string(),
+                    }))
+                    .collect::<Vec<Value>>(),
+            },
+            "investor": self.investor_json(&position, &authority)?,
+        }))
+    }
+
+    /// Queues `change` as the next change of the config and prints when it can execute
+    fn queue_change(&self, change: ConfigChange) -> Result<Value> {
+        let position = self.position()?;
+        let config: PoolPositionConfig = self.load_account(
+            &position.addresses().pool_position_config
+        )?;
+        let change_id = config.next_config_change_id;
+        let signature = self.send(
+            &[client::queue_config_change(&self.payer.pubkey(), &position, change_id, change)],
+            &[]
+        )?;
+        let pending = self.pending_change(&position, change_id)?;
+        Ok(json!({
+            "signature": signature.to_string(),
+            "change_id": change_id,
+            "change": format!("{:?}", change),
+            "executable_at": pending.executable_at,
+        }))
+    }
+
+    fn pending_change(
+        &self,
+        position: &PositionAccounts,
+        change_id: u64
+    ) -> Result<PendingConfigChange> {
+        let (address, _) = pda::pending_config_change(
+            &position.addresses().pool_position_config,
+            change_id
+        );
+        self.load_account(&address).with_context(|| format!("no queued change {change_id}"))
+    }
+
+    /// The position described by the config, before it exists on chain
+    fn configured_position(&self, position_nft_mint: Pubkey) -> Result<PositionAccounts> {
+        let (pool, _) = self.load_pool(&self.config.pool()?)?;
+        Ok(PositionAccounts {
+            name: self.config.name()?.to_string(),
+            pool,
+            range: self.config.range()?,
+            position_nft_mint,
+            ladder: Vec::new(),
+        })
+    }
+
+    /// The position read from chain; the pool and range of the config are not needed once the
+    /// position exists
+    fn position(&self) -> Result<PositionAccounts> {
+        let addresses = PoolPositionAddresses::new(self.config.name()?);
+        let config: PoolPositionConfig = self.load_account(&addresses.pool_position_config)?;
+        let pool_position: PoolPosition = self.load_zero_copy(&addresses.pool_position)?;
+        let (pool, _) = self.load_pool(&config.pool_key)?;
+        Ok(PositionAccounts {
+            name: config.name,
+            pool,
+            range: client::PositionRange {
+                tick_lower_index: config.tick_lower_index,
+                tick_upper_index: config.tick_upper_index,
+            },
+            position_nft_mint: pool_position.position_nft_mint_key,
+            ladder: self.load_ladder(&addresses.pool_position, &pool_position)?,
+        })
+    }
+
+    fn load_ladder(
+        &self,
+        address: &Pubkey,
+        pool_position: &PoolPosition
+    ) -> Result<Vec<LadderRangeKeys>> {
+        (0..pool_position.ladder_ranges)
+            .map(|index| {
+                let (ladder_range, _) = pda::ladder_range(address, index);
+                let ladder_range: LadderRange = self.load_account(&ladder_range)?;
+                Ok(LadderRangeKeys {
+                    range: client::PositionRange {
+                        tick_lower_index: ladder_range.tick_lower_index,
+                        tick_upper_index: ladder_range.tick_upper_index,
+                    },
+                    position_nft_mint: ladder_range.position_nft_mint_key,
+                })
+            })
+            .collect()
+    }
+
+    /// The token accounts given, or the associated token accounts of the manager
+    fn manager_token_accounts(
+        &self,
+        position: &PositionAccounts,
+        token_account_0: Option<String>,
+        token_account_1: Option<String>
+    ) -> Result<(Pubkey, Pubkey)> {
+        let (associated_0, associated_1) = position.pool.associated_token_accounts(
+            &self.payer.pubkey()
+        );
+        let token_account_0 = match token_account_0 {
+            Some(key) => parse_pubkey(&key)?,
+            None => associated_0,
+        };
+        let token_account_1 = match token_account_1 {
+            Some(key) => parse_pubkey(&key)?,
+            None => associated_1,
+        };
+        Ok((token_account_0, token_account_1))
+    }
+
+    fn investor_json(&self, position: &PositionAccounts, authority: &Pubkey) -> Result<Value> {
+        let pool_position_config = position.addresses().pool_position_config;
+        let addresses = InvestorAddresses::new(&pool_position_config, authority);
+        let investor = match self.client.get_account(&addresses.investor_account) {
+            Ok(account) => {
+                read_zero_copy::<Investor>(&addresses.investor_account, &account.data)?
+            }
+            Err(_) => {
+                return Ok(Value::Null);
+            }
+        };
+        Ok(json!({
+            "address": addresses.investor_account.to_string(),
+            "authority": investor.authority.to_string(),
+            "liquidity": investor.liquidity.to_string(),
+            "init_liquidity": investor.init_liquidity.to_string(),
+            "fees_earned0": investor.fees_earned0,
+            "fees_earned1": investor.fees_earned1,
+            "fees_index0": investor.fees_index0.to_string(),
+            "fees_index1": investor.fees_index1.to_string(),
+            "is_manager": investor.is_manager != 0,
+            "deposited_at": investor.deposited_at,
+            "deposit_stable_token_account": addresses.deposit_stable_token_account.to_string(),
+            "stable_balance": self.token_balance(&addresses.deposit_stable_token_account),
+            "token_0_balance": self.token_balance(&addresses.deposit_token_0_account),
+            "token_1_balance": self.token_balance(&addresses.deposit_token_1_account),
+        }))
+    }
+
+    fn token_balance(&self, token_account: &Pubkey) -> Value {
+        match self.client.get_token_account_balance(token_account) {
+            Ok(balance) => Value::String(balance.amount),
+            Err(_) => Value::Null,
+        }
+    }
+
+    fn liquidity_bounds(
+        &self,
+        position: &PositionAccounts,
+        slippage: SlippageArgs
+    ) -> Result<LiquidityBounds> {
+        let (_, state) = self.load_pool(&position.pool.pool_state)?;
+        Ok(LiquidityBounds {
+            min_liquidity_out: slippage.min_liquidity_out,
+            expected_sqrt_price_x64: state.sqrt_price_x64,
+            max_price_deviation: slippage.max_price_deviation,
+        })
+    }
+
+    /// Single hop swap legs from the stable mint. When the stable mint is a position token, leg
+    /// 0 swaps inside the position pool and leg 1 is unused.
+    fn swap_legs(
+        &self,
+        position: &PositionAccounts,
+        slippage: SlippageArgs
+    ) -> Result<(SwapLegAccounts, SwapLegAccounts)> {
+        let stable_mint = self.config.stable_mint;
+        if stable_mint == position.pool.token_mint_0 || stable_mint == position.pool.token_mint_1 {
+            let leg_0 = self.swap_leg(&position.pool.pool_state, slippage.min_out_0)?;
+            let leg_1 = SwapLegAccounts {
+                tick_arrays: Vec::new(),
+                minimum_amount_out: 0,
+                ..leg_0.clone()
+            };
+            return Ok((leg_0, leg_1));
+        }
+        let pool_0 = self.config.swap_pool_0.unwrap_or(position.pool.pool_state);
+        let pool_1 = self.config.swap_pool_1.unwrap_or(position.pool.pool_state);
+        Ok((
+            self.swap_leg(&pool_0, slippage.min_out_0)?,
+            self.swap_leg(&pool_1, slippage.min_out_1)?,
+        ))
+    }
+
+    fn swap_leg(&self, pool_state: &Pubkey, minimum_amount_out: u64) -> Result<SwapLegAccounts> {
+        let (pool, state) = self.load_pool(pool_state)?;
+        let zero_for_one = self.config.stable_mint == pool.token_mint_0;
+        if !zero_for_one && self.config.stable_mint != pool.token_mint_1 {
+            return Err(anyhow!("pool {pool_state} does not trade the stable mint"));
+        }
+        let candidates = pool.swap_tick_arrays(state.tick_current, zero_for_one, SWAP_TICK_ARRAYS);
+        // Raydium starts from the first initialized tick array in the swap direction
+        let existing = self.client.get_multiple_accounts(&candidates)?;
+        let tick_arrays = candidates
+            .into_iter()
+            .zip(existing)
+            .filter(|(_, account)| account.is_some())
+            .map(|(key, _)| key)
+            .collect();
+        Ok(SwapLegAccounts { pool, tick_arrays, hops: Vec::new(), minimum_amount_out })
+    }
+
+    fn load_pool(&self, pool_state: &Pubkey) -> Result<(ClmmPool, PoolState)> {
+        let account = self.client
+            .get_account(pool_state)
+            .with_context(|| format!("fetching pool {pool_state}"))?;
+        let data = account.data;
+        let discriminator = PoolState::DISCRIMINATOR;
+        let end = discriminator.len() + size_of::<PoolState>();
+        if !data.starts_with(discriminator) || data.len() < end {
+            return Err(anyhow!("{pool_state} is not a Raydium pool"));
+        }
+        let state: PoolState = *bytemuck::from_bytes(&data[discriminator.len()..end]);
+        // The token program of each mint is its owner, legacy or Token-2022
+        let mut token_programs = Vec::with_capacity(2);
+        for mint in [state.token_mint_0, state.token_mint_1] {
+            let account = self.client
+                .get_account(&mint)
+                .with_context(|| format!("fetching mint {mint}"))?;
+            token_programs.push(account.owner);
+        }
+        let pool = ClmmPool::from_state(*pool_state, &state).with_token_programs(
+            token_programs[0],
+            token_programs[1]
+        );
+        Ok((pool, state))
+    }
+
+    fn is_legacy(&self, address: &Pubkey, len: usize) -> Result<bool> {
+        let account = self.client
+            .get_account(address)
+            .with_context(|| format!("fetching account {address}"))?;
+        Ok(account.data.len() < len)
+    }
+
+    fn load_account<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let account = self.client
+            .get_account(address)
+            .with_context(|| format!("fetching account {address}"))?;
+        Ok(T::try_deserialize(&mut account.data.as_slice())?)
+    }
+
+    fn load_zero_copy<T: ZeroCopy>(&self, address: &Pubkey) -> Result<T> {
+        let account = self.client
+            .get_account(address)
+            .with_context(|| format!("fetching account {address}"))?;
+        read_zero_copy(address, &account.data)
+    }
+
+    fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<Signature> {
+        let mut all_instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT)
+        ];
+        all_instructions.extend_from_slice(instructions);
+
+        let mut all_signers: Vec<&Keypair> = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+
+        let blockhash = self.client.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &all_instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash
+        );
+        Ok(self.client.send_and_confirm_transaction(&transaction)?)
+    }
+}
+
+/// Reads an allowlist file, one investor address per line, blank lines and `#` comments ignored
+fn read_allowlist(path: &Path) -> Result<AllowlistTree> {
+    let content = std::fs::read_to_string(path).with_context(||
+        format!("reading allowlist {}", path.display())
+    )?;
+    let investors = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(parse_pubkey)
+        .collect::<Result<Vec<_>>>()?;
+    AllowlistTree::new(&investors).ok_or_else(|| anyhow!("{} is empty", path.display()))
+}
+
+fn roles_json(roles: &PositionRoles) -> Value {
+    json!({
+        "strategist": roles.strategist.to_string(),
+        "guardian": roles.guardian.to_string(),
+        "fee_recipient": roles.fee_recipient.to_string(),
+    })
+}
+
+fn protocol_config_json(signature: Signature, clmm_programs: &[Pubkey]) -> Value {
+    json!({
+        "signature": signature.to_string(),
+        "protocol_config": pda::protocol_config().0.to_string(),
+        "clmm_programs": clmm_programs.iter().map(Pubkey::to_string).collect::<Vec<_>>(),
+    })
+}
+
+/// Copies a zero-copy account out of `data`, where its `u128` fields are not aligned as the
+/// host requires
+fn read_zero_copy<T: ZeroCopy>(address: &Pubkey, data: &[u8]) -> Result<T> {
+    let start = T::DISCRIMINATOR.len();
+    let end = start + size_of::<T>();
+    if !data.starts_with(T::DISCRIMINATOR) || data.len() < end {
+        return Err(anyhow!("{address} is not in the current layout, see `migrate`"));
+    }
+    Ok(bytemuck::pod_read_unaligned(&data[start..end]))
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey> {
+    Pubkey::from_str(value).with_context(|| format!("invalid address {value}"))
+}
+
+fn parse_pubkeys(values: &[String]) -> Result<Vec<Pubkey>> {
+    values.iter().map(|value| parse_pubkey(value)).collect()
+}
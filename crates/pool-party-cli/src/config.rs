@@ -0,0 +1,219 @@
+//! CLI configuration, read from an optional TOML file and overridden by flags.
+//!
+//! ```toml
+//! cluster_url = "http://127.0.0.1:8899"
+//! keypair = "~/.config/solana/id.json"
+//! name = "sol-usdc"
+//! pool = "<raydium pool state>"
+//! tick_lower_index = -1200
+//! tick_upper_index = 1200
+//! ```
+
+use std::path::{ Path, PathBuf };
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token::native_mint;
+use anyhow::{ anyhow, Context, Result };
+use clap::Args;
+use pool_party_client::PositionRange;
+use serde::Deserialize;
+
+pub const DEFAULT_CLUSTER_URL: &str = "http://127.0.0.1:8899";
+pub const DEFAULT_KEYPAIR: &str = "~/.config/solana/id.json";
+
+/// Settings read from the TOML file, every field is optional
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub cluster_url: Option<String>,
+    pub keypair: Option<PathBuf>,
+    pub name: Option<String>,
+    pub pool: Option<String>,
+    pub tick_lower_index: Option<i32>,
+    pub tick_upper_index: Option<i32>,
+    pub stable_mint: Option<String>,
+    pub swap_pool_0: Option<String>,
+    pub swap_pool_1: Option<String>,
+}
+
+impl FileConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(||
+            format!("reading config {}", path.display())
+        )?;
+        toml::from_str(&content).with_context(|| format!("parsing config {}", path.display()))
+    }
+}
+
+/// Global flags, taking precedence over the TOML file
+#[derive(Args, Clone, Debug, Default)]
+pub struct ConfigArgs {
+    /// TOML config file
+    #[arg(long, short = 'c', global = true, env = "POOL_PARTY_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// RPC URL of the cluster
+    #[arg(long, short = 'u', global = true)]
+    pub cluster_url: Option<String>,
+
+    /// Keypair signing and paying for the transactions
+    #[arg(long, short = 'k', global = true)]
+    pub keypair: Option<PathBuf>,
+
+    /// Name of the pool position
+    #[arg(long, short = 'n', global = true)]
+    pub name: Option<String>,
+
+    /// Raydium pool state of the position
+    #[arg(long, global = true)]
+    pub pool: Option<String>,
+
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    pub tick_lower_index: Option<i32>,
+
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    pub tick_upper_index: Option<i32>,
+
+    /// Mint of the investor deposits, wrapped SOL by default
+    #[arg(long, global = true)]
+    pub stable_mint: Option<String>,
+
+    /// Raydium pool swapping the stable mint to token 0, the position pool by default
+    #[arg(long, global = true)]
+    pub swap_pool_0: Option<String>,
+
+    /// Raydium pool swapping the stable mint to token 1, the position pool by default
+    #[arg(long, global = true)]
+    pub swap_pool_1: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    pub cluster_url: String,
+    pub keypair: PathBuf,
+    pub name: Option<String>,
+    pub pool: Option<Pubkey>,
+    pub range: Option<PositionRange>,
+    pub stable_mint: Pubkey,
+    pub swap_pool_0: Option<Pubkey>,
+    pub swap_pool_1: Option<Pubkey>,
+}
+
+impl Config {
+    pub fn load(args: &ConfigArgs) -> Result<Self> {
+        let file = match &args.config {
+            Some(path) => FileConfig::load(path)?,
+            None => FileConfig::default(),
+        };
+        Config::resolve(args.clone(), file)
+    }
+
+    pub fn resolve(args: ConfigArgs, file: FileConfig) -> Result<Self> {
+        let tick_lower_index = args.tick_lower_index.or(file.tick_lower_index);
+        let tick_upper_index = args.tick_upper_index.or(file.tick_upper_index);
+        let range = match (tick_lower_index, tick_upper_index) {
+            (Some(tick_lower_index), Some(tick_upper_index)) => {
+                if tick_lower_index >= tick_upper_index {
+                    return Err(anyhow!("tick_lower_index must be below tick_upper_index"));
+                }
+                Some(PositionRange { tick_lower_index, tick_upper_index })
+            }
+            (None, None) => None,
+            _ => {
+                return Err(anyhow!("tick_lower_index and tick_upper_index go together"));
+            }
+        };
+
+        Ok(Config {
+            cluster_url: args.cluster_url
+                .or(file.cluster_url)
+                .unwrap_or_else(|| DEFAULT_CLUSTER_URL.to_string()),
+            keypair: expand_home(
+                &args.keypair.or(file.keypair).unwrap_or_else(|| DEFAULT_KEYPAIR.into())
+            ),
+            name: args.name.or(file.name),
+            pool: parse_pubkey("pool", args.pool.or(file.pool))?,
+            range,
+            stable_mint: parse_pubkey(
+                "stable_mint",
+                args.stable_mint.or(file.stable_mint)
+            )?.unwrap_or(native_mint::ID),
+            swap_pool_0: parse_pubkey("swap_pool_0", args.swap_pool_0.or(file.swap_pool_0))?,
+            swap_pool_1: parse_pubkey("swap_pool_1", args.swap_pool_1.or(file.swap_pool_1))?,
+        })
+    }
+
+    pub fn name(&self) -> Result<&str> {
+        self.name.as_deref().ok_or_else(|| anyhow!("missing position name, set `name` or --name"))
+    }
+
+    pub fn pool(&self) -> Result<Pubkey> {
+        self.pool.ok_or_else(|| anyhow!("missing pool, set `pool` or --pool"))
+    }
+
+    pub fn range(&self) -> Result<PositionRange> {
+        self.range.ok_or_else(||
+            anyhow!("missing range, set `tick_lower_index` and `tick_upper_index`")
+        )
+    }
+}
+
+fn parse_pubkey(field: &str, value: Option<String>) -> Result<Option<Pubkey>> {
+    value
+        .map(|value| Pubkey::from_str(&value).with_context(|| format!("invalid {field}: {value}")))
+        .transpose()
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod config_test {
+    use super::*;
+
+    #[test]
+    fn flags_override_the_file() {
+        let pool = Pubkey::new_unique();
+        let content = format!(
+            "cluster_url = \"http://localhost:8899\"\nname = \"file\"\npool = \"{pool}\"\n\
+             tick_lower_index = -1200\ntick_upper_index = 1200\n"
+        );
+        let file: FileConfig = toml::from_str(&content).unwrap();
+        let args = ConfigArgs {
+            name: Some("flag".to_string()),
+            tick_lower_index: Some(-600),
+            tick_upper_index: Some(600),
+            ..ConfigArgs::default()
+        };
+
+        let config = Config::resolve(args, file).unwrap();
+        assert_eq!(config.cluster_url, "http://localhost:8899");
+        assert_eq!(config.name.as_deref(), Some("flag"));
+        assert_eq!(config.pool, Some(pool));
+        assert_eq!(
+            config.range,
+            Some(PositionRange { tick_lower_index: -600, tick_upper_index: 600 })
+        );
+        assert_eq!(config.stable_mint, native_mint::ID);
+    }
+
+    #[test]
+    fn rejects_partial_or_inverted_ranges_and_unknown_fields() {
+        let partial = ConfigArgs { tick_lower_index: Some(0), ..ConfigArgs::default() };
+        assert!(Config::resolve(partial, FileConfig::default()).is_err());
+
+        let inverted = ConfigArgs {
+            tick_lower_index: Some(600),
+            tick_upper_index: Some(-600),
+            ..ConfigArgs::default()
+        };
+        assert!(Config::resolve(inverted, FileConfig::default()).is_err());
+
+        assert!(toml::from_str::<FileConfig>("tick_lower = 1").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! `pool-party-cli`: operate pool positions against a cluster, meant for a local test validator.
+
+mod commands;
+mod config;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{ Parser, Subcommand };
+
+use crate::config::{ Config, ConfigArgs };
+
+#[derive(Parser, Debug)]
+#[command(name = "pool-party-cli", version, about = "Operate pool-party positions")]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Create the protocol config allowing the CLMM programs, signed by the upgrade authority
+    InitProtocolConfig {
+        /// A Raydium CLMM program positions may use, repeated for each program
+        #[arg(long = "clmm-program", required = true)]
+        clmm_programs: Vec<String>,
+    },
+    /// Replace the CLMM programs allowlist of the protocol config, signed by its admin
+    SetClmmPrograms {
+        /// A Raydium CLMM program positions may use, repeated for each program
+        #[arg(long = "clmm-program", required = true)]
+        clmm_programs: Vec<String>,
+    },
+    /// Create the position config, the position and the manager account
+    CreatePosition,
+    /// Create the token and fees vaults of the position
+    CreateVaults,
+    /// Open the Raydium position with liquidity from the manager token accounts
+    OpenPosition {
+        #[arg(long)]
+        amount_0_max: u64,
+        #[arg(long)]
+        amount_1_max: u64,
+        /// Token account funding token 0, the manager associated token account by default
+        #[arg(long)]
+        token_account_0: Option<String>,
+        /// Token account funding token 1, the manager associated token account by default
+        #[arg(long)]
+        token_account_1: Option<String>,
+    },
+    /// Open a ladder range holding `weight_bps` of the core liquidity, before investors join
+    OpenLadderRange {
+        /// Lower tick of the range, the global tick arguments being the core range
+        #[arg(long, allow_hyphen_values = true)]
+        lower_tick: i32,
+        /// Upper tick of the range
+        #[arg(long, allow_hyphen_values = true)]
+        upper_tick: i32,
+        #[arg(long)]
+        weight_bps: u32,
+        #[arg(long)]
+        amount_0_max: u64,
+        #[arg(long)]
+        amount_1_max: u64,
+        /// Token account funding token 0, the manager associated token account by default
+        #[arg(long)]
+        token_account_0: Option<String>,
+        /// Token account funding token 1, the manager associated token account by default
+        #[arg(long)]
+        token_account_1: Option<String>,
+    },
+    /// Create the investor account and deposit accounts of the keypair
+    CreateInvestor {
+        /// Allowlist file of a permissioned position, one address per line
+        #[arg(long)]
+        allowlist: Option<PathBuf>,
+    },
+    /// Deposit lamports to the stable deposit account of the investor
+    Deposit {
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Swap a stable deposit to the position tokens and add it as liquidity
+    Zap {
+        #[arg(long)]
+        amount: u64,
+        #[command(flatten)]
+        slippage: commands::SlippageArgs,
+    },
+    /// Add the deposited tokens of the investor as liquidity
+    IncreaseLiquidity {
+        #[command(flatten)]
+        slippage: commands::SlippageArgs,
+    },
+    /// Collect the fees of the position and pay the investor share
+    CollectFees,
+    /// Remove liquidity of the investor, less the early exit penalty during the lockup
+    DecreaseLiquidity {
+        #[arg(long)]
+        liquidity: u128,
+        #[arg(long, default_value_t = 0)]
+        amount_0_min: u64,
+        #[arg(long, default_value_t = 0)]
+        amount_1_min: u64,
+    },
+    /// Queue the liquidity caps, zero for no cap, and the minimum deposit of the position
+    SetDepositLimits {
+        #[arg(long, default_value_t = 0)]
+        max_total_liquidity: u128,
+        #[arg(long, default_value_t = 0)]
+        max_investor_liquidity: u128,
+        #[arg(long, default_value_t = 0)]
+        min_deposit: u64,
+    },
+    /// Queue the lockup of the deposits and the early exit penalty, zero for a strict lockup
+    SetLockup {
+        #[arg(long)]
+        lockup_seconds: i64,
+        #[arg(long, default_value_t = 0)]
+        early_exit_penalty_bps: u16,
+    },
+    /// Queue the restriction of the position to an allowlist file, or its opening without one
+    SetAllowlist {
+        /// One address per line, blank lines and `#` comments are ignored
+        #[arg(long)]
+        allowlist: Option<PathBuf>,
+    },
+    /// Queue the proposal of a new manager, who takes over once it accepts
+    ProposeManager {
+        #[arg(long)]
+        new_manager: String,
+    },
+    /// Accept the manager role proposed to the keypair
+    AcceptManager,
+    /// Queue roles of the position, signed by its guardian; roles not given are kept
+    SetRoles {
+        #[arg(long)]
+        strategist: Option<String>,
+        #[arg(long)]
+        guardian: Option<String>,
+        #[arg(long)]
+        fee_recipient: Option<String>,
+    },
+    /// Queue the timelock of the config changes queued afterwards
+    SetTimelock {
+        #[arg(long)]
+        timelock_seconds: i64,
+    },
+    /// Apply a queued config change once its timelock has elapsed
+    ExecuteChange {
+        #[arg(long)]
+        change_id: u64,
+    },
+    /// Drop a queued config change, signed by its proposer or the guardian
+    CancelChange {
+        #[arg(long)]
+        change_id: u64,
+    },
+    /// Block the deposits of the position, signed by its guardian
+    Pause,
+    /// Accept deposits again, signed by the guardian of the position
+    Unpause,
+    /// Upgrade the position accounts, and the investor accounts given, to the current layout
+    Migrate {
+        /// Authority of an investor account to upgrade, repeated for each investor
+        #[arg(long)]
+        investor: Vec<String>,
+    },
+    /// Print the position, and the investor account of the keypair if any, as JSON
+    Show {
+        /// Show this investor instead of the keypair
+        #[arg(long)]
+        investor: Option<String>,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load(&cli.config)?;
+    let ctx = commands::Context::new(config)?;
+
+    let output = match cli.command {
+        Command::InitProtocolConfig { clmm_programs } =>
+            ctx.init_protocol_config(&clmm_programs)?,
+        Command::SetClmmPrograms { clmm_programs } => ctx.set_clmm_programs(&clmm_programs)?,
+        Command::CreatePosition => ctx.create_position()?,
+        Command::CreateVaults => ctx.create_vaults()?,
+        Command::OpenPosition { amount_0_max, amount_1_max, token_account_0, token_account_1 } =>
+            ctx.open_position(amount_0_max, amount_1_max, token_account_0, token_account_1)?,
+        Command::OpenLadderRange {
+            lower_tick,
+            upper_tick,
+            weight_bps,
+            amount_0_max,
+            amount_1_max,
+            token_account_0,
+            token_account_1,
+        } =>
+            ctx.open_ladder_range(
+                lower_tick,
+                upper_tick,
+                weight_bps,
+                amount_0_max,
+                amount_1_max,
+                token_account_0,
+                token_account_1
+            )?,
+        Command::CreateInvestor { allowlist } => ctx.create_investor(allowlist)?,
+        Command::Deposit { amount } => ctx.deposit(amount)?,
+        Command::Zap { amount, slippage } => ctx.zap(amount, slippage)?,
+        Command::IncreaseLiquidity { slippage } => ctx.increase_liquidity(slippage)?,
+        Command::CollectFees => ctx.collect_fees()?,
+        Command::DecreaseLiquidity { liquidity, amount_0_min, amount_1_min } =>
+            ctx.decrease_liquidity(liquidity, amount_0_min, amount_1_min)?,
+        Command::SetDepositLimits { max_total_liquidity, max_investor_liquidity, min_deposit } =>
+            ctx.set_deposit_limits(max_total_liquidity, max_investor_liquidity, min_deposit)?,
+        Command::SetLockup { lockup_seconds, early_exit_penalty_bps } =>
+            ctx.set_lockup(lockup_seconds, early_exit_penalty_bps)?,
+        Command::SetAllowlist { allowlist } => ctx.set_allowlist(allowlist)?,
+        Command::ProposeManager { new_manager } => ctx.propose_manager(&new_manager)?,
+        Command::AcceptManager => ctx.accept_manager()?,
+        Command::SetRoles { strategist, guardian, fee_recipient } =>
+            ctx.set_roles(strategist, guardian, fee_recipient)?,
+        Command::SetTimelock { timelock_seconds } => ctx.set_timelock(timelock_seconds)?,
+        Command::ExecuteChange { change_id } => ctx.execute_change(change_id)?,
+        Command::CancelChange { change_id } => ctx.cancel_change(change_id)?,
+        Command::Pause => ctx.set_paused(true)?,
+        Command::Unpause => ctx.set_paused(false)?,
+        Command::Migrate { investor } => ctx.migrate(&investor)?,
+        Command::Show { investor } => ctx.show(investor)?,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
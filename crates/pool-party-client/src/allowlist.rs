@@ -0,0 +1,94 @@
+//! Merkle trees of the investors allowed in a permissioned pool position.
+//!
+//! The tree hashes leaves and nodes with the functions `create_investor_position` verifies
+//! proofs with. Leaves are sorted, so the root only depends on the set of investors, and the
+//! last node of an odd level is carried up unhashed.
+
+use anchor_lang::prelude::Pubkey;
+use pool_party::libraries::{ allowlist_leaf, allowlist_node };
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllowlistTree {
+    /// Every level of the tree, from the sorted leaves up to the root
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl AllowlistTree {
+    /// Builds the tree of `investors`, duplicates being ignored. Returns `None` for an empty
+    /// allowlist, which has no root.
+    pub fn new(investors: &[Pubkey]) -> Option<Self> {
+        let mut leaves: Vec<[u8; 32]> = investors.iter().map(allowlist_leaf).collect();
+        leaves.sort_unstable();
+        leaves.dedup();
+        if leaves.is_empty() {
+            return None;
+        }
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let level = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => allowlist_node(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(level);
+        }
+        Some(AllowlistTree { levels })
+    }
+
+    /// The root to queue as a `ConfigChange::AllowlistRoot`
+    pub fn root(&self) -> [u8; 32] {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// The proof `create_investor_position` expects from `investor`, `None` if not allowlisted
+    pub fn proof(&self, investor: &Pubkey) -> Option<Vec<[u8; 32]>> {
+        let mut index = self.levels[0].binary_search(&allowlist_leaf(investor)).ok()?;
+        let mut proof = Vec::new();
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Some(proof)
+    }
+}
+
+#[cfg(test)]
+mod allowlist_test {
+    use super::*;
+    use pool_party::libraries::verify_allowlist_proof;
+
+    #[test]
+    fn every_investor_has_a_valid_proof() {
+        for count in 1..=9 {
+            let investors: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+            let tree = AllowlistTree::new(&investors).unwrap();
+            for investor in &investors {
+                let proof = tree.proof(investor).unwrap();
+                assert!(verify_allowlist_proof(&tree.root(), &proof, investor), "{count}");
+            }
+            let outsider = Pubkey::new_unique();
+            assert_eq!(tree.proof(&outsider), None);
+            let proof = tree.proof(&investors[0]).unwrap();
+            assert!(!verify_allowlist_proof(&tree.root(), &proof, &outsider));
+        }
+    }
+
+    #[test]
+    fn root_ignores_order_and_duplicates() {
+        let investors: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let mut shuffled = investors.clone();
+        shuffled.reverse();
+        shuffled.push(investors[2]);
+        assert_eq!(
+            AllowlistTree::new(&investors).unwrap().root(),
+            AllowlistTree::new(&shuffled).unwrap().root()
+        );
+        assert_eq!(AllowlistTree::new(&[]), None);
+    }
+}
@@ -0,0 +1,1191 @@
+//! Builders for every instruction of the pool-party program.
+//!
+//! The builders derive every PDA and Raydium account from the position name, the pool and the
+//! range, so callers only provide the signers, the pool and the amounts.
+
+use anchor_lang::prelude::{ AccountMeta, Pubkey };
+use anchor_lang::solana_program::{
+    bpf_loader_upgradeable,
+    instruction::Instruction,
+    system_program,
+    sysvar,
+};
+use anchor_lang::{ InstructionData, ToAccountMetas };
+use anchor_spl::associated_token::{ self, get_associated_token_address };
+use anchor_spl::memo::spl_memo;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::{ token, token_2022 };
+use pool_party::dex::Dex;
+use pool_party::instructions::{ SwapRouteArgs, SWAP_HOP_ACCOUNTS_LEN };
+use pool_party::libraries::{ PriceSource, RemainingAccountsLayout, LADDER_RANGE_ACCOUNTS_LEN };
+use pool_party::state::ConfigChange;
+
+use crate::pda::{ self, InvestorAddresses, PoolPositionAddresses };
+use crate::raydium::{ metadata_account, ClmmPool, PositionRange, RaydiumPosition };
+
+/// A pool position: its name, the Raydium pool and range it provides liquidity to and the mint
+/// of its position NFT
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PositionAccounts {
+    pub name: String,
+    pub pool: ClmmPool,
+    pub range: PositionRange,
+    pub position_nft_mint: Pubkey,
+    /// The ladder ranges opened besides the core range, in the order of their index
+    pub ladder: Vec<LadderRangeKeys>,
+}
+
+/// A ladder range of a pool position: its range and the mint of its position NFT
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LadderRangeKeys {
+    pub range: PositionRange,
+    pub position_nft_mint: Pubkey,
+}
+
+impl PositionAccounts {
+    pub fn addresses(&self) -> PoolPositionAddresses {
+        PoolPositionAddresses::new(&self.name)
+    }
+
+    pub fn raydium(&self) -> RaydiumPosition {
+        RaydiumPosition::new(&self.pool, self.range, &self.position_nft_mint)
+    }
+
+    /// The position NFT is held by the `PoolPosition` account
+    pub fn position_nft_account(&self) -> Pubkey {
+        get_associated_token_address(&self.addresses().pool_position, &self.position_nft_mint)
+    }
+
+    /// The accounts of every ladder range, passed after the described remaining accounts.
+    /// Instructions moving liquidity or fees take them `writable`.
+    pub fn ladder_metas(&self, writable: bool) -> Vec<AccountMeta> {
+        let pool_position = self.addresses().pool_position;
+        let meta = |pubkey: Pubkey| {
+            if writable {
+                AccountMeta::new(pubkey, false)
+            } else {
+                AccountMeta::new_readonly(pubkey, false)
+            }
+        };
+        let mut metas = Vec::with_capacity(self.ladder.len() * LADDER_RANGE_ACCOUNTS_LEN);
+        for (index, range) in self.ladder.iter().enumerate() {
+            let raydium = RaydiumPosition::new(&self.pool, range.range, &range.position_nft_mint);
+            metas.extend([
+                AccountMeta::new_readonly(pda::ladder_range(&pool_position, index as u8).0, false),
+                AccountMeta::new_readonly(
+                    get_associated_token_address(&pool_position, &range.position_nft_mint),
+                    false
+                ),
+                meta(raydium.protocol_position),
+                meta(raydium.personal_position),
+                meta(raydium.tick_array_lower),
+                meta(raydium.tick_array_upper),
+            ]);
+        }
+        metas
+    }
+}
+
+/// A hop after the first one of a swap route
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapHopAccounts {
+    /// Token account of the investor receiving the output of the previous hop
+    pub input_token_account: Pubkey,
+    pub input_mint: Pubkey,
+    pub pool: ClmmPool,
+    pub tick_arrays: Vec<Pubkey>,
+}
+
+/// A swap route from the stable mint to one of the position tokens
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SwapLegAccounts {
+    /// The first pool of the route, trading the stable mint
+    pub pool: ClmmPool,
+    pub tick_arrays: Vec<Pubkey>,
+    pub hops: Vec<SwapHopAccounts>,
+    pub minimum_amount_out: u64,
+}
+
+impl SwapLegAccounts {
+    /// `(input_vault, output_vault)` of the first pool for a swap from `stable_mint`
+    fn first_vaults(&self, stable_mint: &Pubkey) -> (Pubkey, Pubkey) {
+        let (input_vault, output_vault, _) = self.pool
+            .swap_from(stable_mint)
+            .expect("the first pool of a swap leg must trade the stable mint");
+        (input_vault, output_vault)
+    }
+}
+
+/// Builds the remaining accounts of an instruction: the bitmap extensions of every pool, the tick
+/// arrays of each leg, the tick arrays of the position pool and finally the extra hops of each
+/// leg, along with the layout and route args describing them.
+fn remaining_accounts(
+    legs: &[&SwapLegAccounts],
+    liquidity: Option<(&ClmmPool, &[Pubkey])>
+) -> (RemainingAccountsLayout, Vec<SwapRouteArgs>, Vec<AccountMeta>) {
+    let mut bitmap_extensions: Vec<Pubkey> = Vec::new();
+    let mut push_bitmap_extension = |pool: &ClmmPool| {
+        let bitmap_extension = pool.bitmap_extension();
+        if !bitmap_extensions.contains(&bitmap_extension) {
+            bitmap_extensions.push(bitmap_extension);
+        }
+    };
+    for leg in legs {
+        push_bitmap_extension(&leg.pool);
+    }
+    if let Some((pool, _)) = liquidity {
+        push_bitmap_extension(pool);
+    }
+
+    let mut metas: Vec<AccountMeta> = bitmap_extensions
+        .iter()
+        .map(|key| AccountMeta::new_readonly(*key, false))
+        .collect();
+    for leg in legs {
+        metas.extend(leg.tick_arrays.iter().map(|key| AccountMeta::new(*key, false)));
+    }
+    let liquidity_tick_arrays = liquidity.map(|(_, tick_arrays)| tick_arrays).unwrap_or_default();
+    metas.extend(liquidity_tick_arrays.iter().map(|key| AccountMeta::new(*key, false)));
+
+    let mut routes = Vec::with_capacity(legs.len());
+    for leg in legs {
+        let mut hop_account_counts = Vec::with_capacity(leg.hops.len());
+        for hop in &leg.hops {
+            let (input_vault, output_vault, output_mint) = hop.pool
+                .swap_from(&hop.input_mint)
+                .expect("every hop pool must trade the output of the previous hop");
+            metas.extend([
+                AccountMeta::new(hop.input_token_account, false),
+                AccountMeta::new_readonly(hop.input_mint, false),
+                AccountMeta::new_readonly(hop.pool.amm_config, false),
+                AccountMeta::new(hop.pool.pool_state, false),
+                AccountMeta::new(input_vault, false),
+                AccountMeta::new(output_vault, false),
+                AccountMeta::new_readonly(output_mint, false),
+                AccountMeta::new(hop.pool.observation_state, false),
+                AccountMeta::new_readonly(hop.pool.bitmap_extension(), false),
+            ]);
+            metas.extend(hop.tick_arrays.iter().map(|key| AccountMeta::new(*key, false)));
+            hop_account_counts.push((SWAP_HOP_ACCOUNTS_LEN + 1 + hop.tick_arrays.len()) as u8);
+        }
+        routes.push(SwapRouteArgs {
+            hop_account_counts,
+            minimum_amount_out: leg.minimum_amount_out,
+        });
+    }
+
+    let layout = RemainingAccountsLayout {
+        bitmap_extensions: bitmap_extensions.len() as u8,
+        leg_0_tick_arrays: legs.first().map_or(0, |leg| leg.tick_arrays.len() as u8),
+        leg_1_tick_arrays: legs.get(1).map_or(0, |leg| leg.tick_arrays.len() as u8),
+        liquidity_tick_arrays: liquidity_tick_arrays.len() as u8,
+    };
+    (layout, routes, metas)
+}
+
+fn instruction(
+    accounts: impl ToAccountMetas,
+    data: impl InstructionData,
+    remaining_accounts: Vec<AccountMeta>
+) -> Instruction {
+    let mut accounts = accounts.to_account_metas(None);
+    accounts.extend(remaining_accounts);
+    Instruction { program_id: pool_party::ID, accounts, data: data.data() }
+}
+
+pub fn create_position(manager: &Pubkey, position: &PositionAccounts) -> Instruction {
+    let addresses = position.addresses();
+    instruction(
+        pool_party::accounts::CreatePositionCtx {
+            manager: *manager,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            manager_account: InvestorAddresses::new(
+                &addresses.pool_position_config,
+                manager
+            ).investor_account,
+            token_program: token::ID,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::CreatePosition {
+            name: position.name.clone(),
+            tick_lower_index: position.range.tick_lower_index,
+            tick_upper_index: position.range.tick_upper_index,
+            pool_state_key: position.pool.pool_state,
+            token_vault_0_key: position.pool.token_vault_0,
+            token_vault_1_key: position.pool.token_vault_1,
+            vault_0_mint_key: position.pool.token_mint_0,
+            vault_1_mint_key: position.pool.token_mint_1,
+            // The builders derive Raydium accounts only
+            dex: Dex::RaydiumClmm,
+        },
+        Vec::new()
+    )
+}
+
+pub fn create_position_vaults(manager: &Pubkey, position: &PositionAccounts) -> Instruction {
+    let addresses = position.addresses();
+    instruction(
+        pool_party::accounts::CreatePositionVaultsCtx {
+            manager: *manager,
+            pool_position: addresses.pool_position,
+            pool_position_vault_0_token_account: addresses.pool_position_vault_0,
+            pool_position_vault_1_token_account: addresses.pool_position_vault_1,
+            fees_vault_0_token_account: addresses.fees_vault_0,
+            fees_vault_1_token_account: addresses.fees_vault_1,
+            vault_0_mint: position.pool.token_mint_0,
+            vault_1_mint: position.pool.token_mint_1,
+            token_program_0: position.pool.token_program_0,
+            token_program_1: position.pool.token_program_1,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::CreatePositionVaults {},
+        Vec::new()
+    )
+}
+
+/// Opens the Raydium position of a pool position, funded from the `token_account_0` and
+/// `token_account_1` accounts of the manager. `position.position_nft_mint` must sign.
+pub fn open_position(
+    manager: &Pubkey,
+    position: &PositionAccounts,
+    token_account_0: &Pubkey,
+    token_account_1: &Pubkey,
+    amount_0_max: u64,
+    amount_1_max: u64
+) -> Instruction {
+    let addresses = position.addresses();
+    let raydium = position.raydium();
+    let (layout, _, remaining_accounts) = remaining_accounts(&[], Some((&position.pool, &[])));
+    instruction(
+        pool_party::accounts::OpenPositionCtx {
+            manager: *manager,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            manager_account: InvestorAddresses::new(
+                &addresses.pool_position_config,
+                manager
+            ).investor_account,
+            protocol_config: pda::protocol_config().0,
+            clmm_program: raydium_clmm_cpi::ID,
+            position_nft_mint: position.position_nft_mint,
+            position_nft_account: position.position_nft_account(),
+            metadata_account: metadata_account(&position.position_nft_mint),
+            pool_state: position.pool.pool_state,
+            protocol_position: raydium.protocol_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            personal_position: raydium.personal_position,
+            token_account_0: *token_account_0,
+            token_account_1: *token_account_1,
+            token_vault_0: position.pool.token_vault_0,
+            token_vault_1: position.pool.token_vault_1,
+            vault_0_mint: position.pool.token_mint_0,
+            vault_1_mint: position.pool.token_mint_1,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            metadata_program: mpl_token_metadata::ID,
+            token_program_2022: token_2022::ID,
+        },
+        pool_party::instruction::OpenPosition {
+            amount_0_max,
+            amount_1_max,
+            tick_lower_index: position.range.tick_lower_index,
+            tick_upper_index: position.range.tick_upper_index,
+            tick_array_lower_start_index: raydium.tick_array_lower_start_index,
+            tick_array_upper_start_index: raydium.tick_array_upper_start_index,
+            remaining_accounts_layout: layout,
+        },
+        remaining_accounts
+    )
+}
+
+/// Creates the investor account and deposit accounts of `investor`. `allowlist_proof` is its
+/// proof in the `AllowlistTree` of a permissioned position, empty otherwise.
+pub fn create_investor_position(
+    investor: &Pubkey,
+    position: &PositionAccounts,
+    allowlist_proof: &[[u8; 32]]
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, investor);
+    instruction(
+        pool_party::accounts::CreateInvestorPositionCtx {
+            investor: *investor,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            investor_account: investor_addresses.investor_account,
+            investor_deposit_token_0_account: investor_addresses.deposit_token_0_account,
+            investor_deposit_token_1_account: investor_addresses.deposit_token_1_account,
+            pool_vault_token_0_mint: position.pool.token_mint_0,
+            pool_vault_token_1_mint: position.pool.token_mint_1,
+            token_program_0: position.pool.token_program_0,
+            token_program_1: position.pool.token_program_1,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::CreateInvestorPosition {
+            allowlist_proof: allowlist_proof.to_vec(),
+        },
+        Vec::new()
+    )
+}
+
+/// Deposits `amount` of `stable_mint` from the investor wallet into their stable deposit account
+pub fn deposit(
+    investor: &Pubkey,
+    position: &PositionAccounts,
+    stable_mint: &Pubkey,
+    amount: u64
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, investor);
+    instruction(
+        pool_party::accounts::DepositCtx {
+            investor: *investor,
+            pool_position_config: addresses.pool_position_config,
+            investor_account: investor_addresses.investor_account,
+            investor_deposit_stable_token_account: investor_addresses.deposit_stable_token_account,
+            pool_vault_deposit_stable_mint: *stable_mint,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::Deposit { amount },
+        Vec::new()
+    )
+}
+
+/// Swaps the stable deposit of the investor to the position tokens through `leg_0` and `leg_1`.
+/// When the stable mint is one of the position tokens, only `leg_0`, a swap inside the position
+/// pool, is used and `leg_1` may repeat it.
+pub fn swap_to_ratio_deposit(
+    investor: &Pubkey,
+    position: &PositionAccounts,
+    stable_mint: &Pubkey,
+    leg_0: &SwapLegAccounts,
+    leg_1: &SwapLegAccounts
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, investor);
+    let (pool_vault_0_input, pool_vault_token_0_account) = leg_0.first_vaults(stable_mint);
+    let (pool_vault_1_input, pool_vault_token_1_account) = leg_1.first_vaults(stable_mint);
+    let (layout, mut routes, remaining_accounts) = remaining_accounts(&[leg_0, leg_1], None);
+    let route_1 = routes.pop().unwrap();
+    let route_0 = routes.pop().unwrap();
+    instruction(
+        pool_party::accounts::SwapToRatioDepositCtx {
+            protocol_config: pda::protocol_config().0,
+            clmm_program: raydium_clmm_cpi::ID,
+            pool_position_config: addresses.pool_position_config,
+            investor: *investor,
+            pool_state: position.pool.pool_state,
+            personal_position: position.raydium().personal_position,
+            amm_config_0: leg_0.pool.amm_config,
+            pool_state_0: leg_0.pool.pool_state,
+            amm_config_1: leg_1.pool.amm_config,
+            pool_state_1: leg_1.pool.pool_state,
+            investor_account: investor_addresses.investor_account,
+            investor_deposit_stable_token_account: investor_addresses.deposit_stable_token_account,
+            investor_deposit_token_0_account: investor_addresses.deposit_token_0_account,
+            investor_deposit_token_1_account: investor_addresses.deposit_token_1_account,
+            pool_vault_0_input,
+            pool_vault_1_input,
+            pool_vault_token_0_account,
+            pool_vault_token_1_account,
+            pool_vault_deposit_stable_mint: *stable_mint,
+            pool_vault_token_0_mint: position.pool.token_mint_0,
+            pool_vault_token_1_mint: position.pool.token_mint_1,
+            observation_state_0: leg_0.pool.observation_state,
+            observation_state_1: leg_1.pool.observation_state,
+            token_program: token::ID,
+            token_program_2022: token_2022::ID,
+            associated_token_program: associated_token::ID,
+            memo_program: spl_memo::ID,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::SwapToRatioDeposit {
+            route_0,
+            route_1,
+            remaining_accounts_layout: layout,
+        },
+        remaining_accounts
+    )
+}
+
+/// Slippage bounds of the liquidity added by `increase_liquidity` and `zap_in`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LiquidityBounds {
+    pub min_liquidity_out: u128,
+    pub expected_sqrt_price_x64: u128,
+    /// Maximum move of the pool price away from the square of `expected_sqrt_price_x64`, in basis
+    /// points
+    pub max_price_deviation: u16,
+}
+
+pub fn increase_liquidity(
+    investor: &Pubkey,
+    position: &PositionAccounts,
+    bounds: LiquidityBounds
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, investor);
+    let raydium = position.raydium();
+    let (layout, _, mut remaining_accounts) = remaining_accounts(&[], Some((&position.pool, &[])));
+    remaining_accounts.extend(position.ladder_metas(true));
+    instruction(
+        pool_party::accounts::IncreaseLiquidityCtx {
+            investor: *investor,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            investor_account: investor_addresses.investor_account,
+            investor_deposit_token_a_account: investor_addresses.deposit_token_0_account,
+            investor_deposit_token_b_account: investor_addresses.deposit_token_1_account,
+            pool_position_vault_0_token_account: addresses.pool_position_vault_0,
+            pool_position_vault_1_token_account: addresses.pool_position_vault_1,
+            fees_vault_0_token_account: addresses.fees_vault_0,
+            fees_vault_1_token_account: addresses.fees_vault_1,
+            protocol_config: pda::protocol_config().0,
+            clmm_program: raydium_clmm_cpi::ID,
+            position_nft_account: position.position_nft_account(),
+            pool_state: position.pool.pool_state,
+            protocol_position: raydium.protocol_position,
+            personal_position: raydium.personal_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            token_vault_0: position.pool.token_vault_0,
+            token_vault_1: position.pool.token_vault_1,
+            pool_vault_token_a_mint: position.pool.token_mint_0,
+            pool_vault_token_b_mint: position.pool.token_mint_1,
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            token_program_2022: token_2022::ID,
+            token_program_0: position.pool.token_program_0,
+            token_program_1: position.pool.token_program_1,
+            memo_program: spl_memo::ID,
+        },
+        pool_party::instruction::IncreaseLiquidity {
+            min_liquidity_out: bounds.min_liquidity_out,
+            expected_sqrt_price_x64: bounds.expected_sqrt_price_x64,
+            max_price_deviation: bounds.max_price_deviation,
+            remaining_accounts_layout: layout,
+        },
+        remaining_accounts
+    )
+}
+
+/// Swaps `amount` of the stable deposit through `leg_0` and `leg_1` and adds the result as
+/// liquidity in a single instruction
+pub fn zap_in(
+    investor: &Pubkey,
+    position: &PositionAccounts,
+    stable_mint: &Pubkey,
+    amount: u64,
+    leg_0: &SwapLegAccounts,
+    leg_1: &SwapLegAccounts,
+    bounds: LiquidityBounds
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, investor);
+    let raydium = position.raydium();
+    let (pool_vault_0_input, pool_vault_token_0_account) = leg_0.first_vaults(stable_mint);
+    let (pool_vault_1_input, pool_vault_token_1_account) = leg_1.first_vaults(stable_mint);
+    let (layout, mut routes, remaining_accounts) = remaining_accounts(
+        &[leg_0, leg_1],
+        Some((&position.pool, &[]))
+    );
+    let route_1 = routes.pop().unwrap();
+    let route_0 = routes.pop().unwrap();
+    instruction(
+        pool_party::accounts::ZapInCtx {
+            investor: *investor,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            investor_account: investor_addresses.investor_account,
+            investor_deposit_stable_token_account: investor_addresses.deposit_stable_token_account,
+            investor_deposit_token_0_account: investor_addresses.deposit_token_0_account,
+            investor_deposit_token_1_account: investor_addresses.deposit_token_1_account,
+            pool_position_vault_0_token_account: addresses.pool_position_vault_0,
+            pool_position_vault_1_token_account: addresses.pool_position_vault_1,
+            fees_vault_0_token_account: addresses.fees_vault_0,
+            fees_vault_1_token_account: addresses.fees_vault_1,
+            protocol_config: pda::protocol_config().0,
+            clmm_program: raydium_clmm_cpi::ID,
+            position_nft_account: position.position_nft_account(),
+            pool_state: position.pool.pool_state,
+            protocol_position: raydium.protocol_position,
+            personal_position: raydium.personal_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            token_vault_0: position.pool.token_vault_0,
+            token_vault_1: position.pool.token_vault_1,
+            amm_config_0: leg_0.pool.amm_config,
+            pool_state_0: leg_0.pool.pool_state,
+            observation_state_0: leg_0.pool.observation_state,
+            amm_config_1: leg_1.pool.amm_config,
+            pool_state_1: leg_1.pool.pool_state,
+            observation_state_1: leg_1.pool.observation_state,
+            pool_vault_0_input,
+            pool_vault_1_input,
+            pool_vault_token_0_account,
+            pool_vault_token_1_account,
+            pool_vault_deposit_stable_mint: *stable_mint,
+            pool_vault_token_0_mint: position.pool.token_mint_0,
+            pool_vault_token_1_mint: position.pool.token_mint_1,
+            token_program: token::ID,
+            token_program_2022: token_2022::ID,
+            token_program_0: position.pool.token_program_0,
+            token_program_1: position.pool.token_program_1,
+            associated_token_program: associated_token::ID,
+            memo_program: spl_memo::ID,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::ZapIn {
+            amount,
+            route_0,
+            route_1,
+            min_liquidity_out: bounds.min_liquidity_out,
+            expected_sqrt_price_x64: bounds.expected_sqrt_price_x64,
+            max_price_deviation: bounds.max_price_deviation,
+            remaining_accounts_layout: layout,
+        },
+        remaining_accounts
+    )
+}
+
+/// Collects the position fees and pays the share of the investor to their associated token
+/// accounts
+pub fn collect_fees(investor: &Pubkey, position: &PositionAccounts) -> Instruction {
+    collect_fees_to(investor, position, investor)
+}
+
+/// `collect_fees` paying to the associated token accounts of `fees_recipient`, which must be the
+/// fee recipient of the position when the manager collects
+pub fn collect_fees_to(
+    investor: &Pubkey,
+    position: &PositionAccounts,
+    fees_recipient: &Pubkey
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, investor);
+    let raydium = position.raydium();
+    let (fees_0_account, fees_1_account) = position.pool.associated_token_accounts(fees_recipient);
+    let (layout, _, mut remaining_accounts) = remaining_accounts(&[], Some((&position.pool, &[])));
+    remaining_accounts.extend(position.ladder_metas(true));
+    instruction(
+        pool_party::accounts::CollectFeesCtx {
+            investor: *investor,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            investor_account: investor_addresses.investor_account,
+            fees_vault_0_token_account: addresses.fees_vault_0,
+            fees_vault_1_token_account: addresses.fees_vault_1,
+            fees_recipient: *fees_recipient,
+            investor_deposit_fees_0_account: fees_0_account,
+            investor_deposit_fees_1_account: fees_1_account,
+            protocol_config: pda::protocol_config().0,
+            clmm_program: raydium_clmm_cpi::ID,
+            position_nft_account: position.position_nft_account(),
+            pool_state: position.pool.pool_state,
+            protocol_position: raydium.protocol_position,
+            personal_position: raydium.personal_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            token_vault_0: position.pool.token_vault_0,
+            token_vault_1: position.pool.token_vault_1,
+            pool_vault_token_a_mint: position.pool.token_mint_0,
+            pool_vault_token_b_mint: position.pool.token_mint_1,
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            token_program_2022: token_2022::ID,
+            token_program_0: position.pool.token_program_0,
+            token_program_1: position.pool.token_program_1,
+            memo_program: spl_memo::ID,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::CollectFees { remaining_accounts_layout: layout },
+        remaining_accounts
+    )
+}
+
+/// Removes `liquidity` of the investor and pays its tokens to their associated token accounts,
+/// less the early exit penalty during the lockup
+pub fn decrease_liquidity(
+    investor: &Pubkey,
+    position: &PositionAccounts,
+    liquidity: u128,
+    amount_0_min: u64,
+    amount_1_min: u64
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, investor);
+    let raydium = position.raydium();
+    let (token_0_account, token_1_account) = position.pool.associated_token_accounts(investor);
+    let (layout, _, mut remaining_accounts) = remaining_accounts(&[], Some((&position.pool, &[])));
+    remaining_accounts.extend(position.ladder_metas(true));
+    instruction(
+        pool_party::accounts::DecreaseLiquidityCtx {
+            investor: *investor,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            investor_account: investor_addresses.investor_account,
+            investor_token_0_account: token_0_account,
+            investor_token_1_account: token_1_account,
+            fees_vault_0_token_account: addresses.fees_vault_0,
+            fees_vault_1_token_account: addresses.fees_vault_1,
+            protocol_config: pda::protocol_config().0,
+            clmm_program: raydium_clmm_cpi::ID,
+            position_nft_account: position.position_nft_account(),
+            pool_state: position.pool.pool_state,
+            protocol_position: raydium.protocol_position,
+            personal_position: raydium.personal_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            token_vault_0: position.pool.token_vault_0,
+            token_vault_1: position.pool.token_vault_1,
+            pool_vault_token_a_mint: position.pool.token_mint_0,
+            pool_vault_token_b_mint: position.pool.token_mint_1,
+            associated_token_program: associated_token::ID,
+            token_program: token::ID,
+            token_program_2022: token_2022::ID,
+            token_program_0: position.pool.token_program_0,
+            token_program_1: position.pool.token_program_1,
+            memo_program: spl_memo::ID,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::DecreaseLiquidity {
+            liquidity,
+            amount_0_min,
+            amount_1_min,
+            remaining_accounts_layout: layout,
+        },
+        remaining_accounts
+    )
+}
+
+/// Values the position of `authority` in `stable_mint`. Nothing is written: simulate the
+/// transaction and decode its return data as `pool_party::instructions::InvestorValue`.
+/// `pool_state_0` and `pool_state_1` pair token_0 and token_1 with the stable mint; for a token
+/// that is the stable mint, pass the position pool.
+pub fn get_investor_value(
+    authority: &Pubkey,
+    position: &PositionAccounts,
+    stable_mint: &Pubkey,
+    pool_state_0: &Pubkey,
+    pool_state_1: &Pubkey
+) -> Instruction {
+    let addresses = position.addresses();
+    let investor_addresses = InvestorAddresses::new(&addresses.pool_position_config, authority);
+    let raydium = position.raydium();
+    instruction(
+        pool_party::accounts::GetInvestorValueCtx {
+            investor: *authority,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            investor_account: investor_addresses.investor_account,
+            protocol_config: pda::protocol_config().0,
+            pool_state: position.pool.pool_state,
+            personal_position: raydium.personal_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            pool_vault_deposit_stable_mint: *stable_mint,
+            pool_state_0: *pool_state_0,
+            pool_state_1: *pool_state_1,
+        },
+        pool_party::instruction::GetInvestorValue {},
+        position.ladder_metas(false)
+    )
+}
+
+/// Records the NAV of the position in `stable_mint`, signed by the strategist. `pool_0` and
+/// `pool_1` pair token_0 and token_1 with the stable mint, as for `get_investor_value`; their
+/// observation accounts are read for TWAP prices.
+pub fn snapshot_nav(
+    strategist: &Pubkey,
+    position: &PositionAccounts,
+    stable_mint: &Pubkey,
+    pool_0: &ClmmPool,
+    pool_1: &ClmmPool,
+    price_source: PriceSource
+) -> Instruction {
+    let addresses = position.addresses();
+    let raydium = position.raydium();
+    instruction(
+        pool_party::accounts::SnapshotNavCtx {
+            strategist: *strategist,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            protocol_config: pda::protocol_config().0,
+            pool_state: position.pool.pool_state,
+            personal_position: raydium.personal_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            pool_position_vault_0_token_account: addresses.pool_position_vault_0,
+            pool_position_vault_1_token_account: addresses.pool_position_vault_1,
+            fees_vault_0_token_account: addresses.fees_vault_0,
+            fees_vault_1_token_account: addresses.fees_vault_1,
+            pool_vault_deposit_stable_mint: *stable_mint,
+            pool_state_0: pool_0.pool_state,
+            observation_state_0: pool_0.observation_state,
+            pool_state_1: pool_1.pool_state,
+            observation_state_1: pool_1.observation_state,
+        },
+        pool_party::instruction::SnapshotNav { price_source },
+        position.ladder_metas(false)
+    )
+}
+
+/// Accepts the manager role proposed to `new_manager`, `manager` being the current manager
+pub fn accept_manager(
+    new_manager: &Pubkey,
+    position: &PositionAccounts,
+    manager: &Pubkey
+) -> Instruction {
+    let addresses = position.addresses();
+    let config = addresses.pool_position_config;
+    instruction(
+        pool_party::accounts::AcceptManagerCtx {
+            new_manager: *new_manager,
+            pool_position_config: config,
+            pool_position: addresses.pool_position,
+            previous_manager_account: InvestorAddresses::new(&config, manager).investor_account,
+            new_manager_account: InvestorAddresses::new(&config, new_manager).investor_account,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::AcceptManager {},
+        Vec::new()
+    )
+}
+
+/// Pauses or unpauses the deposits of the position, signed by its guardian
+pub fn set_paused(guardian: &Pubkey, position: &PositionAccounts, paused: bool) -> Instruction {
+    instruction(
+        pool_party::accounts::SetPausedCtx {
+            guardian: *guardian,
+            pool_position_config: position.addresses().pool_position_config,
+        },
+        pool_party::instruction::SetPaused { paused },
+        Vec::new()
+    )
+}
+
+/// Queues `change` of the position config as `change_id`, the `next_config_change_id` of the
+/// config, signed by the manager or by the guardian for roles
+pub fn queue_config_change(
+    proposer: &Pubkey,
+    position: &PositionAccounts,
+    change_id: u64,
+    change: ConfigChange
+) -> Instruction {
+    let config = position.addresses().pool_position_config;
+    instruction(
+        pool_party::accounts::QueueConfigChangeCtx {
+            proposer: *proposer,
+            pool_position_config: config,
+            pending_config_change: pda::pending_config_change(&config, change_id).0,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::QueueConfigChange { change },
+        Vec::new()
+    )
+}
+
+/// Applies the queued change `change_id` once its timelock has elapsed, refunding its rent to
+/// `proposer`
+pub fn execute_config_change(
+    executor: &Pubkey,
+    position: &PositionAccounts,
+    change_id: u64,
+    proposer: &Pubkey
+) -> Instruction {
+    let config = position.addresses().pool_position_config;
+    instruction(
+        pool_party::accounts::ExecuteConfigChangeCtx {
+            executor: *executor,
+            pool_position_config: config,
+            pending_config_change: pda::pending_config_change(&config, change_id).0,
+            proposer: *proposer,
+        },
+        pool_party::instruction::ExecuteConfigChange {},
+        Vec::new()
+    )
+}
+
+/// Drops the queued change `change_id`, signed by its proposer or the guardian
+pub fn cancel_config_change(
+    canceller: &Pubkey,
+    position: &PositionAccounts,
+    change_id: u64,
+    proposer: &Pubkey
+) -> Instruction {
+    let config = position.addresses().pool_position_config;
+    instruction(
+        pool_party::accounts::CancelConfigChangeCtx {
+            canceller: *canceller,
+            pool_position_config: config,
+            pending_config_change: pda::pending_config_change(&config, change_id).0,
+            proposer: *proposer,
+        },
+        pool_party::instruction::CancelConfigChange {},
+        Vec::new()
+    )
+}
+
+/// Opens `range` as the next ladder range of the position, holding `weight_bps` of the core
+/// liquidity and funded from the `token_account_0` and `token_account_1` accounts of the manager.
+/// `position.ladder` holds the ranges opened so far and `range.position_nft_mint` must sign.
+pub fn open_ladder_range(
+    manager: &Pubkey,
+    position: &PositionAccounts,
+    range: &LadderRangeKeys,
+    weight_bps: u32,
+    token_account_0: &Pubkey,
+    token_account_1: &Pubkey,
+    amount_0_max: u64,
+    amount_1_max: u64
+) -> Instruction {
+    let addresses = position.addresses();
+    let raydium = RaydiumPosition::new(&position.pool, range.range, &range.position_nft_mint);
+    let (layout, _, remaining_accounts) = remaining_accounts(&[], Some((&position.pool, &[])));
+    instruction(
+        pool_party::accounts::OpenLadderRangeCtx {
+            manager: *manager,
+            pool_position_config: addresses.pool_position_config,
+            pool_position: addresses.pool_position,
+            manager_account: InvestorAddresses::new(
+                &addresses.pool_position_config,
+                manager
+            ).investor_account,
+            ladder_range: pda::ladder_range(
+                &addresses.pool_position,
+                position.ladder.len() as u8
+            ).0,
+            core_personal_position: position.raydium().personal_position,
+            protocol_config: pda::protocol_config().0,
+            clmm_program: raydium_clmm_cpi::ID,
+            position_nft_mint: range.position_nft_mint,
+            position_nft_account: get_associated_token_address(
+                &addresses.pool_position,
+                &range.position_nft_mint
+            ),
+            metadata_account: metadata_account(&range.position_nft_mint),
+            pool_state: position.pool.pool_state,
+            protocol_position: raydium.protocol_position,
+            tick_array_lower: raydium.tick_array_lower,
+            tick_array_upper: raydium.tick_array_upper,
+            personal_position: raydium.personal_position,
+            token_account_0: *token_account_0,
+            token_account_1: *token_account_1,
+            token_vault_0: position.pool.token_vault_0,
+            token_vault_1: position.pool.token_vault_1,
+            vault_0_mint: position.pool.token_mint_0,
+            vault_1_mint: position.pool.token_mint_1,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_program: token::ID,
+            associated_token_program: associated_token::ID,
+            metadata_program: mpl_token_metadata::ID,
+            token_program_2022: token_2022::ID,
+        },
+        pool_party::instruction::OpenLadderRange {
+            tick_lower_index: range.range.tick_lower_index,
+            tick_upper_index: range.range.tick_upper_index,
+            tick_array_lower_start_index: raydium.tick_array_lower_start_index,
+            tick_array_upper_start_index: raydium.tick_array_upper_start_index,
+            weight_bps,
+            amount_0_max,
+            amount_1_max,
+            remaining_accounts_layout: layout,
+        },
+        remaining_accounts
+    )
+}
+
+/// Creates the protocol config allowing `clmm_programs`, signed by the upgrade authority of the
+/// program
+pub fn initialize_protocol_config(admin: &Pubkey, clmm_programs: Vec<Pubkey>) -> Instruction {
+    instruction(
+        pool_party::accounts::InitializeProtocolConfigCtx {
+            admin: *admin,
+            protocol_config: pda::protocol_config().0,
+            program: pool_party::ID,
+            program_data: bpf_loader_upgradeable::get_program_data_address(&pool_party::ID),
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::InitializeProtocolConfig { clmm_programs },
+        Vec::new()
+    )
+}
+
+/// Replaces the CLMM programs allowlist of the protocol config, signed by its admin
+pub fn set_clmm_programs(admin: &Pubkey, clmm_programs: Vec<Pubkey>) -> Instruction {
+    instruction(
+        pool_party::accounts::SetClmmProgramsCtx {
+            admin: *admin,
+            protocol_config: pda::protocol_config().0,
+        },
+        pool_party::instruction::SetClmmPrograms { clmm_programs },
+        Vec::new()
+    )
+}
+
+/// Upgrades the position config to the current layout, `payer` funding the grown account. The
+/// migrations take the addresses of the position, its accounts can't be read before.
+pub fn migrate_pool_position_config(
+    payer: &Pubkey,
+    addresses: &PoolPositionAddresses
+) -> Instruction {
+    instruction(
+        pool_party::accounts::MigratePoolPositionConfigCtx {
+            payer: *payer,
+            pool_position_config: addresses.pool_position_config,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::MigratePoolPositionConfig {},
+        Vec::new()
+    )
+}
+
+/// Upgrades the pool position to the current layout, `payer` funding the grown account
+pub fn migrate_pool_position(payer: &Pubkey, addresses: &PoolPositionAddresses) -> Instruction {
+    instruction(
+        pool_party::accounts::MigratePoolPositionCtx {
+            payer: *payer,
+            pool_position: addresses.pool_position,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::MigratePoolPosition {},
+        Vec::new()
+    )
+}
+
+/// Upgrades the investor account of `authority` to the current layout, `payer` funding the grown
+/// account
+pub fn migrate_investor(
+    payer: &Pubkey,
+    addresses: &PoolPositionAddresses,
+    authority: &Pubkey
+) -> Instruction {
+    let config = addresses.pool_position_config;
+    instruction(
+        pool_party::accounts::MigrateInvestorCtx {
+            payer: *payer,
+            investor_account: InvestorAddresses::new(&config, authority).investor_account,
+            system_program: system_program::ID,
+        },
+        pool_party::instruction::MigrateInvestor {},
+        Vec::new()
+    )
+}
+
+#[cfg(test)]
+mod instructions_test {
+    use super::*;
+    use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+    fn pool() -> ClmmPool {
+        ClmmPool {
+            pool_state: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            observation_state: Pubkey::new_unique(),
+            token_mint_0: Pubkey::new_unique(),
+            token_mint_1: Pubkey::new_unique(),
+            token_vault_0: Pubkey::new_unique(),
+            token_vault_1: Pubkey::new_unique(),
+            tick_spacing: 10,
+            token_program_0: token::ID,
+            token_program_1: token::ID,
+        }
+    }
+
+    #[test]
+    fn remaining_accounts_follow_the_layout() {
+        let position_pool = pool();
+        let leg_pool = pool();
+        let hop_pool = ClmmPool { token_mint_0: leg_pool.token_mint_1, ..pool() };
+        let hop = SwapHopAccounts {
+            input_token_account: Pubkey::new_unique(),
+            input_mint: leg_pool.token_mint_1,
+            pool: hop_pool,
+            tick_arrays: vec![Pubkey::new_unique()],
+        };
+        let leg_0 = SwapLegAccounts {
+            pool: leg_pool,
+            tick_arrays: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            hops: vec![hop.clone()],
+            minimum_amount_out: 7,
+        };
+        let leg_1 = SwapLegAccounts {
+            pool: leg_pool,
+            tick_arrays: vec![Pubkey::new_unique()],
+            hops: Vec::new(),
+            minimum_amount_out: 9,
+        };
+        let liquidity_tick_arrays = [Pubkey::new_unique()];
+
+        let (layout, routes, metas) = remaining_accounts(
+            &[&leg_0, &leg_1],
+            Some((&position_pool, &liquidity_tick_arrays))
+        );
+
+        // The shared leg pool contributes a single bitmap extension
+        assert_eq!(layout, RemainingAccountsLayout {
+            bitmap_extensions: 2,
+            leg_0_tick_arrays: 2,
+            leg_1_tick_arrays: 1,
+            liquidity_tick_arrays: 1,
+        });
+        assert_eq!(routes, vec![
+            SwapRouteArgs {
+                hop_account_counts: vec![(SWAP_HOP_ACCOUNTS_LEN + 2) as u8],
+                minimum_amount_out: 7,
+            },
+            SwapRouteArgs { hop_account_counts: Vec::new(), minimum_amount_out: 9 }
+        ]);
+
+        let keys: Vec<Pubkey> = metas
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(keys[..6], [
+            leg_pool.bitmap_extension(),
+            position_pool.bitmap_extension(),
+            leg_0.tick_arrays[0],
+            leg_0.tick_arrays[1],
+            leg_1.tick_arrays[0],
+            liquidity_tick_arrays[0],
+        ]);
+        assert_eq!(keys[6..], [
+            hop.input_token_account,
+            hop.input_mint,
+            hop_pool.amm_config,
+            hop_pool.pool_state,
+            hop_pool.token_vault_0,
+            hop_pool.token_vault_1,
+            hop_pool.token_mint_1,
+            hop_pool.observation_state,
+            hop_pool.bitmap_extension(),
+            hop.tick_arrays[0],
+        ]);
+    }
+
+    #[test]
+    fn builders_derive_the_position_accounts() {
+        let manager = Pubkey::new_unique();
+        let position = PositionAccounts {
+            name: "sol-usdc".to_string(),
+            pool: pool(),
+            range: PositionRange { tick_lower_index: -1200, tick_upper_index: 1200 },
+            position_nft_mint: Pubkey::new_unique(),
+            ladder: Vec::new(),
+        };
+        let addresses = position.addresses();
+
+        let ix = create_position(&manager, &position);
+        assert_eq!(ix.program_id, pool_party::ID);
+        assert_eq!(ix.accounts[0], AccountMeta::new(manager, true));
+        assert_eq!(ix.accounts[1].pubkey, addresses.pool_position_config);
+        assert_eq!(ix.accounts[2].pubkey, addresses.pool_position);
+
+        let ix = collect_fees(&manager, &position);
+        let last = ix.accounts.last().unwrap();
+        assert_eq!(last.pubkey, position.pool.bitmap_extension());
+        assert!(!last.is_writable);
+        assert!(ix.accounts.iter().any(|meta| meta.pubkey == pda::protocol_config().0));
+    }
+
+    #[test]
+    fn ladder_ranges_follow_the_liquidity_accounts() {
+        let investor = Pubkey::new_unique();
+        let range = LadderRangeKeys {
+            range: PositionRange { tick_lower_index: -6000, tick_upper_index: 6000 },
+            position_nft_mint: Pubkey::new_unique(),
+        };
+        let position = PositionAccounts {
+            name: "sol-usdc-ladder".to_string(),
+            pool: pool(),
+            range: PositionRange { tick_lower_index: -1200, tick_upper_index: 1200 },
+            position_nft_mint: Pubkey::new_unique(),
+            ladder: vec![range],
+        };
+        let pool_position = position.addresses().pool_position;
+        let raydium = RaydiumPosition::new(&position.pool, range.range, &range.position_nft_mint);
+
+        let ix = collect_fees(&investor, &position);
+        let ladder = &ix.accounts[ix.accounts.len() - LADDER_RANGE_ACCOUNTS_LEN..];
+        assert_eq!(ladder[0].pubkey, pda::ladder_range(&pool_position, 0).0);
+        assert_eq!(
+            ladder[1].pubkey,
+            get_associated_token_address(&pool_position, &range.position_nft_mint)
+        );
+        assert_eq!(ladder[3], AccountMeta::new(raydium.personal_position, false));
+        assert_eq!(ladder[5].pubkey, raydium.tick_array_upper);
+        // the bitmap extension of the pool comes right before
+        let bitmap_extension = &ix.accounts[ix.accounts.len() - LADDER_RANGE_ACCOUNTS_LEN - 1];
+        assert_eq!(bitmap_extension.pubkey, position.pool.bitmap_extension());
+
+        // valuations only read the ranges
+        let ix = get_investor_value(
+            &investor,
+            &position,
+            &position.pool.token_mint_1,
+            &position.pool.pool_state,
+            &position.pool.pool_state
+        );
+        assert!(ix.accounts[ix.accounts.len() - LADDER_RANGE_ACCOUNTS_LEN..]
+            .iter()
+            .all(|meta| !meta.is_writable));
+
+        // the next range is opened at the index following the ladder
+        let next = LadderRangeKeys { position_nft_mint: Pubkey::new_unique(), ..range };
+        let ix = open_ladder_range(
+            &investor,
+            &position,
+            &next,
+            5_000,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1,
+            1
+        );
+        assert_eq!(ix.accounts[4].pubkey, pda::ladder_range(&pool_position, 1).0);
+    }
+
+    #[test]
+    fn token_2022_mints_use_their_token_program() {
+        let investor = Pubkey::new_unique();
+        let position = PositionAccounts {
+            name: "sol-pyusd".to_string(),
+            pool: pool().with_token_programs(token::ID, token_2022::ID),
+            range: PositionRange { tick_lower_index: -1200, tick_upper_index: 1200 },
+            position_nft_mint: Pubkey::new_unique(),
+            ladder: Vec::new(),
+        };
+        let keys = |ix: &Instruction| -> Vec<Pubkey> {
+            ix.accounts
+                .iter()
+                .map(|meta| meta.pubkey)
+                .collect()
+        };
+
+        let ix = create_position_vaults(&investor, &position);
+        assert_eq!(keys(&ix)[8..10], [token::ID, token_2022::ID]);
+
+        // the associated token accounts of a Token-2022 mint are derived with its program
+        let ix = decrease_liquidity(&investor, &position, 1, 0, 0);
+        assert_eq!(
+            ix.accounts[5].pubkey,
+            get_associated_token_address_with_program_id(
+                &investor,
+                &position.pool.token_mint_1,
+                &token_2022::ID
+            )
+        );
+        assert_ne!(
+            ix.accounts[5].pubkey,
+            get_associated_token_address(&investor, &position.pool.token_mint_1)
+        );
+        assert!(keys(&ix).contains(&token_2022::ID));
+    }
+}
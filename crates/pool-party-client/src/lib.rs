@@ -0,0 +1,14 @@
+//! Client side helpers for the pool-party program: PDA derivation, Raydium CLMM account
+//! derivation, builders for every instruction of the program and investor allowlists.
+
+pub mod allowlist;
+pub mod instructions;
+pub mod pda;
+pub mod raydium;
+
+pub use allowlist::AllowlistTree;
+pub use instructions::*;
+pub use raydium::{ ClmmPool, PositionRange, RaydiumPosition };
+
+pub use pool_party::ID as POOL_PARTY_PROGRAM_ID;
+pub use raydium_clmm_cpi::ID as RAYDIUM_CLMM_PROGRAM_ID;
@@ -0,0 +1,139 @@
+//! Program derived addresses of the pool-party program, built on the seeds of `state`.
+//!
+//! Every helper returns the address with its bump, as `Pubkey::find_program_address` does.
+
+use anchor_lang::prelude::Pubkey;
+use pool_party::state::{
+    Investor,
+    LadderRange,
+    PendingConfigChange,
+    PoolPosition,
+    PoolPositionConfig,
+    ProtocolConfig,
+};
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &pool_party::ID)
+}
+
+pub fn pool_position_config(name: &str) -> (Pubkey, u8) {
+    find(&[PoolPositionConfig::POOL_POSITION_CONFIG_SEED.as_bytes(), name.as_bytes()])
+}
+
+pub fn pool_position(pool_position_config: &Pubkey) -> (Pubkey, u8) {
+    find(&[PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.as_ref()])
+}
+
+pub fn pool_position_vault_0(pool_position: &Pubkey) -> (Pubkey, u8) {
+    find(&[PoolPosition::POOL_POSITION_VAULT_0_SEED.as_bytes(), pool_position.as_ref()])
+}
+
+pub fn pool_position_vault_1(pool_position: &Pubkey) -> (Pubkey, u8) {
+    find(&[PoolPosition::POOL_POSITION_VAULT_1_SEED.as_bytes(), pool_position.as_ref()])
+}
+
+pub fn pool_position_fees_vault_0(pool_position: &Pubkey) -> (Pubkey, u8) {
+    find(&[PoolPosition::POOL_POSITION_FEES_VAULT_0_SEED.as_bytes(), pool_position.as_ref()])
+}
+
+pub fn pool_position_fees_vault_1(pool_position: &Pubkey) -> (Pubkey, u8) {
+    find(&[PoolPosition::POOL_POSITION_FEES_VAULT_1_SEED.as_bytes(), pool_position.as_ref()])
+}
+
+/// The `Investor` account of `authority`; the manager account of a position uses the manager key
+pub fn investor(pool_position_config: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    find(
+        &[
+            Investor::INVESTOR_SEED.as_bytes(),
+            pool_position_config.as_ref(),
+            authority.as_ref(),
+        ]
+    )
+}
+
+pub fn investor_deposit_stable_token_account(investor_account: &Pubkey) -> (Pubkey, u8) {
+    find(
+        &[
+            Investor::INVESTOR_DEPOSIT_STABLE_TOKEN_ACCOUNT_SEED.as_bytes(),
+            investor_account.as_ref(),
+        ]
+    )
+}
+
+pub fn investor_deposit_token_0_account(investor_account: &Pubkey) -> (Pubkey, u8) {
+    find(&[Investor::INVESTOR_DEPOSIT_TOKEN_0_ACCOUNT_SEED.as_bytes(), investor_account.as_ref()])
+}
+
+pub fn investor_deposit_token_1_account(investor_account: &Pubkey) -> (Pubkey, u8) {
+    find(&[Investor::INVESTOR_DEPOSIT_TOKEN_1_ACCOUNT_SEED.as_bytes(), investor_account.as_ref()])
+}
+
+/// The `PendingConfigChange` queued as `change_id` on the config
+pub fn pending_config_change(pool_position_config: &Pubkey, change_id: u64) -> (Pubkey, u8) {
+    find(
+        &[
+            PendingConfigChange::PENDING_CONFIG_CHANGE_SEED.as_bytes(),
+            pool_position_config.as_ref(),
+            &change_id.to_le_bytes(),
+        ]
+    )
+}
+
+/// The `ProtocolConfig` shared by every pool position
+pub fn protocol_config() -> (Pubkey, u8) {
+    find(&[ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()])
+}
+
+/// The `LadderRange` at `index` in the ladder of the pool position
+pub fn ladder_range(pool_position: &Pubkey, index: u8) -> (Pubkey, u8) {
+    find(&[LadderRange::LADDER_RANGE_SEED.as_bytes(), pool_position.as_ref(), &[index]])
+}
+
+/// All the addresses of a pool position, derived from its name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolPositionAddresses {
+    pub pool_position_config: Pubkey,
+    pub pool_position: Pubkey,
+    pub pool_position_vault_0: Pubkey,
+    pub pool_position_vault_1: Pubkey,
+    pub fees_vault_0: Pubkey,
+    pub fees_vault_1: Pubkey,
+}
+
+impl PoolPositionAddresses {
+    pub fn new(name: &str) -> Self {
+        let (pool_position_config, _) = pool_position_config(name);
+        let (pool_position, _) = pool_position(&pool_position_config);
+        PoolPositionAddresses {
+            pool_position_config,
+            pool_position,
+            pool_position_vault_0: pool_position_vault_0(&pool_position).0,
+            pool_position_vault_1: pool_position_vault_1(&pool_position).0,
+            fees_vault_0: pool_position_fees_vault_0(&pool_position).0,
+            fees_vault_1: pool_position_fees_vault_1(&pool_position).0,
+        }
+    }
+}
+
+/// All the addresses of an investor of a pool position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvestorAddresses {
+    pub investor_account: Pubkey,
+    pub deposit_stable_token_account: Pubkey,
+    pub deposit_token_0_account: Pubkey,
+    pub deposit_token_1_account: Pubkey,
+}
+
+impl InvestorAddresses {
+    pub fn new(pool_position_config: &Pubkey, authority: &Pubkey) -> Self {
+        let (investor_account, _) = investor(pool_position_config, authority);
+        InvestorAddresses {
+            investor_account,
+            deposit_stable_token_account: investor_deposit_stable_token_account(
+                &investor_account
+            ).0,
+            deposit_token_0_account: investor_deposit_token_0_account(&investor_account).0,
+            deposit_token_1_account: investor_deposit_token_1_account(&investor_account).0,
+        }
+    }
+}
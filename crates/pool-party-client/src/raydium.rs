@@ -0,0 +1,298 @@
+//! Raydium CLMM accounts derived from a pool key and a tick range.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token;
+use raydium_clmm_cpi::states::PoolState;
+
+pub const POOL_SEED: &str = "pool";
+pub const POOL_VAULT_SEED: &str = "pool_vault";
+pub const OBSERVATION_SEED: &str = "observation";
+pub const POSITION_SEED: &str = "position";
+pub const TICK_ARRAY_SEED: &str = "tick_array";
+pub const POOL_TICK_ARRAY_BITMAP_SEED: &str = "pool_tick_array_bitmap_extension";
+
+/// Number of ticks of a tick array, in multiples of the pool tick spacing
+pub const TICK_ARRAY_SIZE: i32 = 60;
+
+fn find(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &raydium_clmm_cpi::ID)
+}
+
+/// Start index of the tick array holding `tick_index`
+pub fn tick_array_start_index(tick_index: i32, tick_spacing: u16) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * (tick_spacing as i32);
+    let mut start = tick_index / ticks_in_array;
+    if tick_index < 0 && tick_index % ticks_in_array != 0 {
+        start -= 1;
+    }
+    start * ticks_in_array
+}
+
+/// Pool of `amm_config` trading `token_mint_0` and `token_mint_1`, sorted by key
+pub fn pool_state(amm_config: &Pubkey, token_mint_0: &Pubkey, token_mint_1: &Pubkey) -> Pubkey {
+    find(
+        &[
+            POOL_SEED.as_bytes(),
+            amm_config.as_ref(),
+            token_mint_0.as_ref(),
+            token_mint_1.as_ref(),
+        ]
+    ).0
+}
+
+pub fn pool_vault(pool_state: &Pubkey, token_mint: &Pubkey) -> Pubkey {
+    find(&[POOL_VAULT_SEED.as_bytes(), pool_state.as_ref(), token_mint.as_ref()]).0
+}
+
+pub fn observation_state(pool_state: &Pubkey) -> Pubkey {
+    find(&[OBSERVATION_SEED.as_bytes(), pool_state.as_ref()]).0
+}
+
+pub fn tick_array(pool_state: &Pubkey, start_index: i32) -> Pubkey {
+    find(&[TICK_ARRAY_SEED.as_bytes(), pool_state.as_ref(), &start_index.to_be_bytes()]).0
+}
+
+pub fn tick_array_bitmap_extension(pool_state: &Pubkey) -> Pubkey {
+    find(&[POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_state.as_ref()]).0
+}
+
+pub fn protocol_position(
+    pool_state: &Pubkey,
+    tick_lower_index: i32,
+    tick_upper_index: i32
+) -> Pubkey {
+    find(
+        &[
+            POSITION_SEED.as_bytes(),
+            pool_state.as_ref(),
+            &tick_lower_index.to_be_bytes(),
+            &tick_upper_index.to_be_bytes(),
+        ]
+    ).0
+}
+
+pub fn personal_position(position_nft_mint: &Pubkey) -> Pubkey {
+    find(&[POSITION_SEED.as_bytes(), position_nft_mint.as_ref()]).0
+}
+
+/// Metaplex metadata account of the position NFT
+pub fn metadata_account(position_nft_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), position_nft_mint.as_ref()],
+        &mpl_token_metadata::ID
+    ).0
+}
+
+/// The accounts of a Raydium pool used by the pool-party instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClmmPool {
+    pub pool_state: Pubkey,
+    pub amm_config: Pubkey,
+    pub observation_state: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub tick_spacing: u16,
+    /// Token programs owning the mints, legacy or Token-2022
+    pub token_program_0: Pubkey,
+    pub token_program_1: Pubkey,
+}
+
+impl ClmmPool {
+    pub fn from_state(pool_state: Pubkey, state: &PoolState) -> Self {
+        ClmmPool {
+            pool_state,
+            amm_config: state.amm_config,
+            observation_state: state.observation_key,
+            token_mint_0: state.token_mint_0,
+            token_mint_1: state.token_mint_1,
+            token_vault_0: state.token_vault_0,
+            token_vault_1: state.token_vault_1,
+            tick_spacing: state.tick_spacing,
+            token_program_0: token::ID,
+            token_program_1: token::ID,
+        }
+    }
+
+    /// The pool with the token programs owning its mints, the legacy program by default
+    pub fn with_token_programs(self, token_program_0: Pubkey, token_program_1: Pubkey) -> Self {
+        ClmmPool { token_program_0, token_program_1, ..self }
+    }
+
+    /// The accounts of the pool `create_pool` initializes for `amm_config` and two mints, in
+    /// any order
+    pub fn derive(amm_config: Pubkey, mint_a: Pubkey, mint_b: Pubkey, tick_spacing: u16) -> Self {
+        let (token_mint_0, token_mint_1) = if mint_a < mint_b {
+            (mint_a, mint_b)
+        } else {
+            (mint_b, mint_a)
+        };
+        let pool_state = pool_state(&amm_config, &token_mint_0, &token_mint_1);
+        ClmmPool {
+            pool_state,
+            amm_config,
+            observation_state: observation_state(&pool_state),
+            token_mint_0,
+            token_mint_1,
+            token_vault_0: pool_vault(&pool_state, &token_mint_0),
+            token_vault_1: pool_vault(&pool_state, &token_mint_1),
+            tick_spacing,
+            token_program_0: token::ID,
+            token_program_1: token::ID,
+        }
+    }
+
+    /// The associated token accounts of `owner` for token_0 and token_1
+    pub fn associated_token_accounts(&self, owner: &Pubkey) -> (Pubkey, Pubkey) {
+        (
+            get_associated_token_address_with_program_id(
+                owner,
+                &self.token_mint_0,
+                &self.token_program_0
+            ),
+            get_associated_token_address_with_program_id(
+                owner,
+                &self.token_mint_1,
+                &self.token_program_1
+            ),
+        )
+    }
+
+    pub fn bitmap_extension(&self) -> Pubkey {
+        tick_array_bitmap_extension(&self.pool_state)
+    }
+
+    /// `(input_vault, output_vault, output_mint)` of a swap from `input_mint`, `None` when the
+    /// pool does not trade it
+    pub fn swap_from(&self, input_mint: &Pubkey) -> Option<(Pubkey, Pubkey, Pubkey)> {
+        if *input_mint == self.token_mint_0 {
+            Some((self.token_vault_0, self.token_vault_1, self.token_mint_1))
+        } else if *input_mint == self.token_mint_1 {
+            Some((self.token_vault_1, self.token_vault_0, self.token_mint_0))
+        } else {
+            None
+        }
+    }
+
+    /// Tick arrays around the current tick, in swap direction, as the Raydium swap expects them
+    pub fn swap_tick_arrays(
+        &self,
+        tick_current: i32,
+        zero_for_one: bool,
+        count: usize
+    ) -> Vec<Pubkey> {
+        let ticks_in_array = TICK_ARRAY_SIZE * (self.tick_spacing as i32);
+        let start_index = tick_array_start_index(tick_current, self.tick_spacing);
+        (0..count as i32)
+            .map(|offset| {
+                let start = if zero_for_one {
+                    start_index - offset * ticks_in_array
+                } else {
+                    start_index + offset * ticks_in_array
+                };
+                tick_array(&self.pool_state, start)
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionRange {
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+}
+
+/// The Raydium accounts of a position of `pool_state` over a tick range
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaydiumPosition {
+    pub protocol_position: Pubkey,
+    pub personal_position: Pubkey,
+    pub tick_array_lower_start_index: i32,
+    pub tick_array_upper_start_index: i32,
+    pub tick_array_lower: Pubkey,
+    pub tick_array_upper: Pubkey,
+    pub bitmap_extension: Pubkey,
+}
+
+impl RaydiumPosition {
+    pub fn new(pool: &ClmmPool, range: PositionRange, position_nft_mint: &Pubkey) -> Self {
+        let tick_array_lower_start_index = tick_array_start_index(
+            range.tick_lower_index,
+            pool.tick_spacing
+        );
+        let tick_array_upper_start_index = tick_array_start_index(
+            range.tick_upper_index,
+            pool.tick_spacing
+        );
+        RaydiumPosition {
+            protocol_position: protocol_position(
+                &pool.pool_state,
+                range.tick_lower_index,
+                range.tick_upper_index
+            ),
+            personal_position: personal_position(position_nft_mint),
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+            tick_array_lower: tick_array(&pool.pool_state, tick_array_lower_start_index),
+            tick_array_upper: tick_array(&pool.pool_state, tick_array_upper_start_index),
+            bitmap_extension: pool.bitmap_extension(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod raydium_test {
+    use super::*;
+
+    #[test]
+    fn tick_array_start_index_rounds_towards_negative_infinity() {
+        assert_eq!(tick_array_start_index(120, 3), 0);
+        assert_eq!(tick_array_start_index(1002, 30), 0);
+        assert_eq!(tick_array_start_index(-120, 3), -180);
+        assert_eq!(tick_array_start_index(-1002, 30), -1800);
+        assert_eq!(tick_array_start_index(-20, 10), -600);
+        assert_eq!(tick_array_start_index(-600, 10), -600);
+        assert_eq!(tick_array_start_index(30720, 1), 30720);
+    }
+
+    #[test]
+    fn swap_tick_arrays_follow_the_swap_direction() {
+        let pool = ClmmPool {
+            pool_state: Pubkey::new_unique(),
+            amm_config: Pubkey::new_unique(),
+            observation_state: Pubkey::new_unique(),
+            token_mint_0: Pubkey::new_unique(),
+            token_mint_1: Pubkey::new_unique(),
+            token_vault_0: Pubkey::new_unique(),
+            token_vault_1: Pubkey::new_unique(),
+            tick_spacing: 10,
+            token_program_0: token::ID,
+            token_program_1: token::ID,
+        };
+
+        let down = pool.swap_tick_arrays(5, true, 2);
+        assert_eq!(down, vec![tick_array(&pool.pool_state, 0), tick_array(&pool.pool_state, -600)]);
+        let up = pool.swap_tick_arrays(5, false, 2);
+        assert_eq!(up, vec![tick_array(&pool.pool_state, 0), tick_array(&pool.pool_state, 600)]);
+
+        let (input_vault, output_vault, output_mint) = pool.swap_from(&pool.token_mint_1).unwrap();
+        assert_eq!((input_vault, output_vault), (pool.token_vault_1, pool.token_vault_0));
+        assert_eq!(output_mint, pool.token_mint_0);
+        assert!(pool.swap_from(&Pubkey::new_unique()).is_none());
+    }
+
+    #[test]
+    fn derive_sorts_the_mints() {
+        let amm_config = Pubkey::new_unique();
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let pool = ClmmPool::derive(amm_config, mint_b, mint_a, 10);
+        assert_eq!(pool, ClmmPool::derive(amm_config, mint_a, mint_b, 10));
+        assert!(pool.token_mint_0 < pool.token_mint_1);
+        assert_eq!(pool.pool_state, pool_state(&amm_config, &pool.token_mint_0, &pool.token_mint_1));
+        assert_eq!(pool.token_vault_1, pool_vault(&pool.pool_state, &pool.token_mint_1));
+    }
+}
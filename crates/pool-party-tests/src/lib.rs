@@ -0,0 +1,536 @@
+//! LiteSVM harness for end-to-end tests of pool-party.
+//!
+//! `TestEnv` loads the program built by `anchor build` next to the Raydium CLMM and Metaplex
+//! metadata programs dumped at the repository root, with the `amm_config` fixtures, and offers
+//! helpers to create mints, token accounts and Raydium pools and to trade on them. The program is
+//! deployed upgradeable by the env payer, who creates the protocol config allowing the CLMM.
+//! `TestEnv::with_mock_clmm` loads the mock CLMM of `programs/mock-clmm` in place of Raydium, to
+//! set fee growth, fees owed and prices directly.
+
+use std::any::type_name;
+use std::mem::size_of;
+use std::path::{ Path, PathBuf };
+
+use anchor_lang::prelude::{ AccountMeta, Pubkey };
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::bpf_loader_upgradeable::UpgradeableLoaderState;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::{ bpf_loader_upgradeable, system_program, sysvar };
+use anchor_lang::{
+    AccountDeserialize,
+    AnchorDeserialize,
+    AnchorSerialize,
+    Discriminator,
+    InstructionData,
+    ToAccountMetas,
+    ZeroCopy,
+};
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::memo::spl_memo;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::spl_token::{ self, native_mint };
+use anchor_spl::token_2022;
+use base64::Engine;
+use litesvm::types::{ FailedTransactionMetadata, TransactionMetadata };
+use litesvm::LiteSVM;
+use pool_party_client::ClmmPool;
+use raydium_clmm_cpi::states::PoolState;
+use serde::Deserialize;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::signature::{ Keypair, Signer };
+use solana_sdk::transaction::Transaction;
+
+/// `amm_config` index 0 of the mainnet CLMM: tick spacing 10, 0.01% trade fee
+pub const AMM_CONFIG: &str = "amm_config_mainnet.json";
+pub const AMM_CONFIG_TICK_SPACING: u16 = 10;
+/// `amm_config` index 4 of the mainnet CLMM: tick spacing 1, 0.01% trade fee
+pub const AMM_CONFIG_0: &str = "amm_config_0_mainnet.json";
+pub const AMM_CONFIG_0_TICK_SPACING: u16 = 1;
+
+const CLMM_PROGRAM: &str = "clmm_mainnet.so";
+const MOCK_CLMM_PROGRAM: &str = "target/deploy/mock_clmm.so";
+const METADATA_PROGRAM: &str = "metadata_program_mainnet.so";
+const POOL_PARTY_PROGRAM: &str = "target/deploy/pool_party.so";
+
+const COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Square root of price 1 in Q64.64
+pub const SQRT_PRICE_1_X64: u128 = 1 << 64;
+
+pub fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+/// Account dumped by `solana account --output json-compact`
+#[derive(Deserialize)]
+struct AccountFixture {
+    pubkey: String,
+    account: AccountFixtureData,
+}
+
+#[derive(Deserialize)]
+struct AccountFixtureData {
+    data: (String, String),
+    executable: bool,
+    lamports: u64,
+    owner: String,
+    #[serde(rename = "rentEpoch")]
+    rent_epoch: u64,
+}
+
+pub struct TestEnv {
+    pub svm: LiteSVM,
+    pub payer: Keypair,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        TestEnv::with_clmm_program(CLMM_PROGRAM)
+    }
+
+    /// Like `new`, with the mock CLMM at the Raydium address. Build it with
+    /// `make build-mock-clmm` first.
+    pub fn with_mock_clmm() -> Self {
+        TestEnv::with_clmm_program(MOCK_CLMM_PROGRAM)
+    }
+
+    fn with_clmm_program(clmm_program: &str) -> Self {
+        let mut svm = LiteSVM::new();
+        let root = repo_root();
+        svm.add_program_from_file(raydium_clmm_cpi::ID, root.join(clmm_program)).unwrap_or_else(
+            |err| panic!("loading the CLMM program {clmm_program}: {err}")
+        );
+        svm.add_program_from_file(mpl_token_metadata::ID, root.join(METADATA_PROGRAM)).expect(
+            "loading the metadata program"
+        );
+
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 1_000_000_000_000).unwrap();
+
+        let mut env = TestEnv { svm, payer };
+        let program = std::fs::read(root.join(POOL_PARTY_PROGRAM)).expect(
+            "loading pool_party.so, run `anchor build` first"
+        );
+        env.deploy_upgradeable(pool_party::ID, &program);
+        env.load_fixture(AMM_CONFIG);
+        env.load_fixture(AMM_CONFIG_0);
+        env.set_mint(native_mint::ID, 9, None);
+        let admin = env.payer.pubkey();
+        env.send_ok(
+            &[pool_party_client::initialize_protocol_config(&admin, vec![raydium_clmm_cpi::ID])],
+            &[]
+        );
+        env
+    }
+
+    /// Deploys `program` with the upgradeable loader, the env payer being its upgrade authority
+    fn deploy_upgradeable(&mut self, program_id: Pubkey, program: &[u8]) {
+        let program_data = bpf_loader_upgradeable::get_program_data_address(&program_id);
+        // `UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address }` followed
+        // by the ELF, bincode encoded
+        let mut data = Vec::with_capacity(
+            UpgradeableLoaderState::size_of_programdata_metadata() + program.len()
+        );
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(self.payer.pubkey().as_ref());
+        data.extend_from_slice(program);
+        self.set_loader_account(program_data, data, false);
+
+        // `UpgradeableLoaderState::Program { programdata_address }`
+        let mut data = Vec::with_capacity(UpgradeableLoaderState::size_of_program());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(program_data.as_ref());
+        self.set_loader_account(program_id, data, true);
+    }
+
+    fn set_loader_account(&mut self, address: Pubkey, data: Vec<u8>, executable: bool) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len());
+        self.svm
+            .set_account(address, Account {
+                lamports,
+                data,
+                owner: bpf_loader_upgradeable::ID,
+                executable,
+                rent_epoch: 0,
+            })
+            .unwrap();
+    }
+
+    /// Loads an account fixture from the repository root and returns its address
+    pub fn load_fixture(&mut self, name: &str) -> Pubkey {
+        let content = std::fs::read_to_string(repo_root().join(name)).expect(name);
+        let fixture: AccountFixture = serde_json::from_str(&content).expect(name);
+        let (data, encoding) = fixture.account.data;
+        assert_eq!(encoding, "base64", "{name} must be base64 encoded");
+        let address: Pubkey = fixture.pubkey.parse().unwrap();
+        self.svm
+            .set_account(address, Account {
+                lamports: fixture.account.lamports,
+                data: base64::engine::general_purpose::STANDARD.decode(data).unwrap(),
+                owner: fixture.account.owner.parse().unwrap(),
+                executable: fixture.account.executable,
+                rent_epoch: fixture.account.rent_epoch,
+            })
+            .unwrap();
+        address
+    }
+
+    pub fn new_funded_keypair(&mut self, lamports: u64) -> Keypair {
+        let keypair = Keypair::new();
+        self.svm.airdrop(&keypair.pubkey(), lamports).unwrap();
+        keypair
+    }
+
+    fn set_mint(&mut self, address: Pubkey, decimals: u8, authority: Option<Pubkey>) {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        let mint = spl_token::state::Mint {
+            mint_authority: authority.into(),
+            supply: 0,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        spl_token::state::Mint::pack(mint, &mut data).unwrap();
+        self.set_token_program_account(address, data, 0);
+    }
+
+    fn set_token_program_account(&mut self, address: Pubkey, data: Vec<u8>, extra_lamports: u64) {
+        let lamports = self.svm.minimum_balance_for_rent_exemption(data.len()) + extra_lamports;
+        self.svm
+            .set_account(address, Account {
+                lamports,
+                data,
+                owner: spl_token::ID,
+                executable: false,
+                rent_epoch: 0,
+            })
+            .unwrap();
+    }
+
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let mint = Pubkey::new_unique();
+        self.set_mint(mint, decimals, Some(self.payer.pubkey()));
+        mint
+    }
+
+    /// Creates the associated token account of `owner` holding `amount`, wrapping lamports for
+    /// the native mint
+    pub fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let address = get_associated_token_address(owner, mint);
+        let is_native = *mint == native_mint::ID;
+        let rent = self.svm.minimum_balance_for_rent_exemption(spl_token::state::Account::LEN);
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        let account = spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: if is_native { COption::Some(rent) } else { COption::None },
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        spl_token::state::Account::pack(account, &mut data).unwrap();
+        self.set_token_program_account(address, data, if is_native { amount } else { 0 });
+        address
+    }
+
+    /// Overwrites the balance of an SPL token account of a non-native mint
+    pub fn set_token_balance(&mut self, token_account: &Pubkey, amount: u64) {
+        let mut account = self.svm.get_account(token_account).expect("missing token account");
+        let mut state = spl_token::state::Account::unpack(&account.data).unwrap();
+        state.amount = amount;
+        spl_token::state::Account::pack(state, &mut account.data).unwrap();
+        self.svm.set_account(*token_account, account).unwrap();
+    }
+
+    pub fn token_balance(&self, token_account: &Pubkey) -> u64 {
+        self.svm
+            .get_account(token_account)
+            .map(|account| spl_token::state::Account::unpack(&account.data).unwrap().amount)
+            .unwrap_or(0)
+    }
+
+    pub fn account<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        let account = self.svm.get_account(address).expect("missing account");
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// Reads a zero-copy account. The `u128` fields are 16-byte aligned on the host, which the
+    /// data after the discriminator is not, so it is copied out rather than cast as on chain.
+    pub fn load<T: ZeroCopy>(&self, address: &Pubkey) -> T {
+        let account = self.svm.get_account(address).expect("missing account");
+        let start = T::DISCRIMINATOR.len();
+        assert_eq!(&account.data[..start], T::DISCRIMINATOR, "not a {}", type_name::<T>());
+        bytemuck::pod_read_unaligned(&account.data[start..start + size_of::<T>()])
+    }
+
+    pub fn pool_state(&self, pool_state: &Pubkey) -> PoolState {
+        let account = self.svm.get_account(pool_state).expect("missing pool");
+        let start = PoolState::DISCRIMINATOR.len();
+        *bytemuck::from_bytes(&account.data[start..start + size_of::<PoolState>()])
+    }
+
+    pub fn warp_seconds(&mut self, seconds: i64) {
+        let mut clock = self.svm.get_sysvar::<Clock>();
+        clock.unix_timestamp += seconds;
+        clock.slot += 1;
+        self.svm.set_sysvar(&clock);
+    }
+
+    /// Sends `instructions` paid by the env payer, with a raised compute unit limit
+    pub fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair]
+    ) -> Result<TransactionMetadata, FailedTransactionMetadata> {
+        let mut all_instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT)
+        ];
+        all_instructions.extend_from_slice(instructions);
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            &all_instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            self.svm.latest_blockhash()
+        );
+        let result = self.svm.send_transaction(transaction);
+        // Identical transactions would otherwise be rejected as already processed
+        self.svm.expire_blockhash();
+        result
+    }
+
+    /// Like `send`, panicking with the program logs on failure
+    pub fn send_ok(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair]
+    ) -> TransactionMetadata {
+        match self.send(instructions, signers) {
+            Ok(meta) => meta,
+            Err(failed) => panic!("{:?}\n{}", failed.err, failed.meta.logs.join("\n")),
+        }
+    }
+
+    /// Like `send`, panicking unless the transaction fails with the Anchor error `error_name`
+    pub fn send_err(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+        error_name: &str
+    ) {
+        match self.send(instructions, signers) {
+            Ok(meta) => panic!("expected {error_name}\n{}", meta.logs.join("\n")),
+            Err(failed) => {
+                let expected = format!("Error Code: {error_name}.");
+                assert!(
+                    failed.meta.logs.iter().any(|log| log.contains(&expected)),
+                    "expected {error_name}, got {:?}\n{}",
+                    failed.err,
+                    failed.meta.logs.join("\n")
+                );
+            }
+        }
+    }
+
+    /// Simulates a read-only `instruction` and decodes its return data, panicking with the
+    /// program logs on failure
+    pub fn view<T: AnchorDeserialize>(&self, instruction: Instruction) -> T {
+        let transaction = Transaction::new_signed_with_payer(
+            &[ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT), instruction],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            self.svm.latest_blockhash()
+        );
+        match self.svm.simulate_transaction(transaction) {
+            Ok(simulated) => {
+                T::deserialize(&mut simulated.meta.return_data.data.as_slice()).unwrap()
+            }
+            Err(failed) => panic!("{:?}\n{}", failed.err, failed.meta.logs.join("\n")),
+        }
+    }
+
+    /// Creates a Raydium pool of the `amm_config` fixture at `sqrt_price_x64`
+    pub fn create_pool(
+        &mut self,
+        amm_config: Pubkey,
+        tick_spacing: u16,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        sqrt_price_x64: u128
+    ) -> ClmmPool {
+        let pool = ClmmPool::derive(amm_config, mint_a, mint_b, tick_spacing);
+        let mut data = anchor_discriminator("create_pool").to_vec();
+        sqrt_price_x64.serialize(&mut data).unwrap();
+        // open_time in the past so the pool trades right away
+        0u64.serialize(&mut data).unwrap();
+        let instruction = Instruction {
+            program_id: raydium_clmm_cpi::ID,
+            accounts: vec![
+                AccountMeta::new(self.payer.pubkey(), true),
+                AccountMeta::new_readonly(amm_config, false),
+                AccountMeta::new(pool.pool_state, false),
+                AccountMeta::new_readonly(pool.token_mint_0, false),
+                AccountMeta::new_readonly(pool.token_mint_1, false),
+                AccountMeta::new(pool.token_vault_0, false),
+                AccountMeta::new(pool.token_vault_1, false),
+                AccountMeta::new(pool.observation_state, false),
+                AccountMeta::new(pool.bitmap_extension(), false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(sysvar::rent::ID, false)
+            ],
+            data,
+        };
+        self.send_ok(&[instruction], &[]);
+        pool
+    }
+
+    /// Initialized tick arrays of `pool` from the current tick in the swap direction, as Raydium
+    /// expects them
+    pub fn swap_tick_arrays(&self, pool: &ClmmPool, zero_for_one: bool) -> Vec<Pubkey> {
+        let state = self.pool_state(&pool.pool_state);
+        pool.swap_tick_arrays(state.tick_current, zero_for_one, 10)
+            .into_iter()
+            .filter(|tick_array| self.svm.get_account(tick_array).is_some())
+            .collect()
+    }
+
+    /// Swaps `amount` of `input_mint` on `pool` straight through Raydium, from and to the
+    /// associated token accounts of `trader`
+    pub fn raydium_swap(
+        &mut self,
+        trader: &Keypair,
+        pool: &ClmmPool,
+        input_mint: &Pubkey,
+        amount: u64
+    ) -> TransactionMetadata {
+        let (input_vault, output_vault, output_mint) = pool.swap_from(input_mint).unwrap();
+        let zero_for_one = *input_mint == pool.token_mint_0;
+        let mut data = anchor_discriminator("swap_v2").to_vec();
+        amount.serialize(&mut data).unwrap();
+        0u64.serialize(&mut data).unwrap();
+        0u128.serialize(&mut data).unwrap();
+        true.serialize(&mut data).unwrap();
+
+        let mut accounts = vec![
+            AccountMeta::new_readonly(trader.pubkey(), true),
+            AccountMeta::new_readonly(pool.amm_config, false),
+            AccountMeta::new(pool.pool_state, false),
+            AccountMeta::new(get_associated_token_address(&trader.pubkey(), input_mint), false),
+            AccountMeta::new(get_associated_token_address(&trader.pubkey(), &output_mint), false),
+            AccountMeta::new(input_vault, false),
+            AccountMeta::new(output_vault, false),
+            AccountMeta::new(pool.observation_state, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(token_2022::ID, false),
+            AccountMeta::new_readonly(spl_memo::ID, false),
+            AccountMeta::new_readonly(*input_mint, false),
+            AccountMeta::new_readonly(output_mint, false),
+            AccountMeta::new(pool.bitmap_extension(), false)
+        ];
+        accounts.extend(
+            self
+                .swap_tick_arrays(pool, zero_for_one)
+                .into_iter()
+                .map(|tick_array| AccountMeta::new(tick_array, false))
+        );
+        let instruction = Instruction { program_id: raydium_clmm_cpi::ID, accounts, data };
+        self.send_ok(&[instruction], &[trader])
+    }
+}
+
+/// Writes of the mock CLMM, only valid on an env created by `TestEnv::with_mock_clmm`
+impl TestEnv {
+    fn send_mock_clmm(
+        &mut self,
+        accounts: impl ToAccountMetas,
+        data: impl InstructionData
+    ) -> TransactionMetadata {
+        let instruction = Instruction {
+            program_id: mock_clmm::ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        };
+        self.send_ok(&[instruction], &[])
+    }
+
+    pub fn set_fee_growth_global(
+        &mut self,
+        pool: &ClmmPool,
+        fee_growth_global_0_x64: u128,
+        fee_growth_global_1_x64: u128
+    ) -> TransactionMetadata {
+        self.send_mock_clmm(
+            mock_clmm::accounts::SetPoolState {
+                owner: self.payer.pubkey(),
+                pool_state: pool.pool_state,
+            },
+            mock_clmm::instruction::SetFeeGrowthGlobal {
+                fee_growth_global_0_x64,
+                fee_growth_global_1_x64,
+            }
+        )
+    }
+
+    pub fn set_sqrt_price(&mut self, pool: &ClmmPool, sqrt_price_x64: u128) -> TransactionMetadata {
+        self.send_mock_clmm(
+            mock_clmm::accounts::SetPoolState {
+                owner: self.payer.pubkey(),
+                pool_state: pool.pool_state,
+            },
+            mock_clmm::instruction::SetSqrtPrice { sqrt_price_x64 }
+        )
+    }
+
+    pub fn set_token_fees_owed(
+        &mut self,
+        pool: &ClmmPool,
+        personal_position: &Pubkey,
+        token_fees_owed_0: u64,
+        token_fees_owed_1: u64
+    ) -> TransactionMetadata {
+        self.send_mock_clmm(
+            mock_clmm::accounts::SetTokenFeesOwed {
+                owner: self.payer.pubkey(),
+                pool_state: pool.pool_state,
+                personal_position: *personal_position,
+            },
+            mock_clmm::instruction::SetTokenFeesOwed { token_fees_owed_0, token_fees_owed_1 }
+        )
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        TestEnv::new()
+    }
+}
+
+/// Events `T` emitted by the transaction of `meta`
+pub fn events<T: AnchorDeserialize + Discriminator>(meta: &TransactionMetadata) -> Vec<T> {
+    meta.logs
+        .iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+        .filter(|data| data.starts_with(T::DISCRIMINATOR))
+        .map(|data| T::deserialize(&mut &data[T::DISCRIMINATOR.len()..]).unwrap())
+        .collect()
+}
+
+/// Anchor instruction discriminator of `name`
+pub fn anchor_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{name}").as_bytes()).to_bytes()[..8]);
+    discriminator
+}
@@ -0,0 +1,265 @@
+//! End-to-end flows of pool-party against the mainnet Raydium CLMM program.
+//!
+//! Requires `target/deploy/pool_party.so`, built with `anchor build`.
+
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::token::spl_token::native_mint;
+use pool_party::instructions::calculate_fees;
+use pool_party::state::{ Investor, PoolPosition };
+use pool_party_client::pda::InvestorAddresses;
+use pool_party_client::{
+    self as client,
+    LiquidityBounds,
+    PositionAccounts,
+    PositionRange,
+    SwapLegAccounts,
+};
+use pool_party_tests::{ TestEnv, AMM_CONFIG, AMM_CONFIG_TICK_SPACING, SQRT_PRICE_1_X64 };
+use raydium_clmm_cpi::states::PersonalPositionState;
+use solana_sdk::signature::{ Keypair, Signer };
+
+const SOL: u64 = 1_000_000_000;
+
+struct OpenedPosition {
+    env: TestEnv,
+    position: PositionAccounts,
+}
+
+/// A WSOL/token pool at price 1 and a pool position over [-1200, 1200] opened by the env payer
+fn open_position() -> OpenedPosition {
+    let mut env = TestEnv::new();
+    let amm_config = env.load_fixture(AMM_CONFIG);
+    let token_mint = env.create_mint(9);
+    let pool = env.create_pool(
+        amm_config,
+        AMM_CONFIG_TICK_SPACING,
+        native_mint::ID,
+        token_mint,
+        SQRT_PRICE_1_X64
+    );
+
+    let manager = env.payer.pubkey();
+    let token_account_0 = env.create_token_account(&pool.token_mint_0, &manager, 100 * SOL);
+    let token_account_1 = env.create_token_account(&pool.token_mint_1, &manager, 100 * SOL);
+
+    let position_nft_mint = Keypair::new();
+    let position = PositionAccounts {
+        name: "sol-token".to_string(),
+        pool,
+        range: PositionRange { tick_lower_index: -1200, tick_upper_index: 1200 },
+        position_nft_mint: position_nft_mint.pubkey(),
+        ladder: Vec::new(),
+    };
+    env.send_ok(&[client::create_position(&manager, &position)], &[]);
+    env.send_ok(&[client::create_position_vaults(&manager, &position)], &[]);
+    env.send_ok(
+        &[
+            client::open_position(
+                &manager,
+                &position,
+                &token_account_0,
+                &token_account_1,
+                SOL,
+                SOL
+            ),
+        ],
+        &[&position_nft_mint]
+    );
+
+    OpenedPosition { env, position }
+}
+
+/// Swap legs from WSOL inside the position pool, the single swap path of the program
+fn single_swap_legs(
+    env: &TestEnv,
+    position: &PositionAccounts
+) -> (SwapLegAccounts, SwapLegAccounts) {
+    let zero_for_one = position.pool.token_mint_0 == native_mint::ID;
+    let leg_0 = SwapLegAccounts {
+        pool: position.pool,
+        tick_arrays: env.swap_tick_arrays(&position.pool, zero_for_one),
+        hops: Vec::new(),
+        minimum_amount_out: 0,
+    };
+    let leg_1 = SwapLegAccounts { tick_arrays: Vec::new(), ..leg_0.clone() };
+    (leg_0, leg_1)
+}
+
+fn liquidity_bounds(env: &TestEnv, position: &PositionAccounts) -> LiquidityBounds {
+    LiquidityBounds {
+        min_liquidity_out: 1,
+        expected_sqrt_price_x64: env.pool_state(&position.pool.pool_state).sqrt_price_x64,
+        max_price_deviation: 100,
+    }
+}
+
+#[test]
+fn open_position_mints_the_position_nft_to_the_pool_position() {
+    let OpenedPosition { env, position } = open_position();
+    let addresses = position.addresses();
+
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    assert_eq!(pool_position.position_nft_mint_key, position.position_nft_mint);
+    assert_eq!(pool_position.name(), position.name);
+    assert_eq!(env.token_balance(&position.position_nft_account()), 1);
+
+    let personal_position: PersonalPositionState = env.account(
+        &position.raydium().personal_position
+    );
+    assert!(personal_position.liquidity > 0);
+    assert_eq!(pool_position.liquidity, personal_position.liquidity);
+
+    let manager_addresses = InvestorAddresses::new(
+        &addresses.pool_position_config,
+        &env.payer.pubkey()
+    );
+    let manager: Investor = env.load(&manager_addresses.investor_account);
+    assert_eq!(manager.is_manager, 1);
+    assert_eq!(manager.liquidity, pool_position.liquidity);
+}
+
+#[test]
+fn investor_flow_deposits_swaps_adds_liquidity_and_collects_fees() {
+    let OpenedPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let pool = position.pool;
+
+    // create investor -> deposit
+    let investor = env.new_funded_keypair(100 * SOL);
+    let investor_addresses = InvestorAddresses::new(
+        &addresses.pool_position_config,
+        &investor.pubkey()
+    );
+    env.send_ok(
+        &[client::create_investor_position(&investor.pubkey(), &position, &[])],
+        &[&investor]
+    );
+    env.send_ok(
+        &[client::deposit(&investor.pubkey(), &position, &native_mint::ID, SOL)],
+        &[&investor]
+    );
+    assert_eq!(env.token_balance(&investor_addresses.deposit_stable_token_account), SOL);
+
+    // swap to the position ratio
+    let (leg_0, leg_1) = single_swap_legs(&env, &position);
+    env.send_ok(
+        &[
+            client::swap_to_ratio_deposit(
+                &investor.pubkey(),
+                &position,
+                &native_mint::ID,
+                &leg_0,
+                &leg_1
+            ),
+        ],
+        &[&investor]
+    );
+    assert_eq!(env.token_balance(&investor_addresses.deposit_stable_token_account), 0);
+    let deposit_0 = env.token_balance(&investor_addresses.deposit_token_0_account);
+    let deposit_1 = env.token_balance(&investor_addresses.deposit_token_1_account);
+    assert!(deposit_0 > 0 && deposit_1 > 0);
+    assert!(deposit_0 + deposit_1 <= SOL);
+
+    // increase liquidity
+    let liquidity_before: PoolPosition = env.load(&addresses.pool_position);
+    env.send_ok(
+        &[
+            client::increase_liquidity(
+                &investor.pubkey(),
+                &position,
+                liquidity_bounds(&env, &position)
+            ),
+        ],
+        &[&investor]
+    );
+    let investor_account: Investor = env.load(&investor_addresses.investor_account);
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    let personal_position: PersonalPositionState = env.account(
+        &position.raydium().personal_position
+    );
+    assert!(investor_account.liquidity > 0);
+    assert_eq!(pool_position.liquidity, liquidity_before.liquidity + investor_account.liquidity);
+    assert_eq!(pool_position.liquidity, personal_position.liquidity);
+    assert!(
+        env.token_balance(&investor_addresses.deposit_token_0_account) < deposit_0 ||
+            env.token_balance(&investor_addresses.deposit_token_1_account) < deposit_1
+    );
+
+    // trade both ways through Raydium to accrue fees on the position
+    let trader = env.new_funded_keypair(100 * SOL);
+    env.create_token_account(&pool.token_mint_0, &trader.pubkey(), 10 * SOL);
+    env.create_token_account(&pool.token_mint_1, &trader.pubkey(), 10 * SOL);
+    for _ in 0..3 {
+        env.raydium_swap(&trader, &pool, &pool.token_mint_0, SOL / 10);
+        env.raydium_swap(&trader, &pool, &pool.token_mint_1, SOL / 10);
+    }
+
+    // collect fees
+    let fees_0_account = get_associated_token_address(&investor.pubkey(), &pool.token_mint_0);
+    let fees_1_account = get_associated_token_address(&investor.pubkey(), &pool.token_mint_1);
+    let investor_before: Investor = env.load(&investor_addresses.investor_account);
+    let position_before: PoolPosition = env.load(&addresses.pool_position);
+    let fees_vault_0_before = env.token_balance(&addresses.fees_vault_0);
+    let fees_vault_1_before = env.token_balance(&addresses.fees_vault_1);
+    let token_vault_0_before = env.token_balance(&pool.token_vault_0);
+    let token_vault_1_before = env.token_balance(&pool.token_vault_1);
+
+    env.send_ok(&[client::collect_fees(&investor.pubkey(), &position)], &[&investor]);
+
+    let investor_after: Investor = env.load(&investor_addresses.investor_account);
+    let position_after: PoolPosition = env.load(&addresses.pool_position);
+    assert!(position_after.fees_index0 > position_before.fees_index0);
+    assert!(position_after.fees_index1 > position_before.fees_index1);
+    assert_eq!(investor_after.fees_index0, position_after.fees_index0);
+    assert_eq!(investor_after.fees_index1, position_after.fees_index1);
+    assert_eq!((investor_after.fees_earned0, investor_after.fees_earned1), (0, 0));
+
+    // The investor is paid its liquidity share of the index growth
+    let paid_0 = env.token_balance(&fees_0_account);
+    let paid_1 = env.token_balance(&fees_1_account);
+    let expected_0 =
+        investor_before.fees_earned0 +
+        calculate_fees(
+            investor_before.liquidity,
+            position_after.fees_index0,
+            investor_before.fees_index0
+        );
+    let expected_1 =
+        investor_before.fees_earned1 +
+        calculate_fees(
+            investor_before.liquidity,
+            position_after.fees_index1,
+            investor_before.fees_index1
+        );
+    assert!(paid_0 > 0 && paid_1 > 0);
+    assert_eq!((paid_0, paid_1), (expected_0, expected_1));
+
+    // Everything Raydium paid out lands in the fees vaults or with the investor
+    let collected_0 = token_vault_0_before - env.token_balance(&pool.token_vault_0);
+    let collected_1 = token_vault_1_before - env.token_balance(&pool.token_vault_1);
+    assert_eq!(
+        env.token_balance(&addresses.fees_vault_0) + paid_0,
+        fees_vault_0_before + collected_0
+    );
+    assert_eq!(
+        env.token_balance(&addresses.fees_vault_1) + paid_1,
+        fees_vault_1_before + collected_1
+    );
+}
+
+#[test]
+fn collect_fees_without_liquidity_pays_nothing() {
+    let OpenedPosition { mut env, position } = open_position();
+    let investor = env.new_funded_keypair(10 * SOL);
+    env.send_ok(
+        &[client::create_investor_position(&investor.pubkey(), &position, &[])],
+        &[&investor]
+    );
+
+    env.send_ok(&[client::collect_fees(&investor.pubkey(), &position)], &[&investor]);
+
+    for mint in [position.pool.token_mint_0, position.pool.token_mint_1] {
+        let fees_account = get_associated_token_address(&investor.pubkey(), &mint);
+        assert_eq!(env.token_balance(&fees_account), 0);
+    }
+}
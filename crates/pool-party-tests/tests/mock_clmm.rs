@@ -0,0 +1,1347 @@
+//! Fee accounting of pool-party against the mock CLMM, with fee growth, fees owed and price set
+//! directly instead of generated by trades.
+//!
+//! Requires `target/deploy/pool_party.so` and `target/deploy/mock_clmm.so`, built with
+//! `anchor build` and `make build-mock-clmm`.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{ AccountSerialize, AnchorSerialize, Discriminator };
+use anchor_spl::associated_token::get_associated_token_address;
+use anchor_spl::metadata::mpl_token_metadata;
+use anchor_spl::token::spl_token::native_mint;
+use litesvm::types::TransactionMetadata;
+use pool_party::constants::{
+    MAX_LADDER_RANGES,
+    MIN_TIMELOCK_SECONDS,
+    NAV_SNAPSHOT_INTERVAL_SECONDS,
+};
+use pool_party::dex::Dex;
+use pool_party::events::{ ManagerProposalWithdrawn, ManagerProposed };
+use pool_party::instructions::{ calculate_fees, fees_indexes, InvestorValue };
+use pool_party::libraries::{
+    early_exit_penalty,
+    fixed_point_64,
+    ladder_range_liquidity,
+    liquidity_math,
+    nav_per_liquidity_x64,
+    quote_at_sqrt_price,
+    tick_math,
+    PriceSource,
+};
+use pool_party::state::{
+    ConfigChange,
+    Investor,
+    InvestorV0,
+    LadderRange,
+    PendingConfigChange,
+    PoolPosition,
+    PoolPositionConfig,
+    PoolPositionConfigV0,
+    PoolPositionV0,
+    PositionRoles,
+    ProtocolConfig,
+};
+use pool_party_client::pda::{ self, InvestorAddresses };
+use pool_party_client::{
+    self as client,
+    AllowlistTree,
+    LadderRangeKeys,
+    LiquidityBounds,
+    PositionAccounts,
+    PositionRange,
+    RaydiumPosition,
+};
+use pool_party_tests::{
+    events,
+    TestEnv,
+    AMM_CONFIG,
+    AMM_CONFIG_TICK_SPACING,
+    SQRT_PRICE_1_X64,
+};
+use raydium_clmm_cpi::states::PersonalPositionState;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{ Keypair, Signer };
+
+const TOKEN: u64 = 1_000_000_000;
+
+/// Minimum deposit set on the position, in lamports of the wrapped SOL stable mint
+const SOL_DEPOSIT: u64 = TOKEN / 10;
+
+const NO_DEPOSIT_LIMITS: ConfigChange = ConfigChange::DepositLimits {
+    max_total_liquidity: 0,
+    max_investor_liquidity: 0,
+    min_deposit: 0,
+};
+
+const NO_ALLOWLIST: ConfigChange = ConfigChange::AllowlistRoot { allowlist_root: None };
+
+/// Compute units `increase_liquidity`, `collect_fees` and `decrease_liquidity` may each consume
+/// against the mock CLMM, the compute budget instruction of `TestEnv::send` included
+const INVESTOR_INSTRUCTION_CU_BUDGET: u64 = 200_000;
+
+/// Fee growth worth a thousandth of a token per unit of liquidity
+const FEE_GROWTH_X64: u128 = fixed_point_64::Q64 / 1000;
+
+struct MockPosition {
+    env: TestEnv,
+    position: PositionAccounts,
+}
+
+/// A pool of two fresh mints at price 1 on the mock CLMM and a pool position over [-1200, 1200]
+/// opened by the env payer
+fn open_position() -> MockPosition {
+    let mut env = TestEnv::with_mock_clmm();
+    let amm_config = env.load_fixture(AMM_CONFIG);
+    let (mint_a, mint_b) = (env.create_mint(9), env.create_mint(9));
+    let pool = env.create_pool(
+        amm_config,
+        AMM_CONFIG_TICK_SPACING,
+        mint_a,
+        mint_b,
+        SQRT_PRICE_1_X64
+    );
+
+    let manager = env.payer.pubkey();
+    let token_account_0 = env.create_token_account(&pool.token_mint_0, &manager, 100 * TOKEN);
+    let token_account_1 = env.create_token_account(&pool.token_mint_1, &manager, 100 * TOKEN);
+
+    let position_nft_mint = Keypair::new();
+    let position = PositionAccounts {
+        name: "mock".to_string(),
+        pool,
+        range: PositionRange { tick_lower_index: -1200, tick_upper_index: 1200 },
+        position_nft_mint: position_nft_mint.pubkey(),
+        ladder: Vec::new(),
+    };
+    env.send_ok(&[client::create_position(&manager, &position)], &[]);
+    env.send_ok(&[client::create_position_vaults(&manager, &position)], &[]);
+    env.send_ok(
+        &[
+            client::open_position(
+                &manager,
+                &position,
+                &token_account_0,
+                &token_account_1,
+                TOKEN,
+                TOKEN
+            ),
+        ],
+        &[&position_nft_mint]
+    );
+
+    MockPosition { env, position }
+}
+
+/// Creates an investor and adds liquidity from `amount` of both tokens credited to its deposit
+/// accounts
+fn add_investor(env: &mut TestEnv, position: &PositionAccounts, amount: u64) -> Keypair {
+    let investor = env.new_funded_keypair(10 * TOKEN);
+    let addresses = InvestorAddresses::new(
+        &position.addresses().pool_position_config,
+        &investor.pubkey()
+    );
+    env.send_ok(
+        &[client::create_investor_position(&investor.pubkey(), position, &[])],
+        &[&investor]
+    );
+    env.set_token_balance(&addresses.deposit_token_0_account, amount);
+    env.set_token_balance(&addresses.deposit_token_1_account, amount);
+
+    let bounds = LiquidityBounds {
+        min_liquidity_out: 1,
+        expected_sqrt_price_x64: env.pool_state(&position.pool.pool_state).sqrt_price_x64,
+        max_price_deviation: 100,
+    };
+    env.send_ok(
+        &[client::increase_liquidity(&investor.pubkey(), position, bounds)],
+        &[&investor]
+    );
+    investor
+}
+
+fn investor_account(env: &TestEnv, position: &PositionAccounts, authority: &Pubkey) -> Investor {
+    let addresses = InvestorAddresses::new(&position.addresses().pool_position_config, authority);
+    env.load(&addresses.investor_account)
+}
+
+/// Rewrites `address` with the `data` of a legacy layout, zero-padded to the `len` the program
+/// allocated then
+fn write_legacy(env: &mut TestEnv, address: &Pubkey, mut data: Vec<u8>, len: usize) {
+    data.resize(len, 0);
+    let mut account = env.svm.get_account(address).unwrap();
+    account.data = data;
+    env.svm.set_account(*address, account).unwrap();
+}
+
+fn serialized<T: AccountSerialize>(account: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    data
+}
+
+/// Borsh encoded account of a legacy layout, behind the discriminator of `T`
+fn legacy_serialized<T: Discriminator>(fields: &impl AnchorSerialize) -> Vec<u8> {
+    [T::DISCRIMINATOR, &fields.try_to_vec().unwrap()[..]].concat()
+}
+
+/// Size the baseline `create_position` allocated to a config: a second discriminator on top of
+/// `PoolPositionConfig::LEN` and the name length counted twice
+fn baseline_config_len(name: &str) -> usize {
+    8 + PoolPositionConfigV0::LEN + 4 + name.len()
+}
+
+/// Size the baseline program allocated to a position, with a second discriminator
+const BASELINE_POOL_POSITION_LEN: usize = 8 + PoolPositionV0::LEN;
+
+/// Size the baseline program allocated to an investor: a second discriminator and the
+/// `size_of::<Investor>()` of the SBF target, 152 bytes with its `u128` aligned to 8
+const BASELINE_INVESTOR_LEN: usize = 8 + 8 + 152;
+
+fn baseline_config(config: &PoolPositionConfig) -> PoolPositionConfigV0 {
+    PoolPositionConfigV0 {
+        bump: config.bump,
+        pda_bump: config.pda_bump,
+        tick_lower_index: config.tick_lower_index,
+        tick_upper_index: config.tick_upper_index,
+        pool_key: config.pool_key,
+        pool_position_key: config.pool_position_key,
+        manager_key: config.manager_key,
+        token_vault_0_key: config.token_vault_0_key,
+        token_vault_1_key: config.token_vault_1_key,
+        vault_0_mint_key: config.vault_0_mint_key,
+        vault_1_mint_key: config.vault_1_mint_key,
+        name: config.name.clone(),
+    }
+}
+
+fn baseline_pool_position(pool_position: &PoolPosition) -> PoolPositionV0 {
+    PoolPositionV0 {
+        bump: pool_position.bump,
+        pool_position_nft_account_bump: pool_position.pool_position_nft_account_bump,
+        pool_position_config_key: pool_position.pool_position_config_key,
+        manager_key: Pubkey::default(),
+        pool_position_nft_key: pool_position.pool_position_nft_key,
+        position_nft_mint_key: pool_position.position_nft_mint_key,
+        position_nft_account_key: pool_position.position_nft_account_key,
+        name: pool_position.name().to_string(),
+        fees_index0: pool_position.fees_index0,
+        fees_index1: pool_position.fees_index1,
+        liquidity: pool_position.liquidity,
+        vaults_initialized: pool_position.vaults_initialized != 0,
+        created_at: pool_position.created_at,
+    }
+}
+
+fn baseline_investor(investor: &Investor) -> InvestorV0 {
+    InvestorV0 {
+        bump: investor.bump,
+        authority: investor.authority,
+        pool_position_key: investor.pool_position_key,
+        init_liquidity: investor.init_liquidity,
+        liquidity: investor.liquidity,
+        fees_earned0: investor.fees_earned0,
+        fees_earned1: investor.fees_earned1,
+        fees_index0: investor.fees_index0,
+        fees_index1: investor.fees_index1,
+        is_manager: investor.is_manager != 0,
+    }
+}
+
+/// Balances of the fees accounts of `authority`, zero until `collect_fees` creates them
+fn fees_balances(env: &TestEnv, position: &PositionAccounts, authority: &Pubkey) -> (u64, u64) {
+    let balance = |mint| env.token_balance(&get_associated_token_address(authority, mint));
+    (balance(&position.pool.token_mint_0), balance(&position.pool.token_mint_1))
+}
+
+/// Queues `change` as the next change of the position, proposed by `proposer`
+fn queue_change(
+    env: &TestEnv,
+    position: &PositionAccounts,
+    proposer: &Pubkey,
+    change: ConfigChange
+) -> Instruction {
+    let config: PoolPositionConfig = env.account(&position.addresses().pool_position_config);
+    client::queue_config_change(proposer, position, config.next_config_change_id, change)
+}
+
+/// Queues `change` signed by `proposer`, waits for the timelock of the position and executes it
+fn config_change(
+    env: &mut TestEnv,
+    position: &PositionAccounts,
+    proposer: &Pubkey,
+    change: ConfigChange,
+    signers: &[&Keypair]
+) -> TransactionMetadata {
+    let config: PoolPositionConfig = env.account(&position.addresses().pool_position_config);
+    let change_id = config.next_config_change_id;
+    env.send_ok(&[queue_change(env, position, proposer, change)], signers);
+    env.warp_seconds(config.timelock_seconds);
+    let executor = env.payer.pubkey();
+    env.send_ok(&[client::execute_config_change(&executor, position, change_id, proposer)], &[])
+}
+
+/// Fees owed to `liquidity` for a fee growth of `fee_growth_x64`, rounded down like Raydium
+fn owed_fees(fee_growth_x64: u128, liquidity: u128) -> u64 {
+    ((fee_growth_x64 * liquidity) >> 64) as u64
+}
+
+/// Opens a ladder range of `weight_bps` over [-2400 * (index + 1), 2400 * (index + 1)] funded by
+/// the manager and adds it to `position`
+fn open_ladder_range(env: &mut TestEnv, position: &mut PositionAccounts, weight_bps: u32) {
+    let width = 2400 * (position.ladder.len() as i32 + 1);
+    let range_nft_mint = Keypair::new();
+    let range = LadderRangeKeys {
+        range: PositionRange { tick_lower_index: -width, tick_upper_index: width },
+        position_nft_mint: range_nft_mint.pubkey(),
+    };
+    env.send_ok(&[open_ladder_range_ix(env, position, &range, weight_bps)], &[&range_nft_mint]);
+    position.ladder.push(range);
+}
+
+fn open_ladder_range_ix(
+    env: &TestEnv,
+    position: &PositionAccounts,
+    range: &LadderRangeKeys,
+    weight_bps: u32
+) -> Instruction {
+    let manager = env.payer.pubkey();
+    client::open_ladder_range(
+        &manager,
+        position,
+        range,
+        weight_bps,
+        &get_associated_token_address(&manager, &position.pool.token_mint_0),
+        &get_associated_token_address(&manager, &position.pool.token_mint_1),
+        TOKEN,
+        TOKEN
+    )
+}
+
+fn range_personal_position(env: &TestEnv, position: &PositionAccounts, index: usize) -> u128 {
+    let range = position.ladder[index];
+    let raydium = RaydiumPosition::new(&position.pool, range.range, &range.position_nft_mint);
+    env.account::<PersonalPositionState>(&raydium.personal_position).liquidity
+}
+
+#[test]
+fn collect_fees_pays_the_liquidity_share_of_the_fee_growth() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+
+    let position_before: PoolPosition = env.load(&addresses.pool_position);
+    let investor_before = investor_account(&env, &position, &investor.pubkey());
+    env.set_fee_growth_global(&position.pool, FEE_GROWTH_X64, 2 * FEE_GROWTH_X64);
+    env.send_ok(&[client::collect_fees(&investor.pubkey(), &position)], &[&investor]);
+
+    let owed_0 = owed_fees(FEE_GROWTH_X64, position_before.liquidity);
+    let owed_1 = owed_fees(2 * FEE_GROWTH_X64, position_before.liquidity);
+    let (index_0, index_1) = fees_indexes(position_before.liquidity, owed_0, owed_1);
+    let position_after: PoolPosition = env.load(&addresses.pool_position);
+    assert_eq!(position_after.fees_index0, position_before.fees_index0 + index_0);
+    assert_eq!(position_after.fees_index1, position_before.fees_index1 + index_1);
+
+    let paid = fees_balances(&env, &position, &investor.pubkey());
+    let expected = (
+        calculate_fees(
+            investor_before.liquidity,
+            position_after.fees_index0,
+            investor_before.fees_index0
+        ),
+        calculate_fees(
+            investor_before.liquidity,
+            position_after.fees_index1,
+            investor_before.fees_index1
+        ),
+    );
+    assert!(expected.0 > 0 && expected.1 > expected.0);
+    assert_eq!(paid, expected);
+
+    // What was harvested and not paid out stays in the fees vaults for the other investors
+    assert_eq!(env.token_balance(&addresses.fees_vault_0), owed_0 - paid.0);
+    assert_eq!(env.token_balance(&addresses.fees_vault_1), owed_1 - paid.1);
+    let personal_position: PersonalPositionState = env.account(
+        &position.raydium().personal_position
+    );
+    assert_eq!((personal_position.token_fees_owed_0, personal_position.token_fees_owed_1), (0, 0));
+}
+
+#[test]
+fn collect_fees_harvests_token_fees_owed() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+    let position_before: PoolPosition = env.load(&addresses.pool_position);
+    let balances_before = fees_balances(&env, &position, &manager);
+
+    env.set_token_fees_owed(&position.pool, &position.raydium().personal_position, 12_345, 678);
+    env.send_ok(&[client::collect_fees(&manager, &position)], &[]);
+
+    let (index_0, index_1) = fees_indexes(position_before.liquidity, 12_345, 678);
+    let position_after: PoolPosition = env.load(&addresses.pool_position);
+    assert_eq!((position_after.fees_index0, position_after.fees_index1), (index_0, index_1));
+
+    // The manager holds all the liquidity and is paid everything but the index rounding
+    let balances_after = fees_balances(&env, &position, &manager);
+    let paid = (balances_after.0 - balances_before.0, balances_after.1 - balances_before.1);
+    assert_eq!(paid, (
+        calculate_fees(position_before.liquidity, index_0, 0),
+        calculate_fees(position_before.liquidity, index_1, 0),
+    ));
+    assert!(paid.0 <= 12_345 && 12_345 - paid.0 <= 1);
+    assert!(paid.1 <= 678 && 678 - paid.1 <= 1);
+}
+
+#[test]
+fn increase_liquidity_does_not_credit_fees_accrued_before_the_deposit() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+
+    // Fees accrue to the manager alone, then an investor joins and harvests them
+    env.set_fee_growth_global(&position.pool, FEE_GROWTH_X64, FEE_GROWTH_X64);
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    let investor_after = investor_account(&env, &position, &investor.pubkey());
+    assert!(pool_position.fees_index0 > 0 && pool_position.fees_index1 > 0);
+    assert_eq!(investor_after.fees_index0, pool_position.fees_index0);
+    assert_eq!(investor_after.fees_index1, pool_position.fees_index1);
+    assert_eq!((investor_after.fees_earned0, investor_after.fees_earned1), (0, 0));
+
+    env.send_ok(&[client::collect_fees(&investor.pubkey(), &position)], &[&investor]);
+    assert_eq!(fees_balances(&env, &position, &investor.pubkey()), (0, 0));
+
+    // The manager is paid the fees of its liquidity
+    let manager_account = investor_account(&env, &position, &manager);
+    let balances_before = fees_balances(&env, &position, &manager);
+    env.send_ok(&[client::collect_fees(&manager, &position)], &[]);
+    let balances_after = fees_balances(&env, &position, &manager);
+    let expected_0 = calculate_fees(
+        manager_account.liquidity,
+        pool_position.fees_index0,
+        manager_account.fees_index0
+    );
+    assert!(expected_0 > 0);
+    assert_eq!(balances_after.0 - balances_before.0, expected_0);
+}
+
+#[test]
+fn fees_stop_accruing_when_the_price_leaves_the_range() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+
+    env.set_sqrt_price(&position.pool, tick_math::get_sqrt_price_at_tick(2000).unwrap());
+    env.set_fee_growth_global(&position.pool, FEE_GROWTH_X64, FEE_GROWTH_X64);
+    env.send_ok(&[client::collect_fees(&investor.pubkey(), &position)], &[&investor]);
+
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    assert_eq!((pool_position.fees_index0, pool_position.fees_index1), (0, 0));
+    assert_eq!(fees_balances(&env, &position, &investor.pubkey()), (0, 0));
+}
+
+#[test]
+fn swaps_accrue_fee_growth_on_the_input_token() {
+    let MockPosition { mut env, position } = open_position();
+    let pool = position.pool;
+    let manager = env.payer.pubkey();
+
+    let trader = env.new_funded_keypair(10 * TOKEN);
+    env.create_token_account(&pool.token_mint_0, &trader.pubkey(), 10 * TOKEN);
+    env.create_token_account(&pool.token_mint_1, &trader.pubkey(), 0);
+    env.raydium_swap(&trader, &pool, &pool.token_mint_0, TOKEN / 10);
+
+    let pool_state = env.pool_state(&pool.pool_state);
+    assert!(pool_state.fee_growth_global_0_x64 > 0);
+    assert_eq!(pool_state.fee_growth_global_1_x64, 0);
+    assert!(pool_state.sqrt_price_x64 < SQRT_PRICE_1_X64);
+
+    let balances_before = fees_balances(&env, &position, &manager);
+    env.send_ok(&[client::collect_fees(&manager, &position)], &[]);
+    let balances_after = fees_balances(&env, &position, &manager);
+    assert!(balances_after.0 > balances_before.0);
+    assert_eq!(balances_after.1, balances_before.1);
+}
+
+#[test]
+fn get_investor_value_reports_what_collect_fees_pays() {
+    let MockPosition { mut env, position } = open_position();
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+    env.set_fee_growth_global(&position.pool, FEE_GROWTH_X64, 2 * FEE_GROWTH_X64);
+    let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(300).unwrap();
+    env.set_sqrt_price(&position.pool, sqrt_price_x64);
+
+    // Valued in token_0, priced through the position pool itself
+    let pool = position.pool;
+    let value: InvestorValue = env.view(
+        client::get_investor_value(
+            &investor.pubkey(),
+            &position,
+            &pool.token_mint_0,
+            &pool.pool_state,
+            &pool.pool_state
+        )
+    );
+
+    let investor_account = investor_account(&env, &position, &investor.pubkey());
+    let pool_position: PoolPosition = env.load(&position.addresses().pool_position);
+    let (amount_0, amount_1) = liquidity_math::get_amounts_for_liquidity(
+        sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(position.range.tick_lower_index).unwrap(),
+        tick_math::get_sqrt_price_at_tick(position.range.tick_upper_index).unwrap(),
+        investor_account.liquidity,
+        false
+    ).unwrap();
+    assert_eq!(value.liquidity, investor_account.liquidity);
+    assert_eq!(value.position_liquidity, pool_position.liquidity);
+    assert_eq!(
+        u128::from(value.share_bps),
+        (investor_account.liquidity * 10_000) / pool_position.liquidity
+    );
+    assert_eq!((value.amount_0, value.amount_1), (amount_0, amount_1));
+    assert!(value.fees_0 > 0 && value.fees_1 > value.fees_0);
+    let value_1 = quote_at_sqrt_price(amount_1 + value.fees_1, sqrt_price_x64, false).unwrap();
+    assert_eq!(value.value_in_stable, amount_0 + value.fees_0 + value_1);
+
+    env.send_ok(&[client::collect_fees(&investor.pubkey(), &position)], &[&investor]);
+    assert_eq!(
+        fees_balances(&env, &position, &investor.pubkey()),
+        (value.fees_0, value.fees_1)
+    );
+}
+
+#[test]
+fn snapshot_nav_values_the_position_once_per_interval() {
+    let MockPosition { mut env, position } = open_position();
+    let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(300).unwrap();
+    env.set_sqrt_price(&position.pool, sqrt_price_x64);
+    // The clock of a fresh env starts at 0, the timestamp of an empty snapshot
+    env.warp_seconds(NAV_SNAPSHOT_INTERVAL_SECONDS);
+
+    let manager = env.payer.pubkey();
+    let pool = position.pool;
+    let snapshot_nav = client::snapshot_nav(
+        &manager,
+        &position,
+        &pool.token_mint_0,
+        &pool,
+        &pool,
+        PriceSource::Spot
+    );
+    env.send_ok(&[snapshot_nav.clone()], &[]);
+
+    let pool_position: PoolPosition = env.load(&position.addresses().pool_position);
+    let (amount_0, amount_1) = liquidity_math::get_amounts_for_liquidity(
+        sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(position.range.tick_lower_index).unwrap(),
+        tick_math::get_sqrt_price_at_tick(position.range.tick_upper_index).unwrap(),
+        pool_position.liquidity,
+        false
+    ).unwrap();
+    let nav = amount_0 + quote_at_sqrt_price(amount_1, sqrt_price_x64, false).unwrap();
+    let snapshot = pool_position.latest_nav_snapshot();
+    assert_eq!(snapshot.nav, nav);
+    assert_eq!(snapshot.liquidity, pool_position.liquidity);
+    assert_eq!(snapshot.nav_per_liquidity_x64, nav_per_liquidity_x64(nav, snapshot.liquidity));
+
+    env.send_err(&[snapshot_nav.clone()], &[], "NavSnapshotTooEarly");
+    env.warp_seconds(NAV_SNAPSHOT_INTERVAL_SECONDS);
+    env.send_ok(&[snapshot_nav], &[]);
+    let pool_position: PoolPosition = env.load(&position.addresses().pool_position);
+    assert_eq!(
+        pool_position.latest_nav_snapshot().timestamp,
+        snapshot.timestamp + NAV_SNAPSHOT_INTERVAL_SECONDS
+    );
+}
+
+#[test]
+fn positions_are_only_created_on_raydium() {
+    let mut env = TestEnv::with_mock_clmm();
+    let amm_config = env.load_fixture(AMM_CONFIG);
+    let (mint_a, mint_b) = (env.create_mint(9), env.create_mint(9));
+    let pool = env.create_pool(
+        amm_config,
+        AMM_CONFIG_TICK_SPACING,
+        mint_a,
+        mint_b,
+        SQRT_PRICE_1_X64
+    );
+    let position = PositionAccounts {
+        name: "orca".to_string(),
+        pool,
+        range: PositionRange { tick_lower_index: -1200, tick_upper_index: 1200 },
+        position_nft_mint: Pubkey::new_unique(),
+        ladder: Vec::new(),
+    };
+
+    // `dex` is the last argument, the builder always passes `Dex::RaydiumClmm`, the only DEX
+    let mut create_position = client::create_position(&env.payer.pubkey(), &position);
+    *create_position.data.last_mut().unwrap() = Dex::RaydiumClmm as u8 + 1;
+    env.send_err(&[create_position], &[], "InstructionDidNotDeserialize");
+    assert!(env.svm.get_account(&position.addresses().pool_position_config).is_none());
+}
+
+#[test]
+fn deposits_respect_the_limits_of_the_position() {
+    let MockPosition { mut env, position } = open_position();
+    let manager = env.payer.pubkey();
+    let position_liquidity = env.load::<PoolPosition>(&position.addresses().pool_position)
+        .liquidity;
+    let first = add_investor(&mut env, &position, TOKEN / 2);
+    let first_liquidity = investor_account(&env, &position, &first.pubkey()).liquidity;
+
+    // Room for one more investor of the same size, which is also the investor cap
+    let limits = ConfigChange::DepositLimits {
+        max_total_liquidity: position_liquidity + 2 * first_liquidity,
+        max_investor_liquidity: first_liquidity,
+        min_deposit: SOL_DEPOSIT,
+    };
+    config_change(&mut env, &position, &manager, limits, &[]);
+    let config: PoolPositionConfig = env.account(&position.addresses().pool_position_config);
+    assert_eq!(config.max_investor_liquidity, first_liquidity);
+    assert_eq!(config.min_deposit, SOL_DEPOSIT);
+
+    let investor = env.new_funded_keypair(10 * TOKEN);
+    env.send_ok(
+        &[client::create_investor_position(&investor.pubkey(), &position, &[])],
+        &[&investor]
+    );
+    env.send_err(
+        &[client::deposit(&investor.pubkey(), &position, &native_mint::ID, SOL_DEPOSIT - 1)],
+        &[&investor],
+        "DepositBelowMinimum"
+    );
+    env.send_ok(
+        &[client::deposit(&investor.pubkey(), &position, &native_mint::ID, SOL_DEPOSIT)],
+        &[&investor]
+    );
+
+    let addresses = InvestorAddresses::new(
+        &position.addresses().pool_position_config,
+        &investor.pubkey()
+    );
+    let bounds = LiquidityBounds {
+        min_liquidity_out: 1,
+        expected_sqrt_price_x64: env.pool_state(&position.pool.pool_state).sqrt_price_x64,
+        max_price_deviation: 100,
+    };
+    env.set_token_balance(&addresses.deposit_token_0_account, TOKEN);
+    env.set_token_balance(&addresses.deposit_token_1_account, TOKEN);
+    env.send_err(
+        &[client::increase_liquidity(&investor.pubkey(), &position, bounds)],
+        &[&investor],
+        "InvestorLiquidityCapExceeded"
+    );
+    env.set_token_balance(&addresses.deposit_token_0_account, TOKEN / 2);
+    env.set_token_balance(&addresses.deposit_token_1_account, TOKEN / 2);
+    env.send_ok(
+        &[client::increase_liquidity(&investor.pubkey(), &position, bounds)],
+        &[&investor]
+    );
+
+    // The total cap is reached, a third investor cannot add even a small amount
+    let late = env.new_funded_keypair(10 * TOKEN);
+    env.send_ok(&[client::create_investor_position(&late.pubkey(), &position, &[])], &[&late]);
+    let late_addresses = InvestorAddresses::new(
+        &position.addresses().pool_position_config,
+        &late.pubkey()
+    );
+    env.set_token_balance(&late_addresses.deposit_token_0_account, TOKEN / 10);
+    env.set_token_balance(&late_addresses.deposit_token_1_account, TOKEN / 10);
+    env.send_err(
+        &[client::increase_liquidity(&late.pubkey(), &position, bounds)],
+        &[&late],
+        "TotalLiquidityCapExceeded"
+    );
+
+    // Zero caps lift the limits
+    config_change(&mut env, &position, &manager, NO_DEPOSIT_LIMITS, &[]);
+    env.send_ok(&[client::increase_liquidity(&late.pubkey(), &position, bounds)], &[&late]);
+}
+
+#[test]
+fn only_the_manager_updates_the_deposit_limits() {
+    let MockPosition { mut env, position } = open_position();
+    let investor = env.new_funded_keypair(TOKEN);
+    env.send_err(
+        &[queue_change(&env, &position, &investor.pubkey(), NO_DEPOSIT_LIMITS)],
+        &[&investor],
+        "Unauthorized"
+    );
+}
+
+#[test]
+fn allowlisted_position_requires_a_proof() {
+    let MockPosition { mut env, position } = open_position();
+    let manager = env.payer.pubkey();
+    let partner = env.new_funded_keypair(TOKEN);
+    let outsider = env.new_funded_keypair(TOKEN);
+    let tree = AllowlistTree::new(&[partner.pubkey(), Pubkey::new_unique(), Pubkey::new_unique()])
+        .unwrap();
+    let allowlist_root = ConfigChange::AllowlistRoot { allowlist_root: Some(tree.root()) };
+    config_change(&mut env, &position, &manager, allowlist_root, &[]);
+
+    let partner_proof = tree.proof(&partner.pubkey()).unwrap();
+    env.send_err(
+        &[client::create_investor_position(&outsider.pubkey(), &position, &partner_proof)],
+        &[&outsider],
+        "NotAllowlisted"
+    );
+    env.send_err(
+        &[client::create_investor_position(&partner.pubkey(), &position, &[])],
+        &[&partner],
+        "NotAllowlisted"
+    );
+    env.send_ok(
+        &[client::create_investor_position(&partner.pubkey(), &position, &partner_proof)],
+        &[&partner]
+    );
+    let investor = investor_account(&env, &position, &partner.pubkey());
+    assert_eq!(investor.authority, partner.pubkey());
+
+    // Without a root the position is open again
+    env.send_err(
+        &[queue_change(&env, &position, &outsider.pubkey(), NO_ALLOWLIST)],
+        &[&outsider],
+        "Unauthorized"
+    );
+    config_change(&mut env, &position, &manager, NO_ALLOWLIST, &[]);
+    env.send_ok(
+        &[client::create_investor_position(&outsider.pubkey(), &position, &[])],
+        &[&outsider]
+    );
+}
+
+#[test]
+fn strict_lockup_blocks_withdrawals_until_it_ends() {
+    let MockPosition { mut env, position } = open_position();
+    let manager = env.payer.pubkey();
+    let lockup = ConfigChange::Lockup { lockup_seconds: 3600, early_exit_penalty_bps: 0 };
+    config_change(&mut env, &position, &manager, lockup, &[]);
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+    let liquidity = investor_account(&env, &position, &investor.pubkey()).liquidity;
+
+    let decrease = client::decrease_liquidity(&investor.pubkey(), &position, liquidity, 0, 0);
+    env.send_err(&[decrease.clone()], &[&investor], "LiquidityLocked");
+    env.warp_seconds(3600);
+    env.send_ok(&[decrease], &[&investor]);
+
+    assert_eq!(investor_account(&env, &position, &investor.pubkey()).liquidity, 0);
+    let (balance_0, balance_1) = fees_balances(&env, &position, &investor.pubkey());
+    // Everything deposited comes back but the rounding of the liquidity
+    assert!(balance_0 > TOKEN / 2 - 10 && balance_0 <= TOKEN / 2);
+    assert!(balance_1 > TOKEN / 2 - 10 && balance_1 <= TOKEN / 2);
+}
+
+#[test]
+fn early_exit_penalty_is_paid_to_the_remaining_investors() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+    let lockup = ConfigChange::Lockup { lockup_seconds: 3600, early_exit_penalty_bps: 100 };
+    config_change(&mut env, &position, &manager, lockup, &[]);
+    let staying = add_investor(&mut env, &position, TOKEN / 2);
+    let leaving = add_investor(&mut env, &position, TOKEN / 2);
+    let liquidity = investor_account(&env, &position, &leaving.pubkey()).liquidity;
+
+    env.send_ok(
+        &[client::decrease_liquidity(&leaving.pubkey(), &position, liquidity, 0, 0)],
+        &[&leaving]
+    );
+
+    let penalty_0 = env.token_balance(&addresses.fees_vault_0);
+    let penalty_1 = env.token_balance(&addresses.fees_vault_1);
+    let (received_0, received_1) = fees_balances(&env, &position, &leaving.pubkey());
+    assert!(penalty_0 > 0 && penalty_1 > 0);
+    assert_eq!(penalty_0, early_exit_penalty(received_0 + penalty_0, 100));
+    assert_eq!(penalty_1, early_exit_penalty(received_1 + penalty_1, 100));
+
+    // Credited over the liquidity left, which the leaving investor no longer holds
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    let (index_0, index_1) = fees_indexes(pool_position.liquidity, penalty_0, penalty_1);
+    assert_eq!((pool_position.fees_index0, pool_position.fees_index1), (index_0, index_1));
+    let leaving_account = investor_account(&env, &position, &leaving.pubkey());
+    assert_eq!(leaving_account.fees_index0, index_0);
+
+    let staying_liquidity = investor_account(&env, &position, &staying.pubkey()).liquidity;
+    env.send_ok(&[client::collect_fees(&staying.pubkey(), &position)], &[&staying]);
+    assert_eq!(
+        fees_balances(&env, &position, &staying.pubkey()),
+        (
+            calculate_fees(staying_liquidity, index_0, 0),
+            calculate_fees(staying_liquidity, index_1, 0),
+        )
+    );
+
+    // Past the lockup no penalty is due
+    env.warp_seconds(3600);
+    let staying_liquidity = investor_account(&env, &position, &staying.pubkey()).liquidity;
+    let vault_0_before = env.token_balance(&addresses.fees_vault_0);
+    env.send_ok(
+        &[client::decrease_liquidity(&staying.pubkey(), &position, staying_liquidity, 0, 0)],
+        &[&staying]
+    );
+    assert_eq!(env.token_balance(&addresses.fees_vault_0), vault_0_before);
+}
+
+#[test]
+fn withdrawal_minimums_apply_to_the_amounts_net_of_the_penalty() {
+    let MockPosition { mut env, position } = open_position();
+    let manager = env.payer.pubkey();
+    let lockup = ConfigChange::Lockup { lockup_seconds: 3600, early_exit_penalty_bps: 100 };
+    config_change(&mut env, &position, &manager, lockup, &[]);
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+    let liquidity = investor_account(&env, &position, &investor.pubkey()).liquidity;
+
+    // The removed liquidity is paid out net of the 1% penalty, rounded up against the investor
+    let (gross_0, gross_1) = liquidity_math::get_amounts_for_liquidity(
+        env.pool_state(&position.pool.pool_state).sqrt_price_x64,
+        tick_math::get_sqrt_price_at_tick(position.range.tick_lower_index).unwrap(),
+        tick_math::get_sqrt_price_at_tick(position.range.tick_upper_index).unwrap(),
+        liquidity,
+        false
+    ).unwrap();
+    let net_0 = gross_0 - (gross_0 * 100).div_ceil(10_000);
+    let net_1 = gross_1 - (gross_1 * 100).div_ceil(10_000);
+
+    for (amount_0_min, amount_1_min) in [(net_0 + 1, net_1), (net_0, net_1 + 1)] {
+        env.send_err(
+            &[
+                client::decrease_liquidity(
+                    &investor.pubkey(),
+                    &position,
+                    liquidity,
+                    amount_0_min,
+                    amount_1_min
+                ),
+            ],
+            &[&investor],
+            "AmountSlippageExceeded"
+        );
+    }
+    env.send_ok(
+        &[client::decrease_liquidity(&investor.pubkey(), &position, liquidity, net_0, net_1)],
+        &[&investor]
+    );
+    assert_eq!(fees_balances(&env, &position, &investor.pubkey()), (net_0, net_1));
+}
+
+#[test]
+fn manager_handover_takes_a_proposal_and_an_acceptance() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+    let successor = env.new_funded_keypair(TOKEN);
+    let outsider = env.new_funded_keypair(TOKEN);
+    let manager_liquidity = investor_account(&env, &position, &manager).liquidity;
+    let strategist = Pubkey::new_unique();
+    let roles = PositionRoles { strategist, guardian: manager, fee_recipient: manager };
+    config_change(&mut env, &position, &manager, ConfigChange::Roles { roles }, &[]);
+
+    let outsider_proposal = ConfigChange::PendingManager { new_manager: outsider.pubkey() };
+    env.send_err(
+        &[queue_change(&env, &position, &outsider.pubkey(), outsider_proposal)],
+        &[&outsider],
+        "Unauthorized"
+    );
+
+    // Proposing the current manager withdraws a proposal
+    let proposal = ConfigChange::PendingManager { new_manager: successor.pubkey() };
+    let meta = config_change(&mut env, &position, &manager, proposal, &[]);
+    assert_eq!(events::<ManagerProposed>(&meta).len(), 1);
+    let withdrawal = ConfigChange::PendingManager { new_manager: manager };
+    let meta = config_change(&mut env, &position, &manager, withdrawal, &[]);
+    assert!(events::<ManagerProposed>(&meta).is_empty());
+    assert_eq!(events::<ManagerProposalWithdrawn>(&meta).len(), 1);
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(config.pending_manager_key, None);
+
+    config_change(&mut env, &position, &manager, proposal, &[]);
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(config.pending_manager_key, Some(successor.pubkey()));
+    assert_eq!(config.manager_key, manager);
+
+    env.send_err(
+        &[client::accept_manager(&outsider.pubkey(), &position, &manager)],
+        &[&outsider],
+        "Unauthorized"
+    );
+    env.send_ok(
+        &[client::accept_manager(&successor.pubkey(), &position, &manager)],
+        &[&successor]
+    );
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(config.manager_key, successor.pubkey());
+    assert_eq!(config.pending_manager_key, None);
+    // The roles of the previous manager go to the successor, the others stay
+    assert_eq!(config.roles, PositionRoles {
+        strategist,
+        guardian: successor.pubkey(),
+        fee_recipient: successor.pubkey(),
+    });
+    env.send_err(&[client::set_paused(&manager, &position, true)], &[], "Unauthorized");
+    env.send_ok(
+        &[client::set_paused(&successor.pubkey(), &position, true)],
+        &[&successor]
+    );
+
+    // Only the flag moves, the liquidity stays with the account that deposited it
+    let previous = investor_account(&env, &position, &manager);
+    assert_eq!(previous.is_manager, 0);
+    assert_eq!(previous.liquidity, manager_liquidity);
+    let current = investor_account(&env, &position, &successor.pubkey());
+    assert_eq!(current.is_manager, 1);
+    assert_eq!(current.authority, successor.pubkey());
+    assert_eq!(current.liquidity, 0);
+
+    env.send_err(
+        &[queue_change(&env, &position, &manager, NO_DEPOSIT_LIMITS)],
+        &[],
+        "Unauthorized"
+    );
+    config_change(&mut env, &position, &successor.pubkey(), NO_DEPOSIT_LIMITS, &[&successor]);
+}
+
+#[test]
+fn roles_gate_operations_pauses_and_manager_fees() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(config.roles, PositionRoles {
+        strategist: manager,
+        guardian: manager,
+        fee_recipient: manager,
+    });
+    assert!(!config.paused);
+
+    let strategist = env.new_funded_keypair(TOKEN);
+    let guardian = env.new_funded_keypair(TOKEN);
+    let fee_recipient = Pubkey::new_unique();
+    let roles = PositionRoles {
+        strategist: strategist.pubkey(),
+        guardian: guardian.pubkey(),
+        fee_recipient,
+    };
+    env.send_err(
+        &[queue_change(&env, &position, &strategist.pubkey(), ConfigChange::Roles { roles })],
+        &[&strategist],
+        "Unauthorized"
+    );
+    config_change(&mut env, &position, &manager, ConfigChange::Roles { roles }, &[]);
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(config.roles, roles);
+
+    // Operations need the strategist, pausing needs the guardian
+    let pool = position.pool;
+    let snapshot_nav = |signer: &Pubkey| {
+        client::snapshot_nav(signer, &position, &pool.token_mint_0, &pool, &pool, PriceSource::Spot)
+    };
+    env.send_err(&[snapshot_nav(&manager)], &[], "Unauthorized");
+    env.warp_seconds(NAV_SNAPSHOT_INTERVAL_SECONDS);
+    env.send_ok(&[snapshot_nav(&strategist.pubkey())], &[&strategist]);
+    env.send_err(&[client::set_paused(&manager, &position, true)], &[], "Unauthorized");
+
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+    env.send_ok(&[client::set_paused(&guardian.pubkey(), &position, true)], &[&guardian]);
+    let investor_addresses = InvestorAddresses::new(
+        &addresses.pool_position_config,
+        &investor.pubkey()
+    );
+    env.set_token_balance(&investor_addresses.deposit_token_0_account, TOKEN / 2);
+    env.set_token_balance(&investor_addresses.deposit_token_1_account, TOKEN / 2);
+    let bounds = LiquidityBounds {
+        min_liquidity_out: 1,
+        expected_sqrt_price_x64: SQRT_PRICE_1_X64,
+        max_price_deviation: 100,
+    };
+    env.send_err(
+        &[client::increase_liquidity(&investor.pubkey(), &position, bounds)],
+        &[&investor],
+        "PositionPaused"
+    );
+    // Withdrawals stay open while paused
+    let liquidity = investor_account(&env, &position, &investor.pubkey()).liquidity;
+    env.send_ok(
+        &[client::decrease_liquidity(&investor.pubkey(), &position, liquidity, 0, 0)],
+        &[&investor]
+    );
+    env.send_ok(&[client::set_paused(&guardian.pubkey(), &position, false)], &[&guardian]);
+    env.send_ok(
+        &[client::increase_liquidity(&investor.pubkey(), &position, bounds)],
+        &[&investor]
+    );
+
+    // The fees of the manager are paid to the fee recipient only
+    env.set_token_fees_owed(&position.pool, &position.raydium().personal_position, 12_345, 678);
+    env.send_err(&[client::collect_fees(&manager, &position)], &[], "ConstraintAddress");
+    let manager_before = fees_balances(&env, &position, &manager);
+    env.send_ok(&[client::collect_fees_to(&manager, &position, &fee_recipient)], &[]);
+    assert_eq!(fees_balances(&env, &position, &manager), manager_before);
+    let (paid_0, paid_1) = fees_balances(&env, &position, &fee_recipient);
+    assert!(paid_0 > 0 && paid_1 > 0);
+}
+
+#[test]
+fn config_changes_wait_for_the_timelock() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+    let guardian = env.new_funded_keypair(TOKEN);
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(config.timelock_seconds, MIN_TIMELOCK_SECONDS);
+    let roles = PositionRoles { guardian: guardian.pubkey(), ..config.roles };
+    config_change(&mut env, &position, &manager, ConfigChange::Roles { roles }, &[]);
+    let timelock_seconds = 2 * MIN_TIMELOCK_SECONDS;
+    let timelock = ConfigChange::Timelock { timelock_seconds };
+    config_change(&mut env, &position, &manager, timelock, &[]);
+    let invalid = ConfigChange::Lockup { lockup_seconds: 0, early_exit_penalty_bps: 10_001 };
+    env.send_err(
+        &[client::queue_config_change(&manager, &position, 2, invalid)],
+        &[],
+        "InvalidEarlyExitPenalty"
+    );
+    let invalid = ConfigChange::Timelock { timelock_seconds: MIN_TIMELOCK_SECONDS - 1 };
+    env.send_err(
+        &[client::queue_config_change(&manager, &position, 2, invalid)],
+        &[],
+        "InvalidTimelock"
+    );
+
+    let limits = ConfigChange::DepositLimits {
+        max_total_liquidity: 1,
+        max_investor_liquidity: 1,
+        min_deposit: 1,
+    };
+    env.send_ok(&[client::queue_config_change(&manager, &position, 2, limits)], &[]);
+    let pending_address = pda::pending_config_change(&addresses.pool_position_config, 2).0;
+    let pending: PendingConfigChange = env.account(&pending_address);
+    assert_eq!(pending.change, limits);
+    assert_eq!(pending.executable_at, pending.queued_at + timelock_seconds);
+
+    let outsider = env.new_funded_keypair(TOKEN);
+    env.send_err(
+        &[client::execute_config_change(&outsider.pubkey(), &position, 2, &manager)],
+        &[&outsider],
+        "ConfigChangeTimelocked"
+    );
+    env.warp_seconds(timelock_seconds);
+    env.send_ok(
+        &[client::execute_config_change(&outsider.pubkey(), &position, 2, &manager)],
+        &[&outsider]
+    );
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!((config.max_total_liquidity, config.min_deposit), (1, 1));
+    assert_eq!(env.svm.get_balance(&pending_address).unwrap_or(0), 0);
+
+    // The guardian can drop a change of the manager before it applies
+    let lockup = ConfigChange::Lockup { lockup_seconds: 3600, early_exit_penalty_bps: 0 };
+    env.send_ok(&[client::queue_config_change(&manager, &position, 3, lockup)], &[]);
+    env.send_err(
+        &[client::cancel_config_change(&outsider.pubkey(), &position, 3, &manager)],
+        &[&outsider],
+        "Unauthorized"
+    );
+    env.send_ok(
+        &[client::cancel_config_change(&guardian.pubkey(), &position, 3, &manager)],
+        &[&guardian]
+    );
+    env.warp_seconds(timelock_seconds);
+    env.send_err(
+        &[client::execute_config_change(&manager, &position, 3, &manager)],
+        &[],
+        "AccountNotInitialized"
+    );
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(config.lockup_seconds, 0);
+    assert_eq!(config.next_config_change_id, 4);
+}
+
+#[test]
+fn ladder_ranges_split_deposits_and_share_fees_over_the_whole_ladder() {
+    let MockPosition { mut env, mut position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+    let core_liquidity = env.load::<PoolPosition>(&addresses.pool_position).liquidity;
+
+    open_ladder_range(&mut env, &mut position, 5000);
+    let ladder_range: LadderRange = env.account(&pda::ladder_range(&addresses.pool_position, 0).0);
+    assert_eq!((ladder_range.index, ladder_range.weight_bps), (0, 5000));
+    assert_eq!(ladder_range.position_nft_mint_key, position.ladder[0].position_nft_mint);
+    let range_liquidity = range_personal_position(&env, &position, 0);
+    assert_eq!(range_liquidity, ladder_range_liquidity(core_liquidity, 5000));
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    assert_eq!(pool_position.ladder_ranges, 1);
+    assert_eq!(pool_position.liquidity, core_liquidity + range_liquidity);
+    assert_eq!(investor_account(&env, &position, &manager).liquidity, pool_position.liquidity);
+
+    // A deposit adds liquidity to every leg in proportion of its weight
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+    let investor_liquidity = investor_account(&env, &position, &investor.pubkey()).liquidity;
+    let core_added = env
+        .account::<PersonalPositionState>(&position.raydium().personal_position)
+        .liquidity - core_liquidity;
+    let range_added = range_personal_position(&env, &position, 0) - range_liquidity;
+    assert!(core_added > 0);
+    assert_eq!(range_added, ladder_range_liquidity(core_added, 5000));
+    assert_eq!(investor_liquidity, core_added + range_added);
+
+    // Fees of a range are shared over the liquidity of the whole ladder
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    let range = position.ladder[0];
+    let range_raydium = RaydiumPosition::new(&position.pool, range.range, &range.position_nft_mint);
+    env.set_token_fees_owed(&position.pool, &range_raydium.personal_position, 100_000, 0);
+    env.send_ok(&[client::collect_fees(&investor.pubkey(), &position)], &[&investor]);
+    let (index_0, _) = fees_indexes(pool_position.liquidity, 100_000, 0);
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    assert_eq!(pool_position.fees_index0, index_0);
+    let paid = fees_balances(&env, &position, &investor.pubkey());
+    assert!(paid.0 > 0);
+    assert_eq!(paid, (calculate_fees(investor_liquidity, index_0, 0), 0));
+
+    // Withdrawing takes the investor share out of every leg
+    env.send_ok(
+        &[client::decrease_liquidity(&investor.pubkey(), &position, investor_liquidity, 0, 0)],
+        &[&investor]
+    );
+    assert_eq!(investor_account(&env, &position, &investor.pubkey()).liquidity, 0);
+    // Each leg rounds its share down, leaving at most a unit of liquidity per leg behind
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    let manager_liquidity = investor_account(&env, &position, &manager).liquidity;
+    assert!(pool_position.liquidity >= manager_liquidity);
+    assert!(pool_position.liquidity - manager_liquidity <= 2);
+    let core_left = env
+        .account::<PersonalPositionState>(&position.raydium().personal_position)
+        .liquidity;
+    assert_eq!(pool_position.liquidity, core_left + range_personal_position(&env, &position, 0));
+    let withdrawn = fees_balances(&env, &position, &investor.pubkey());
+    assert!(withdrawn.0 > paid.0 && withdrawn.1 > paid.1);
+}
+
+#[test]
+fn ladder_ranges_cannot_stand_in_for_the_core_position() {
+    let MockPosition { mut env, mut position } = open_position();
+    let addresses = position.addresses();
+    open_ladder_range(&mut env, &mut position, 5000);
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+    let liquidity = env.load::<PoolPosition>(&addresses.pool_position).liquidity;
+
+    // Range 0 passed as the core position while still listed in the ladder
+    let duplicated = PositionAccounts {
+        range: position.ladder[0].range,
+        position_nft_mint: position.ladder[0].position_nft_mint,
+        ..position.clone()
+    };
+    let investor_addresses = InvestorAddresses::new(
+        &addresses.pool_position_config,
+        &investor.pubkey()
+    );
+    env.set_token_balance(&investor_addresses.deposit_token_0_account, TOKEN / 2);
+    env.set_token_balance(&investor_addresses.deposit_token_1_account, TOKEN / 2);
+    let bounds = LiquidityBounds {
+        min_liquidity_out: 1,
+        expected_sqrt_price_x64: SQRT_PRICE_1_X64,
+        max_price_deviation: 100,
+    };
+    env.send_err(
+        &[client::increase_liquidity(&investor.pubkey(), &duplicated, bounds)],
+        &[&investor],
+        "InvalidClmmAccount"
+    );
+    env.send_err(
+        &[client::collect_fees(&investor.pubkey(), &duplicated)],
+        &[&investor],
+        "InvalidClmmAccount"
+    );
+    assert_eq!(env.load::<PoolPosition>(&addresses.pool_position).liquidity, liquidity);
+}
+
+#[test]
+fn ladder_ranges_open_before_investors_up_to_the_maximum() {
+    let MockPosition { mut env, mut position } = open_position();
+    let next_nft_mint = Keypair::new();
+    let next_range = LadderRangeKeys {
+        range: PositionRange { tick_lower_index: -2400, tick_upper_index: 2400 },
+        position_nft_mint: next_nft_mint.pubkey(),
+    };
+    env.send_err(
+        &[open_ladder_range_ix(&env, &position, &next_range, 0)],
+        &[&next_nft_mint],
+        "InvalidLadderWeight"
+    );
+
+    for _ in 0..MAX_LADDER_RANGES {
+        open_ladder_range(&mut env, &mut position, 2500);
+    }
+    let pool_position: PoolPosition = env.load(&position.addresses().pool_position);
+    assert_eq!(pool_position.ladder_ranges, MAX_LADDER_RANGES);
+    env.send_err(
+        &[open_ladder_range_ix(&env, &position, &next_range, 2500)],
+        &[&next_nft_mint],
+        "LadderFull"
+    );
+
+    // Investors hold liquidity of the ladder as it was when they joined
+    let MockPosition { mut env, position } = open_position();
+    add_investor(&mut env, &position, TOKEN / 2);
+    env.send_err(
+        &[open_ladder_range_ix(&env, &position, &next_range, 2500)],
+        &[&next_nft_mint],
+        "LadderHasInvestors"
+    );
+}
+
+#[test]
+fn only_allowlisted_clmm_programs_are_accepted() {
+    let MockPosition { mut env, position } = open_position();
+    let admin = env.payer.pubkey();
+    let protocol_config: ProtocolConfig = env.account(&pda::protocol_config().0);
+    assert_eq!(protocol_config.admin, admin);
+    assert_eq!(protocol_config.clmm_programs, vec![raydium_clmm_cpi::ID]);
+
+    // Another executable program passed as the CLMM program
+    let mut collect_fees = client::collect_fees(&admin, &position);
+    for meta in collect_fees.accounts.iter_mut() {
+        if meta.pubkey == raydium_clmm_cpi::ID {
+            meta.pubkey = mpl_token_metadata::ID;
+        }
+    }
+    env.send_err(&[collect_fees], &[], "ClmmProgramNotAllowed");
+
+    // Only the admin replaces the allowlist, up to its capacity
+    let outsider = env.new_funded_keypair(TOKEN);
+    env.send_err(
+        &[client::set_clmm_programs(&outsider.pubkey(), vec![Pubkey::new_unique()])],
+        &[&outsider],
+        "Unauthorized"
+    );
+    env.send_err(
+        &[client::set_clmm_programs(&admin, (0..5).map(|_| Pubkey::new_unique()).collect())],
+        &[],
+        "TooManyClmmPrograms"
+    );
+
+    // Positions on a program dropped from the allowlist are frozen until it is allowed again
+    let fork = Pubkey::new_unique();
+    env.send_ok(&[client::set_clmm_programs(&admin, vec![fork])], &[]);
+    env.send_err(&[client::collect_fees(&admin, &position)], &[], "ClmmProgramNotAllowed");
+    env.send_ok(&[client::set_clmm_programs(&admin, vec![fork, raydium_clmm_cpi::ID])], &[]);
+    env.send_ok(&[client::collect_fees(&admin, &position)], &[]);
+
+    // The protocol config is created once
+    let initialize = client::initialize_protocol_config(&admin, vec![raydium_clmm_cpi::ID]);
+    assert!(env.send(&[initialize], &[]).is_err());
+}
+
+#[test]
+fn migrations_upgrade_accounts_of_the_baseline_layouts() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let manager = env.payer.pubkey();
+    let manager_account = InvestorAddresses::new(&addresses.pool_position_config, &manager)
+        .investor_account;
+    let config: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    let pool_position: PoolPosition = env.load(&addresses.pool_position);
+    let investor: Investor = env.load(&manager_account);
+    assert_eq!(config.version, PoolPositionConfig::VERSION);
+    assert_eq!(pool_position.version, PoolPosition::VERSION);
+    assert_eq!(investor.version, Investor::VERSION);
+
+    // The three accounts as the baseline program wrote them, at the sizes it allocated
+    let data = legacy_serialized::<PoolPositionConfig>(&baseline_config(&config));
+    let len = baseline_config_len(&config.name);
+    write_legacy(&mut env, &addresses.pool_position_config, data, len);
+    let data = legacy_serialized::<PoolPosition>(&baseline_pool_position(&pool_position));
+    write_legacy(&mut env, &addresses.pool_position, data, BASELINE_POOL_POSITION_LEN);
+    let data = legacy_serialized::<Investor>(&baseline_investor(&investor));
+    write_legacy(&mut env, &manager_account, data, BASELINE_INVESTOR_LEN);
+    env.send_err(&[client::collect_fees(&manager, &position)], &[], "AccountDidNotDeserialize");
+
+    let payer = env.new_funded_keypair(TOKEN);
+    env.send_ok(
+        &[
+            client::migrate_pool_position_config(&payer.pubkey(), &addresses),
+            client::migrate_pool_position(&payer.pubkey(), &addresses),
+            client::migrate_investor(&payer.pubkey(), &addresses, &manager),
+        ],
+        &[&payer]
+    );
+    for (address, len) in [
+        (addresses.pool_position_config, PoolPositionConfig::LEN),
+        (addresses.pool_position, PoolPosition::LEN),
+        (manager_account, Investor::LEN),
+    ] {
+        assert_eq!(env.svm.get_account(&address).unwrap().data.len(), len);
+    }
+    // The fields of the baseline read as before. The later ones take the values of a new
+    // position, which has no NAV snapshot, ladder range or config change yet.
+    let migrated: PoolPositionConfig = env.account(&addresses.pool_position_config);
+    assert_eq!(migrated.roles, PositionRoles {
+        strategist: manager,
+        guardian: manager,
+        fee_recipient: manager,
+    });
+    assert_eq!(migrated.timelock_seconds, MIN_TIMELOCK_SECONDS);
+    assert_eq!(serialized(&migrated), serialized(&config));
+    let migrated: PoolPosition = env.load(&addresses.pool_position);
+    assert_eq!(bytemuck::bytes_of(&migrated), bytemuck::bytes_of(&pool_position));
+    assert_eq!(migrated.name(), "mock");
+    let migrated: Investor = env.load(&manager_account);
+    let expected = Investor { deposited_at: 0, ..investor };
+    assert_eq!(bytemuck::bytes_of(&migrated), bytemuck::bytes_of(&expected));
+
+    env.send_ok(&[client::collect_fees(&manager, &position)], &[]);
+    for migrate in [
+        client::migrate_pool_position_config(&payer.pubkey(), &addresses),
+        client::migrate_pool_position(&payer.pubkey(), &addresses),
+        client::migrate_investor(&payer.pubkey(), &addresses, &manager),
+    ] {
+        env.send_err(&[migrate], &[&payer], "AccountAlreadyMigrated");
+    }
+}
+
+#[test]
+fn investor_instructions_stay_within_their_compute_budget() {
+    let MockPosition { mut env, position } = open_position();
+    let addresses = position.addresses();
+    let investor = add_investor(&mut env, &position, TOKEN / 2);
+    let investor_addresses = InvestorAddresses::new(
+        &addresses.pool_position_config,
+        &investor.pubkey()
+    );
+
+    env.set_token_balance(&investor_addresses.deposit_token_0_account, TOKEN / 2);
+    env.set_token_balance(&investor_addresses.deposit_token_1_account, TOKEN / 2);
+    let bounds = LiquidityBounds {
+        min_liquidity_out: 1,
+        expected_sqrt_price_x64: env.pool_state(&position.pool.pool_state).sqrt_price_x64,
+        max_price_deviation: 100,
+    };
+    let increase = env.send_ok(
+        &[client::increase_liquidity(&investor.pubkey(), &position, bounds)],
+        &[&investor]
+    );
+    env.set_fee_growth_global(&position.pool, FEE_GROWTH_X64, 2 * FEE_GROWTH_X64);
+    let collect = env.send_ok(
+        &[client::collect_fees(&investor.pubkey(), &position)],
+        &[&investor]
+    );
+    let liquidity = investor_account(&env, &position, &investor.pubkey()).liquidity;
+    let decrease = env.send_ok(
+        &[client::decrease_liquidity(&investor.pubkey(), &position, liquidity / 2, 0, 0)],
+        &[&investor]
+    );
+
+    for (instruction, meta) in [
+        ("increase_liquidity", increase),
+        ("collect_fees", collect),
+        ("decrease_liquidity", decrease),
+    ] {
+        assert!(
+            meta.compute_units_consumed <= INVESTOR_INSTRUCTION_CU_BUDGET,
+            "{instruction} consumed {} compute units",
+            meta.compute_units_consumed
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Invalid tick range")]
+    InvalidTickRange,
+
+    #[msg("Tick array does not match the position ticks")]
+    InvalidTickArray,
+
+    #[msg("The position NFT is not held by the owner")]
+    NotApproved,
+
+    #[msg("Liquidity must be greater than zero")]
+    ZeroLiquidity,
+
+    #[msg("Removed liquidity exceeds the position liquidity")]
+    InvalidDecreaseLiquidity,
+
+    #[msg("Swap amount must be greater than zero")]
+    ZeroAmountSpecified,
+
+    #[msg("Token amount exceeds the maximum")]
+    PriceSlippageCheck,
+
+    #[msg("Token amount is below the minimum")]
+    TooLittleOutputReceived,
+
+    #[msg("Swap input or output exceeds the threshold")]
+    TooMuchInputPaid,
+
+    #[msg("Invalid input token account or vault")]
+    InvalidInputPoolVault,
+
+    #[msg("sqrt_price_limit_x64 out of range")]
+    SqrtPriceLimitOverflow,
+
+    #[msg("Math overflow")]
+    MathOverflow,
+}
@@ -0,0 +1,131 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{ Mint, TokenAccount };
+use pool_party::libraries::tick_math;
+use raydium_clmm_cpi::states::{ AmmConfig, ObservationState, PoolState, TickArrayBitmapExtension };
+
+use crate::libraries::{
+    OBSERVATION_SEED,
+    POOL_SEED,
+    POOL_TICK_ARRAY_BITMAP_SEED,
+    POOL_VAULT_SEED,
+};
+
+/// Same accounts and arguments as Raydium `create_pool`
+#[derive(Accounts)]
+pub struct CreatePool<'info> {
+    #[account(mut)]
+    pub pool_creator: Signer<'info>,
+
+    /// Provides the tick spacing and trade fee rate of the pool
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(
+        init,
+        seeds = [
+            POOL_SEED.as_bytes(),
+            amm_config.key().as_ref(),
+            token_mint_0.key().as_ref(),
+            token_mint_1.key().as_ref(),
+        ],
+        bump,
+        payer = pool_creator,
+        space = 8 + std::mem::size_of::<PoolState>()
+    )]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(constraint = token_mint_0.key() < token_mint_1.key())]
+    pub token_mint_0: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_mint_1: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        seeds = [
+            POOL_VAULT_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            token_mint_0.key().as_ref(),
+        ],
+        bump,
+        payer = pool_creator,
+        token::mint = token_mint_0,
+        token::authority = pool_state,
+        token::token_program = token_program_0
+    )]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        seeds = [
+            POOL_VAULT_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            token_mint_1.key().as_ref(),
+        ],
+        bump,
+        payer = pool_creator,
+        token::mint = token_mint_1,
+        token::authority = pool_state,
+        token::token_program = token_program_1
+    )]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Initialized for the account checks of callers, never updated
+    #[account(
+        init,
+        seeds = [OBSERVATION_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+        payer = pool_creator,
+        space = 8 + std::mem::size_of::<ObservationState>()
+    )]
+    pub observation_state: AccountLoader<'info, ObservationState>,
+
+    /// Initialized for the account checks of callers, never updated
+    #[account(
+        init,
+        seeds = [POOL_TICK_ARRAY_BITMAP_SEED.as_bytes(), pool_state.key().as_ref()],
+        bump,
+        payer = pool_creator,
+        space = 8 + std::mem::size_of::<TickArrayBitmapExtension>()
+    )]
+    pub tick_array_bitmap: AccountLoader<'info, TickArrayBitmapExtension>,
+
+    pub token_program_0: Program<'info, Token>,
+
+    pub token_program_1: Program<'info, Token>,
+
+    pub system_program: Program<'info, System>,
+
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreatePool<'info> {
+    pub fn create_pool(
+        &mut self,
+        sqrt_price_x64: u128,
+        open_time: u64,
+        bumps: &CreatePoolBumps
+    ) -> Result<()> {
+        let tick_current = tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?;
+        let pool_id = self.pool_state.key();
+
+        let mut pool = self.pool_state.load_init()?;
+        pool.bump = [bumps.pool_state];
+        pool.amm_config = self.amm_config.key();
+        pool.owner = self.pool_creator.key();
+        pool.token_mint_0 = self.token_mint_0.key();
+        pool.token_mint_1 = self.token_mint_1.key();
+        pool.token_vault_0 = self.token_vault_0.key();
+        pool.token_vault_1 = self.token_vault_1.key();
+        pool.observation_key = self.observation_state.key();
+        pool.mint_decimals_0 = self.token_mint_0.decimals;
+        pool.mint_decimals_1 = self.token_mint_1.decimals;
+        pool.tick_spacing = self.amm_config.tick_spacing;
+        pool.sqrt_price_x64 = sqrt_price_x64;
+        pool.tick_current = tick_current;
+        pool.open_time = open_time;
+
+        self.observation_state.load_init()?.pool_id = pool_id;
+        self.tick_array_bitmap.load_init()?.pool_id = pool_id;
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::memo::spl_memo;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount };
+use pool_party::libraries::{ tick_math, transfer_token };
+use raydium_clmm_cpi::states::{
+    PersonalPositionState,
+    PoolState,
+    ProtocolPositionState,
+    TickArrayState,
+};
+
+use crate::errors::ErrorCode;
+use crate::libraries::{ amounts_for_liquidity, update_fees_owed, PoolSigner };
+
+/// Same accounts as Raydium `decrease_liquidity_v2`. The fees owed to the position are paid out
+/// with the removed liquidity, so a call with zero liquidity harvests the fees.
+#[derive(Accounts)]
+pub struct DecreaseLiquidityV2<'info> {
+    pub nft_owner: Signer<'info>,
+
+    #[account(
+        constraint = nft_account.mint == personal_position.nft_mint @ ErrorCode::NotApproved,
+        constraint = nft_account.amount == 1 @ ErrorCode::NotApproved,
+        token::authority = nft_owner
+    )]
+    pub nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        mut,
+        constraint = protocol_position.pool_id == pool_state.key(),
+        constraint = protocol_position.tick_lower_index == personal_position.tick_lower_index,
+        constraint = protocol_position.tick_upper_index == personal_position.tick_upper_index
+    )]
+    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+
+    #[account(mut, address = pool_state.load()?.token_vault_0)]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = pool_state.load()?.token_vault_1)]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(mut, constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    #[account(mut, token::mint = token_vault_0.mint)]
+    pub recipient_token_account_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = token_vault_1.mint)]
+    pub recipient_token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// CHECK: memo program
+    #[account(address = spl_memo::id())]
+    pub memo_program: UncheckedAccount<'info>,
+
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+}
+
+impl<'info> DecreaseLiquidityV2<'info> {
+    pub fn decrease_liquidity(
+        &mut self,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64
+    ) -> Result<()> {
+        let (amount_0, amount_1, pool_signer) = {
+            let mut pool = self.pool_state.load_mut()?;
+            let personal_position = &mut self.personal_position;
+            update_fees_owed(
+                personal_position,
+                &self.tick_array_lower.to_account_info(),
+                &self.tick_array_upper.to_account_info(),
+                &pool
+            )?;
+            require!(liquidity <= personal_position.liquidity, ErrorCode::InvalidDecreaseLiquidity);
+
+            let (amount_0, amount_1) = if liquidity > 0 {
+                amounts_for_liquidity(
+                    pool.sqrt_price_x64,
+                    tick_math::get_sqrt_price_at_tick(personal_position.tick_lower_index)?,
+                    tick_math::get_sqrt_price_at_tick(personal_position.tick_upper_index)?,
+                    liquidity,
+                    false
+                )?
+            } else {
+                (0, 0)
+            };
+            require!(
+                amount_0 >= amount_0_min && amount_1 >= amount_1_min,
+                ErrorCode::TooLittleOutputReceived
+            );
+
+            personal_position.liquidity -= liquidity;
+            pool.liquidity = pool.liquidity.checked_sub(liquidity).ok_or(ErrorCode::MathOverflow)?;
+
+            let fees_0 = personal_position.token_fees_owed_0;
+            let fees_1 = personal_position.token_fees_owed_1;
+            personal_position.token_fees_owed_0 = 0;
+            personal_position.token_fees_owed_1 = 0;
+
+            (
+                amount_0.checked_add(fees_0).ok_or(ErrorCode::MathOverflow)?,
+                amount_1.checked_add(fees_1).ok_or(ErrorCode::MathOverflow)?,
+                PoolSigner::new(&pool),
+            )
+        };
+        self.protocol_position.liquidity = self.protocol_position.liquidity
+            .checked_sub(liquidity)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let seeds = pool_signer.seeds();
+        let signer_seeds: &[&[&[u8]]] = &[&seeds[..]];
+        let pool_state = self.pool_state.to_account_info();
+        transfer_token(
+            &self.token_vault_0,
+            &self.recipient_token_account_0,
+            &amount_0,
+            &self.vault_0_mint,
+            &pool_state,
+            &self.token_program,
+            Some(signer_seeds)
+        )?;
+        transfer_token(
+            &self.token_vault_1,
+            &self.recipient_token_account_1,
+            &amount_1,
+            &self.vault_1_mint,
+            &pool_state,
+            &self.token_program,
+            Some(signer_seeds)
+        )
+    }
+}
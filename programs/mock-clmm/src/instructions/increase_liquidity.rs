@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount };
+use pool_party::libraries::transfer_token;
+use raydium_clmm_cpi::states::{
+    PersonalPositionState,
+    PoolState,
+    ProtocolPositionState,
+    TickArrayState,
+};
+
+use crate::errors::ErrorCode;
+use crate::libraries::{ liquidity_to_add, update_fees_owed };
+
+/// Same accounts as Raydium `increase_liquidity_v2`
+#[derive(Accounts)]
+pub struct IncreaseLiquidityV2<'info> {
+    pub nft_owner: Signer<'info>,
+
+    #[account(
+        constraint = nft_account.mint == personal_position.nft_mint @ ErrorCode::NotApproved,
+        constraint = nft_account.amount == 1 @ ErrorCode::NotApproved,
+        token::authority = nft_owner
+    )]
+    pub nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        mut,
+        constraint = protocol_position.pool_id == pool_state.key(),
+        constraint = protocol_position.tick_lower_index == personal_position.tick_lower_index,
+        constraint = protocol_position.tick_upper_index == personal_position.tick_upper_index
+    )]
+    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+
+    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    #[account(mut, constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
+    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+
+    #[account(mut, constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
+    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+
+    #[account(mut, token::mint = token_vault_0.mint)]
+    pub token_account_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = token_vault_1.mint)]
+    pub token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = pool_state.load()?.token_vault_0)]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = pool_state.load()?.token_vault_1)]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+}
+
+impl<'info> IncreaseLiquidityV2<'info> {
+    pub fn increase_liquidity(
+        &mut self,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64
+    ) -> Result<()> {
+        let (liquidity, amount_0, amount_1) = {
+            let mut pool = self.pool_state.load_mut()?;
+            let personal_position = &mut self.personal_position;
+            update_fees_owed(
+                personal_position,
+                &self.tick_array_lower.to_account_info(),
+                &self.tick_array_upper.to_account_info(),
+                &pool
+            )?;
+            let (liquidity, amount_0, amount_1) = liquidity_to_add(
+                &pool,
+                personal_position.tick_lower_index,
+                personal_position.tick_upper_index,
+                liquidity,
+                amount_0_max,
+                amount_1_max
+            )?;
+            personal_position.liquidity = personal_position.liquidity
+                .checked_add(liquidity)
+                .ok_or(ErrorCode::MathOverflow)?;
+            pool.liquidity = pool.liquidity.checked_add(liquidity).ok_or(ErrorCode::MathOverflow)?;
+            (liquidity, amount_0, amount_1)
+        };
+        self.protocol_position.liquidity = self.protocol_position.liquidity
+            .checked_add(liquidity)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let nft_owner = self.nft_owner.to_account_info();
+        transfer_token(
+            &self.token_account_0,
+            &self.token_vault_0,
+            &amount_0,
+            &self.vault_0_mint,
+            &nft_owner,
+            &self.token_program,
+            None
+        )?;
+        transfer_token(
+            &self.token_account_1,
+            &self.token_vault_1,
+            &amount_1,
+            &self.vault_1_mint,
+            &nft_owner,
+            &self.token_program,
+            None
+        )
+    }
+}
@@ -0,0 +1,13 @@
+pub mod create_pool;
+pub mod open_position;
+pub mod increase_liquidity;
+pub mod decrease_liquidity;
+pub mod swap;
+pub mod set_state;
+
+pub use create_pool::*;
+pub use open_position::*;
+pub use increase_liquidity::*;
+pub use decrease_liquidity::*;
+pub use swap::*;
+pub use set_state::*;
@@ -0,0 +1,266 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{ self, Token };
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount };
+use pool_party::libraries::transfer_token;
+use pool_party::state::TickArrayStateExt;
+use raydium_clmm_cpi::states::{
+    PersonalPositionState,
+    PoolState,
+    ProtocolPositionState,
+    TickArrayState,
+};
+
+use crate::errors::ErrorCode;
+use crate::libraries::{
+    check_ticks,
+    create_tick_array_if_needed,
+    fee_growth_inside,
+    init_tick,
+    liquidity_to_add,
+    PoolSigner,
+    POSITION_SEED,
+    TICK_ARRAY_SEED,
+};
+
+/// Same accounts as Raydium `open_position_v2`. No metadata is created for the position NFT.
+#[derive(Accounts)]
+#[instruction(
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    tick_array_lower_start_index: i32,
+    tick_array_upper_start_index: i32
+)]
+pub struct OpenPositionV2<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: receives the position NFT
+    pub position_nft_owner: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        mint::decimals = 0,
+        mint::authority = pool_state,
+        payer = payer,
+        mint::token_program = token_program
+    )]
+    pub position_nft_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        associated_token::mint = position_nft_mint,
+        associated_token::authority = position_nft_owner,
+        payer = payer,
+        associated_token::token_program = token_program
+    )]
+    pub position_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: unused, the mock creates no metadata
+    #[account(mut)]
+    pub metadata_account: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(
+        init_if_needed,
+        seeds = [
+            POSITION_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_lower_index.to_be_bytes(),
+            &tick_upper_index.to_be_bytes(),
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<ProtocolPositionState>()
+    )]
+    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+
+    /// CHECK: created by the instruction when empty
+    #[account(
+        mut,
+        seeds = [
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_array_lower_start_index.to_be_bytes(),
+        ],
+        bump
+    )]
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// CHECK: created by the instruction when empty
+    #[account(
+        mut,
+        seeds = [
+            TICK_ARRAY_SEED.as_bytes(),
+            pool_state.key().as_ref(),
+            &tick_array_upper_start_index.to_be_bytes(),
+        ],
+        bump
+    )]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        seeds = [POSITION_SEED.as_bytes(), position_nft_mint.key().as_ref()],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<PersonalPositionState>()
+    )]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+
+    #[account(mut, token::mint = token_vault_0.mint)]
+    pub token_account_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, token::mint = token_vault_1.mint)]
+    pub token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = pool_state.load()?.token_vault_0)]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = pool_state.load()?.token_vault_1)]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub rent: Sysvar<'info, Rent>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// CHECK: unused, the mock creates no metadata
+    pub metadata_program: UncheckedAccount<'info>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+}
+
+impl<'info> OpenPositionV2<'info> {
+    pub fn open_position(
+        &mut self,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        bumps: &OpenPositionV2Bumps
+    ) -> Result<()> {
+        let pool_id = self.pool_state.key();
+        let tick_spacing = self.pool_state.load()?.tick_spacing;
+        check_ticks(tick_lower_index, tick_upper_index, tick_spacing)?;
+        require_eq!(
+            TickArrayState::get_array_start_index(tick_lower_index, tick_spacing),
+            tick_array_lower_start_index,
+            ErrorCode::InvalidTickArray
+        );
+        require_eq!(
+            TickArrayState::get_array_start_index(tick_upper_index, tick_spacing),
+            tick_array_upper_start_index,
+            ErrorCode::InvalidTickArray
+        );
+
+        let payer = self.payer.to_account_info();
+        let system_program = self.system_program.to_account_info();
+        let tick_array_lower = self.tick_array_lower.to_account_info();
+        let tick_array_upper = self.tick_array_upper.to_account_info();
+        create_tick_array_if_needed(
+            &payer,
+            &tick_array_lower,
+            &system_program,
+            pool_id,
+            tick_array_lower_start_index,
+            bumps.tick_array_lower
+        )?;
+        create_tick_array_if_needed(
+            &payer,
+            &tick_array_upper,
+            &system_program,
+            pool_id,
+            tick_array_upper_start_index,
+            bumps.tick_array_upper
+        )?;
+        init_tick(&tick_array_lower, tick_lower_index, tick_spacing)?;
+        init_tick(&tick_array_upper, tick_upper_index, tick_spacing)?;
+
+        let (liquidity, amount_0, amount_1, pool_signer) = {
+            let mut pool = self.pool_state.load_mut()?;
+            let (liquidity, amount_0, amount_1) = liquidity_to_add(
+                &pool,
+                tick_lower_index,
+                tick_upper_index,
+                liquidity,
+                amount_0_max,
+                amount_1_max
+            )?;
+            let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = fee_growth_inside(
+                &tick_array_lower,
+                &tick_array_upper,
+                tick_lower_index,
+                tick_upper_index,
+                &pool
+            )?;
+            pool.liquidity = pool.liquidity.checked_add(liquidity).ok_or(ErrorCode::MathOverflow)?;
+
+            let personal_position = &mut self.personal_position;
+            personal_position.nft_mint = self.position_nft_mint.key();
+            personal_position.pool_id = pool_id;
+            personal_position.tick_lower_index = tick_lower_index;
+            personal_position.tick_upper_index = tick_upper_index;
+            personal_position.liquidity = liquidity;
+            personal_position.fee_growth_inside_0_last_x64 = fee_growth_inside_0_x64;
+            personal_position.fee_growth_inside_1_last_x64 = fee_growth_inside_1_x64;
+
+            (liquidity, amount_0, amount_1, PoolSigner::new(&pool))
+        };
+
+        let protocol_position = &mut self.protocol_position;
+        protocol_position.pool_id = pool_id;
+        protocol_position.tick_lower_index = tick_lower_index;
+        protocol_position.tick_upper_index = tick_upper_index;
+        protocol_position.liquidity = protocol_position.liquidity
+            .checked_add(liquidity)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        transfer_token(
+            &self.token_account_0,
+            &self.token_vault_0,
+            &amount_0,
+            &self.vault_0_mint,
+            &payer,
+            &self.token_program,
+            None
+        )?;
+        transfer_token(
+            &self.token_account_1,
+            &self.token_vault_1,
+            &amount_1,
+            &self.vault_1_mint,
+            &payer,
+            &self.token_program,
+            None
+        )?;
+
+        let seeds = pool_signer.seeds();
+        token::mint_to(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                token::MintTo {
+                    mint: self.position_nft_mint.to_account_info(),
+                    to: self.position_nft_account.to_account_info(),
+                    authority: self.pool_state.to_account_info(),
+                },
+                &[&seeds[..]]
+            ),
+            1
+        )
+    }
+}
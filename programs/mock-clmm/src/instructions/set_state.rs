@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use pool_party::libraries::{ tick_math, MAX_SQRT_PRICE_X64, MIN_SQRT_PRICE_X64 };
+use raydium_clmm_cpi::states::{ PersonalPositionState, PoolState };
+
+use crate::errors::ErrorCode;
+
+/// Test-only writes to a pool, signed by the pool creator
+#[derive(Accounts)]
+pub struct SetPoolState<'info> {
+    #[account(address = pool_state.load()?.owner)]
+    pub owner: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+}
+
+impl<'info> SetPoolState<'info> {
+    /// Sets the fee growth of the pool. Positions accrue fees from the difference with the fee
+    /// growth they last saw, so the new value should not be below the current one.
+    pub fn set_fee_growth_global(
+        &mut self,
+        fee_growth_global_0_x64: u128,
+        fee_growth_global_1_x64: u128
+    ) -> Result<()> {
+        let mut pool = self.pool_state.load_mut()?;
+        pool.fee_growth_global_0_x64 = fee_growth_global_0_x64;
+        pool.fee_growth_global_1_x64 = fee_growth_global_1_x64;
+        Ok(())
+    }
+
+    /// Moves the pool price, and the current tick with it, without trading
+    pub fn set_sqrt_price(&mut self, sqrt_price_x64: u128) -> Result<()> {
+        require!(
+            sqrt_price_x64 >= MIN_SQRT_PRICE_X64 && sqrt_price_x64 < MAX_SQRT_PRICE_X64,
+            ErrorCode::SqrtPriceLimitOverflow
+        );
+        let mut pool = self.pool_state.load_mut()?;
+        pool.sqrt_price_x64 = sqrt_price_x64;
+        pool.tick_current = tick_math::get_tick_at_sqrt_price(sqrt_price_x64)?;
+        Ok(())
+    }
+}
+
+/// Test-only write to a position of a pool, signed by the pool creator
+#[derive(Accounts)]
+pub struct SetTokenFeesOwed<'info> {
+    #[account(address = pool_state.load()?.owner)]
+    pub owner: Signer<'info>,
+
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
+    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+}
+
+impl<'info> SetTokenFeesOwed<'info> {
+    /// Sets the fees owed to the position, paid out by its next `decrease_liquidity_v2`. The
+    /// vaults must hold enough tokens to pay them.
+    pub fn set_token_fees_owed(
+        &mut self,
+        token_fees_owed_0: u64,
+        token_fees_owed_1: u64
+    ) -> Result<()> {
+        let personal_position = &mut self.personal_position;
+        personal_position.token_fees_owed_0 = token_fees_owed_0;
+        personal_position.token_fees_owed_1 = token_fees_owed_1;
+        Ok(())
+    }
+}
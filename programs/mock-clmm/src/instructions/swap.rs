@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use anchor_spl::memo::spl_memo;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount };
+use pool_party::libraries::{
+    fixed_point_64,
+    swap_math,
+    tick_math,
+    transfer_token,
+    MulDiv,
+    MAX_SQRT_PRICE_X64,
+    MIN_SQRT_PRICE_X64,
+    U128,
+};
+use raydium_clmm_cpi::states::{ AmmConfig, PoolState };
+
+use crate::errors::ErrorCode;
+use crate::libraries::PoolSigner;
+
+/// Same accounts as Raydium `swap_v2`. The bitmap extension and tick arrays passed as remaining
+/// accounts are ignored.
+#[derive(Accounts)]
+pub struct SwapSingleV2<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(address = pool_state.load()?.amm_config)]
+    pub amm_config: Box<Account<'info, AmmConfig>>,
+
+    #[account(mut)]
+    pub pool_state: AccountLoader<'info, PoolState>,
+
+    #[account(mut)]
+    pub input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub output_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub input_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub output_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: never updated by the mock
+    #[account(mut, address = pool_state.load()?.observation_key)]
+    pub observation_state: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// CHECK: memo program
+    #[account(address = spl_memo::id())]
+    pub memo_program: UncheckedAccount<'info>,
+
+    #[account(address = input_vault.mint)]
+    pub input_vault_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = output_vault.mint)]
+    pub output_vault_mint: Box<InterfaceAccount<'info, Mint>>,
+}
+
+impl<'info> SwapSingleV2<'info> {
+    /// Swaps in a single step at the pool liquidity, towards `sqrt_price_limit_x64` or the price
+    /// bound when it is zero. The trade fee accrues to the fee growth of the input token.
+    pub fn swap(
+        &mut self,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::ZeroAmountSpecified);
+
+        let (amount_in, amount_out, pool_signer) = {
+            let mut pool = self.pool_state.load_mut()?;
+            let vaults = (self.input_vault.key(), self.output_vault.key());
+            let zero_for_one = if vaults == (pool.token_vault_0, pool.token_vault_1) {
+                true
+            } else if vaults == (pool.token_vault_1, pool.token_vault_0) {
+                false
+            } else {
+                return err!(ErrorCode::InvalidInputPoolVault);
+            };
+            require!(pool.liquidity > 0, ErrorCode::ZeroLiquidity);
+
+            let sqrt_price_x64 = pool.sqrt_price_x64;
+            let sqrt_price_limit_x64 = match (sqrt_price_limit_x64, zero_for_one) {
+                (0, true) => MIN_SQRT_PRICE_X64 + 1,
+                (0, false) => MAX_SQRT_PRICE_X64 - 1,
+                (limit, _) => limit,
+            };
+            if zero_for_one {
+                require!(
+                    sqrt_price_limit_x64 < sqrt_price_x64 &&
+                        sqrt_price_limit_x64 > MIN_SQRT_PRICE_X64,
+                    ErrorCode::SqrtPriceLimitOverflow
+                );
+            } else {
+                require!(
+                    sqrt_price_limit_x64 > sqrt_price_x64 &&
+                        sqrt_price_limit_x64 < MAX_SQRT_PRICE_X64,
+                    ErrorCode::SqrtPriceLimitOverflow
+                );
+            }
+
+            let step = swap_math::compute_swap_step(
+                sqrt_price_x64,
+                sqrt_price_limit_x64,
+                pool.liquidity,
+                amount,
+                self.amm_config.trade_fee_rate,
+                is_base_input,
+                zero_for_one
+            )?;
+            let amount_in = step.amount_in
+                .checked_add(step.fee_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            if is_base_input {
+                require!(
+                    step.amount_out >= other_amount_threshold,
+                    ErrorCode::TooLittleOutputReceived
+                );
+            } else {
+                require!(amount_in <= other_amount_threshold, ErrorCode::TooMuchInputPaid);
+            }
+
+            let fee_growth_x64 = U128::from(step.fee_amount)
+                .mul_div_floor(U128::from(fixed_point_64::Q64), U128::from(pool.liquidity))
+                .unwrap()
+                .as_u128();
+            if zero_for_one {
+                pool.fee_growth_global_0_x64 = pool.fee_growth_global_0_x64.wrapping_add(
+                    fee_growth_x64
+                );
+            } else {
+                pool.fee_growth_global_1_x64 = pool.fee_growth_global_1_x64.wrapping_add(
+                    fee_growth_x64
+                );
+            }
+            pool.sqrt_price_x64 = step.sqrt_price_next_x64;
+            pool.tick_current = tick_math::get_tick_at_sqrt_price(step.sqrt_price_next_x64)?;
+
+            (amount_in, step.amount_out, PoolSigner::new(&pool))
+        };
+
+        transfer_token(
+            &self.input_token_account,
+            &self.input_vault,
+            &amount_in,
+            &self.input_vault_mint,
+            &self.payer.to_account_info(),
+            &self.token_program,
+            None
+        )?;
+        let seeds = pool_signer.seeds();
+        transfer_token(
+            &self.output_vault,
+            &self.output_token_account,
+            &amount_out,
+            &self.output_vault_mint,
+            &self.pool_state.to_account_info(),
+            &self.token_program,
+            Some(&[&seeds[..]])
+        )
+    }
+}
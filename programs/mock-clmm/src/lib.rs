@@ -0,0 +1,116 @@
+//! A small stand-in for the Raydium CLMM program, for tests of pool-party.
+//!
+//! It is deployed at the Raydium CLMM address and implements `open_position_v2`,
+//! `increase_liquidity_v2`, `decrease_liquidity_v2` and `swap_v2` with the account layouts and
+//! arguments of `raydium_clmm_cpi`, on accounts laid out as the `raydium_clmm_cpi::states` types.
+//! Everything else is simplified:
+//!
+//! - pools are created by `create_pool`, with the accounts and arguments of Raydium, and their
+//!   bitmap extension and observation account are initialized but never updated
+//! - the pool liquidity is the sum of the liquidity of all positions, active at every price;
+//!   ticks only record their index so that the fee growth inside a range is derived from
+//!   `fee_growth_global` as Raydium does
+//! - `swap_v2` is a single swap step at constant liquidity, never crossing a tick
+//! - only SPL Token mints are supported
+//!
+//! `set_fee_growth_global`, `set_token_fees_owed` and `set_sqrt_price` write the pool and position
+//! state directly, so tests can produce fees and move the price deterministically.
+
+use anchor_lang::prelude::*;
+
+pub mod errors;
+pub mod instructions;
+pub mod libraries;
+
+use instructions::*;
+
+#[cfg(feature = "devnet")]
+declare_id!("devi51mZmdwUJGU9hjN27vEz64Gps7uUefqxg27EAtH");
+#[cfg(not(feature = "devnet"))]
+declare_id!("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK");
+
+#[program]
+pub mod mock_clmm {
+    use super::*;
+
+    pub fn create_pool(
+        ctx: Context<CreatePool>,
+        sqrt_price_x64: u128,
+        open_time: u64
+    ) -> Result<()> {
+        ctx.accounts.create_pool(sqrt_price_x64, open_time, &ctx.bumps)
+    }
+
+    pub fn open_position_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, OpenPositionV2<'info>>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        _with_metadata: bool,
+        _base_flag: Option<bool>
+    ) -> Result<()> {
+        ctx.accounts.open_position(
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            &ctx.bumps
+        )
+    }
+
+    pub fn increase_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, IncreaseLiquidityV2<'info>>,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        _base_flag: Option<bool>
+    ) -> Result<()> {
+        ctx.accounts.increase_liquidity(liquidity, amount_0_max, amount_1_max)
+    }
+
+    pub fn decrease_liquidity_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidityV2<'info>>,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64
+    ) -> Result<()> {
+        ctx.accounts.decrease_liquidity(liquidity, amount_0_min, amount_1_min)
+    }
+
+    pub fn swap_v2<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SwapSingleV2<'info>>,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool
+    ) -> Result<()> {
+        ctx.accounts.swap(amount, other_amount_threshold, sqrt_price_limit_x64, is_base_input)
+    }
+
+    pub fn set_fee_growth_global(
+        ctx: Context<SetPoolState>,
+        fee_growth_global_0_x64: u128,
+        fee_growth_global_1_x64: u128
+    ) -> Result<()> {
+        ctx.accounts.set_fee_growth_global(fee_growth_global_0_x64, fee_growth_global_1_x64)
+    }
+
+    pub fn set_sqrt_price(ctx: Context<SetPoolState>, sqrt_price_x64: u128) -> Result<()> {
+        ctx.accounts.set_sqrt_price(sqrt_price_x64)
+    }
+
+    pub fn set_token_fees_owed(
+        ctx: Context<SetTokenFeesOwed>,
+        token_fees_owed_0: u64,
+        token_fees_owed_1: u64
+    ) -> Result<()> {
+        ctx.accounts.set_token_fees_owed(token_fees_owed_0, token_fees_owed_1)
+    }
+}
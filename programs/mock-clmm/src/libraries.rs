@@ -0,0 +1,326 @@
+use std::cell::{ Ref, RefMut };
+
+use anchor_lang::{ prelude::*, system_program, Discriminator };
+use pool_party::libraries::{
+    fixed_point_64,
+    sqrt_price_math,
+    tick_math,
+    MulDiv,
+    MAX_TICK,
+    MIN_TICK,
+    U128,
+};
+use pool_party::state::{ get_fee_growth_inside, TickArrayStateExt };
+use raydium_clmm_cpi::states::{ PersonalPositionState, PoolState, TickArrayState };
+
+use crate::errors::ErrorCode;
+
+pub const POOL_SEED: &str = "pool";
+pub const POOL_VAULT_SEED: &str = "pool_vault";
+pub const OBSERVATION_SEED: &str = "observation";
+pub const POSITION_SEED: &str = "position";
+pub const TICK_ARRAY_SEED: &str = "tick_array";
+pub const POOL_TICK_ARRAY_BITMAP_SEED: &str = "pool_tick_array_bitmap_extension";
+
+/// Seeds of a pool PDA, to sign transfers out of its vaults and NFT mints
+pub struct PoolSigner {
+    amm_config: Pubkey,
+    token_mint_0: Pubkey,
+    token_mint_1: Pubkey,
+    bump: [u8; 1],
+}
+
+impl PoolSigner {
+    pub fn new(pool: &PoolState) -> Self {
+        PoolSigner {
+            amm_config: pool.amm_config,
+            token_mint_0: pool.token_mint_0,
+            token_mint_1: pool.token_mint_1,
+            bump: pool.bump,
+        }
+    }
+
+    pub fn seeds(&self) -> [&[u8]; 5] {
+        [
+            POOL_SEED.as_bytes(),
+            self.amm_config.as_ref(),
+            self.token_mint_0.as_ref(),
+            self.token_mint_1.as_ref(),
+            &self.bump,
+        ]
+    }
+}
+
+pub fn check_ticks(tick_lower_index: i32, tick_upper_index: i32, tick_spacing: u16) -> Result<()> {
+    require!(
+        tick_lower_index < tick_upper_index &&
+            tick_lower_index >= MIN_TICK &&
+            tick_upper_index <= MAX_TICK &&
+            tick_lower_index % i32::from(tick_spacing) == 0 &&
+            tick_upper_index % i32::from(tick_spacing) == 0,
+        ErrorCode::InvalidTickRange
+    );
+    Ok(())
+}
+
+/// Creates the tick array of `pool_id` starting at `start_tick_index` unless it already exists,
+/// as Raydium does when a position opens on a fresh tick array
+pub fn create_tick_array_if_needed<'info>(
+    payer: &AccountInfo<'info>,
+    tick_array: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    pool_id: Pubkey,
+    start_tick_index: i32,
+    bump: u8
+) -> Result<()> {
+    if !tick_array.data_is_empty() {
+        return Ok(());
+    }
+    let start_tick_index_bytes = start_tick_index.to_be_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[
+        &[TICK_ARRAY_SEED.as_bytes(), pool_id.as_ref(), &start_tick_index_bytes, &[bump]],
+    ];
+    let space = 8 + std::mem::size_of::<TickArrayState>();
+    system_program::create_account(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::CreateAccount { from: payer.clone(), to: tick_array.clone() },
+            signer_seeds
+        ),
+        Rent::get()?.minimum_balance(space),
+        space as u64,
+        &crate::ID
+    )?;
+    tick_array.try_borrow_mut_data()?[..8].copy_from_slice(TickArrayState::DISCRIMINATOR);
+
+    let mut state = load_tick_array_mut(tick_array)?;
+    state.pool_id = pool_id;
+    state.start_tick_index = start_tick_index;
+    Ok(())
+}
+
+fn check_tick_array(tick_array: &AccountInfo) -> Result<()> {
+    require_keys_eq!(*tick_array.owner, crate::ID, ErrorCode::InvalidTickArray);
+    require!(
+        tick_array.try_borrow_data()?.starts_with(TickArrayState::DISCRIMINATOR),
+        ErrorCode::InvalidTickArray
+    );
+    Ok(())
+}
+
+/// Reads a tick array from its account, which may be passed twice to an instruction when both
+/// position ticks fall in the same array
+pub fn load_tick_array<'a>(tick_array: &'a AccountInfo) -> Result<Ref<'a, TickArrayState>> {
+    check_tick_array(tick_array)?;
+    Ok(
+        Ref::map(tick_array.try_borrow_data()?, |data| {
+            bytemuck::from_bytes(&data[8..8 + std::mem::size_of::<TickArrayState>()])
+        })
+    )
+}
+
+pub fn load_tick_array_mut<'a>(tick_array: &'a AccountInfo) -> Result<RefMut<'a, TickArrayState>> {
+    check_tick_array(tick_array)?;
+    Ok(
+        RefMut::map(tick_array.try_borrow_mut_data()?, |data| {
+            bytemuck::from_bytes_mut(&mut data[8..8 + std::mem::size_of::<TickArrayState>()])
+        })
+    )
+}
+
+/// Marks `tick_index` as a position boundary, so that fee growth inside the position is measured
+/// from it. The mock keeps no liquidity on ticks, swaps never cross them.
+pub fn init_tick(tick_array: &AccountInfo, tick_index: i32, tick_spacing: u16) -> Result<()> {
+    let mut state = load_tick_array_mut(tick_array)?;
+    state.get_tick_state_mut(tick_index, tick_spacing)?.tick = tick_index;
+    Ok(())
+}
+
+/// Fee growth inside `[tick_lower_index, tick_upper_index)` at the current pool state
+pub fn fee_growth_inside(
+    tick_array_lower: &AccountInfo,
+    tick_array_upper: &AccountInfo,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    pool: &PoolState
+) -> Result<(u128, u128)> {
+    let lower = load_tick_array(tick_array_lower)?;
+    let upper = load_tick_array(tick_array_upper)?;
+    Ok(
+        get_fee_growth_inside(
+            lower.get_tick_state(tick_lower_index, pool.tick_spacing)?,
+            upper.get_tick_state(tick_upper_index, pool.tick_spacing)?,
+            pool.tick_current,
+            pool.fee_growth_global_0_x64,
+            pool.fee_growth_global_1_x64
+        )
+    )
+}
+
+/// Accrues into `token_fees_owed_*` the fees `position` earned since its last update
+pub fn update_fees_owed(
+    position: &mut PersonalPositionState,
+    tick_array_lower: &AccountInfo,
+    tick_array_upper: &AccountInfo,
+    pool: &PoolState
+) -> Result<()> {
+    let (fee_growth_inside_0_x64, fee_growth_inside_1_x64) = fee_growth_inside(
+        tick_array_lower,
+        tick_array_upper,
+        position.tick_lower_index,
+        position.tick_upper_index,
+        pool
+    )?;
+    position.token_fees_owed_0 = position.token_fees_owed_0
+        .checked_add(
+            fees_earned(
+                fee_growth_inside_0_x64,
+                position.fee_growth_inside_0_last_x64,
+                position.liquidity
+            )
+        )
+        .ok_or(ErrorCode::MathOverflow)?;
+    position.token_fees_owed_1 = position.token_fees_owed_1
+        .checked_add(
+            fees_earned(
+                fee_growth_inside_1_x64,
+                position.fee_growth_inside_1_last_x64,
+                position.liquidity
+            )
+        )
+        .ok_or(ErrorCode::MathOverflow)?;
+    position.fee_growth_inside_0_last_x64 = fee_growth_inside_0_x64;
+    position.fee_growth_inside_1_last_x64 = fee_growth_inside_1_x64;
+    Ok(())
+}
+
+/// `(fee_growth_inside - fee_growth_inside_last) * liquidity`, in tokens
+fn fees_earned(
+    fee_growth_inside_x64: u128,
+    fee_growth_inside_last_x64: u128,
+    liquidity: u128
+) -> u64 {
+    U128::from(fee_growth_inside_x64.wrapping_sub(fee_growth_inside_last_x64))
+        .mul_div_floor(U128::from(liquidity), U128::from(fixed_point_64::Q64))
+        .unwrap()
+        .to_underflow_u64()
+}
+
+/// Token amounts backing `liquidity` over `[sqrt_price_lower_x64, sqrt_price_upper_x64]` at
+/// `sqrt_price_x64`
+pub fn amounts_for_liquidity(
+    sqrt_price_x64: u128,
+    sqrt_price_lower_x64: u128,
+    sqrt_price_upper_x64: u128,
+    liquidity: u128,
+    round_up: bool
+) -> Result<(u64, u64)> {
+    if sqrt_price_x64 <= sqrt_price_lower_x64 {
+        let amount_0 = sqrt_price_math::get_delta_amount_0_unsigned(
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+            liquidity,
+            round_up
+        )?;
+        Ok((amount_0, 0))
+    } else if sqrt_price_x64 < sqrt_price_upper_x64 {
+        let amount_0 = sqrt_price_math::get_delta_amount_0_unsigned(
+            sqrt_price_x64,
+            sqrt_price_upper_x64,
+            liquidity,
+            round_up
+        )?;
+        let amount_1 = sqrt_price_math::get_delta_amount_1_unsigned(
+            sqrt_price_lower_x64,
+            sqrt_price_x64,
+            liquidity,
+            round_up
+        )?;
+        Ok((amount_0, amount_1))
+    } else {
+        let amount_1 = sqrt_price_math::get_delta_amount_1_unsigned(
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+            liquidity,
+            round_up
+        )?;
+        Ok((0, amount_1))
+    }
+}
+
+/// Liquidity added by a position change and the token amounts it takes.
+///
+/// Unlike Raydium, `base_flag` is ignored: when `liquidity` is zero the mock adds the most
+/// liquidity both maximums allow.
+pub fn liquidity_to_add(
+    pool: &PoolState,
+    tick_lower_index: i32,
+    tick_upper_index: i32,
+    liquidity: u128,
+    amount_0_max: u64,
+    amount_1_max: u64
+) -> Result<(u128, u64, u64)> {
+    let sqrt_price_lower_x64 = tick_math::get_sqrt_price_at_tick(tick_lower_index)?;
+    let sqrt_price_upper_x64 = tick_math::get_sqrt_price_at_tick(tick_upper_index)?;
+    let liquidity = if liquidity > 0 {
+        liquidity
+    } else {
+        pool_party::libraries::liquidity_math::get_liquidity_from_amounts(
+            pool.sqrt_price_x64,
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64,
+            amount_0_max,
+            amount_1_max
+        )
+    };
+    require!(liquidity > 0, ErrorCode::ZeroLiquidity);
+
+    let (amount_0, amount_1) = amounts_for_liquidity(
+        pool.sqrt_price_x64,
+        sqrt_price_lower_x64,
+        sqrt_price_upper_x64,
+        liquidity,
+        true
+    )?;
+    require!(
+        amount_0 <= amount_0_max && amount_1 <= amount_1_max,
+        ErrorCode::PriceSlippageCheck
+    );
+    Ok((liquidity, amount_0, amount_1))
+}
+
+#[cfg(test)]
+mod libraries_test {
+    use super::*;
+
+    #[test]
+    fn amounts_for_liquidity_depend_on_the_price_position() {
+        let lower = tick_math::get_sqrt_price_at_tick(-1200).unwrap();
+        let upper = tick_math::get_sqrt_price_at_tick(1200).unwrap();
+        let liquidity = 1_000_000_000u128;
+
+        let below = amounts_for_liquidity(lower / 2, lower, upper, liquidity, false).unwrap();
+        let (below_0, below_1) = below;
+        assert!(below_0 > 0);
+        assert_eq!(below_1, 0);
+
+        let above = amounts_for_liquidity(upper + 1, lower, upper, liquidity, false).unwrap();
+        let (above_0, above_1) = above;
+        assert_eq!(above_0, 0);
+        assert!(above_1 > 0);
+
+        let (in_0, in_1) = amounts_for_liquidity(1 << 64, lower, upper, liquidity, false).unwrap();
+        assert!(in_0 > 0 && in_0 < below_0);
+        assert!(in_1 > 0 && in_1 < above_1);
+    }
+
+    #[test]
+    fn fees_earned_scales_growth_by_liquidity() {
+        let liquidity = 3_000_000u128;
+        assert_eq!(fees_earned(fixed_point_64::Q64, 0, liquidity), 3_000_000);
+        assert_eq!(fees_earned(fixed_point_64::Q64 / 2, 0, liquidity), 1_500_000);
+        assert_eq!(fees_earned(5, 5, liquidity), 0);
+        // fee growth is a wrapping counter
+        assert_eq!(fees_earned(fixed_point_64::Q64 - 1, u128::MAX, liquidity), 3_000_000);
+    }
+}
@@ -1,3 +1,20 @@
 pub const ANCHOR_DISCRIMINATOR_SIZE: usize = 8;
 
 pub const DENOMINATOR_MULTIPLIER: u64 = 1_000_000_000;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// NAV snapshots kept on a pool position
+pub const NAV_SNAPSHOTS_LEN: usize = 24;
+
+/// Minimum time between two NAV snapshots of a pool position
+pub const NAV_SNAPSHOT_INTERVAL_SECONDS: i64 = 3600;
+
+/// Ranges a pool position can open besides its core range
+pub const MAX_LADDER_RANGES: u8 = 3;
+
+/// Maximum weight of a ladder range, ten times the liquidity of the core range
+pub const MAX_LADDER_WEIGHT_BPS: u32 = 100_000;
+
+/// Minimum delay of the config changes, the timelock of new positions
+pub const MIN_TIMELOCK_SECONDS: i64 = 86_400;
@@ -0,0 +1,98 @@
+//! Adapters over the concentrated liquidity DEXes a pool position holds its liquidity in.
+//!
+//! Each DEX implements `DexAdapter` over bundles of its own accounts: the position instructions
+//! keep the accounting of investors and fees, and only the adapter knows how the DEX is called.
+
+use anchor_lang::prelude::*;
+
+pub mod raydium;
+
+pub use raydium::*;
+
+/// The DEX of a pool position, recorded on its `PoolPositionConfig`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace)]
+pub enum Dex {
+    #[default]
+    RaydiumClmm,
+}
+
+/// Ticks of a position and the start indexes of the tick arrays holding them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionTicks {
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub tick_array_lower_start_index: i32,
+    pub tick_array_upper_start_index: i32,
+}
+
+/// Liquidity of a DEX position and the fees it is owed, including those accrued since the DEX
+/// last updated it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PositionFees {
+    pub liquidity: u128,
+    pub fees_owed_0: u64,
+    pub fees_owed_1: u64,
+}
+
+/// Operations of pool-party on a concentrated liquidity DEX.
+///
+/// Positions are owned by the `PoolPosition` PDA, which signs with `signer_seeds`. A `liquidity`
+/// of zero lets the DEX derive the liquidity from the maximum amounts.
+pub trait DexAdapter {
+    /// Accounts opening a position and funding its first liquidity
+    type OpenPositionAccounts<'info>;
+    /// Accounts of an opened position and of the token accounts paying its liquidity
+    type IncreaseLiquidityAccounts<'info>;
+    /// Accounts of an opened position and of the token accounts receiving its tokens and fees
+    type DecreaseLiquidityAccounts<'info>;
+    /// Accounts reading the liquidity and fee growth of a position
+    type PositionStateAccounts<'info>;
+    /// Accounts of a swap through a single pool
+    type SwapAccounts<'info>;
+
+    fn open_position<'info>(
+        accounts: Self::OpenPositionAccounts<'info>,
+        ticks: PositionTicks,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()>;
+
+    fn increase_liquidity<'info>(
+        accounts: Self::IncreaseLiquidityAccounts<'info>,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()>;
+
+    fn decrease_liquidity<'info>(
+        accounts: Self::DecreaseLiquidityAccounts<'info>,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()>;
+
+    /// Harvests the fees owed to the position into the recipient accounts
+    fn collect_fees<'info>(
+        accounts: Self::DecreaseLiquidityAccounts<'info>,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()>;
+
+    /// Swaps `amount` of the input token for an exact input, or to receive `amount` of the output
+    /// token otherwise, `other_amount_threshold` bounding the other side. A zero
+    /// `sqrt_price_limit_x64` is no limit.
+    fn swap<'info>(
+        accounts: Self::SwapAccounts<'info>,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()>;
+
+    /// Reads the liquidity of the position and the fees it is owed at the fee growth of the pool
+    fn position_fees<'info>(accounts: &Self::PositionStateAccounts<'info>) -> Result<PositionFees>;
+}
@@ -0,0 +1,307 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use raydium_clmm_cpi::{
+    cpi,
+    states::{ PersonalPositionState, PoolState, ProtocolPositionState, TickArrayState },
+};
+
+use crate::dex::{ DexAdapter, PositionFees, PositionTicks };
+use crate::errors::ErrorCode;
+use crate::instructions::{ get_owed_fees, CollectFeesArgs, IncreaseLiquidityArgs, SwapArgs };
+use crate::libraries::{ ClmmAccount, ClmmLoader };
+
+/// Raydium CLMM, whose positions are NFTs held by the `PoolPosition` account
+pub struct RaydiumAdapter;
+
+/// Accounts of `open_position_v2`, paid by `payer` and minting the position NFT to `nft_owner`
+pub struct OpenPositionArgs<'info> {
+    pub clmm_program: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub nft_owner: AccountInfo<'info>,
+    pub position_nft_mint: AccountInfo<'info>,
+    pub position_nft_account: AccountInfo<'info>,
+    pub metadata_account: AccountInfo<'info>,
+    pub pool_state: AccountInfo<'info>,
+    pub protocol_position: AccountInfo<'info>,
+    pub tick_array_lower: AccountInfo<'info>,
+    pub tick_array_upper: AccountInfo<'info>,
+    pub personal_position: AccountInfo<'info>,
+    pub token_account_0: AccountInfo<'info>,
+    pub token_account_1: AccountInfo<'info>,
+    pub token_vault_0: AccountInfo<'info>,
+    pub token_vault_1: AccountInfo<'info>,
+    pub rent: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub metadata_program: AccountInfo<'info>,
+    pub token_program_2022: AccountInfo<'info>,
+    pub vault_0_mint: AccountInfo<'info>,
+    pub vault_1_mint: AccountInfo<'info>,
+    pub remaining_accounts: Vec<AccountInfo<'info>>,
+}
+
+/// The Raydium position and the pool and tick arrays its fee growth is read from
+pub struct RaydiumPositionState<'info> {
+    pub pool_state: ClmmLoader<'info, PoolState>,
+    pub personal_position: Box<ClmmAccount<'info, PersonalPositionState>>,
+    pub tick_array_lower: ClmmLoader<'info, TickArrayState>,
+    pub tick_array_upper: ClmmLoader<'info, TickArrayState>,
+}
+
+impl<'info> RaydiumPositionState<'info> {
+    /// Reads a position of `clmm_program` along with its pool and the tick arrays of its bounds,
+    /// checking they all belong to the pool
+    pub fn try_from(
+        clmm_program: &Pubkey,
+        pool_state: &AccountInfo<'info>,
+        personal_position: &AccountInfo<'info>,
+        tick_array_lower: &AccountInfo<'info>,
+        tick_array_upper: &AccountInfo<'info>
+    ) -> Result<Self> {
+        let pool_state = ClmmLoader::<PoolState>::try_from(pool_state, clmm_program)?;
+        let personal_position = Box::new(
+            ClmmAccount::<PersonalPositionState>::try_from(personal_position, clmm_program)?
+        );
+        let tick_array_lower = ClmmLoader::<TickArrayState>::try_from(
+            tick_array_lower,
+            clmm_program
+        )?;
+        let tick_array_upper = ClmmLoader::<TickArrayState>::try_from(
+            tick_array_upper,
+            clmm_program
+        )?;
+
+        let pool_id = pool_state.key();
+        require_keys_eq!(personal_position.pool_id, pool_id, ErrorCode::InvalidClmmAccount);
+        require_keys_eq!(tick_array_lower.load()?.pool_id, pool_id, ErrorCode::InvalidClmmAccount);
+        require_keys_eq!(tick_array_upper.load()?.pool_id, pool_id, ErrorCode::InvalidClmmAccount);
+        Ok(RaydiumPositionState {
+            pool_state,
+            personal_position,
+            tick_array_lower,
+            tick_array_upper,
+        })
+    }
+
+    /// Checks the accounts passed along the position to its liquidity CPIs: the protocol position
+    /// and token vaults of the pool, and the token account holding the position NFT
+    pub fn check_cpi_accounts(
+        &self,
+        clmm_program: &Pubkey,
+        protocol_position: &AccountInfo<'info>,
+        position_nft_account: &TokenAccount,
+        token_vault_0: Pubkey,
+        token_vault_1: Pubkey
+    ) -> Result<()> {
+        let protocol_position = ClmmAccount::<ProtocolPositionState>::try_from(
+            protocol_position,
+            clmm_program
+        )?;
+        require_keys_eq!(
+            protocol_position.pool_id,
+            self.pool_state.key(),
+            ErrorCode::InvalidClmmAccount
+        );
+        require_keys_eq!(
+            position_nft_account.mint,
+            self.personal_position.nft_mint,
+            ErrorCode::InvalidClmmAccount
+        );
+        let pool_state = self.pool_state.load()?;
+        require_keys_eq!(token_vault_0, pool_state.token_vault_0, ErrorCode::InvalidClmmAccount);
+        require_keys_eq!(token_vault_1, pool_state.token_vault_1, ErrorCode::InvalidClmmAccount);
+        Ok(())
+    }
+}
+
+impl DexAdapter for RaydiumAdapter {
+    type OpenPositionAccounts<'info> = OpenPositionArgs<'info>;
+    type IncreaseLiquidityAccounts<'info> = IncreaseLiquidityArgs<'info>;
+    type DecreaseLiquidityAccounts<'info> = CollectFeesArgs<'info>;
+    type PositionStateAccounts<'info> = RaydiumPositionState<'info>;
+    type SwapAccounts<'info> = SwapArgs<'info>;
+
+    /// Raydium charges the amounts the liquidity needs up to the maxima, or bases the liquidity
+    /// on `amount_0_max` when it is zero
+    fn open_position<'info>(
+        args: OpenPositionArgs<'info>,
+        ticks: PositionTicks,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()> {
+        let cpi_accounts = cpi::accounts::OpenPositionV2 {
+            payer: args.payer,
+            position_nft_owner: args.nft_owner,
+            position_nft_mint: args.position_nft_mint,
+            position_nft_account: args.position_nft_account,
+            metadata_account: args.metadata_account,
+            pool_state: args.pool_state,
+            protocol_position: args.protocol_position,
+            tick_array_lower: args.tick_array_lower,
+            tick_array_upper: args.tick_array_upper,
+            personal_position: args.personal_position,
+            token_account_0: args.token_account_0,
+            token_account_1: args.token_account_1,
+            token_vault_0: args.token_vault_0,
+            token_vault_1: args.token_vault_1,
+            rent: args.rent,
+            system_program: args.system_program,
+            token_program: args.token_program,
+            associated_token_program: args.associated_token_program,
+            metadata_program: args.metadata_program,
+            token_program_2022: args.token_program_2022,
+            vault_0_mint: args.vault_0_mint,
+            vault_1_mint: args.vault_1_mint,
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            args.clmm_program,
+            cpi_accounts,
+            signer_seeds
+        ).with_remaining_accounts(args.remaining_accounts);
+        cpi::open_position_v2(
+            cpi_context,
+            ticks.tick_lower_index,
+            ticks.tick_upper_index,
+            ticks.tick_array_lower_start_index,
+            ticks.tick_array_upper_start_index,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            false,
+            base_flag(liquidity)
+        )
+    }
+
+    fn increase_liquidity<'info>(
+        args: IncreaseLiquidityArgs<'info>,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()> {
+        let cpi_accounts = cpi::accounts::IncreaseLiquidityV2 {
+            nft_owner: args.nft_owner,
+            nft_account: args.nft_account,
+            pool_state: args.pool_state,
+            protocol_position: args.protocol_position,
+            personal_position: args.personal_position,
+            tick_array_lower: args.tick_array_lower,
+            tick_array_upper: args.tick_array_upper,
+            token_account_0: args.token_account_0,
+            token_account_1: args.token_account_1,
+            token_vault_0: args.token_vault_0,
+            token_vault_1: args.token_vault_1,
+            token_program: args.token_program,
+            token_program_2022: args.token_program_2022,
+            vault_0_mint: args.vault_0_mint,
+            vault_1_mint: args.vault_1_mint,
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            args.clmm_program,
+            cpi_accounts,
+            signer_seeds
+        ).with_remaining_accounts(args.remaining_accounts);
+        cpi::increase_liquidity_v2(
+            cpi_context,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            base_flag(liquidity)
+        )
+    }
+
+    /// The fees owed to the position go to the recipient accounts along with its tokens
+    fn decrease_liquidity<'info>(
+        args: CollectFeesArgs<'info>,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()> {
+        let cpi_accounts = cpi::accounts::DecreaseLiquidityV2 {
+            nft_owner: args.nft_owner,
+            nft_account: args.nft_account,
+            pool_state: args.pool_state,
+            protocol_position: args.protocol_position,
+            personal_position: args.personal_position,
+            tick_array_lower: args.tick_array_lower,
+            tick_array_upper: args.tick_array_upper,
+            recipient_token_account_0: args.recipient_token_account_0,
+            recipient_token_account_1: args.recipient_token_account_1,
+            token_vault_0: args.token_vault_0,
+            token_vault_1: args.token_vault_1,
+            token_program: args.token_program,
+            token_program_2022: args.token_program_2022,
+            vault_0_mint: args.vault_0_mint,
+            vault_1_mint: args.vault_1_mint,
+            memo_program: args.memo_program,
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            args.clmm_program,
+            cpi_accounts,
+            signer_seeds
+        ).with_remaining_accounts(args.remaining_accounts);
+        cpi::decrease_liquidity_v2(cpi_context, liquidity, amount_0_min, amount_1_min)
+    }
+
+    /// Raydium pays the fees owed when removing liquidity, even none
+    fn collect_fees<'info>(args: CollectFeesArgs<'info>, signer_seeds: &[&[&[u8]]]) -> Result<()> {
+        Self::decrease_liquidity(args, 0, 0, 0, signer_seeds)
+    }
+
+    fn swap<'info>(
+        args: SwapArgs<'info>,
+        amount: u64,
+        other_amount_threshold: u64,
+        sqrt_price_limit_x64: u128,
+        is_base_input: bool,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()> {
+        let cpi_accounts = cpi::accounts::SwapSingleV2 {
+            payer: args.payer,
+            amm_config: args.amm_config,
+            pool_state: args.pool_state,
+            input_token_account: args.input_token_account,
+            output_token_account: args.output_token_account,
+            input_vault: args.input_vault,
+            output_vault: args.output_vault,
+            observation_state: args.observation_state,
+            token_program: args.token_program,
+            token_program_2022: args.token_program_2022,
+            memo_program: args.memo_program,
+            input_vault_mint: args.input_vault_mint,
+            output_vault_mint: args.output_vault_mint,
+        };
+        let cpi_context = CpiContext::new_with_signer(args.clmm_program, cpi_accounts, signer_seeds)
+            .with_remaining_accounts(args.remaining_accounts);
+        cpi::swap_v2(
+            cpi_context,
+            amount,
+            other_amount_threshold,
+            sqrt_price_limit_x64,
+            is_base_input
+        )
+    }
+
+    fn position_fees<'info>(accounts: &RaydiumPositionState<'info>) -> Result<PositionFees> {
+        let (fees_owed_0, fees_owed_1) = get_owed_fees(
+            accounts.tick_array_lower.clone(),
+            accounts.tick_array_upper.clone(),
+            &accounts.personal_position,
+            &accounts.pool_state
+        );
+        Ok(PositionFees {
+            liquidity: accounts.personal_position.liquidity,
+            fees_owed_0,
+            fees_owed_1,
+        })
+    }
+}
+
+/// Without a liquidity Raydium derives it from `amount_0_max`
+fn base_flag(liquidity: u128) -> Option<bool> {
+    if liquidity == 0 { Some(true) } else { None }
+}
@@ -18,4 +18,106 @@ pub enum ErrorCode {
 
     #[msg("Unauthorized")]
     Unauthorized,
+
+    #[msg("Liquidity added is below the minimum expected")]
+    LiquiditySlippageExceeded,
+
+    #[msg("Pool price deviates more than allowed from the expected price")]
+    PriceDeviationExceeded,
+
+    #[msg("Remaining accounts do not match the expected layout")]
+    InvalidRemainingAccounts,
+
+    #[msg("Max token overflow")]
+    MaxTokenOverflow,
+
+    #[msg("Liquidity overflow or underflow while crossing a tick")]
+    LiquidityOverflow,
+
+    #[msg("The swap pool does not match the expected pool")]
+    InvalidSwapPool,
+
+    #[msg("The swap route does not chain from the stable mint to the expected token")]
+    InvalidSwapRoute,
+
+    #[msg("Swap route output is below the minimum expected")]
+    TooLittleOutputReceived,
+
+    #[msg("The price pool does not pair the token with the stable mint")]
+    InvalidPricePool,
+
+    #[msg("The TWAP window must be positive and start after the epoch")]
+    InvalidTwapWindow,
+
+    #[msg("The oracle has no observation old enough for the TWAP window")]
+    NotEnoughObservations,
+
+    #[msg("A NAV snapshot was already taken during the snapshot interval")]
+    NavSnapshotTooEarly,
+
+    #[msg("The deposit is below the minimum deposit of the position")]
+    DepositBelowMinimum,
+
+    #[msg("The liquidity of the investor would exceed the per-investor cap")]
+    InvestorLiquidityCapExceeded,
+
+    #[msg("The liquidity of the position would exceed its total cap")]
+    TotalLiquidityCapExceeded,
+
+    #[msg("The investor is not in the allowlist of the position")]
+    NotAllowlisted,
+
+    #[msg("The liquidity of the investor is still locked")]
+    LiquidityLocked,
+
+    #[msg("The early exit penalty must not exceed 10000 basis points")]
+    InvalidEarlyExitPenalty,
+
+    #[msg("The lockup must not be negative")]
+    InvalidLockup,
+
+    #[msg("Cannot remove more liquidity than the investor holds")]
+    InsufficientLiquidity,
+
+    #[msg("The position is paused")]
+    PositionPaused,
+
+    #[msg("The timelock is shorter than the minimum")]
+    InvalidTimelock,
+
+    #[msg("The timelock of the config change has not elapsed")]
+    ConfigChangeTimelocked,
+
+    #[msg("The ladder weight must be positive and at most ten times the core range")]
+    InvalidLadderWeight,
+
+    #[msg("The position already holds the maximum number of ladder ranges")]
+    LadderFull,
+
+    #[msg("Ladder ranges can only be opened while the manager holds all the liquidity")]
+    LadderHasInvestors,
+
+    #[msg("The instruction does not support positions with ladder ranges")]
+    LadderNotSupported,
+
+    #[msg("Token amounts are below the minimum expected")]
+    AmountSlippageExceeded,
+
+    #[msg("The token program does not own the mint")]
+    InvalidTokenProgram,
+
+    #[msg("The CLMM program is not in the allowlist of the protocol config")]
+    ClmmProgramNotAllowed,
+
+    #[msg("The account is not a state of the CLMM program, or belongs to another pool")]
+    InvalidClmmAccount,
+
+    #[msg("The protocol config holds too many CLMM programs")]
+    TooManyClmmPrograms,
+
+    #[msg("The account already has the current layout version")]
+    AccountAlreadyMigrated,
+
+    #[msg("The position name is longer than 32 bytes")]
+    NameTooLong,
 }
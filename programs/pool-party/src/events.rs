@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+use crate::state::ConfigChange;
+
+/// A queued change proposed a new manager of a pool position, who still has to accept
+#[event]
+pub struct ManagerProposed {
+    pub pool_position_config: Pubkey,
+    pub manager: Pubkey,
+    pub pending_manager: Pubkey,
+}
+
+/// A queued change withdrew the proposal of a new manager
+#[event]
+pub struct ManagerProposalWithdrawn {
+    pub pool_position_config: Pubkey,
+    pub manager: Pubkey,
+}
+
+/// The proposed manager accepted and now holds the manager role
+#[event]
+pub struct ManagerAccepted {
+    pub pool_position_config: Pubkey,
+    pub previous_manager: Pubkey,
+    pub manager: Pubkey,
+}
+
+/// The guardian paused or unpaused a pool position
+#[event]
+pub struct PausedSet {
+    pub pool_position_config: Pubkey,
+    pub paused: bool,
+}
+
+/// A config change was queued, investors can exit before `executable_at`
+#[event]
+pub struct ConfigChangeQueued {
+    pub pool_position_config: Pubkey,
+    pub change_id: u64,
+    pub proposer: Pubkey,
+    pub change: ConfigChange,
+    pub executable_at: i64,
+}
+
+/// A queued config change was applied
+#[event]
+pub struct ConfigChangeExecuted {
+    pub pool_position_config: Pubkey,
+    pub change_id: u64,
+    pub change: ConfigChange,
+}
+
+/// A queued config change was dropped without applying
+#[event]
+pub struct ConfigChangeCancelled {
+    pub pool_position_config: Pubkey,
+    pub change_id: u64,
+}
+
+/// The manager opened a ladder range besides the core range of a pool position
+#[event]
+pub struct LadderRangeOpened {
+    pub pool_position: Pubkey,
+    pub index: u8,
+    pub position_nft_mint: Pubkey,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub weight_bps: u32,
+    pub liquidity: u128,
+}
+
+/// The CLMM programs allowlist of the protocol config was set
+#[event]
+pub struct ClmmProgramsSet {
+    pub protocol_config: Pubkey,
+    pub clmm_programs: Vec<Pubkey>,
+}
+
+/// An account was upgraded in place to the current layout version
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::events::ManagerAccepted;
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig };
+
+#[derive(Accounts)]
+pub struct AcceptManagerCtx<'info> {
+    #[account(mut)]
+    pub new_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = pool_position_config.pending_manager_key == Some(new_manager.key()) @
+        ErrorCode::Unauthorized
+    )]
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
+        bump
+    )]
+    pub pool_position: AccountLoader<'info, PoolPosition>,
+
+    #[account(
+        mut,
+        seeds = [
+            Investor::INVESTOR_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            pool_position_config.manager_key.as_ref(),
+        ],
+        bump
+    )]
+    pub previous_manager_account: AccountLoader<'info, Investor>,
+
+    /// Created when the new manager is not an investor yet
+    #[account(
+        init_if_needed,
+        payer = new_manager,
+        seeds = [
+            Investor::INVESTOR_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            new_manager.key().as_ref(),
+        ],
+        bump,
+        space = Investor::LEN
+    )]
+    pub new_manager_account: AccountLoader<'info, Investor>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptManagerCtx<'info> {
+    /// Moves the manager role and the `is_manager` flag, along with the roles the previous
+    /// manager still holds, so that its key keeps no control of the position. The liquidity and
+    /// fees of both investor accounts stay with their owners.
+    pub fn accept_manager(&mut self, bumps: &AcceptManagerCtxBumps) -> Result<()> {
+        let new_manager = self.new_manager.key();
+        let pool_position_config = &mut self.pool_position_config;
+        let previous_manager = pool_position_config.manager_key;
+        pool_position_config.manager_key = new_manager;
+        pool_position_config.pending_manager_key = None;
+        pool_position_config.roles.rotate(previous_manager, new_manager);
+
+        self.previous_manager_account.load_mut()?.is_manager = 0;
+        // The discriminator of a created account is only written on exit
+        let mut new_manager_account = match self.new_manager_account.load_init() {
+            Ok(mut new_manager_account) => {
+                new_manager_account.bump = bumps.new_manager_account;
+                new_manager_account.authority = new_manager;
+                new_manager_account.pool_position_key = self.pool_position.key();
+                new_manager_account.version = Investor::VERSION;
+                new_manager_account
+            }
+            Err(_) => self.new_manager_account.load_mut()?,
+        };
+        new_manager_account.is_manager = 1;
+
+        emit!(ManagerAccepted {
+            pool_position_config: pool_position_config.key(),
+            previous_manager,
+            manager: new_manager,
+        });
+        Ok(())
+    }
+}
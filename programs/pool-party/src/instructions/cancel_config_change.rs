@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::events::ConfigChangeCancelled;
+use crate::state::{ PendingConfigChange, PoolPositionConfig };
+
+#[derive(Accounts)]
+pub struct CancelConfigChangeCtx<'info> {
+    /// The proposer of the change, or the guardian
+    #[account(
+        constraint = canceller.key() == pending_config_change.proposer ||
+        canceller.key() == pool_position_config.roles.guardian @ ErrorCode::Unauthorized
+    )]
+    pub canceller: Signer<'info>,
+
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [
+            PendingConfigChange::PENDING_CONFIG_CHANGE_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            pending_config_change.change_id.to_le_bytes().as_ref(),
+        ],
+        bump = pending_config_change.bump
+    )]
+    pub pending_config_change: Box<Account<'info, PendingConfigChange>>,
+
+    /// CHECK: refunded the rent of the pending change
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+impl<'info> CancelConfigChangeCtx<'info> {
+    pub fn cancel_config_change(&mut self) -> Result<()> {
+        emit!(ConfigChangeCancelled {
+            pool_position_config: self.pool_position_config.key(),
+            change_id: self.pending_config_change.change_id,
+        });
+        Ok(())
+    }
+}
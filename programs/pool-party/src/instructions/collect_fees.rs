@@ -3,17 +3,33 @@ use anchor_lang::system_program::System;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::memo::spl_memo;
 use anchor_spl::token::Token;
-use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount };
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount, TokenInterface };
 use num_bigint::BigInt;
-use raydium_clmm_cpi::{
-    cpi,
-    program::RaydiumClmm,
-    states::{ PersonalPositionState, PoolState, ProtocolPositionState, TickArrayState },
-    ID as RAYDIUM_CLMM_ID,
+use raydium_clmm_cpi::states::{ PersonalPositionState, PoolState, TickArrayState };
+use crate::dex::{ DexAdapter, RaydiumAdapter, RaydiumPositionState };
+use crate::errors::ErrorCode;
+use crate::libraries::{
+    harvest_ladder_fees,
+    ladder_liquidity,
+    ladder_owed_fees,
+    received_amount,
+    transfer_token,
+    fixed_point_64,
+    ClmmLoader,
+    MulDiv,
+    RemainingAccountsLayout,
+    U128,
+};
+use crate::state::{
+    tick_array,
+    Investor,
+    PoolPosition,
+    PoolPositionConfig,
+    ProtocolConfig,
+    TickArrayStateExt,
 };
-use crate::libraries::{ transfer_token, fixed_point_64, MulDiv, U128 };
-use crate::state::{ tick_array, Investor, PoolPosition, PoolPositionConfig, TickArrayStateExt };
 
+#[derive(Clone)]
 pub struct CollectFeesArgs<'info> {
     pub clmm_program: AccountInfo<'info>,
     pub nft_owner: AccountInfo<'info>,
@@ -50,7 +66,7 @@ pub struct CollectFeesCtx<'info> {
         seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
         bump,
     )]
-    pub pool_position: Box<Account<'info, PoolPosition>>,
+    pub pool_position: AccountLoader<'info, PoolPosition>,
 
     /// CHECK:
     #[account(
@@ -62,7 +78,7 @@ pub struct CollectFeesCtx<'info> {
         ],
         bump,
     )]
-    pub investor_account: Box<Account<'info, Investor>>,
+    pub investor_account: AccountLoader<'info, Investor>,
 
     #[account(
         mut,
@@ -84,12 +100,17 @@ pub struct CollectFeesCtx<'info> {
     )]
     pub fees_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    /// The investor, or the fee recipient of the position for the fees of the manager
+    /// CHECK: only the owner of the fees accounts
+    #[account(address = pool_position_config.fees_recipient(&*investor_account.load()?))]
+    pub fees_recipient: UncheckedAccount<'info>,
+
     #[account(
         init_if_needed,
         payer = investor,
         associated_token::mint = pool_vault_token_a_mint,
-        associated_token::authority = investor,
-        associated_token::token_program = token_program
+        associated_token::authority = fees_recipient,
+        associated_token::token_program = token_program_0
     )]
     pub investor_deposit_fees_0_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -97,62 +118,66 @@ pub struct CollectFeesCtx<'info> {
         init_if_needed,
         payer = investor,
         associated_token::mint = pool_vault_token_b_mint,
-        associated_token::authority = investor,
-        associated_token::token_program = token_program
+        associated_token::authority = fees_recipient,
+        associated_token::token_program = token_program_1
     )]
     pub investor_deposit_fees_1_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(address = RAYDIUM_CLMM_ID)]
-    pub clmm_program: Program<'info, RaydiumClmm>,
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
 
-    /// The token account for nft
+    /// CHECK: a CLMM program of the protocol config allowlist
     #[account(
-        constraint = position_nft_account.mint == personal_position.nft_mint,
-        token::token_program = token_program
+        executable,
+        constraint = protocol_config.is_clmm_program(clmm_program.key)
+            @ ErrorCode::ClmmProgramNotAllowed
     )]
+    pub clmm_program: UncheckedAccount<'info>,
+
+    /// The token account for nft
+    #[account(token::token_program = token_program)]
     pub position_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(mut)]
-    pub pool_state: AccountLoader<'info, PoolState>,
+    /// CHECK: a pool of `clmm_program`, read by `RaydiumPositionState`
+    #[account(mut, address = pool_position_config.pool_key)]
+    pub pool_state: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        constraint = protocol_position.pool_id == pool_state.key(),
-    )]
-    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+    /// CHECK: a protocol position of `pool_state`, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
 
     /// Increase liquidity for this position
-    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
-    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+    /// CHECK: a personal position of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub personal_position: UncheckedAccount<'info>,
 
     /// Stores init state for the lower tick
-    #[account(mut, constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
-    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
 
     /// Stores init state for the upper tick
-    #[account(mut, constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
-    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
 
-    /// The address that holds pool tokens for token_0
-    #[account(
-        mut,
-        constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
-    )]
+    /// The address that holds pool tokens for token_0, checked by `RaydiumPositionState`
+    #[account(mut)]
     pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// The address that holds pool tokens for token_1
-    #[account(
-        mut,
-        constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
-    )]
+    /// The address that holds pool tokens for token_1, checked by `RaydiumPositionState`
+    #[account(mut)]
     pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// The mint of token vault 0
-    #[account(address = token_vault_0.mint)]
+    #[account(address = token_vault_0.mint, mint::token_program = token_program_0)]
     pub pool_vault_token_a_mint: Box<InterfaceAccount<'info, Mint>>,
 
     /// The mint of token vault 1
-    #[account(address = token_vault_1.mint)]
+    #[account(address = token_vault_1.mint, mint::token_program = token_program_1)]
     pub pool_vault_token_b_mint: Box<InterfaceAccount<'info, Mint>>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -162,6 +187,13 @@ pub struct CollectFeesCtx<'info> {
 
     /// Token program 2022
     pub token_program_2022: Program<'info, Token2022>,
+
+    /// Token program of the mint of token vault 0, legacy or Token-2022
+    pub token_program_0: Interface<'info, TokenInterface>,
+
+    /// Token program of the mint of token vault 1
+    pub token_program_1: Interface<'info, TokenInterface>,
+
     /// memo program
     /// CHECK:
     #[account(address = spl_memo::id())]
@@ -185,20 +217,47 @@ pub struct CollectFeesCtx<'info> {
 impl<'info> CollectFeesCtx<'info> {
     pub fn collect_fees<'a, 'b, 'c: 'info>(
         &mut self,
+        remaining_accounts_layout: RemainingAccountsLayout,
         remaining_accounts: &'c [AccountInfo<'info>],
         bumps: &CollectFeesCtxBumps
     ) -> Result<()> {
-        let (fees_owed0, fees_owed1) = get_owed_fees(
-            self.tick_array_lower.clone(),
-            self.tick_array_upper.clone(),
-            &self.personal_position,
-            &self.pool_state
+        let (position_nft_mint_key, ladder_ranges) = {
+            let pool_position = self.pool_position.load()?;
+            (pool_position.position_nft_mint_key, pool_position.ladder_ranges)
+        };
+        let position = self.raydium_position()?;
+        // The ladder ranges are owned by the pool position too, only the core one is accepted
+        require_keys_eq!(
+            position.personal_position.nft_mint,
+            position_nft_mint_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        let (remaining_accounts, ranges) = remaining_accounts_layout.parse_liquidity_ladder(
+            remaining_accounts,
+            self.clmm_program.key,
+            self.pool_state.key(),
+            self.pool_position.key(),
+            ladder_ranges
+        )?;
+        let (core_fees_owed0, core_fees_owed1) = get_owed_fees(
+            position.tick_array_lower.clone(),
+            position.tick_array_upper.clone(),
+            &position.personal_position,
+            &position.pool_state
+        );
+        let (ladder_fees_owed0, ladder_fees_owed1) = ladder_owed_fees(
+            &ranges,
+            &position.pool_state
         );
+        let fees_owed0 = core_fees_owed0.checked_add(ladder_fees_owed0).unwrap();
+        let fees_owed1 = core_fees_owed1.checked_add(ladder_fees_owed1).unwrap();
         msg!("fees_owed0: {}", fees_owed0);
         msg!("fees_owed1: {}", fees_owed1);
 
+        let fees_0_before = self.fees_vault_0_token_account.amount;
+        let fees_1_before = self.fees_vault_1_token_account.amount;
         if fees_owed0 > 0 || fees_owed1 > 0 {
-            let pool_position_bump_seed = self.pool_position.bump;
+            let pool_position_bump_seed = self.pool_position.load()?.bump;
             let pool_position_config_key = self.pool_position_config.key();
             let signer_seeds: &[&[&[u8]]] = &[
                 &[
@@ -208,74 +267,102 @@ impl<'info> CollectFeesCtx<'info> {
                 ],
             ];
 
-            collect_fees(
-                CollectFeesArgs {
-                    clmm_program: self.clmm_program.to_account_info(),
-                    nft_owner: self.pool_position.to_account_info(),
-                    nft_account: self.position_nft_account.to_account_info(),
-                    pool_state: self.pool_state.to_account_info(),
-                    protocol_position: self.protocol_position.to_account_info(),
-                    personal_position: self.personal_position.to_account_info(),
-                    tick_array_lower: self.tick_array_lower.to_account_info(),
-                    tick_array_upper: self.tick_array_upper.to_account_info(),
-                    recipient_token_account_0: self.fees_vault_0_token_account.to_account_info(),
-                    recipient_token_account_1: self.fees_vault_1_token_account.to_account_info(),
-                    token_vault_0: self.token_vault_0.to_account_info(),
-                    token_vault_1: self.token_vault_1.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
-                    token_program_2022: self.token_program_2022.to_account_info(),
-                    vault_0_mint: self.pool_vault_token_a_mint.to_account_info(),
-                    vault_1_mint: self.pool_vault_token_b_mint.to_account_info(),
-                    memo_program: self.memo_program.to_account_info(),
-                    remaining_accounts: remaining_accounts.to_vec(),
-                },
-                signer_seeds
-            )?;
+            let args = CollectFeesArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                nft_owner: self.pool_position.to_account_info(),
+                nft_account: self.position_nft_account.to_account_info(),
+                pool_state: self.pool_state.to_account_info(),
+                protocol_position: self.protocol_position.to_account_info(),
+                personal_position: self.personal_position.to_account_info(),
+                tick_array_lower: self.tick_array_lower.to_account_info(),
+                tick_array_upper: self.tick_array_upper.to_account_info(),
+                recipient_token_account_0: self.fees_vault_0_token_account.to_account_info(),
+                recipient_token_account_1: self.fees_vault_1_token_account.to_account_info(),
+                token_vault_0: self.token_vault_0.to_account_info(),
+                token_vault_1: self.token_vault_1.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                vault_0_mint: self.pool_vault_token_a_mint.to_account_info(),
+                vault_1_mint: self.pool_vault_token_b_mint.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                remaining_accounts,
+            };
+            harvest_ladder_fees(&ranges, &position.pool_state, &args, signer_seeds)?;
+            if core_fees_owed0 > 0 || core_fees_owed1 > 0 {
+                RaydiumAdapter::collect_fees(args, signer_seeds)?;
+            }
         }
-        let investor_account = self.investor_account.clone();
-        let pool_position = self.pool_position.clone();
-        let liquidity = self.personal_position.liquidity;
-
-        let (fees_index0, fees_index1) = fees_indexes(liquidity, fees_owed0, fees_owed1);
-
-        let last_fees_index0 = pool_position.fees_index0;
-        let last_fees_index1 = pool_position.fees_index1;
-
-        let investor_liquidity = investor_account.liquidity;
-
-        let fees_index0 = U128::from(last_fees_index0)
-            .checked_add(U128::from(fees_index0))
-            .unwrap()
-            .as_u128();
-        let fees_index1 = U128::from(last_fees_index1)
-            .checked_add(U128::from(fees_index1))
-            .unwrap()
-            .as_u128();
-
-        let fees_earned0 = investor_account.fees_earned0
-            .checked_add(
-                calculate_fees(investor_liquidity, fees_index0, investor_account.fees_index0)
-            )
-            .unwrap();
-        let fees_earned1 = investor_account.fees_earned1
-            .checked_add(
-                calculate_fees(investor_liquidity, fees_index1, investor_account.fees_index1)
-            )
+        // The fees of every range are shared over the liquidity of the whole ladder
+        let liquidity = position.personal_position.liquidity
+            .checked_add(ladder_liquidity(&ranges))
             .unwrap();
 
-        self.transfer_fees(fees_earned0, fees_earned1, bumps)?;
-
-        let pool_position = &mut self.pool_position;
-        let investor_account = &mut self.investor_account;
-
-        pool_position.fees_index0 = fees_index0;
-        pool_position.fees_index1 = fees_index1;
-        investor_account.fees_earned0 = 0;
-        investor_account.fees_earned1 = 0;
-        investor_account.fees_index0 = pool_position.fees_index0;
-        investor_account.fees_index1 = pool_position.fees_index1;
+        // The investors share the fees the vaults received, net of the transfer fees
+        let fees_received0 = received_amount(&mut self.fees_vault_0_token_account, fees_0_before)?;
+        let fees_received1 = received_amount(&mut self.fees_vault_1_token_account, fees_1_before)?;
+        let (fees_index0, fees_index1) = fees_indexes(liquidity, fees_received0, fees_received1);
+
+        let (fees_earned0, fees_earned1) = {
+            let mut pool_position = self.pool_position.load_mut()?;
+            let mut investor_account = self.investor_account.load_mut()?;
+            let investor_liquidity = investor_account.liquidity;
+
+            pool_position.fees_index0 = U128::from(pool_position.fees_index0)
+                .checked_add(U128::from(fees_index0))
+                .unwrap()
+                .as_u128();
+            pool_position.fees_index1 = U128::from(pool_position.fees_index1)
+                .checked_add(U128::from(fees_index1))
+                .unwrap()
+                .as_u128();
+
+            let fees_earned0 = investor_account.fees_earned0
+                .checked_add(
+                    calculate_fees(
+                        investor_liquidity,
+                        pool_position.fees_index0,
+                        investor_account.fees_index0
+                    )
+                )
+                .unwrap();
+            let fees_earned1 = investor_account.fees_earned1
+                .checked_add(
+                    calculate_fees(
+                        investor_liquidity,
+                        pool_position.fees_index1,
+                        investor_account.fees_index1
+                    )
+                )
+                .unwrap();
+
+            investor_account.fees_earned0 = 0;
+            investor_account.fees_earned1 = 0;
+            investor_account.fees_index0 = pool_position.fees_index0;
+            investor_account.fees_index1 = pool_position.fees_index1;
+            (fees_earned0, fees_earned1)
+        };
+
+        // The token CPIs run once the loaded accounts are released
+        self.transfer_fees(fees_earned0, fees_earned1, bumps)
+    }
 
-        Ok(())
+    /// The Raydium position of the pool position, checked against the accounts of its CPIs
+    fn raydium_position(&self) -> Result<RaydiumPositionState<'info>> {
+        let position = RaydiumPositionState::try_from(
+            self.clmm_program.key,
+            &self.pool_state,
+            &self.personal_position,
+            &self.tick_array_lower,
+            &self.tick_array_upper
+        )?;
+        position.check_cpi_accounts(
+            self.clmm_program.key,
+            &self.protocol_position,
+            &self.position_nft_account,
+            self.token_vault_0.key(),
+            self.token_vault_1.key()
+        )?;
+        Ok(position)
     }
 
     fn transfer_fees(
@@ -297,17 +384,13 @@ impl<'info> CollectFeesCtx<'info> {
                 &[fees_vault_0_token_account_bump_seed],
             ],
         ];
-        let token_program = self.token_program.clone();
-        let recipient_0 = self.investor_deposit_fees_0_account.clone();
-        let fees_vault_0_token_account = self.fees_vault_0_token_account.clone();
-        let token_0_mint_acc = self.pool_vault_token_a_mint.clone();
         transfer_token(
-            &fees_vault_0_token_account,
-            &recipient_0,
+            &self.fees_vault_0_token_account,
+            &self.investor_deposit_fees_0_account,
             &amount_0,
-            &token_0_mint_acc,
-            &fees_vault_0_token_account.to_account_info(),
-            &token_program,
+            &self.pool_vault_token_a_mint,
+            &self.fees_vault_0_token_account.to_account_info(),
+            &self.token_program_0,
             Some(a_seeds)
         )?;
 
@@ -319,26 +402,23 @@ impl<'info> CollectFeesCtx<'info> {
                 &[fees_vault_1_token_account_bump_seed],
             ],
         ];
-        let recipient_1 = self.investor_deposit_fees_1_account.clone();
-        let fees_vault_1_token_account = self.fees_vault_1_token_account.clone();
-        let token_1_mint_acc = self.pool_vault_token_b_mint.clone();
         transfer_token(
-            &fees_vault_1_token_account,
-            &recipient_1,
+            &self.fees_vault_1_token_account,
+            &self.investor_deposit_fees_1_account,
             &amount_1,
-            &token_1_mint_acc,
-            &fees_vault_1_token_account.to_account_info(),
-            &token_program,
+            &self.pool_vault_token_b_mint,
+            &self.fees_vault_1_token_account.to_account_info(),
+            &self.token_program_1,
             Some(b_seeds)
         )
     }
 }
 
 pub fn get_owed_fees<'info>(
-    tick_array_lower: AccountLoader<'info, TickArrayState>,
-    tick_array_upper: AccountLoader<'info, TickArrayState>,
+    tick_array_lower: ClmmLoader<'info, TickArrayState>,
+    tick_array_upper: ClmmLoader<'info, TickArrayState>,
     personal_position: &PersonalPositionState,
-    pool_state: &AccountLoader<'info, PoolState>
+    pool_state: &ClmmLoader<'info, PoolState>
 ) -> (u64, u64) {
     let pool_state = match pool_state.clone().load() {
         Ok(state) => Box::new(state.clone()),
@@ -371,8 +451,8 @@ pub fn get_owed_fees<'info>(
 }
 
 pub fn get_fees_growth_inside_last<'info>(
-    tick_array_lower: AccountLoader<'info, TickArrayState>,
-    tick_array_upper: AccountLoader<'info, TickArrayState>,
+    tick_array_lower: ClmmLoader<'info, TickArrayState>,
+    tick_array_upper: ClmmLoader<'info, TickArrayState>,
     personal_position: &PersonalPositionState,
     pool_state: &PoolState
 ) -> (u128, u128) {
@@ -461,31 +541,3 @@ pub fn calculate_fees(investor_liquidity: u128, fee_index: u128, investor_fee_in
         .to_string();
     res.parse::<u64>().unwrap()
 }
-
-pub fn collect_fees<'info>(args: CollectFeesArgs<'info>, signer_seeds: &[&[&[u8]]]) -> Result<()> {
-    let cpi_accounts = cpi::accounts::DecreaseLiquidityV2 {
-        nft_owner: args.nft_owner,
-        nft_account: args.nft_account,
-        pool_state: args.pool_state,
-        protocol_position: args.protocol_position,
-        personal_position: args.personal_position,
-        tick_array_lower: args.tick_array_lower,
-        tick_array_upper: args.tick_array_upper,
-        recipient_token_account_0: args.recipient_token_account_0,
-        recipient_token_account_1: args.recipient_token_account_1,
-        token_vault_0: args.token_vault_0,
-        token_vault_1: args.token_vault_1,
-        token_program: args.token_program,
-        token_program_2022: args.token_program_2022,
-        vault_0_mint: args.vault_0_mint,
-        vault_1_mint: args.vault_1_mint,
-        memo_program: args.memo_program,
-    };
-
-    let cpi_context = CpiContext::new_with_signer(
-        args.clmm_program.to_account_info(),
-        cpi_accounts,
-        signer_seeds
-    ).with_remaining_accounts(args.remaining_accounts.to_vec());
-    cpi::decrease_liquidity_v2(cpi_context, 0, 0, 0)
-}
@@ -1,18 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::Token;
-use anchor_spl::token_interface::{ Mint, TokenAccount };
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
 
-use crate::constants::ANCHOR_DISCRIMINATOR_SIZE;
-use crate::state::{ Investor, PoolPosition };
+use crate::errors::ErrorCode;
+use crate::libraries::verify_allowlist_proof;
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig };
 
 #[derive(Accounts)]
 pub struct CreateInvestorPositionCtx<'info> {
     #[account(mut)]
     pub investor: Signer<'info>,
 
-    /// CHECK:
-    #[account( mut )]
-    pub pool_position_config: UncheckedAccount<'info>,
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
 
     /// CHECK:
     #[account( 
@@ -23,7 +21,7 @@ pub struct CreateInvestorPositionCtx<'info> {
         ],
         bump,
     )]
-    pub pool_position: Box<Account<'info, PoolPosition>>,
+    pub pool_position: AccountLoader<'info, PoolPosition>,
 
     #[account(
         init_if_needed,
@@ -34,9 +32,9 @@ pub struct CreateInvestorPositionCtx<'info> {
             investor.key().as_ref(),
         ],
         bump,
-        space = ANCHOR_DISCRIMINATOR_SIZE + Investor::LEN
+        space = Investor::LEN
     )]
-    pub investor_account: Box<Account<'info, Investor>>,
+    pub investor_account: AccountLoader<'info, Investor>,
 
     #[account(
         init_if_needed,
@@ -47,7 +45,8 @@ pub struct CreateInvestorPositionCtx<'info> {
         ],
         bump,
         token::mint = pool_vault_token_0_mint,
-        token::authority = investor_deposit_token_0_account
+        token::authority = investor_deposit_token_0_account,
+        token::token_program = token_program_0
     )]
     pub investor_deposit_token_0_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -60,18 +59,24 @@ pub struct CreateInvestorPositionCtx<'info> {
         ],
         bump,
         token::mint = pool_vault_token_1_mint,
-        token::authority = investor_deposit_token_1_account
+        token::authority = investor_deposit_token_1_account,
+        token::token_program = token_program_1
     )]
     pub investor_deposit_token_1_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// The mint of token vault 0
+    #[account(mint::token_program = token_program_0)]
     pub pool_vault_token_0_mint: Box<InterfaceAccount<'info, Mint>>,
 
     /// The mint of token vault 1
+    #[account(mint::token_program = token_program_1)]
     pub pool_vault_token_1_mint: Box<InterfaceAccount<'info, Mint>>,
 
-    /// SPL program for token transfers
-    pub token_program: Program<'info, Token>,
+    /// Token program of the mint of token vault 0, legacy or Token-2022
+    pub token_program_0: Interface<'info, TokenInterface>,
+
+    /// Token program of the mint of token vault 1
+    pub token_program_1: Interface<'info, TokenInterface>,
 
     /// System program
     pub system_program: Program<'info, System>,
@@ -80,12 +85,23 @@ pub struct CreateInvestorPositionCtx<'info> {
 impl<'info> CreateInvestorPositionCtx<'info> {
     pub fn create_investor_position<'a, 'b, 'c: 'info>(
         &mut self,
+        allowlist_proof: Vec<[u8; 32]>,
         bumps: &CreateInvestorPositionCtxBumps
     ) -> Result<()> {
-        let investor = &mut self.investor_account;
-        investor.bump = bumps.investor_account;
-        investor.authority = *self.investor.key;
-        investor.pool_position_key = self.pool_position.key();
+        if let Some(root) = self.pool_position_config.allowlist_root {
+            require!(
+                verify_allowlist_proof(&root, &allowlist_proof, self.investor.key),
+                ErrorCode::NotAllowlisted
+            );
+        }
+
+        // An existing account already has its discriminator and is left as it is
+        if let Ok(mut investor) = self.investor_account.load_init() {
+            investor.bump = bumps.investor_account;
+            investor.authority = *self.investor.key;
+            investor.pool_position_key = self.pool_position.key();
+            investor.version = Investor::VERSION;
+        }
 
         Ok(())
     }
@@ -1,10 +1,8 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::Token;
 
-use crate::{
-    constants::ANCHOR_DISCRIMINATOR_SIZE,
-    state::{ Investor, PoolPosition, PoolPositionConfig },
-};
+use crate::constants::MIN_TIMELOCK_SECONDS;
+use crate::{ dex::Dex, state::{ Investor, PoolPosition, PoolPositionConfig, PositionRoles } };
 
 #[derive(Accounts)]
 #[instruction(
@@ -19,7 +17,7 @@ pub struct CreatePositionCtx<'info> {
         payer = manager,
         seeds = [PoolPositionConfig::POOL_POSITION_CONFIG_SEED.as_bytes(), name.as_bytes()],
         bump,
-        space = ANCHOR_DISCRIMINATOR_SIZE + PoolPositionConfig::LEN + 4 + name.len()
+        space = PoolPositionConfig::LEN
     )]
     pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
 
@@ -28,9 +26,9 @@ pub struct CreatePositionCtx<'info> {
         payer = manager,
         seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
         bump,
-        space = ANCHOR_DISCRIMINATOR_SIZE + PoolPosition::LEN
+        space = PoolPosition::LEN
     )]
-    pub pool_position: Box<Account<'info, PoolPosition>>,
+    pub pool_position: AccountLoader<'info, PoolPosition>,
 
     #[account(
         init,
@@ -41,9 +39,9 @@ pub struct CreatePositionCtx<'info> {
             manager.key().as_ref(),
         ],
         bump,
-        space = ANCHOR_DISCRIMINATOR_SIZE + Investor::LEN
+        space = Investor::LEN
     )]
-    pub manager_account: Box<Account<'info, Investor>>,
+    pub manager_account: AccountLoader<'info, Investor>,
 
     /// Program to create mint account and mint tokens
     pub token_program: Program<'info, Token>,
@@ -63,32 +61,43 @@ impl<'info> CreatePositionCtx<'info> {
         token_vault_1_key: Pubkey,
         vault_0_mint_key: Pubkey,
         vault_1_mint_key: Pubkey,
+        dex: Dex,
         bumps: &CreatePositionCtxBumps
     ) -> Result<()> {
         let pool_position_config = &mut self.pool_position_config;
-        let pool_position = &mut self.pool_position;
         pool_position_config.name = name.clone();
         pool_position_config.tick_lower_index = tick_lower_index;
         pool_position_config.tick_upper_index = tick_upper_index;
         pool_position_config.pool_key = pool_state_key;
-        pool_position_config.pool_position_key = pool_position.key();
+        pool_position_config.pool_position_key = self.pool_position.key();
         pool_position_config.manager_key = self.manager.key();
         pool_position_config.token_vault_0_key = token_vault_0_key;
         pool_position_config.token_vault_1_key = token_vault_1_key;
         pool_position_config.vault_0_mint_key = vault_0_mint_key;
         pool_position_config.vault_1_mint_key = vault_1_mint_key;
         pool_position_config.bump = bumps.pool_position_config;
+        pool_position_config.dex = dex;
+        pool_position_config.timelock_seconds = MIN_TIMELOCK_SECONDS;
+        pool_position_config.version = PoolPositionConfig::VERSION;
+        pool_position_config.roles = PositionRoles {
+            strategist: self.manager.key(),
+            guardian: self.manager.key(),
+            fee_recipient: self.manager.key(),
+        };
 
-        let manager_account = &mut self.manager_account;
-
+        let mut pool_position = self.pool_position.load_init()?;
         pool_position.bump = bumps.pool_position;
         pool_position.pool_position_config_key = pool_position_config.key();
         pool_position.liquidity = 0;
+        pool_position.set_name(&name)?;
+        pool_position.version = PoolPosition::VERSION;
 
+        let mut manager_account = self.manager_account.load_init()?;
         manager_account.bump = bumps.manager_account;
         manager_account.authority = self.manager.key();
-        manager_account.pool_position_key = pool_position.key();
-        manager_account.is_manager = true;
+        manager_account.pool_position_key = self.pool_position.key();
+        manager_account.is_manager = 1;
+        manager_account.version = Investor::VERSION;
 
         Ok(())
     }
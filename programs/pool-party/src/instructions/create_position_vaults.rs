@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{ token::Token, token_interface::{ Mint, TokenAccount } };
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
 
 use crate::{ errors, state::PoolPosition };
 
@@ -8,9 +8,8 @@ pub struct CreatePositionVaultsCtx<'info> {
     #[account(mut)]
     pub manager: Signer<'info>,
 
-    /// CHECK:
-    #[account( mut )]
-    pub pool_position: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub pool_position: AccountLoader<'info, PoolPosition>,
 
     #[account(
         init,
@@ -19,7 +18,8 @@ pub struct CreatePositionVaultsCtx<'info> {
         bump,
         token::mint = vault_0_mint,
         // Must be the pool position account because the NFT owner in the raydium is the pool position account
-        token::authority = pool_position
+        token::authority = pool_position,
+        token::token_program = token_program_0
     )]
     pub pool_position_vault_0_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -30,7 +30,8 @@ pub struct CreatePositionVaultsCtx<'info> {
         bump,
         token::mint = vault_1_mint,
         // Must be the pool position account because the NFT owner in the raydium is the pool position account
-        token::authority = pool_position
+        token::authority = pool_position,
+        token::token_program = token_program_1
     )]
     pub pool_position_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -43,7 +44,8 @@ pub struct CreatePositionVaultsCtx<'info> {
         ],
         bump,
         token::mint = vault_0_mint,
-        token::authority = fees_vault_0_token_account
+        token::authority = fees_vault_0_token_account,
+        token::token_program = token_program_0
     )]
     pub fees_vault_0_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
@@ -56,17 +58,23 @@ pub struct CreatePositionVaultsCtx<'info> {
         ],
         bump,
         token::mint = vault_1_mint,
-        token::authority = fees_vault_1_token_account
+        token::authority = fees_vault_1_token_account,
+        token::token_program = token_program_1
     )]
     pub fees_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// The mint of token vault 0
+    #[account(mint::token_program = token_program_0)]
     pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
     /// The mint of token vault 1
+    #[account(mint::token_program = token_program_1)]
     pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
 
-    /// Program to create mint account and mint tokens
-    pub token_program: Program<'info, Token>,
+    /// Token program of the mint of token vault 0, legacy or Token-2022
+    pub token_program_0: Interface<'info, TokenInterface>,
+
+    /// Token program of the mint of token vault 1
+    pub token_program_1: Interface<'info, TokenInterface>,
 
     /// Program to create the position manager state account
     pub system_program: Program<'info, System>,
@@ -74,19 +82,12 @@ pub struct CreatePositionVaultsCtx<'info> {
 
 impl<'info> CreatePositionVaultsCtx<'info> {
     pub fn create_position_vaults(&mut self) -> Result<()> {
-        let mut data: &[u8] = &mut self.pool_position.try_borrow_data()?;
-        let mut pool_position = PoolPosition::try_deserialize(&mut data)?;
-        if pool_position.vaults_initialized {
-            return Err(errors::ErrorCode::VaultsAlreadyInitialized.into());
-        }
-        pool_position.vaults_initialized = true;
-        let mut vec_data = pool_position.try_to_vec()?;
-        let slice_data: &mut [u8] = &mut vec_data[..];
-
-        let result = self.pool_position.serialize_data(&slice_data);
-        if result.is_err() {
-            return Err(anchor_lang::error::ErrorCode::AccountDidNotSerialize.into());
-        }
+        let mut pool_position = self.pool_position.load_mut()?;
+        require!(
+            pool_position.vaults_initialized == 0,
+            errors::ErrorCode::VaultsAlreadyInitialized
+        );
+        pool_position.vaults_initialized = 1;
         Ok(())
     }
 }
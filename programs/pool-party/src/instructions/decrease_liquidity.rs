@@ -0,0 +1,438 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::spl_memo;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount, TokenInterface };
+
+use crate::dex::{ DexAdapter, RaydiumAdapter, RaydiumPositionState };
+use crate::errors::ErrorCode;
+use crate::instructions::{
+    accrue_investor_fees,
+    fees_indexes,
+    get_owed_fees,
+    updated_liquidity_personal_position,
+    CollectFeesArgs,
+};
+use crate::libraries::{
+    early_exit_penalty,
+    exit_penalty_bps,
+    harvest_ladder_fees,
+    ladder_liquidity,
+    ladder_owed_fees,
+    range_share,
+    received_amount,
+    transfer_token,
+    RemainingAccountsLayout,
+};
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig, ProtocolConfig };
+
+#[derive(Accounts)]
+pub struct DecreaseLiquidityCtx<'info> {
+    #[account(mut)]
+    pub investor: Signer<'info>,
+
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        mut,
+        seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
+        bump
+    )]
+    pub pool_position: AccountLoader<'info, PoolPosition>,
+
+    #[account(
+        mut,
+        seeds = [
+            Investor::INVESTOR_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            investor.key().as_ref(),
+        ],
+        bump
+    )]
+    pub investor_account: AccountLoader<'info, Investor>,
+
+    /// Receives the token_0 of the removed liquidity, less the penalty
+    #[account(
+        init_if_needed,
+        payer = investor,
+        associated_token::mint = pool_vault_token_a_mint,
+        associated_token::authority = investor,
+        associated_token::token_program = token_program_0
+    )]
+    pub investor_token_0_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the token_1 of the removed liquidity, less the penalty
+    #[account(
+        init_if_needed,
+        payer = investor,
+        associated_token::mint = pool_vault_token_b_mint,
+        associated_token::authority = investor,
+        associated_token::token_program = token_program_1
+    )]
+    pub investor_token_1_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PoolPosition::POOL_POSITION_FEES_VAULT_0_SEED.as_bytes(),
+            pool_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub fees_vault_0_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PoolPosition::POOL_POSITION_FEES_VAULT_1_SEED.as_bytes(),
+            pool_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub fees_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: a CLMM program of the protocol config allowlist
+    #[account(
+        executable,
+        constraint = protocol_config.is_clmm_program(clmm_program.key)
+            @ ErrorCode::ClmmProgramNotAllowed
+    )]
+    pub clmm_program: UncheckedAccount<'info>,
+
+    /// The token account for nft
+    #[account(token::token_program = token_program)]
+    pub position_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: a pool of `clmm_program`, read by `RaydiumPositionState`
+    #[account(mut, address = pool_position_config.pool_key)]
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// CHECK: a protocol position of `pool_state`, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
+
+    /// Decrease liquidity for this position
+    /// CHECK: the personal position of `pool_position`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub personal_position: UncheckedAccount<'info>,
+
+    /// Stores init state for the lower tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// Stores init state for the upper tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    /// The address that holds pool tokens for token_0, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The address that holds pool tokens for token_1, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The mint of token vault 0
+    #[account(address = token_vault_0.mint, mint::token_program = token_program_0)]
+    pub pool_vault_token_a_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of token vault 1
+    #[account(address = token_vault_1.mint, mint::token_program = token_program_1)]
+    pub pool_vault_token_b_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// Program to create mint account and mint tokens
+    pub token_program: Program<'info, Token>,
+
+    /// Token program 2022
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// Token program of the mint of token vault 0, legacy or Token-2022
+    pub token_program_0: Interface<'info, TokenInterface>,
+
+    /// Token program of the mint of token vault 1
+    pub token_program_1: Interface<'info, TokenInterface>,
+
+    /// memo program
+    /// CHECK:
+    #[account(address = spl_memo::id())]
+    pub memo_program: UncheckedAccount<'info>,
+
+    /// Required for init_if_needed constraint
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DecreaseLiquidityCtx<'info> {
+    /// Removes `liquidity` of the investor and pays out its tokens. During the lockup the
+    /// withdrawal fails, or the early exit penalty is withheld and credited to the other
+    /// investors through the fee indexes.
+    pub fn decrease_liquidity<'a, 'b, 'c: 'info>(
+        &mut self,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        remaining_accounts_layout: RemainingAccountsLayout,
+        remaining_accounts: &'c [AccountInfo<'info>]
+    ) -> Result<()> {
+        let (investor_liquidity, deposited_at) = {
+            let investor_account = self.investor_account.load()?;
+            (investor_account.liquidity, investor_account.deposited_at)
+        };
+        let (position_nft_mint_key, ladder_ranges, pool_position_bump_seed) = {
+            let pool_position = self.pool_position.load()?;
+            (pool_position.position_nft_mint_key, pool_position.ladder_ranges, pool_position.bump)
+        };
+        require!(liquidity <= investor_liquidity, ErrorCode::InsufficientLiquidity);
+        let penalty_bps = exit_penalty_bps(
+            deposited_at,
+            self.pool_position_config.lockup_seconds,
+            self.pool_position_config.early_exit_penalty_bps,
+            Clock::get()?.unix_timestamp
+        )?;
+        let position = self.raydium_position()?;
+        require_keys_eq!(
+            position.personal_position.nft_mint,
+            position_nft_mint_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        let (remaining_accounts, ranges) = remaining_accounts_layout.parse_liquidity_ladder(
+            remaining_accounts,
+            self.clmm_program.key,
+            self.pool_state.key(),
+            self.pool_position.key(),
+            ladder_ranges
+        )?;
+
+        let (core_fees_owed0, core_fees_owed1) = get_owed_fees(
+            position.tick_array_lower.clone(),
+            position.tick_array_upper.clone(),
+            &position.personal_position,
+            &position.pool_state
+        );
+        let (ladder_fees_owed0, ladder_fees_owed1) = ladder_owed_fees(
+            &ranges,
+            &position.pool_state
+        );
+        let fees_owed0 = core_fees_owed0.checked_add(ladder_fees_owed0).unwrap();
+        let fees_owed1 = core_fees_owed1.checked_add(ladder_fees_owed1).unwrap();
+        let core_liquidity = position.personal_position.liquidity;
+        let position_liquidity = core_liquidity.checked_add(ladder_liquidity(&ranges)).unwrap();
+        let pool_position_config_key = self.pool_position_config.key();
+        let signer_seeds: &[&[&[u8]]] = &[
+            &[
+                PoolPosition::POOL_POSITION_SEED.as_bytes(),
+                pool_position_config_key.as_ref(),
+                &[pool_position_bump_seed],
+            ],
+        ];
+
+        // Fees are harvested first so that only the liquidity is paid to the investor
+        let fees_0_before = self.fees_vault_0_token_account.amount;
+        let fees_1_before = self.fees_vault_1_token_account.amount;
+        if fees_owed0 > 0 || fees_owed1 > 0 {
+            let args = self.decrease_liquidity_args(
+                self.fees_vault_0_token_account.to_account_info(),
+                self.fees_vault_1_token_account.to_account_info(),
+                remaining_accounts.clone()
+            );
+            harvest_ladder_fees(&ranges, &position.pool_state, &args, signer_seeds)?;
+            if core_fees_owed0 > 0 || core_fees_owed1 > 0 {
+                RaydiumAdapter::collect_fees(args, signer_seeds)?;
+            }
+        }
+        // The investors share the fees the vaults received, net of the transfer fees
+        let fees_received0 = received_amount(&mut self.fees_vault_0_token_account, fees_0_before)?;
+        let fees_received1 = received_amount(&mut self.fees_vault_1_token_account, fees_1_before)?;
+        let (fees_index0, fees_index1) = accrue_investor_fees(
+            &*self.pool_position.load()?,
+            &mut *self.investor_account.load_mut()?,
+            position_liquidity,
+            fees_received0,
+            fees_received1
+        );
+
+        let balance_0_before = self.investor_token_0_account.amount;
+        let balance_1_before = self.investor_token_1_account.amount;
+        let args = self.decrease_liquidity_args(
+            self.investor_token_0_account.to_account_info(),
+            self.investor_token_1_account.to_account_info(),
+            remaining_accounts
+        );
+        if ranges.is_empty() {
+            RaydiumAdapter::decrease_liquidity(
+                args,
+                liquidity,
+                amount_0_min,
+                amount_1_min,
+                signer_seeds
+            )?;
+        } else {
+            // Every range gives up the share of the investor liquidity in the whole ladder, the
+            // minimum amounts are checked on the sum
+            for range in ranges.iter() {
+                let range_liquidity = range_share(
+                    range.personal_position.liquidity,
+                    liquidity,
+                    position_liquidity
+                );
+                if range_liquidity > 0 {
+                    RaydiumAdapter::decrease_liquidity(
+                        range.collect_fees_args(&args),
+                        range_liquidity,
+                        0,
+                        0,
+                        signer_seeds
+                    )?;
+                }
+            }
+            let core_share = range_share(core_liquidity, liquidity, position_liquidity);
+            if core_share > 0 {
+                RaydiumAdapter::decrease_liquidity(args, core_share, 0, 0, signer_seeds)?;
+            }
+        }
+        self.investor_token_0_account.reload()?;
+        self.investor_token_1_account.reload()?;
+        let amount_0 = self.investor_token_0_account.amount - balance_0_before;
+        let amount_1 = self.investor_token_1_account.amount - balance_1_before;
+
+        let mut liquidity_after = updated_liquidity_personal_position(
+            self.personal_position.to_account_info()
+        )?;
+        for range in ranges.iter() {
+            liquidity_after = liquidity_after.checked_add(range.updated_liquidity()?).unwrap();
+        }
+        let investor_liquidity = investor_liquidity - liquidity;
+
+        // The penalty goes to the liquidity of the other investors only, none is due without
+        let other_liquidity = liquidity_after.saturating_sub(investor_liquidity);
+        let (penalty_0, penalty_1) = if other_liquidity > 0 {
+            (early_exit_penalty(amount_0, penalty_bps), early_exit_penalty(amount_1, penalty_bps))
+        } else {
+            (0, 0)
+        };
+        // The minimum amounts bound what the investor keeps, net of the penalty
+        require!(
+            amount_0 - penalty_0 >= amount_0_min && amount_1 - penalty_1 >= amount_1_min,
+            ErrorCode::AmountSlippageExceeded
+        );
+        let (penalty_received0, penalty_received1) = self.pay_penalty(penalty_0, penalty_1)?;
+        let (penalty_index0, penalty_index1) = fees_indexes(
+            other_liquidity,
+            penalty_received0,
+            penalty_received1
+        );
+        let fees_index0 = fees_index0.checked_add(penalty_index0).unwrap();
+        let fees_index1 = fees_index1.checked_add(penalty_index1).unwrap();
+        msg!(
+            "decrease liquidity: {}, amounts: ({}, {}), penalty: ({}, {})",
+            liquidity,
+            amount_0,
+            amount_1,
+            penalty_0,
+            penalty_1
+        );
+
+        // The investor is settled before the penalty, it earns no share of it
+        let mut investor_account = self.investor_account.load_mut()?;
+        investor_account.liquidity = investor_liquidity;
+        investor_account.fees_index0 = fees_index0;
+        investor_account.fees_index1 = fees_index1;
+
+        let mut pool_position = self.pool_position.load_mut()?;
+        pool_position.liquidity = liquidity_after;
+        pool_position.fees_index0 = fees_index0;
+        pool_position.fees_index1 = fees_index1;
+
+        Ok(())
+    }
+
+    /// The Raydium position of the pool position, checked against the accounts of its CPIs
+    fn raydium_position(&self) -> Result<RaydiumPositionState<'info>> {
+        let position = RaydiumPositionState::try_from(
+            self.clmm_program.key,
+            &self.pool_state,
+            &self.personal_position,
+            &self.tick_array_lower,
+            &self.tick_array_upper
+        )?;
+        position.check_cpi_accounts(
+            self.clmm_program.key,
+            &self.protocol_position,
+            &self.position_nft_account,
+            self.token_vault_0.key(),
+            self.token_vault_1.key()
+        )?;
+        Ok(position)
+    }
+
+    fn decrease_liquidity_args(
+        &self,
+        recipient_token_account_0: AccountInfo<'info>,
+        recipient_token_account_1: AccountInfo<'info>,
+        remaining_accounts: Vec<AccountInfo<'info>>
+    ) -> CollectFeesArgs<'info> {
+        CollectFeesArgs {
+            clmm_program: self.clmm_program.to_account_info(),
+            nft_owner: self.pool_position.to_account_info(),
+            nft_account: self.position_nft_account.to_account_info(),
+            pool_state: self.pool_state.to_account_info(),
+            protocol_position: self.protocol_position.to_account_info(),
+            personal_position: self.personal_position.to_account_info(),
+            tick_array_lower: self.tick_array_lower.to_account_info(),
+            tick_array_upper: self.tick_array_upper.to_account_info(),
+            recipient_token_account_0,
+            recipient_token_account_1,
+            token_vault_0: self.token_vault_0.to_account_info(),
+            token_vault_1: self.token_vault_1.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            token_program_2022: self.token_program_2022.to_account_info(),
+            vault_0_mint: self.pool_vault_token_a_mint.to_account_info(),
+            vault_1_mint: self.pool_vault_token_b_mint.to_account_info(),
+            memo_program: self.memo_program.to_account_info(),
+            remaining_accounts,
+        }
+    }
+
+    /// Moves the penalty from the investor token accounts to the fees vaults and returns the
+    /// amounts the vaults received, net of the transfer fees
+    fn pay_penalty(&mut self, penalty_0: u64, penalty_1: u64) -> Result<(u64, u64)> {
+        let investor = self.investor.to_account_info();
+        let fees_0_before = self.fees_vault_0_token_account.amount;
+        let fees_1_before = self.fees_vault_1_token_account.amount;
+        if penalty_0 > 0 {
+            transfer_token(
+                &self.investor_token_0_account,
+                &self.fees_vault_0_token_account,
+                &penalty_0,
+                &self.pool_vault_token_a_mint,
+                &investor,
+                &self.token_program_0,
+                None
+            )?;
+        }
+        if penalty_1 > 0 {
+            transfer_token(
+                &self.investor_token_1_account,
+                &self.fees_vault_1_token_account,
+                &penalty_1,
+                &self.pool_vault_token_b_mint,
+                &investor,
+                &self.token_program_1,
+                None
+            )?;
+        }
+        Ok((
+            received_amount(&mut self.fees_vault_0_token_account, fees_0_before)?,
+            received_amount(&mut self.fees_vault_1_token_account, fees_1_before)?,
+        ))
+    }
+}
@@ -3,17 +3,17 @@ use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{ Token };
 use anchor_spl::token_interface::{ Mint, TokenAccount };
 
-use crate::libraries::{ transfer_sol };
-use crate::state::{ Investor };
+use crate::errors::ErrorCode;
+use crate::libraries::{ check_min_deposit, transfer_sol };
+use crate::state::{ Investor, PoolPositionConfig };
 
 #[derive(Accounts)]
 pub struct DepositCtx<'info> {
     #[account(mut)]
     pub investor: Signer<'info>,
 
-    /// CHECK:
-    #[account( mut )]
-    pub pool_position_config: UncheckedAccount<'info>,
+    #[account(constraint = !pool_position_config.paused @ ErrorCode::PositionPaused)]
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
 
     #[account(
         mut, 
@@ -24,7 +24,7 @@ pub struct DepositCtx<'info> {
         ],
         bump, 
     )]
-    pub investor_account: Box<Account<'info, Investor>>,
+    pub investor_account: AccountLoader<'info, Investor>,
 
     #[account(
         init_if_needed,
@@ -54,6 +54,7 @@ pub struct DepositCtx<'info> {
 
 impl<'info> DepositCtx<'info> {
     pub fn deposit<'a, 'b, 'c: 'info>(&mut self, amount: u64) -> Result<()> {
+        check_min_deposit(amount, self.pool_position_config.min_deposit)?;
         transfer_sol(
             &self.investor.to_account_info(),
             &self.investor_deposit_stable_token_account.to_account_info(),
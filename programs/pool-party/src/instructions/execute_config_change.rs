@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::events::{ ConfigChangeExecuted, ManagerProposalWithdrawn, ManagerProposed };
+use crate::state::{ ConfigChange, PendingConfigChange, PoolPositionConfig };
+
+#[derive(Accounts)]
+pub struct ExecuteConfigChangeCtx<'info> {
+    /// Anyone can execute a change once its timelock has elapsed
+    pub executor: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        mut,
+        close = proposer,
+        has_one = proposer,
+        seeds = [
+            PendingConfigChange::PENDING_CONFIG_CHANGE_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            pending_config_change.change_id.to_le_bytes().as_ref(),
+        ],
+        bump = pending_config_change.bump
+    )]
+    pub pending_config_change: Box<Account<'info, PendingConfigChange>>,
+
+    /// CHECK: refunded the rent of the pending change
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+impl<'info> ExecuteConfigChangeCtx<'info> {
+    pub fn execute_config_change(&mut self) -> Result<()> {
+        let pending_config_change = &self.pending_config_change;
+        require!(
+            Clock::get()?.unix_timestamp >= pending_config_change.executable_at,
+            ErrorCode::ConfigChangeTimelocked
+        );
+        let change = pending_config_change.change;
+        let pool_position_config = &mut self.pool_position_config;
+        change.apply(pool_position_config);
+        msg!("config change {}: {:?}", pending_config_change.change_id, change);
+
+        if let ConfigChange::PendingManager { .. } = change {
+            match pool_position_config.pending_manager_key {
+                Some(pending_manager) => {
+                    emit!(ManagerProposed {
+                        pool_position_config: pool_position_config.key(),
+                        manager: pool_position_config.manager_key,
+                        pending_manager,
+                    });
+                }
+                // Proposing the current manager withdraws the proposal
+                None => {
+                    emit!(ManagerProposalWithdrawn {
+                        pool_position_config: pool_position_config.key(),
+                        manager: pool_position_config.manager_key,
+                    });
+                }
+            }
+        }
+        emit!(ConfigChangeExecuted {
+            pool_position_config: pool_position_config.key(),
+            change_id: pending_config_change.change_id,
+            change,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use raydium_clmm_cpi::states::PoolState;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::dex::RaydiumPositionState;
+use crate::errors::ErrorCode;
+use crate::instructions::{ calculate_fees, fees_indexes, get_owed_fees };
+use crate::libraries::{
+    ladder_liquidity,
+    ladder_owed_fees,
+    liquidity_math,
+    parse_ladder_ranges,
+    range_share,
+    tick_math,
+    value_in_stable,
+    ClmmLoader,
+};
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig, ProtocolConfig };
+
+/// Value of an investor position, returned by `get_investor_value`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvestorValue {
+    /// Liquidity of the investor
+    pub liquidity: u128,
+    /// Liquidity of the whole pool position
+    pub position_liquidity: u128,
+    /// Share of the position liquidity held by the investor, in basis points
+    pub share_bps: u16,
+    /// Token amounts backing the investor liquidity at the current pool price
+    pub amount_0: u64,
+    pub amount_1: u64,
+    /// Fees the investor would be paid by `collect_fees` now, uncollected Raydium fees included
+    pub fees_0: u64,
+    pub fees_1: u64,
+    /// Amounts and fees valued in the stable mint at the spot prices of the price pools
+    pub value_in_stable: u64,
+}
+
+/// Read-only: no account is written, so it can be run with `simulateTransaction` and the value
+/// read from the return data.
+#[derive(Accounts)]
+pub struct GetInvestorValueCtx<'info> {
+    /// CHECK: only used to derive the investor account
+    pub investor: UncheckedAccount<'info>,
+
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
+        bump
+    )]
+    pub pool_position: AccountLoader<'info, PoolPosition>,
+
+    #[account(
+        seeds = [
+            Investor::INVESTOR_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            investor.key().as_ref(),
+        ],
+        bump
+    )]
+    pub investor_account: AccountLoader<'info, Investor>,
+
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: a pool of a CLMM program of the protocol config allowlist, read by
+    /// `RaydiumPositionState`
+    #[account(
+        address = pool_position_config.pool_key,
+        constraint = protocol_config.is_clmm_program(pool_state.owner)
+            @ ErrorCode::ClmmProgramNotAllowed
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// The Raydium position of the pool position
+    /// CHECK: the personal position of `pool_position`, read by `RaydiumPositionState`
+    pub personal_position: UncheckedAccount<'info>,
+
+    /// Stores init state for the lower tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// Stores init state for the upper tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    /// The mint the position is valued in
+    pub pool_vault_deposit_stable_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The pool pricing token_0 in the stable mint. Its price is ignored when token_0 is the
+    /// stable mint, the position pool can be passed then.
+    /// CHECK: a pool of the CLMM program of `pool_state`, read by the handler
+    pub pool_state_0: UncheckedAccount<'info>,
+
+    /// The pool pricing token_1 in the stable mint, ignored when token_1 is the stable mint
+    /// CHECK: a pool of the CLMM program of `pool_state`, read by the handler
+    pub pool_state_1: UncheckedAccount<'info>,
+}
+
+impl<'info> GetInvestorValueCtx<'info> {
+    /// `remaining_accounts` holds the ladder ranges of the position
+    pub fn get_investor_value<'c: 'info>(
+        &self,
+        remaining_accounts: &'c [AccountInfo<'info>]
+    ) -> Result<InvestorValue> {
+        let investor = self.investor_account.load()?;
+        let pool_position = self.pool_position.load()?;
+        // The price pools are read from the CLMM program of the position pool
+        let clmm_program = self.pool_state.owner;
+        let position = RaydiumPositionState::try_from(
+            clmm_program,
+            &self.pool_state,
+            &self.personal_position,
+            &self.tick_array_lower,
+            &self.tick_array_upper
+        )?;
+        require_keys_eq!(
+            position.personal_position.nft_mint,
+            pool_position.position_nft_mint_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        let ranges = parse_ladder_ranges(
+            remaining_accounts,
+            clmm_program,
+            self.pool_position.key(),
+            pool_position.ladder_ranges,
+            self.pool_state.key()
+        )?;
+
+        // Fees still owed by Raydium are credited as `collect_fees` would after harvesting them
+        let (core_fees_owed0, core_fees_owed1) = get_owed_fees(
+            position.tick_array_lower.clone(),
+            position.tick_array_upper.clone(),
+            &position.personal_position,
+            &position.pool_state
+        );
+        let (ladder_fees_owed0, ladder_fees_owed1) = ladder_owed_fees(
+            &ranges,
+            &position.pool_state
+        );
+        let core_liquidity = position.personal_position.liquidity;
+        let position_liquidity = core_liquidity.checked_add(ladder_liquidity(&ranges)).unwrap();
+        let (fees_index0, fees_index1) = fees_indexes(
+            position_liquidity,
+            core_fees_owed0.checked_add(ladder_fees_owed0).ok_or(ErrorCode::MaxTokenOverflow)?,
+            core_fees_owed1.checked_add(ladder_fees_owed1).ok_or(ErrorCode::MaxTokenOverflow)?
+        );
+        let fees_0 = investor.fees_earned0
+            .checked_add(
+                calculate_fees(
+                    investor.liquidity,
+                    pool_position.fees_index0 + fees_index0,
+                    investor.fees_index0
+                )
+            )
+            .ok_or(ErrorCode::MaxTokenOverflow)?;
+        let fees_1 = investor.fees_earned1
+            .checked_add(
+                calculate_fees(
+                    investor.liquidity,
+                    pool_position.fees_index1 + fees_index1,
+                    investor.fees_index1
+                )
+            )
+            .ok_or(ErrorCode::MaxTokenOverflow)?;
+
+        // The investor holds the same share of every range of the ladder
+        let pool_state = position.pool_state.load()?;
+        let (mut amount_0, mut amount_1) = liquidity_math::get_amounts_for_liquidity(
+            pool_state.sqrt_price_x64,
+            tick_math::get_sqrt_price_at_tick(self.pool_position_config.tick_lower_index)?,
+            tick_math::get_sqrt_price_at_tick(self.pool_position_config.tick_upper_index)?,
+            range_share(core_liquidity, investor.liquidity, position_liquidity),
+            false
+        )?;
+        for range in ranges.iter() {
+            let range_liquidity = range_share(
+                range.personal_position.liquidity,
+                investor.liquidity,
+                position_liquidity
+            );
+            let (range_amount_0, range_amount_1) = range.amounts_for_liquidity(
+                pool_state.sqrt_price_x64,
+                range_liquidity
+            )?;
+            amount_0 = amount_0.checked_add(range_amount_0).ok_or(ErrorCode::MaxTokenOverflow)?;
+            amount_1 = amount_1.checked_add(range_amount_1).ok_or(ErrorCode::MaxTokenOverflow)?;
+        }
+
+        let stable_mint = self.pool_vault_deposit_stable_mint.key();
+        let pool_state_0 = ClmmLoader::<PoolState>::try_from(&self.pool_state_0, clmm_program)?;
+        let pool_state_0 = pool_state_0.load()?;
+        let value_0 = value_in_stable(
+            amount_0.checked_add(fees_0).ok_or(ErrorCode::MaxTokenOverflow)?,
+            pool_state.token_mint_0,
+            stable_mint,
+            &pool_state_0,
+            pool_state_0.sqrt_price_x64
+        )?;
+        let pool_state_1 = ClmmLoader::<PoolState>::try_from(&self.pool_state_1, clmm_program)?;
+        let pool_state_1 = pool_state_1.load()?;
+        let value_1 = value_in_stable(
+            amount_1.checked_add(fees_1).ok_or(ErrorCode::MaxTokenOverflow)?,
+            pool_state.token_mint_1,
+            stable_mint,
+            &pool_state_1,
+            pool_state_1.sqrt_price_x64
+        )?;
+
+        let share_bps = if pool_position.liquidity > 0 {
+            (investor.liquidity * u128::from(BPS_DENOMINATOR)) / pool_position.liquidity
+        } else {
+            0
+        };
+
+        let value = InvestorValue {
+            liquidity: investor.liquidity,
+            position_liquidity: pool_position.liquidity,
+            share_bps: share_bps as u16,
+            amount_0,
+            amount_1,
+            fees_0,
+            fees_1,
+            value_in_stable: value_0.checked_add(value_1).ok_or(ErrorCode::MaxTokenOverflow)?,
+        };
+        msg!("investor value: {:?}", value);
+        Ok(value)
+    }
+}
@@ -2,24 +2,61 @@ use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::memo::spl_memo;
 use anchor_spl::token::Token;
-use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount };
-use raydium_clmm_cpi::{
-    cpi,
-    program::RaydiumClmm,
-    states::{ PersonalPositionState, PoolState, ProtocolPositionState, TickArrayState },
-    ID as RAYDIUM_CLMM_ID,
-};
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount, TokenInterface };
+use raydium_clmm_cpi::states::PoolState;
 
+use crate::constants::BPS_DENOMINATOR;
+use crate::dex::{ DexAdapter, RaydiumAdapter, RaydiumPositionState };
+use crate::errors::ErrorCode;
 use crate::instructions::{
     calculate_fees,
-    collect_fees,
     fees_indexes,
     get_owed_fees,
     updated_liquidity_personal_position,
     CollectFeesArgs,
 };
-use crate::libraries::{ transfer_token, U128 };
-use crate::state::{ Investor, PoolPosition, PoolPositionConfig };
+use crate::libraries::{
+    amount_after_transfer_fee,
+    check_liquidity_caps,
+    check_min_liquidity_out,
+    check_price_deviation,
+    harvest_ladder_fees,
+    ladder_core_liquidity,
+    ladder_liquidity,
+    ladder_owed_fees,
+    ladder_range_liquidity,
+    received_amount,
+    tick_math,
+    transfer_token,
+    weighted_deposited_at,
+    ClmmLoader,
+    LadderLeg,
+    LadderRangeAccounts,
+    RemainingAccountsLayout,
+    U128,
+};
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig, ProtocolConfig };
+
+#[derive(Clone)]
+pub struct IncreaseLiquidityArgs<'info> {
+    pub clmm_program: AccountInfo<'info>,
+    pub nft_owner: AccountInfo<'info>,
+    pub nft_account: AccountInfo<'info>,
+    pub pool_state: AccountInfo<'info>,
+    pub protocol_position: AccountInfo<'info>,
+    pub personal_position: AccountInfo<'info>,
+    pub tick_array_lower: AccountInfo<'info>,
+    pub tick_array_upper: AccountInfo<'info>,
+    pub token_account_0: AccountInfo<'info>,
+    pub token_account_1: AccountInfo<'info>,
+    pub token_vault_0: AccountInfo<'info>,
+    pub token_vault_1: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub token_program_2022: AccountInfo<'info>,
+    pub vault_0_mint: AccountInfo<'info>,
+    pub vault_1_mint: AccountInfo<'info>,
+    pub remaining_accounts: Vec<AccountInfo<'info>>,
+}
 
 #[derive(Accounts)]
 pub struct IncreaseLiquidityCtx<'info> {
@@ -27,7 +64,7 @@ pub struct IncreaseLiquidityCtx<'info> {
     pub investor: Signer<'info>,
 
     /// CHECK:
-    #[account()]
+    #[account(constraint = !pool_position_config.paused @ ErrorCode::PositionPaused)]
     pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
 
     /// CHECK:
@@ -36,7 +73,7 @@ pub struct IncreaseLiquidityCtx<'info> {
         seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
         bump,
     )]
-    pub pool_position: Box<Account<'info, PoolPosition>>,
+    pub pool_position: AccountLoader<'info, PoolPosition>,
 
     /// CHECK:
     #[account(
@@ -48,7 +85,7 @@ pub struct IncreaseLiquidityCtx<'info> {
         ],
         bump,
     )]
-    pub investor_account: Box<Account<'info, Investor>>,
+    pub investor_account: AccountLoader<'info, Investor>,
 
     #[account(
             mut,
@@ -105,57 +142,61 @@ pub struct IncreaseLiquidityCtx<'info> {
     )]
     pub fees_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(address = RAYDIUM_CLMM_ID)]
-    pub clmm_program: Program<'info, RaydiumClmm>,
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
 
-    /// The token account for nft
+    /// CHECK: a CLMM program of the protocol config allowlist
     #[account(
-        constraint = position_nft_account.mint == personal_position.nft_mint,
-        token::token_program = token_program
+        executable,
+        constraint = protocol_config.is_clmm_program(clmm_program.key)
+            @ ErrorCode::ClmmProgramNotAllowed
     )]
+    pub clmm_program: UncheckedAccount<'info>,
+
+    /// The token account for nft
+    #[account(token::token_program = token_program)]
     pub position_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    #[account(mut)]
-    pub pool_state: AccountLoader<'info, PoolState>,
+    /// CHECK: a pool of `clmm_program`, read by `RaydiumPositionState`
+    #[account(mut, address = pool_position_config.pool_key)]
+    pub pool_state: UncheckedAccount<'info>,
 
-    #[account(
-        mut,
-        constraint = protocol_position.pool_id == pool_state.key(),
-    )]
-    pub protocol_position: Box<Account<'info, ProtocolPositionState>>,
+    /// CHECK: a protocol position of `pool_state`, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
 
     /// Increase liquidity for this position
-    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
-    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+    /// CHECK: a personal position of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub personal_position: UncheckedAccount<'info>,
 
     /// Stores init state for the lower tick
-    #[account(mut, constraint = tick_array_lower.load()?.pool_id == pool_state.key())]
-    pub tick_array_lower: AccountLoader<'info, TickArrayState>,
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
 
     /// Stores init state for the upper tick
-    #[account(mut, constraint = tick_array_upper.load()?.pool_id == pool_state.key())]
-    pub tick_array_upper: AccountLoader<'info, TickArrayState>,
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
 
-    /// The address that holds pool tokens for token_0
-    #[account(
-        mut,
-        constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
-    )]
+    /// The address that holds pool tokens for token_0, checked by `RaydiumPositionState`
+    #[account(mut)]
     pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// The address that holds pool tokens for token_1
-    #[account(
-        mut,
-        constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
-    )]
+    /// The address that holds pool tokens for token_1, checked by `RaydiumPositionState`
+    #[account(mut)]
     pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// The mint of token vault 0
-    #[account(address = token_vault_0.mint)]
+    #[account(address = token_vault_0.mint, mint::token_program = token_program_0)]
     pub pool_vault_token_a_mint: Box<InterfaceAccount<'info, Mint>>,
 
     /// The mint of token vault 1
-    #[account(address = token_vault_1.mint)]
+    #[account(address = token_vault_1.mint, mint::token_program = token_program_1)]
     pub pool_vault_token_b_mint: Box<InterfaceAccount<'info, Mint>>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
@@ -166,6 +207,12 @@ pub struct IncreaseLiquidityCtx<'info> {
     /// Token program 2022
     pub token_program_2022: Program<'info, Token2022>,
 
+    /// Token program of the mint of token vault 0, legacy or Token-2022
+    pub token_program_0: Interface<'info, TokenInterface>,
+
+    /// Token program of the mint of token vault 1
+    pub token_program_1: Interface<'info, TokenInterface>,
+
     /// memo program
     /// CHECK:
     #[account(address = spl_memo::id())]
@@ -185,66 +232,77 @@ pub struct IncreaseLiquidityCtx<'info> {
 impl<'info> IncreaseLiquidityCtx<'info> {
     pub fn increase_liquidity<'a, 'b, 'c: 'info>(
         &mut self,
+        min_liquidity_out: u128,
+        expected_sqrt_price_x64: u128,
+        max_price_deviation: u16,
+        remaining_accounts_layout: RemainingAccountsLayout,
         remaining_accounts: &'c [AccountInfo<'info>],
         bumps: &IncreaseLiquidityCtxBumps
     ) -> Result<()> {
-        let amount_0_max = self.investor_deposit_token_a_account.amount;
-        let amount_1_max = self.investor_deposit_token_b_account.amount;
+        let (position_nft_mint_key, ladder_ranges, vaults_initialized, pool_position_bump_seed) = {
+            let pool_position = self.pool_position.load()?;
+            (
+                pool_position.position_nft_mint_key,
+                pool_position.ladder_ranges,
+                pool_position.vaults_initialized,
+                pool_position.bump,
+            )
+        };
+        let position = self.raydium_position()?;
+        // The ladder ranges are owned by the pool position too, only the core one is accepted
+        require_keys_eq!(
+            position.personal_position.nft_mint,
+            position_nft_mint_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        let (remaining_accounts, ranges) = remaining_accounts_layout.parse_liquidity_ladder(
+            remaining_accounts,
+            self.clmm_program.key,
+            self.pool_state.key(),
+            self.pool_position.key(),
+            ladder_ranges
+        )?;
+        check_price_deviation(
+            position.pool_state.load()?.sqrt_price_x64,
+            expected_sqrt_price_x64,
+            max_price_deviation
+        )?;
 
-        msg!("vaults_initialized: {}", self.pool_position.vaults_initialized);
+        let amount_0 = self.investor_deposit_token_a_account.amount;
+        let amount_1 = self.investor_deposit_token_b_account.amount;
 
-        self.transfer_tokens(amount_0_max, amount_1_max, bumps)?;
+        msg!("vaults_initialized: {}", vaults_initialized);
 
-        let (fees_owed0, fees_owed1) = get_owed_fees(
-            self.tick_array_lower.clone(),
-            self.tick_array_upper.clone(),
-            &self.personal_position,
-            &self.pool_state
+        // With a transfer fee the vaults receive less than the deposits, only that is added
+        let vault_0_before = self.pool_position_vault_0_token_account.amount;
+        let vault_1_before = self.pool_position_vault_1_token_account.amount;
+        self.transfer_tokens(amount_0, amount_1, bumps)?;
+        let amount_0_max = received_amount(
+            &mut self.pool_position_vault_0_token_account,
+            vault_0_before
+        )?;
+        let amount_1_max = received_amount(
+            &mut self.pool_position_vault_1_token_account,
+            vault_1_before
+        )?;
+
+        let (core_fees_owed0, core_fees_owed1) = get_owed_fees(
+            position.tick_array_lower.clone(),
+            position.tick_array_upper.clone(),
+            &position.personal_position,
+            &position.pool_state
+        );
+        let (ladder_fees_owed0, ladder_fees_owed1) = ladder_owed_fees(
+            &ranges,
+            &position.pool_state
         );
+        let fees_owed0 = core_fees_owed0.checked_add(ladder_fees_owed0).unwrap();
+        let fees_owed1 = core_fees_owed1.checked_add(ladder_fees_owed1).unwrap();
 
-        let personal_position = self.personal_position.clone();
-        let pool_position = self.pool_position.clone();
-        let investor_account = &mut self.investor_account;
-        let liquidity = personal_position.liquidity;
-
-        let (feess_index0, feess_index1) = fees_indexes(liquidity, fees_owed0, fees_owed1);
-
-        let last_fees_index0 = pool_position.fees_index0;
-        let last_fees_index1 = pool_position.fees_index1;
-
-        let investor_liquidity = investor_account.liquidity;
-
-        let fees_index0 = U128::from(last_fees_index0)
-            .checked_add(U128::from(feess_index0))
-            .unwrap()
-            .as_u128();
-        let fees_index1 = U128::from(last_fees_index1)
-            .checked_add(U128::from(feess_index1))
-            .unwrap()
-            .as_u128();
-
-        investor_account.fees_earned0 = investor_account.fees_earned0
-            .checked_add(
-                calculate_fees(
-                    investor_liquidity,
-                    pool_position.fees_index0,
-                    investor_account.fees_index0
-                )
-            )
-            .unwrap();
-        investor_account.fees_earned1 = investor_account.fees_earned1
-            .checked_add(
-                calculate_fees(
-                    investor_liquidity,
-                    pool_position.fees_index1,
-                    investor_account.fees_index1
-                )
-            )
+        let liquidity = position.personal_position.liquidity
+            .checked_add(ladder_liquidity(&ranges))
             .unwrap();
-        investor_account.fees_index0 = pool_position.fees_index0;
-        investor_account.fees_index1 = pool_position.fees_index1;
 
-        let pool_position_bump_seed = self.pool_position.bump;
         let pool_position_config_key = self.pool_position_config.key();
         let signer_seeds: &[&[&[u8]]] = &[
             &[
@@ -254,33 +312,47 @@ impl<'info> IncreaseLiquidityCtx<'info> {
             ],
         ];
 
+        let fees_0_before = self.fees_vault_0_token_account.amount;
+        let fees_1_before = self.fees_vault_1_token_account.amount;
         if fees_owed0 > 0 || fees_owed1 > 0 {
-            collect_fees(
-                CollectFeesArgs {
-                    clmm_program: self.clmm_program.to_account_info(),
-                    nft_owner: self.pool_position.to_account_info(),
-                    nft_account: self.position_nft_account.to_account_info(),
-                    pool_state: self.pool_state.to_account_info(),
-                    protocol_position: self.protocol_position.to_account_info(),
-                    personal_position: self.personal_position.to_account_info(),
-                    tick_array_lower: self.tick_array_lower.to_account_info(),
-                    tick_array_upper: self.tick_array_upper.to_account_info(),
-                    recipient_token_account_0: self.fees_vault_0_token_account.to_account_info(),
-                    recipient_token_account_1: self.fees_vault_1_token_account.to_account_info(),
-                    token_vault_0: self.token_vault_0.to_account_info(),
-                    token_vault_1: self.token_vault_1.to_account_info(),
-                    token_program: self.token_program.to_account_info(),
-                    token_program_2022: self.token_program_2022.to_account_info(),
-                    vault_0_mint: self.pool_vault_token_a_mint.to_account_info(),
-                    vault_1_mint: self.pool_vault_token_b_mint.to_account_info(),
-                    memo_program: self.memo_program.to_account_info(),
-                    remaining_accounts: remaining_accounts.to_vec(),
-                },
-                signer_seeds
-            )?;
+            let args = CollectFeesArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                nft_owner: self.pool_position.to_account_info(),
+                nft_account: self.position_nft_account.to_account_info(),
+                pool_state: self.pool_state.to_account_info(),
+                protocol_position: self.protocol_position.to_account_info(),
+                personal_position: self.personal_position.to_account_info(),
+                tick_array_lower: self.tick_array_lower.to_account_info(),
+                tick_array_upper: self.tick_array_upper.to_account_info(),
+                recipient_token_account_0: self.fees_vault_0_token_account.to_account_info(),
+                recipient_token_account_1: self.fees_vault_1_token_account.to_account_info(),
+                token_vault_0: self.token_vault_0.to_account_info(),
+                token_vault_1: self.token_vault_1.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                vault_0_mint: self.pool_vault_token_a_mint.to_account_info(),
+                vault_1_mint: self.pool_vault_token_b_mint.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                remaining_accounts: remaining_accounts.clone(),
+            };
+            harvest_ladder_fees(&ranges, &position.pool_state, &args, signer_seeds)?;
+            if core_fees_owed0 > 0 || core_fees_owed1 > 0 {
+                RaydiumAdapter::collect_fees(args, signer_seeds)?;
+            }
         }
+        // The investors share the fees the vaults received, net of the transfer fees
+        let fees_received0 = received_amount(&mut self.fees_vault_0_token_account, fees_0_before)?;
+        let fees_received1 = received_amount(&mut self.fees_vault_1_token_account, fees_1_before)?;
+        let (fees_index0, fees_index1) = accrue_investor_fees(
+            &*self.pool_position.load()?,
+            &mut *self.investor_account.load_mut()?,
+            liquidity,
+            fees_received0,
+            fees_received1
+        );
 
-        let cpi_accounts = cpi::accounts::IncreaseLiquidityV2 {
+        let args = IncreaseLiquidityArgs {
+            clmm_program: self.clmm_program.to_account_info(),
             nft_owner: self.pool_position.to_account_info(),
             nft_account: self.position_nft_account.to_account_info(),
             pool_state: self.pool_state.to_account_info(),
@@ -296,24 +368,49 @@ impl<'info> IncreaseLiquidityCtx<'info> {
             token_program_2022: self.token_program_2022.to_account_info(),
             vault_0_mint: self.pool_vault_token_a_mint.to_account_info(),
             vault_1_mint: self.pool_vault_token_b_mint.to_account_info(),
+            remaining_accounts,
         };
-        let cpi_context = CpiContext::new_with_signer(
-            self.clmm_program.to_account_info(),
-            cpi_accounts,
-            signer_seeds
-        ).with_remaining_accounts(remaining_accounts.to_vec());
-        cpi::increase_liquidity_v2(cpi_context, 0, amount_0_max, amount_1_max, Some(true))?;
-
-        let liquidity_after = updated_liquidity_personal_position(
+        if ranges.is_empty() {
+            RaydiumAdapter::increase_liquidity(args, 0, amount_0_max, amount_1_max, signer_seeds)?;
+        } else {
+            self.increase_ladder_liquidity(
+                args,
+                &ranges,
+                &position.pool_state,
+                amount_0_max,
+                amount_1_max,
+                signer_seeds
+            )?;
+        }
+
+        let mut liquidity_after = updated_liquidity_personal_position(
             self.personal_position.to_account_info()
         )?;
+        for range in ranges.iter() {
+            liquidity_after = liquidity_after.checked_add(range.updated_liquidity()?).unwrap();
+        }
 
         let liquidity_delta = liquidity_after.checked_sub(liquidity).unwrap();
+        check_min_liquidity_out(liquidity_delta, min_liquidity_out)?;
+
+        let mut investor_account = self.investor_account.load_mut()?;
+        investor_account.deposited_at = weighted_deposited_at(
+            investor_account.deposited_at,
+            investor_account.liquidity,
+            Clock::get()?.unix_timestamp,
+            liquidity_delta
+        );
         investor_account.liquidity = investor_account.liquidity
             .checked_add(liquidity_delta)
             .unwrap();
+        check_liquidity_caps(
+            investor_account.liquidity,
+            liquidity_after,
+            self.pool_position_config.max_investor_liquidity,
+            self.pool_position_config.max_total_liquidity
+        )?;
 
-        let pool_position = &mut self.pool_position;
+        let mut pool_position = self.pool_position.load_mut()?;
         pool_position.liquidity = liquidity_after;
         pool_position.fees_index0 = fees_index0;
         pool_position.fees_index1 = fees_index1;
@@ -321,6 +418,90 @@ impl<'info> IncreaseLiquidityCtx<'info> {
         Ok(())
     }
 
+    /// The Raydium position of the pool position, checked against the accounts of its CPIs
+    fn raydium_position(&self) -> Result<RaydiumPositionState<'info>> {
+        let position = RaydiumPositionState::try_from(
+            self.clmm_program.key,
+            &self.pool_state,
+            &self.personal_position,
+            &self.tick_array_lower,
+            &self.tick_array_upper
+        )?;
+        position.check_cpi_accounts(
+            self.clmm_program.key,
+            &self.protocol_position,
+            &self.position_nft_account,
+            self.token_vault_0.key(),
+            self.token_vault_1.key()
+        )?;
+        Ok(position)
+    }
+
+    /// Adds the largest core liquidity whose ladder can be paid with the investor amounts, and
+    /// the weighted liquidity of every range along with it
+    fn increase_ladder_liquidity(
+        &mut self,
+        core: IncreaseLiquidityArgs<'info>,
+        ranges: &[LadderRangeAccounts<'info>],
+        pool_state: &ClmmLoader<'info, PoolState>,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        signer_seeds: &[&[&[u8]]]
+    ) -> Result<()> {
+        let mut legs = Vec::with_capacity(ranges.len() + 1);
+        legs.push(LadderLeg {
+            sqrt_price_lower_x64: tick_math::get_sqrt_price_at_tick(
+                self.pool_position_config.tick_lower_index
+            )?,
+            sqrt_price_upper_x64: tick_math::get_sqrt_price_at_tick(
+                self.pool_position_config.tick_upper_index
+            )?,
+            weight_bps: BPS_DENOMINATOR as u32,
+        });
+        for range in ranges {
+            legs.push(range.leg()?);
+        }
+        // Raydium charges the transfer fee of its deposits on top of the amounts it adds
+        let core_liquidity = ladder_core_liquidity(
+            pool_state.load()?.sqrt_price_x64,
+            &legs,
+            amount_after_transfer_fee(&self.pool_vault_token_a_mint, amount_0_max)?,
+            amount_after_transfer_fee(&self.pool_vault_token_b_mint, amount_1_max)?
+        )?;
+        if core_liquidity == 0 {
+            return Ok(());
+        }
+
+        // Each leg may only spend what is left of the investor amounts, the vaults also hold the
+        // idle tokens of the other investors
+        let mut legs_args = vec![(core.clone(), BPS_DENOMINATOR as u32)];
+        for range in ranges {
+            legs_args.push((range.increase_liquidity_args(&core), range.ladder_range.weight_bps));
+        }
+        self.pool_position_vault_0_token_account.reload()?;
+        self.pool_position_vault_1_token_account.reload()?;
+        let vault_0_before = self.pool_position_vault_0_token_account.amount;
+        let vault_1_before = self.pool_position_vault_1_token_account.amount;
+        for (args, weight_bps) in legs_args {
+            let liquidity = ladder_range_liquidity(core_liquidity, weight_bps);
+            if liquidity == 0 {
+                continue;
+            }
+            self.pool_position_vault_0_token_account.reload()?;
+            self.pool_position_vault_1_token_account.reload()?;
+            let spent_0 = vault_0_before - self.pool_position_vault_0_token_account.amount;
+            let spent_1 = vault_1_before - self.pool_position_vault_1_token_account.amount;
+            RaydiumAdapter::increase_liquidity(
+                args,
+                liquidity,
+                amount_0_max - spent_0,
+                amount_1_max - spent_1,
+                signer_seeds
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn transfer_tokens(
         &self,
         amount_0_max: u64,
@@ -336,7 +517,6 @@ impl<'info> IncreaseLiquidityCtx<'info> {
                 &[investor_deposit_token_a_account_bump_seed],
             ],
         ];
-        let token_program = self.token_program.clone();
         let vault_0 = self.pool_position_vault_0_token_account.clone();
         let investor_deposit_token_a_account = self.investor_deposit_token_a_account.clone();
         let token_0_mint_acc = self.pool_vault_token_a_mint.clone();
@@ -346,7 +526,7 @@ impl<'info> IncreaseLiquidityCtx<'info> {
             &amount_0_max,
             &token_0_mint_acc,
             &investor_deposit_token_a_account.to_account_info(),
-            &token_program,
+            &self.token_program_0,
             Some(a_seeds)
         )?;
         let investor_deposit_token_b_account_bump_seed = bumps.investor_deposit_token_b_account;
@@ -366,8 +546,47 @@ impl<'info> IncreaseLiquidityCtx<'info> {
             &amount_1_max,
             &token_1_mint_acc,
             &investor_deposit_token_b_account.to_account_info(),
-            &token_program,
+            &self.token_program_1,
             Some(b_seeds)
         )
     }
 }
+
+/// Returns the pool fee indexes updated with the owed fees that are about to be harvested and
+/// credits the investor with its share of them.
+pub fn accrue_investor_fees(
+    pool_position: &PoolPosition,
+    investor_account: &mut Investor,
+    liquidity: u128,
+    fees_owed0: u64,
+    fees_owed1: u64
+) -> (u128, u128) {
+    let (feess_index0, feess_index1) = fees_indexes(liquidity, fees_owed0, fees_owed1);
+
+    let last_fees_index0 = pool_position.fees_index0;
+    let last_fees_index1 = pool_position.fees_index1;
+
+    let investor_liquidity = investor_account.liquidity;
+
+    let fees_index0 = U128::from(last_fees_index0)
+        .checked_add(U128::from(feess_index0))
+        .unwrap()
+        .as_u128();
+    let fees_index1 = U128::from(last_fees_index1)
+        .checked_add(U128::from(feess_index1))
+        .unwrap()
+        .as_u128();
+
+    // The investor liquidity earned its share of the fees being harvested, but not of the
+    // liquidity about to be added, so it is settled at the updated indexes
+    investor_account.fees_earned0 = investor_account.fees_earned0
+        .checked_add(calculate_fees(investor_liquidity, fees_index0, investor_account.fees_index0))
+        .unwrap();
+    investor_account.fees_earned1 = investor_account.fees_earned1
+        .checked_add(calculate_fees(investor_liquidity, fees_index1, investor_account.fees_index1))
+        .unwrap();
+    investor_account.fees_index0 = fees_index0;
+    investor_account.fees_index1 = fees_index1;
+
+    (fees_index0, fees_index1)
+}
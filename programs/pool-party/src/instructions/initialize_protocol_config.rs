@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::events::ClmmProgramsSet;
+use crate::program::PoolParty;
+use crate::state::ProtocolConfig;
+
+#[derive(Accounts)]
+pub struct InitializeProtocolConfigCtx<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolConfig::LEN,
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, PoolParty>,
+
+    /// Only the upgrade authority of the program creates the protocol config
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key())
+            @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeProtocolConfigCtx<'info> {
+    pub fn initialize_protocol_config(
+        &mut self,
+        clmm_programs: Vec<Pubkey>,
+        bumps: &InitializeProtocolConfigCtxBumps
+    ) -> Result<()> {
+        require!(
+            clmm_programs.len() <= ProtocolConfig::MAX_CLMM_PROGRAMS,
+            ErrorCode::TooManyClmmPrograms
+        );
+        let protocol_config = &mut self.protocol_config;
+        protocol_config.bump = bumps.protocol_config;
+        protocol_config.admin = self.admin.key();
+        protocol_config.clmm_programs = clmm_programs.clone();
+        emit!(ClmmProgramsSet { protocol_config: protocol_config.key(), clmm_programs });
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use anchor_lang::{ __private::bytemuck, prelude::*, Discriminator };
+
+use crate::errors::ErrorCode;
+use crate::events::AccountMigrated;
+use crate::libraries::grow_account;
+use crate::state::{ Investor, InvestorV0 };
+
+#[derive(Accounts)]
+pub struct MigrateInvestorCtx<'info> {
+    /// Funds the rent of the grown account, anyone can migrate
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// A Borsh `Investor` of the unversioned layout, rewritten in the zero-copy one. It is
+    /// shorter than `Investor::LEN`, so it can't be loaded as an `AccountLoader`.
+    /// CHECK: owned by the program, the handler checks its discriminator and length
+    #[account(mut, owner = crate::ID)]
+    pub investor_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateInvestorCtx<'info> {
+    /// Decodes the Borsh fields, grows the account and writes them back in place
+    pub fn migrate_investor(&mut self) -> Result<()> {
+        let info = self.investor_account.to_account_info();
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(data.len() < Investor::LEN, ErrorCode::AccountAlreadyMigrated);
+            require!(
+                data.starts_with(Investor::DISCRIMINATOR),
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            InvestorV0::deserialize(&mut &data[Investor::DISCRIMINATOR.len()..])?
+        };
+
+        grow_account(&info, &self.payer.to_account_info(), &self.system_program, Investor::LEN)?;
+        let mut data = info.try_borrow_mut_data()?;
+        // The realloc only zeroes the appended bytes
+        data[Investor::DISCRIMINATOR.len()..].fill(0);
+        let investor: &mut Investor = bytemuck::from_bytes_mut(
+            &mut data[Investor::DISCRIMINATOR.len()..Investor::LEN]
+        );
+        legacy.migrate(investor);
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version: 0,
+            to_version: Investor::VERSION,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::{ __private::bytemuck, prelude::*, Discriminator };
+
+use crate::errors::ErrorCode;
+use crate::events::AccountMigrated;
+use crate::libraries::grow_account;
+use crate::state::{ PoolPosition, PoolPositionV0 };
+
+#[derive(Accounts)]
+pub struct MigratePoolPositionCtx<'info> {
+    /// Funds the rent of the grown account, anyone can migrate
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// A Borsh `PoolPosition` of the unversioned layout, rewritten in the zero-copy one. It is
+    /// shorter than `PoolPosition::LEN`, so it can't be loaded as an `AccountLoader`.
+    /// CHECK: owned by the program, the handler checks its discriminator and length
+    #[account(mut, owner = crate::ID)]
+    pub pool_position: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigratePoolPositionCtx<'info> {
+    /// Decodes the Borsh fields, grows the account and writes them back in place
+    pub fn migrate_pool_position(&mut self) -> Result<()> {
+        let info = self.pool_position.to_account_info();
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(data.len() < PoolPosition::LEN, ErrorCode::AccountAlreadyMigrated);
+            require!(
+                data.starts_with(PoolPosition::DISCRIMINATOR),
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            PoolPositionV0::deserialize(&mut &data[PoolPosition::DISCRIMINATOR.len()..])?
+        };
+
+        grow_account(
+            &info,
+            &self.payer.to_account_info(),
+            &self.system_program,
+            PoolPosition::LEN
+        )?;
+        let mut data = info.try_borrow_mut_data()?;
+        // The realloc only zeroes the appended bytes
+        data[PoolPosition::DISCRIMINATOR.len()..].fill(0);
+        let pool_position: &mut PoolPosition = bytemuck::from_bytes_mut(
+            &mut data[PoolPosition::DISCRIMINATOR.len()..PoolPosition::LEN]
+        );
+        legacy.migrate(pool_position)?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version: 0,
+            to_version: PoolPosition::VERSION,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::{ prelude::*, Discriminator };
+
+use crate::errors::ErrorCode;
+use crate::events::AccountMigrated;
+use crate::libraries::grow_account;
+use crate::state::{ PoolPositionConfig, PoolPositionConfigV0 };
+
+#[derive(Accounts)]
+pub struct MigratePoolPositionConfigCtx<'info> {
+    /// Funds the rent of the grown account, anyone can migrate
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// A `PoolPositionConfig` of the unversioned layout, rewritten in the current one. It is
+    /// shorter than `PoolPositionConfig::LEN`, so it can't be loaded as an `Account`.
+    /// CHECK: owned by the program, the handler checks its discriminator and length
+    #[account(mut, owner = crate::ID)]
+    pub pool_position_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigratePoolPositionConfigCtx<'info> {
+    /// Decodes the fields of the unversioned layout, grows the account and writes the config
+    /// back in the current layout
+    pub fn migrate_pool_position_config(&mut self) -> Result<()> {
+        let info = self.pool_position_config.to_account_info();
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(data.len() < PoolPositionConfig::LEN, ErrorCode::AccountAlreadyMigrated);
+            require!(
+                data.starts_with(PoolPositionConfig::DISCRIMINATOR),
+                anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+            );
+            PoolPositionConfigV0::deserialize(
+                &mut &data[PoolPositionConfig::DISCRIMINATOR.len()..]
+            )?
+        };
+
+        grow_account(
+            &info,
+            &self.payer.to_account_info(),
+            &self.system_program,
+            PoolPositionConfig::LEN
+        )?;
+        let mut data = info.try_borrow_mut_data()?;
+        // The realloc only zeroes the appended bytes
+        data.fill(0);
+        legacy.migrate().try_serialize(&mut &mut data[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version: 0,
+            to_version: PoolPositionConfig::VERSION,
+        });
+        Ok(())
+    }
+}
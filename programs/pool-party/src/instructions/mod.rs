@@ -6,6 +6,21 @@ pub mod swap_to_ratio_deposit;
 pub mod create_investor_position;
 pub mod create_position_vaults;
 pub mod collect_fees;
+pub mod zap_in;
+pub mod get_investor_value;
+pub mod snapshot_nav;
+pub mod decrease_liquidity;
+pub mod accept_manager;
+pub mod set_paused;
+pub mod queue_config_change;
+pub mod execute_config_change;
+pub mod cancel_config_change;
+pub mod open_ladder_range;
+pub mod initialize_protocol_config;
+pub mod set_clmm_programs;
+pub mod migrate_investor;
+pub mod migrate_pool_position;
+pub mod migrate_pool_position_config;
 
 pub use open_position::*;
 pub use create_position::*;
@@ -15,3 +30,18 @@ pub use create_position_vaults::*;
 pub use increase_liquidity::*;
 pub use deposit::*;
 pub use collect_fees::*;
+pub use zap_in::*;
+pub use get_investor_value::*;
+pub use snapshot_nav::*;
+pub use decrease_liquidity::*;
+pub use accept_manager::*;
+pub use set_paused::*;
+pub use queue_config_change::*;
+pub use execute_config_change::*;
+pub use cancel_config_change::*;
+pub use open_ladder_range::*;
+pub use initialize_protocol_config::*;
+pub use set_clmm_programs::*;
+pub use migrate_investor::*;
+pub use migrate_pool_position::*;
+pub use migrate_pool_position_config::*;
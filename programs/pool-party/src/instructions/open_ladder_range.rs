@@ -0,0 +1,307 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    metadata::Metadata,
+    token::Token,
+    token_interface::{ Mint, Token2022, TokenAccount },
+};
+use raydium_clmm_cpi::states::{ PersonalPositionState, PoolState };
+
+use crate::constants::{ MAX_LADDER_RANGES, MAX_LADDER_WEIGHT_BPS };
+use crate::dex::{ DexAdapter, OpenPositionArgs, PositionTicks, RaydiumAdapter };
+use crate::errors::ErrorCode;
+use crate::events::LadderRangeOpened;
+use crate::instructions::updated_liquidity_personal_position;
+use crate::libraries::{
+    ladder_range_liquidity,
+    ClmmAccount,
+    ClmmLoader,
+    RemainingAccountsLayout,
+};
+use crate::state::{ Investor, LadderRange, PoolPosition, PoolPositionConfig, ProtocolConfig };
+
+#[derive(Accounts)]
+pub struct OpenLadderRangeCtx<'info> {
+    #[account(mut, address = pool_position_config.manager_key @ ErrorCode::Unauthorized)]
+    pub manager: Signer<'info>,
+
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        mut,
+        seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
+        bump,
+        constraint = pool_position.load()?.ladder_ranges < MAX_LADDER_RANGES @ ErrorCode::LadderFull
+    )]
+    pub pool_position: AccountLoader<'info, PoolPosition>,
+
+    /// Holds all the liquidity of the position, the ranges are opened before investors join
+    #[account(
+        mut,
+        seeds = [
+            Investor::INVESTOR_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            manager.key().as_ref(),
+        ],
+        bump,
+        constraint = manager_account.load()?.liquidity == pool_position.load()?.liquidity
+            @ ErrorCode::LadderHasInvestors
+    )]
+    pub manager_account: AccountLoader<'info, Investor>,
+
+    #[account(
+        init,
+        payer = manager,
+        space = LadderRange::LEN,
+        seeds = [
+            LadderRange::LADDER_RANGE_SEED.as_bytes(),
+            pool_position.key().as_ref(),
+            &[pool_position.load()?.ladder_ranges],
+        ],
+        bump
+    )]
+    pub ladder_range: Box<Account<'info, LadderRange>>,
+
+    /// The Raydium position of the core range, the range liquidity is weighted against
+    /// CHECK: the personal position of `pool_position`, read by the handler
+    pub core_personal_position: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: a CLMM program of the protocol config allowlist
+    #[account(
+        executable,
+        constraint = protocol_config.is_clmm_program(clmm_program.key)
+            @ ErrorCode::ClmmProgramNotAllowed
+    )]
+    pub clmm_program: UncheckedAccount<'info>,
+
+    /// CHECK: Unique token mint address, random keypair
+    #[account(mut)]
+    pub position_nft_mint: Signer<'info>,
+
+    /// CHECK: Token account where position NFT will be minted
+    #[account(mut)]
+    pub position_nft_account: UncheckedAccount<'info>,
+
+    /// CHECK: Safety check performed inside function body
+    #[account(mut)]
+    pub metadata_account: UncheckedAccount<'info>,
+
+    /// CHECK: a pool of `clmm_program`, read by the handler
+    #[account(mut, address = pool_position_config.pool_key)]
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// CHECK: Store the information of market marking in range
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
+
+    /// CHECK: Account to mark the lower tick as initialized
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// CHECK: Account to store data for the position's upper tick
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    /// CHECK: personal position state
+    #[account(mut)]
+    pub personal_position: UncheckedAccount<'info>,
+
+    /// The token_0 account of the manager paying for the range
+    #[account(mut, token::mint = token_vault_0.mint)]
+    pub token_account_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The token_1 account of the manager paying for the range
+    #[account(mut, token::mint = token_vault_1.mint)]
+    pub token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The address that holds pool tokens for token_0, checked by the handler
+    #[account(mut)]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The address that holds pool tokens for token_1, checked by the handler
+    #[account(mut)]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The mint of token vault 0
+    #[account(address = token_vault_0.mint)]
+    pub vault_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of token vault 1
+    #[account(address = token_vault_1.mint)]
+    pub vault_1_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Sysvar for token mint and ATA creation
+    pub rent: Sysvar<'info, Rent>,
+
+    pub system_program: Program<'info, System>,
+
+    pub token_program: Program<'info, Token>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// CHECK: Metadata program address constraint applied
+    pub metadata_program: Program<'info, Metadata>,
+
+    pub token_program_2022: Program<'info, Token2022>,
+}
+
+impl<'info> OpenLadderRangeCtx<'info> {
+    /// Opens a Raydium position holding `weight_bps` of the core liquidity, paid by the manager
+    /// and credited to its liquidity
+    pub fn open_ladder_range<'a, 'b, 'c: 'info>(
+        &mut self,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        weight_bps: u32,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        remaining_accounts_layout: RemainingAccountsLayout,
+        remaining_accounts: &'c [AccountInfo<'info>],
+        bumps: &OpenLadderRangeCtxBumps
+    ) -> Result<()> {
+        require!(
+            weight_bps > 0 && weight_bps <= MAX_LADDER_WEIGHT_BPS,
+            ErrorCode::InvalidLadderWeight
+        );
+        let core_liquidity = self.core_liquidity()?;
+        let remaining_accounts = remaining_accounts_layout.parse_liquidity(
+            remaining_accounts,
+            self.clmm_program.key,
+            self.pool_state.key()
+        )?;
+
+        let liquidity = ladder_range_liquidity(core_liquidity, weight_bps);
+        self.dex_open_position(
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+            liquidity,
+            amount_0_max,
+            amount_1_max,
+            remaining_accounts
+        )?;
+        let liquidity = updated_liquidity_personal_position(
+            self.personal_position.to_account_info()
+        )?;
+
+        let mut pool_position = self.pool_position.load_mut()?;
+        let index = pool_position.ladder_ranges;
+        self.ladder_range.set_inner(LadderRange {
+            bump: bumps.ladder_range,
+            pool_position_key: self.pool_position.key(),
+            index,
+            position_nft_mint_key: self.position_nft_mint.key(),
+            tick_lower_index,
+            tick_upper_index,
+            weight_bps,
+        });
+        pool_position.ladder_ranges = index + 1;
+        pool_position.liquidity = pool_position.liquidity.checked_add(liquidity).unwrap();
+
+        let mut manager_account = self.manager_account.load_mut()?;
+        manager_account.liquidity = manager_account.liquidity.checked_add(liquidity).unwrap();
+        manager_account.deposited_at = Clock::get()?.unix_timestamp;
+
+        emit!(LadderRangeOpened {
+            pool_position: self.pool_position.key(),
+            index,
+            position_nft_mint: self.position_nft_mint.key(),
+            tick_lower_index,
+            tick_upper_index,
+            weight_bps,
+            liquidity,
+        });
+        Ok(())
+    }
+
+    /// Liquidity of the core range, checking the pool is one of `clmm_program` along with its
+    /// token vaults and the core Raydium position
+    fn core_liquidity(&self) -> Result<u128> {
+        let clmm_program = self.clmm_program.key;
+        let pool_state = ClmmLoader::<PoolState>::try_from(&self.pool_state, clmm_program)?;
+        {
+            let pool_state = pool_state.load()?;
+            require_keys_eq!(
+                self.token_vault_0.key(),
+                pool_state.token_vault_0,
+                ErrorCode::InvalidClmmAccount
+            );
+            require_keys_eq!(
+                self.token_vault_1.key(),
+                pool_state.token_vault_1,
+                ErrorCode::InvalidClmmAccount
+            );
+        }
+        let core_personal_position = ClmmAccount::<PersonalPositionState>::try_from(
+            &self.core_personal_position,
+            clmm_program
+        )?;
+        require_keys_eq!(
+            core_personal_position.nft_mint,
+            self.pool_position.load()?.position_nft_mint_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        require_keys_eq!(
+            core_personal_position.pool_id,
+            pool_state.key(),
+            ErrorCode::InvalidClmmAccount
+        );
+        Ok(core_personal_position.liquidity)
+    }
+
+    fn dex_open_position(
+        &mut self,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        liquidity: u128,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        remaining_accounts: Vec<AccountInfo<'info>>
+    ) -> Result<()> {
+        let args = OpenPositionArgs {
+            clmm_program: self.clmm_program.to_account_info(),
+            payer: self.manager.to_account_info(),
+            nft_owner: self.pool_position.to_account_info(),
+            position_nft_mint: self.position_nft_mint.to_account_info(),
+            position_nft_account: self.position_nft_account.to_account_info(),
+            metadata_account: self.metadata_account.to_account_info(),
+            pool_state: self.pool_state.to_account_info(),
+            protocol_position: self.protocol_position.to_account_info(),
+            tick_array_lower: self.tick_array_lower.to_account_info(),
+            tick_array_upper: self.tick_array_upper.to_account_info(),
+            personal_position: self.personal_position.to_account_info(),
+            token_account_0: self.token_account_0.to_account_info(),
+            token_account_1: self.token_account_1.to_account_info(),
+            token_vault_0: self.token_vault_0.to_account_info(),
+            token_vault_1: self.token_vault_1.to_account_info(),
+            rent: self.rent.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program.to_account_info(),
+            associated_token_program: self.associated_token_program.to_account_info(),
+            metadata_program: self.metadata_program.to_account_info(),
+            token_program_2022: self.token_program_2022.to_account_info(),
+            vault_0_mint: self.vault_0_mint.to_account_info(),
+            vault_1_mint: self.vault_1_mint.to_account_info(),
+            remaining_accounts,
+        };
+        let ticks = PositionTicks {
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+        };
+        // The liquidity is given, Raydium charges the amounts it needs up to the maxima
+        RaydiumAdapter::open_position(args, ticks, liquidity, amount_0_max, amount_1_max, &[])
+    }
+}
@@ -6,21 +6,19 @@ use anchor_spl::{
     token_interface::{ Mint, Token2022, TokenAccount },
 };
 
-use raydium_clmm_cpi::{
-    cpi,
-    program::RaydiumClmm,
-    states::{ PersonalPositionState, PoolState, ProtocolPositionState },
-    ID as RAYDIUM_CLMM_ID,
-};
+use raydium_clmm_cpi::states::{ PersonalPositionState, PoolState, ProtocolPositionState };
 
-use crate::state::{ Investor, PoolPosition, PoolPositionConfig };
+use crate::dex::{ DexAdapter, OpenPositionArgs, PositionTicks, RaydiumAdapter };
+use crate::errors::ErrorCode;
+use crate::libraries::{ ClmmLoader, RemainingAccountsLayout };
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig, ProtocolConfig };
 
 #[derive(Accounts)]
 pub struct OpenPositionCtx<'info> {
-    #[account(mut)]
+    #[account(mut, address = pool_position_config.manager_key @ ErrorCode::Unauthorized)]
     pub manager: Signer<'info>,
 
-    #[account( mut )]
+    #[account(mut)]
     pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
 
     #[account(
@@ -28,7 +26,7 @@ pub struct OpenPositionCtx<'info> {
         seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
         bump,
     )]
-    pub pool_position: Box<Account<'info, PoolPosition>>,
+    pub pool_position: AccountLoader<'info, PoolPosition>,
 
     #[account(
         mut,
@@ -39,10 +37,21 @@ pub struct OpenPositionCtx<'info> {
         ],
         bump,
     )]
-    pub manager_account: Box<Account<'info, Investor>>,
+    pub manager_account: AccountLoader<'info, Investor>,
 
-    #[account(address = RAYDIUM_CLMM_ID)]
-    pub clmm_program: Program<'info, RaydiumClmm>,
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: a CLMM program of the protocol config allowlist
+    #[account(
+        executable,
+        constraint = protocol_config.is_clmm_program(clmm_program.key)
+            @ ErrorCode::ClmmProgramNotAllowed
+    )]
+    pub clmm_program: UncheckedAccount<'info>,
 
     /// CHECK: Unique token mint address, random keypair
     #[account(mut)]
@@ -58,9 +67,9 @@ pub struct OpenPositionCtx<'info> {
     #[account(mut)]
     pub metadata_account: UncheckedAccount<'info>,
 
-    /// CHECK: Add liquidity for this pool
+    /// CHECK: Add liquidity for this pool, a pool of `clmm_program` read by the handler
     #[account(mut)]
-    pub pool_state: AccountLoader<'info, PoolState>,
+    pub pool_state: UncheckedAccount<'info>,
 
     /// CHECK: Store the information of market marking in range
     #[account(
@@ -100,18 +109,12 @@ pub struct OpenPositionCtx<'info> {
     )]
     pub token_account_1: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// The address that holds pool tokens for token_0
-    #[account(
-        mut,
-        constraint = token_vault_0.key() == pool_state.load()?.token_vault_0
-    )]
+    /// The address that holds pool tokens for token_0, checked by the handler
+    #[account(mut)]
     pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
 
     // / The address that holds pool tokens for token_1
-    #[account(
-        mut,
-        constraint = token_vault_1.key() == pool_state.load()?.token_vault_1
-    )]
+    #[account(mut)]
     pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// The mint of token vault 0
@@ -169,8 +172,15 @@ impl<'info> OpenPositionCtx<'info> {
         tick_upper_index: i32,
         tick_array_lower_start_index: i32,
         tick_array_upper_start_index: i32,
+        remaining_accounts_layout: RemainingAccountsLayout,
         remaining_accounts: &'c [AccountInfo<'info>]
     ) -> Result<()> {
+        self.check_pool_state()?;
+        let remaining_accounts = remaining_accounts_layout.parse_liquidity(
+            remaining_accounts,
+            self.clmm_program.key,
+            self.pool_state.key()
+        )?;
         self.dex_open_position(
             tick_lower_index,
             tick_upper_index,
@@ -185,8 +195,8 @@ impl<'info> OpenPositionCtx<'info> {
             self.personal_position.to_account_info()
         )?;
 
-        let pool_position = &mut self.pool_position;
-        let manager_account = &mut self.manager_account;
+        let mut pool_position = self.pool_position.load_mut()?;
+        let mut manager_account = self.manager_account.load_mut()?;
 
         pool_position.pool_position_nft_key = self.position_nft_account.key();
         pool_position.position_nft_mint_key = self.position_nft_mint.key();
@@ -195,13 +205,34 @@ impl<'info> OpenPositionCtx<'info> {
 
         manager_account.init_liquidity = liquidity;
         manager_account.liquidity = liquidity;
+        manager_account.deposited_at = Clock::get()?.unix_timestamp;
 
         msg!("Pool Position NFT : {:?}", self.position_nft_mint.key());
 
         Ok(())
     }
 
-    fn dex_open_position<'a, 'b, 'c: 'info>(
+    /// Checks the pool is one of `clmm_program` and the token vaults are its own
+    fn check_pool_state(&self) -> Result<()> {
+        let pool_state = ClmmLoader::<PoolState>::try_from(
+            &self.pool_state,
+            self.clmm_program.key
+        )?;
+        let pool_state = pool_state.load()?;
+        require_keys_eq!(
+            self.token_vault_0.key(),
+            pool_state.token_vault_0,
+            ErrorCode::InvalidClmmAccount
+        );
+        require_keys_eq!(
+            self.token_vault_1.key(),
+            pool_state.token_vault_1,
+            ErrorCode::InvalidClmmAccount
+        );
+        Ok(())
+    }
+
+    fn dex_open_position(
         &mut self,
         tick_lower_index: i32,
         tick_upper_index: i32,
@@ -210,11 +241,12 @@ impl<'info> OpenPositionCtx<'info> {
         liquidity: u128,
         amount_0_max: u64,
         amount_1_max: u64,
-        remaining_accounts: &'c [AccountInfo<'info>]
+        remaining_accounts: Vec<AccountInfo<'info>>
     ) -> Result<()> {
-        let cpi_accounts = cpi::accounts::OpenPositionV2 {
+        let args = OpenPositionArgs {
+            clmm_program: self.clmm_program.to_account_info(),
             payer: self.manager.to_account_info(),
-            position_nft_owner: self.pool_position.to_account_info(),
+            nft_owner: self.pool_position.to_account_info(),
             position_nft_mint: self.position_nft_mint.to_account_info(),
             position_nft_account: self.position_nft_account.to_account_info(),
             metadata_account: self.metadata_account.to_account_info(),
@@ -235,24 +267,14 @@ impl<'info> OpenPositionCtx<'info> {
             token_program_2022: self.token_program_2022.to_account_info(),
             vault_0_mint: self.vault_0_mint.to_account_info(),
             vault_1_mint: self.vault_1_mint.to_account_info(),
+            remaining_accounts,
         };
-
-        let cpi_context = CpiContext::new(
-            self.clmm_program.to_account_info(),
-            cpi_accounts
-        ).with_remaining_accounts(remaining_accounts.to_vec());
-
-        cpi::open_position_v2(
-            cpi_context,
+        let ticks = PositionTicks {
             tick_lower_index,
             tick_upper_index,
             tick_array_lower_start_index,
             tick_array_upper_start_index,
-            liquidity,
-            amount_0_max,
-            amount_1_max,
-            false,
-            Some(true)
-        )
+        };
+        RaydiumAdapter::open_position(args, ticks, liquidity, amount_0_max, amount_1_max, &[])
     }
 }
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::ANCHOR_DISCRIMINATOR_SIZE;
+use crate::errors::ErrorCode;
+use crate::events::ConfigChangeQueued;
+use crate::state::{ ConfigChange, PendingConfigChange, PoolPositionConfig };
+
+#[derive(Accounts)]
+#[instruction(change: ConfigChange)]
+pub struct QueueConfigChangeCtx<'info> {
+    /// The manager, or the guardian for a change of roles
+    #[account(mut, address = change.proposer(&pool_position_config) @ ErrorCode::Unauthorized)]
+    pub proposer: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [
+            PendingConfigChange::PENDING_CONFIG_CHANGE_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            pool_position_config.next_config_change_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = ANCHOR_DISCRIMINATOR_SIZE + PendingConfigChange::INIT_SPACE
+    )]
+    pub pending_config_change: Box<Account<'info, PendingConfigChange>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> QueueConfigChangeCtx<'info> {
+    /// Queues `change` until the timelock of the config has elapsed. The event lets investors
+    /// exit before the change applies.
+    pub fn queue_config_change(
+        &mut self,
+        change: ConfigChange,
+        bumps: &QueueConfigChangeCtxBumps
+    ) -> Result<()> {
+        change.validate()?;
+        let now = Clock::get()?.unix_timestamp;
+        let pool_position_config = &mut self.pool_position_config;
+        let change_id = pool_position_config.next_config_change_id;
+        let executable_at = now
+            .checked_add(pool_position_config.timelock_seconds)
+            .ok_or(ErrorCode::InvalidTimelock)?;
+        pool_position_config.next_config_change_id = change_id + 1;
+
+        let pending_config_change = &mut self.pending_config_change;
+        pending_config_change.bump = bumps.pending_config_change;
+        pending_config_change.pool_position_config_key = pool_position_config.key();
+        pending_config_change.change_id = change_id;
+        pending_config_change.proposer = self.proposer.key();
+        pending_config_change.change = change;
+        pending_config_change.queued_at = now;
+        pending_config_change.executable_at = executable_at;
+
+        emit!(ConfigChangeQueued {
+            pool_position_config: pool_position_config.key(),
+            change_id,
+            proposer: self.proposer.key(),
+            change,
+            executable_at,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::events::ClmmProgramsSet;
+use crate::state::ProtocolConfig;
+
+#[derive(Accounts)]
+pub struct SetClmmProgramsCtx<'info> {
+    #[account(address = protocol_config.admin @ ErrorCode::Unauthorized)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+}
+
+impl<'info> SetClmmProgramsCtx<'info> {
+    /// Replaces the allowlist. Positions on a pool of a removed program can't be used until it is
+    /// allowed again.
+    pub fn set_clmm_programs(&mut self, clmm_programs: Vec<Pubkey>) -> Result<()> {
+        require!(
+            clmm_programs.len() <= ProtocolConfig::MAX_CLMM_PROGRAMS,
+            ErrorCode::TooManyClmmPrograms
+        );
+        let protocol_config = &mut self.protocol_config;
+        protocol_config.clmm_programs = clmm_programs.clone();
+        emit!(ClmmProgramsSet { protocol_config: protocol_config.key(), clmm_programs });
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::events::PausedSet;
+use crate::state::PoolPositionConfig;
+
+#[derive(Accounts)]
+pub struct SetPausedCtx<'info> {
+    #[account(address = pool_position_config.roles.guardian @ ErrorCode::Unauthorized)]
+    pub guardian: Signer<'info>,
+
+    #[account(mut)]
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+}
+
+impl<'info> SetPausedCtx<'info> {
+    /// A paused position rejects deposits, investors can still collect fees and withdraw
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        let pool_position_config = &mut self.pool_position_config;
+        pool_position_config.paused = paused;
+        emit!(PausedSet { pool_position_config: pool_position_config.key(), paused });
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount };
+use raydium_clmm_cpi::states::{ ObservationState, PoolState };
+
+use crate::constants::NAV_SNAPSHOT_INTERVAL_SECONDS;
+use crate::dex::RaydiumPositionState;
+use crate::errors::ErrorCode;
+use crate::instructions::get_owed_fees;
+use crate::libraries::{
+    ladder_owed_fees,
+    liquidity_math,
+    nav_per_liquidity_x64,
+    parse_ladder_ranges,
+    source_sqrt_price_x64,
+    tick_math,
+    value_in_stable,
+    ClmmLoader,
+    LadderRangeAccounts,
+    PositionHoldings,
+    PriceSource,
+};
+use crate::state::{ NavSnapshot, PoolPosition, PoolPositionConfig, ProtocolConfig };
+
+#[derive(Accounts)]
+pub struct SnapshotNavCtx<'info> {
+    #[account(address = pool_position_config.roles.strategist @ ErrorCode::Unauthorized)]
+    pub strategist: Signer<'info>,
+
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        mut,
+        seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
+        bump
+    )]
+    pub pool_position: AccountLoader<'info, PoolPosition>,
+
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: a pool of a CLMM program of the protocol config allowlist, read by
+    /// `RaydiumPositionState`
+    #[account(
+        address = pool_position_config.pool_key,
+        constraint = protocol_config.is_clmm_program(pool_state.owner)
+            @ ErrorCode::ClmmProgramNotAllowed
+    )]
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// The Raydium position of the pool position
+    /// CHECK: the personal position of `pool_position`, read by `RaydiumPositionState`
+    pub personal_position: UncheckedAccount<'info>,
+
+    /// Stores init state for the lower tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// Stores init state for the upper tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [PoolPosition::POOL_POSITION_VAULT_0_SEED.as_bytes(), pool_position.key().as_ref()],
+        bump
+    )]
+    pub pool_position_vault_0_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [PoolPosition::POOL_POSITION_VAULT_1_SEED.as_bytes(), pool_position.key().as_ref()],
+        bump
+    )]
+    pub pool_position_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [
+            PoolPosition::POOL_POSITION_FEES_VAULT_0_SEED.as_bytes(),
+            pool_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub fees_vault_0_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [
+            PoolPosition::POOL_POSITION_FEES_VAULT_1_SEED.as_bytes(),
+            pool_position.key().as_ref(),
+        ],
+        bump
+    )]
+    pub fees_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The mint the position is valued in
+    pub pool_vault_deposit_stable_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The pool pricing token_0 in the stable mint. Its price is ignored when token_0 is the
+    /// stable mint, the position pool can be passed then.
+    /// CHECK: a pool of the CLMM program of `pool_state`, read by the handler
+    pub pool_state_0: UncheckedAccount<'info>,
+
+    /// The oracle of `pool_state_0`, read for TWAP prices
+    /// CHECK: the observation state of `pool_state_0`, checked by the handler
+    pub observation_state_0: UncheckedAccount<'info>,
+
+    /// The pool pricing token_1 in the stable mint, ignored when token_1 is the stable mint
+    /// CHECK: a pool of the CLMM program of `pool_state`, read by the handler
+    pub pool_state_1: UncheckedAccount<'info>,
+
+    /// The oracle of `pool_state_1`, read for TWAP prices
+    /// CHECK: the observation state of `pool_state_1`, checked by the handler
+    pub observation_state_1: UncheckedAccount<'info>,
+}
+
+impl<'info> SnapshotNavCtx<'info> {
+    /// Values the pool position in the stable mint and records its NAV per liquidity, at most
+    /// once every `NAV_SNAPSHOT_INTERVAL_SECONDS`. `remaining_accounts` holds the ladder ranges
+    /// of the position.
+    pub fn snapshot_nav<'c: 'info>(
+        &mut self,
+        price_source: PriceSource,
+        remaining_accounts: &'c [AccountInfo<'info>]
+    ) -> Result<NavSnapshot> {
+        let (position_nft_mint_key, ladder_ranges, liquidity) = {
+            let pool_position = self.pool_position.load()?;
+            (
+                pool_position.position_nft_mint_key,
+                pool_position.ladder_ranges,
+                pool_position.liquidity,
+            )
+        };
+        // The price pools are read from the CLMM program of the position pool
+        let clmm_program = self.pool_state.owner;
+        let position = RaydiumPositionState::try_from(
+            clmm_program,
+            &self.pool_state,
+            &self.personal_position,
+            &self.tick_array_lower,
+            &self.tick_array_upper
+        )?;
+        require_keys_eq!(
+            position.personal_position.nft_mint,
+            position_nft_mint_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        let ranges = parse_ladder_ranges(
+            remaining_accounts,
+            clmm_program,
+            self.pool_position.key(),
+            ladder_ranges,
+            self.pool_state.key()
+        )?;
+        let holdings = self.holdings(&position, &ranges)?;
+        let stable_mint = self.pool_vault_deposit_stable_mint.key();
+        let clock = Clock::get()?;
+        let now = clock.unix_timestamp as u32;
+
+        let (token_mint_0, token_mint_1) = {
+            let pool_state = position.pool_state.load()?;
+            (pool_state.token_mint_0, pool_state.token_mint_1)
+        };
+        let pool_state_0 = ClmmLoader::<PoolState>::try_from(&self.pool_state_0, clmm_program)?;
+        let value_0 = value_in_stable(
+            holdings.total_0()?,
+            token_mint_0,
+            stable_mint,
+            &*pool_state_0.load()?,
+            self.price(price_source, &pool_state_0, &self.observation_state_0, token_mint_0, now)?
+        )?;
+        let pool_state_1 = ClmmLoader::<PoolState>::try_from(&self.pool_state_1, clmm_program)?;
+        let value_1 = value_in_stable(
+            holdings.total_1()?,
+            token_mint_1,
+            stable_mint,
+            &*pool_state_1.load()?,
+            self.price(price_source, &pool_state_1, &self.observation_state_1, token_mint_1, now)?
+        )?;
+        let nav = value_0.checked_add(value_1).ok_or(ErrorCode::MaxTokenOverflow)?;
+
+        let snapshot = NavSnapshot {
+            timestamp: clock.unix_timestamp,
+            nav,
+            liquidity,
+            nav_per_liquidity_x64: nav_per_liquidity_x64(nav, liquidity),
+        };
+        msg!("nav snapshot: {:?}", snapshot);
+        self.pool_position.load_mut()?.push_nav_snapshot(snapshot, NAV_SNAPSHOT_INTERVAL_SECONDS)?;
+        Ok(snapshot)
+    }
+
+    fn holdings(
+        &self,
+        position: &RaydiumPositionState<'info>,
+        ranges: &[LadderRangeAccounts<'info>]
+    ) -> Result<PositionHoldings> {
+        let (core_fees_0, core_fees_1) = get_owed_fees(
+            position.tick_array_lower.clone(),
+            position.tick_array_upper.clone(),
+            &position.personal_position,
+            &position.pool_state
+        );
+        let (ladder_fees_0, ladder_fees_1) = ladder_owed_fees(ranges, &position.pool_state);
+        let sqrt_price_x64 = position.pool_state.load()?.sqrt_price_x64;
+        let (mut liquidity_amount_0, mut liquidity_amount_1) =
+            liquidity_math::get_amounts_for_liquidity(
+                sqrt_price_x64,
+                tick_math::get_sqrt_price_at_tick(self.pool_position_config.tick_lower_index)?,
+                tick_math::get_sqrt_price_at_tick(self.pool_position_config.tick_upper_index)?,
+                position.personal_position.liquidity,
+                false
+            )?;
+        for range in ranges {
+            let (range_amount_0, range_amount_1) = range.amounts_for_liquidity(
+                sqrt_price_x64,
+                range.personal_position.liquidity
+            )?;
+            liquidity_amount_0 = liquidity_amount_0
+                .checked_add(range_amount_0)
+                .ok_or(ErrorCode::MaxTokenOverflow)?;
+            liquidity_amount_1 = liquidity_amount_1
+                .checked_add(range_amount_1)
+                .ok_or(ErrorCode::MaxTokenOverflow)?;
+        }
+        let uncollected_fees_0 = core_fees_0
+            .checked_add(ladder_fees_0)
+            .ok_or(ErrorCode::MaxTokenOverflow)?;
+        let uncollected_fees_1 = core_fees_1
+            .checked_add(ladder_fees_1)
+            .ok_or(ErrorCode::MaxTokenOverflow)?;
+        Ok(PositionHoldings {
+            liquidity_amount_0,
+            liquidity_amount_1,
+            uncollected_fees_0,
+            uncollected_fees_1,
+            fees_vault_0: self.fees_vault_0_token_account.amount,
+            fees_vault_1: self.fees_vault_1_token_account.amount,
+            idle_0: self.pool_position_vault_0_token_account.amount,
+            idle_1: self.pool_position_vault_1_token_account.amount,
+        })
+    }
+
+    /// Sqrt price of a price pool, not read for the stable mint itself
+    fn price(
+        &self,
+        price_source: PriceSource,
+        pool_state: &ClmmLoader<'info, PoolState>,
+        observation_state: &AccountInfo<'info>,
+        mint: Pubkey,
+        now: u32
+    ) -> Result<u128> {
+        if mint == self.pool_vault_deposit_stable_mint.key() {
+            return Ok(0);
+        }
+        let pool_state = pool_state.load()?;
+        require_keys_eq!(
+            observation_state.key(),
+            pool_state.observation_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        let observation_state = ClmmLoader::<ObservationState>::try_from(
+            observation_state,
+            self.pool_state.owner
+        )?;
+        source_sqrt_price_x64(price_source, &pool_state, &*observation_state.load()?, now)
+    }
+}
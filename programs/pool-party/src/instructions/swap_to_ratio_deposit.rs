@@ -1,57 +1,81 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use num_bigint::BigInt;
 use anchor_spl::memo::Memo;
 use anchor_spl::token::Token;
 use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount };
-use raydium_clmm_cpi::states::PersonalPositionState;
-use raydium_clmm_cpi::{
-    cpi,
-    program::RaydiumClmm,
-    states::{ AmmConfig, ObservationState, PoolState },
-    ID as RAYDIUM_CLMM_ID,
+use raydium_clmm_cpi::states::{ AmmConfig, PersonalPositionState, PoolState, TickArrayState };
+
+use crate::dex::{ DexAdapter, RaydiumAdapter };
+use crate::errors::ErrorCode;
+use crate::libraries::{
+    quote_optimal_split,
+    quote_single_swap_split,
+    split_pool_accounts,
+    tick_math,
+    transfer_token,
+    ClmmAccount,
+    ClmmLoader,
+    RatioTarget,
+    RemainingAccounts,
+    RemainingAccountsLayout,
+    SwapLeg,
+    SwapPoolSnapshot,
+    SwapRoute,
+    TickLiquidity,
 };
-
-use crate::constants::DENOMINATOR_MULTIPLIER;
-use crate::libraries::tick_math;
-use crate::state::{ Investor };
+use crate::state::{ Investor, PoolPositionConfig, ProtocolConfig };
 
 #[derive(Accounts)]
 pub struct SwapToRatioDepositCtx<'info> {
-    #[account(address = RAYDIUM_CLMM_ID)]
-    pub clmm_program: Program<'info, RaydiumClmm>,
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
 
-    /// CHECK:
-    #[account( mut )]
-    pub pool_position_config: UncheckedAccount<'info>,
+    /// CHECK: a CLMM program of the protocol config allowlist
+    #[account(
+        executable,
+        constraint = protocol_config.is_clmm_program(clmm_program.key)
+            @ ErrorCode::ClmmProgramNotAllowed
+    )]
+    pub clmm_program: UncheckedAccount<'info>,
+
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
 
     /// The user performing the swap
     #[account(mut)]
     pub investor: Signer<'info>,
 
     /// The program account of the pool in which the swap will be performed
+    /// CHECK: a pool of `clmm_program`, read by the handler
     #[account(mut)]
-    pub pool_state: AccountLoader<'info, PoolState>,
+    pub pool_state: UncheckedAccount<'info>,
 
     /// Increase liquidity for this position
-    #[account(mut, constraint = personal_position.pool_id == pool_state.key())]
-    pub personal_position: Box<Account<'info, PersonalPositionState>>,
+    /// CHECK: a personal position of `pool_state`, read by the handler
+    #[account(mut)]
+    pub personal_position: UncheckedAccount<'info>,
 
     /// The factory state to read protocol fees
-    #[account(mut, address = pool_state_0.load()?.amm_config)]
-    pub amm_config_0: Box<Account<'info, AmmConfig>>,
+    /// CHECK: the AMM config of `pool_state_0`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub amm_config_0: UncheckedAccount<'info>,
 
     /// The program account of the pool in which the swap will be performed
+    /// CHECK: a pool of `clmm_program`, read by `SwapLegPool`
     #[account(mut)]
-    pub pool_state_0: AccountLoader<'info, PoolState>,
+    pub pool_state_0: UncheckedAccount<'info>,
 
     // / The factory state to read protocol fees
-    #[account(mut, address = pool_state_1.load()?.amm_config)]
-    pub amm_config_1: Box<Account<'info, AmmConfig>>,
+    /// CHECK: the AMM config of `pool_state_1`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub amm_config_1: UncheckedAccount<'info>,
 
     /// The program account of the pool in which the swap will be performed
+    /// CHECK: a pool of `clmm_program`, read by `SwapLegPool`
     #[account(mut)]
-    pub pool_state_1: AccountLoader<'info, PoolState>,
+    pub pool_state_1: UncheckedAccount<'info>,
 
     #[account( 
       mut,
@@ -62,7 +86,7 @@ pub struct SwapToRatioDepositCtx<'info> {
       ],
       bump, 
   )]
-    pub investor_account: Box<Account<'info, Investor>>,
+    pub investor_account: AccountLoader<'info, Investor>,
 
     #[account(
         mut,
@@ -127,12 +151,14 @@ pub struct SwapToRatioDepositCtx<'info> {
     pub pool_vault_token_1_mint: Box<InterfaceAccount<'info, Mint>>,
 
     /// The program account for the most recent oracle observation
-    #[account(mut, address = pool_state_0.load()?.observation_key)]
-    pub observation_state_0: AccountLoader<'info, ObservationState>,
+    /// CHECK: the observation state of `pool_state_0`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub observation_state_0: UncheckedAccount<'info>,
 
     /// The program account for the most recent oracle observation
-    #[account(mut, address = pool_state_1.load()?.observation_key)]
-    pub observation_state_1: AccountLoader<'info, ObservationState>,
+    /// CHECK: the observation state of `pool_state_1`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub observation_state_1: UncheckedAccount<'info>,
 
     /// SPL program for token transfers
     pub token_program: Program<'info, Token>,
@@ -149,50 +175,191 @@ pub struct SwapToRatioDepositCtx<'info> {
     pub system_program: Program<'info, System>,
 }
 
+pub struct SwapArgs<'info> {
+    pub clmm_program: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub amm_config: AccountInfo<'info>,
+    pub pool_state: AccountInfo<'info>,
+    pub input_token_account: AccountInfo<'info>,
+    pub output_token_account: AccountInfo<'info>,
+    pub input_vault: AccountInfo<'info>,
+    pub output_vault: AccountInfo<'info>,
+    pub observation_state: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub token_program_2022: AccountInfo<'info>,
+    pub memo_program: AccountInfo<'info>,
+    pub input_vault_mint: AccountInfo<'info>,
+    pub output_vault_mint: AccountInfo<'info>,
+    pub remaining_accounts: Vec<AccountInfo<'info>>,
+}
+
+/// The pool of a swap hop with its AMM config, read from the CLMM program
+pub struct SwapLegPool<'info> {
+    pub pool_state: ClmmLoader<'info, PoolState>,
+    pub amm_config: Box<ClmmAccount<'info, AmmConfig>>,
+}
+
+impl<'info> SwapLegPool<'info> {
+    /// Reads the pool `pool_state` of `clmm_program`, checking `amm_config` and
+    /// `observation_state` are those of the pool
+    pub fn try_from(
+        clmm_program: &Pubkey,
+        pool_state: &AccountInfo<'info>,
+        amm_config: &AccountInfo<'info>,
+        observation_state: &AccountInfo<'info>
+    ) -> Result<Self> {
+        let pool_state = ClmmLoader::<PoolState>::try_from(pool_state, clmm_program)?;
+        let amm_config = Box::new(ClmmAccount::<AmmConfig>::try_from(amm_config, clmm_program)?);
+        {
+            let pool = pool_state.load()?;
+            require_keys_eq!(amm_config.key(), pool.amm_config, ErrorCode::InvalidClmmAccount);
+            require_keys_eq!(
+                observation_state.key(),
+                pool.observation_key,
+                ErrorCode::InvalidClmmAccount
+            );
+        }
+        Ok(SwapLegPool { pool_state, amm_config })
+    }
+}
+
+/// Number of accounts of every hop after the first one, before its tick arrays
+pub const SWAP_HOP_ACCOUNTS_LEN: usize = 8;
+
+/// Route of one stable-to-token leg.
+///
+/// The first hop swaps through the leg accounts of the context, with the bitmap extension and
+/// tick arrays described by the `RemainingAccountsLayout` of the instruction. Every following hop
+/// takes `hop_account_counts[i]` accounts from the end of the remaining accounts, in order:
+/// `[input_token_account, input_vault_mint, amm_config, pool_state, input_vault, output_vault,
+/// output_vault_mint, observation_state]`, then an optional bitmap extension and the tick arrays of
+/// its pool, where `input_token_account` is a token account of the investor receiving the output of
+/// the previous hop.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SwapRouteArgs {
+    pub hop_account_counts: Vec<u8>,
+    /// Minimum amount of the target token received from the final hop
+    pub minimum_amount_out: u64,
+}
+
+/// Accounts of a hop after the first one of a route
+pub struct SwapHop<'info> {
+    pub input_token_account: &'info AccountInfo<'info>,
+    pub input_vault_mint: &'info AccountInfo<'info>,
+    pub amm_config: &'info AccountInfo<'info>,
+    pub pool_state: &'info AccountInfo<'info>,
+    pub input_vault: &'info AccountInfo<'info>,
+    pub output_vault: &'info AccountInfo<'info>,
+    pub output_vault_mint: &'info AccountInfo<'info>,
+    pub observation_state: &'info AccountInfo<'info>,
+    pub bitmap_extension: Option<&'info AccountInfo<'info>>,
+    pub tick_arrays: &'info [AccountInfo<'info>],
+}
+
+pub struct SwapRouteAccounts<'info> {
+    /// Bitmap extension and tick arrays forwarded to the first hop
+    pub remaining_accounts: Vec<AccountInfo<'info>>,
+    /// Tick arrays of the first hop
+    pub tick_arrays: &'info [AccountInfo<'info>],
+    pub hops: Vec<SwapHop<'info>>,
+}
+
+impl<'info> SwapHop<'info> {
+    fn remaining_accounts(&self) -> Vec<AccountInfo<'info>> {
+        self.bitmap_extension
+            .into_iter()
+            .chain(self.tick_arrays)
+            .cloned()
+            .collect()
+    }
+}
+
 impl<'info> SwapToRatioDepositCtx<'info> {
     pub fn swap_to_ratio_deposit<'a, 'b, 'c: 'info>(
         &mut self,
-        other_amount_threshold: u64,
-        sqrt_price_limit_x64: u128,
-        is_base_input: bool,
+        route_0: SwapRouteArgs,
+        route_1: SwapRouteArgs,
+        remaining_accounts_layout: RemainingAccountsLayout,
         remaining_accounts: &'c [AccountInfo<'info>],
         bumps: &SwapToRatioDepositCtxBumps
     ) -> Result<()> {
-        let sqrt_price_x64 = self.pool_state.load()?.sqrt_price_x64;
-        let low_sqrt_price = tick_math
-            ::get_sqrt_price_at_tick(self.personal_position.tick_lower_index)
-            .unwrap();
-        let high_sqrt_price = tick_math
-            ::get_sqrt_price_at_tick(self.personal_position.tick_upper_index)
-            .unwrap();
-
         let amount = self.investor_deposit_stable_token_account.amount;
 
         msg!("amount: {}", amount);
 
-        let (amount_a, amount_b) = self.calc_ratio_amounts(
+        let clmm_program = self.clmm_program.key;
+        let personal_position = ClmmAccount::<PersonalPositionState>::try_from(
+            &self.personal_position,
+            clmm_program
+        )?;
+        require_keys_eq!(
+            personal_position.pool_id,
+            self.pool_state.key(),
+            ErrorCode::InvalidClmmAccount
+        );
+        let leg_0 = SwapLegPool::try_from(
+            clmm_program,
+            &self.pool_state_0,
+            &self.amm_config_0,
+            &self.observation_state_0
+        )?;
+
+        let stable_mint = self.pool_vault_deposit_stable_mint.key();
+        if let Some(stable_is_token_0) = single_swap_side(&self.pool_position_config, stable_mint) {
+            let accounts = remaining_accounts_layout.parse(remaining_accounts, clmm_program)?;
+            let routes = parse_swap_routes(&accounts, &[(self.pool_state_0.key(), &route_0)])?;
+            return self.single_swap_to_ratio(
+                stable_is_token_0,
+                amount,
+                &leg_0,
+                &personal_position,
+                &routes[0],
+                route_0.minimum_amount_out,
+                bumps
+            );
+        }
+
+        let pool_state = ClmmLoader::<PoolState>::try_from(&self.pool_state, clmm_program)?;
+        let leg_1 = SwapLegPool::try_from(
+            clmm_program,
+            &self.pool_state_1,
+            &self.amm_config_1,
+            &self.observation_state_1
+        )?;
+        let accounts = remaining_accounts_layout.parse(remaining_accounts, clmm_program)?;
+        let routes = parse_swap_routes(
+            &accounts,
+            &[
+                (self.pool_state_0.key(), &route_0),
+                (self.pool_state_1.key(), &route_1),
+            ]
+        )?;
+
+        let quote_route_0 = load_swap_route(
+            clmm_program,
+            &leg_0,
+            stable_mint,
+            self.investor_deposit_token_0_account.mint,
+            &routes[0]
+        )?;
+        let quote_route_1 = load_swap_route(
+            clmm_program,
+            &leg_1,
+            stable_mint,
+            self.investor_deposit_token_1_account.mint,
+            &routes[1]
+        )?;
+        let (amount_a, amount_b) = calc_ratio_amounts(
             amount,
-            low_sqrt_price,
-            high_sqrt_price,
-            sqrt_price_x64
+            &pool_state,
+            &personal_position,
+            &quote_route_0,
+            &quote_route_1
         )?;
 
         msg!("amount_a: {}", amount_a);
         msg!("amount_b: {}", amount_b);
 
-        // Split the remaining accounts into two slices, one for each CPI
-        let mut accounts_iter = remaining_accounts.iter();
-        let mut split_index = 0;
-        while let Some(account_info) = accounts_iter.next() {
-            if account_info.key() == Pubkey::default() {
-                break;
-            }
-            split_index += 1;
-        }
-
-        let remaining_accounts_0 = &remaining_accounts[..split_index];
-        let remaining_accounts_1 = &remaining_accounts[split_index + 1..];
-
         let bumps = bumps.investor_deposit_stable_token_account;
         let investor_account_key = self.investor_account.key();
         let signer_seeds: &[&[&[u8]]] = &[
@@ -202,99 +369,393 @@ impl<'info> SwapToRatioDepositCtx<'info> {
                 &[bumps],
             ],
         ];
-        let cpi_0_accounts = cpi::accounts::SwapSingleV2 {
-            payer: self.investor_deposit_stable_token_account.to_account_info(),
-            amm_config: self.amm_config_0.to_account_info(),
-            pool_state: self.pool_state_0.to_account_info(),
-            input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
-            output_token_account: self.investor_deposit_token_0_account.to_account_info(),
-            input_vault: self.pool_vault_0_input.to_account_info(),
-            output_vault: self.pool_vault_token_0_account.to_account_info(),
-            observation_state: self.observation_state_0.to_account_info(),
-            token_program: self.token_program.to_account_info(),
-            token_program_2022: self.token_program_2022.to_account_info(),
-            memo_program: self.memo_program.to_account_info(),
-            input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
-            output_vault_mint: self.pool_vault_token_0_mint.to_account_info(),
-        };
-        let cpi_0_context = CpiContext::new(self.clmm_program.to_account_info(), cpi_0_accounts)
-            .with_remaining_accounts(remaining_accounts_0.to_vec())
-            .with_signer(signer_seeds);
-        cpi::swap_v2(
-            cpi_0_context,
+        swap_route(
+            SwapArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                payer: self.investor_deposit_stable_token_account.to_account_info(),
+                amm_config: self.amm_config_0.to_account_info(),
+                pool_state: self.pool_state_0.to_account_info(),
+                input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
+                output_token_account: self.investor_deposit_token_0_account.to_account_info(),
+                input_vault: self.pool_vault_0_input.to_account_info(),
+                output_vault: self.pool_vault_token_0_account.to_account_info(),
+                observation_state: self.observation_state_0.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
+                output_vault_mint: self.pool_vault_token_0_mint.to_account_info(),
+                remaining_accounts: routes[0].remaining_accounts.clone(),
+            },
+            &routes[0].hops,
+            self.investor.to_account_info(),
             amount_a,
-            other_amount_threshold,
-            sqrt_price_limit_x64,
-            is_base_input
+            route_0.minimum_amount_out,
+            signer_seeds
         )?;
 
-        let cpi_1_accounts = cpi::accounts::SwapSingleV2 {
-            payer: self.investor_deposit_stable_token_account.to_account_info(),
-            amm_config: self.amm_config_1.to_account_info(),
-            pool_state: self.pool_state_1.to_account_info(),
-            input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
-            output_token_account: self.investor_deposit_token_1_account.to_account_info(),
-            input_vault: self.pool_vault_1_input.to_account_info(),
-            output_vault: self.pool_vault_token_1_account.to_account_info(),
-            observation_state: self.observation_state_1.to_account_info(),
-            token_program: self.token_program.to_account_info(),
-            token_program_2022: self.token_program_2022.to_account_info(),
-            memo_program: self.memo_program.to_account_info(),
-            input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
-            output_vault_mint: self.pool_vault_token_1_mint.to_account_info(),
-        };
-        let cpi_1_context = CpiContext::new(self.clmm_program.to_account_info(), cpi_1_accounts)
-            .with_remaining_accounts(remaining_accounts_1.to_vec())
-            .with_signer(signer_seeds);
-        cpi::swap_v2(
-            cpi_1_context,
+        swap_route(
+            SwapArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                payer: self.investor_deposit_stable_token_account.to_account_info(),
+                amm_config: self.amm_config_1.to_account_info(),
+                pool_state: self.pool_state_1.to_account_info(),
+                input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
+                output_token_account: self.investor_deposit_token_1_account.to_account_info(),
+                input_vault: self.pool_vault_1_input.to_account_info(),
+                output_vault: self.pool_vault_token_1_account.to_account_info(),
+                observation_state: self.observation_state_1.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
+                output_vault_mint: self.pool_vault_token_1_mint.to_account_info(),
+                remaining_accounts: routes[1].remaining_accounts.clone(),
+            },
+            &routes[1].hops,
+            self.investor.to_account_info(),
             amount_b,
-            other_amount_threshold,
-            sqrt_price_limit_x64,
-            is_base_input
+            route_1.minimum_amount_out,
+            signer_seeds
         )?;
         Ok(())
     }
 
-    fn calc_ratio_amounts(
+    /// The stable mint is one side of the target pool: swap the needed part inside the target
+    /// pool (passed as `pool_state_0` and its accounts) and keep the rest as is. Only `route_0` is
+    /// used and it must be a single hop; the leg 1 accounts may repeat the leg 0 accounts.
+    fn single_swap_to_ratio(
         &self,
+        stable_is_token_0: bool,
         amount: u64,
-        low_sqrt_price: u128,
-        high_sqrt_price: u128,
-        current_sqrt_price: u128
-    ) -> Result<(u64, u64)> {
-        let denominator_multiplier = BigInt::from(DENOMINATOR_MULTIPLIER);
-
-        let low_times_high = BigInt::from(low_sqrt_price)
-            .checked_mul(&BigInt::from(high_sqrt_price))
-            .unwrap();
-        let high_times_current = BigInt::from(high_sqrt_price)
-            .checked_mul(&BigInt::from(current_sqrt_price))
-            .unwrap();
-
-        let a = low_times_high.sqrt().checked_sub(&high_times_current.sqrt()).unwrap();
-        let b = BigInt::from(current_sqrt_price).checked_sub(&high_times_current.sqrt()).unwrap();
-        let c = a
-            .checked_mul(&denominator_multiplier)
-            .unwrap()
-            .checked_div(&b)
-            .unwrap()
-            .checked_add(&denominator_multiplier)
-            .unwrap();
-
-        let ratio_a = denominator_multiplier
-            .checked_mul(&denominator_multiplier)
-            .unwrap()
-            .checked_div(&c)
-            .unwrap();
-
-        let amount_1 = BigInt::from(amount)
-            .checked_mul(&ratio_a)
-            .unwrap()
-            .checked_div(&denominator_multiplier)
-            .unwrap();
-        let amount_2 = BigInt::from(amount).checked_sub(&amount_1).unwrap();
-
-        Ok((amount_1.to_string().parse().unwrap(), amount_2.to_string().parse().unwrap()))
+        pool: &SwapLegPool<'info>,
+        personal_position: &PersonalPositionState,
+        route: &SwapRouteAccounts<'info>,
+        minimum_amount_out: u64,
+        bumps: &SwapToRatioDepositCtxBumps
+    ) -> Result<()> {
+        require_keys_eq!(self.pool_state_0.key(), self.pool_state.key(), ErrorCode::InvalidSwapPool);
+        require!(route.hops.is_empty(), ErrorCode::InvalidSwapRoute);
+
+        let stable_mint = self.pool_vault_deposit_stable_mint.key();
+        let leg = load_swap_leg(self.clmm_program.key, pool, stable_mint, route.tick_arrays)?;
+        let (amount_swapped, amount_kept) = calc_single_swap_amounts(
+            amount,
+            personal_position,
+            &leg
+        )?;
+
+        let (kept_token_account, output_token_account, output_vault_mint) = if stable_is_token_0 {
+            (
+                &self.investor_deposit_token_0_account,
+                &self.investor_deposit_token_1_account,
+                &self.pool_vault_token_1_mint,
+            )
+        } else {
+            (
+                &self.investor_deposit_token_1_account,
+                &self.investor_deposit_token_0_account,
+                &self.pool_vault_token_0_mint,
+            )
+        };
+
+        let investor_account_key = self.investor_account.key();
+        let signer_seeds: &[&[&[u8]]] = &[
+            &[
+                Investor::INVESTOR_DEPOSIT_STABLE_TOKEN_ACCOUNT_SEED.as_bytes(),
+                investor_account_key.as_ref(),
+                &[bumps.investor_deposit_stable_token_account],
+            ],
+        ];
+
+        swap_route(
+            SwapArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                payer: self.investor_deposit_stable_token_account.to_account_info(),
+                amm_config: self.amm_config_0.to_account_info(),
+                pool_state: self.pool_state_0.to_account_info(),
+                input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
+                output_token_account: output_token_account.to_account_info(),
+                input_vault: self.pool_vault_0_input.to_account_info(),
+                output_vault: self.pool_vault_token_0_account.to_account_info(),
+                observation_state: self.observation_state_0.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
+                output_vault_mint: output_vault_mint.to_account_info(),
+                remaining_accounts: route.remaining_accounts.clone(),
+            },
+            &[],
+            self.investor.to_account_info(),
+            amount_swapped,
+            minimum_amount_out,
+            signer_seeds
+        )?;
+
+        if amount_kept > 0 {
+            transfer_token(
+                &self.investor_deposit_stable_token_account,
+                kept_token_account,
+                &amount_kept,
+                &self.pool_vault_deposit_stable_mint,
+                &self.investor_deposit_stable_token_account.to_account_info(),
+                &self.token_program,
+                Some(signer_seeds)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the routes of the swap legs. `legs` holds the first hop pool of each leg, in order, with
+/// its route; the first hop uses the tick arrays of the matching leg group of `accounts` and the
+/// following hops are read from `accounts.rest`, which they must use up.
+pub fn parse_swap_routes<'info>(
+    accounts: &RemainingAccounts<'info>,
+    legs: &[(Pubkey, &SwapRouteArgs)]
+) -> Result<Vec<SwapRouteAccounts<'info>>> {
+    let leg_tick_arrays = [accounts.leg_0_tick_arrays, accounts.leg_1_tick_arrays];
+    let mut result = Vec::with_capacity(legs.len());
+    let mut rest = accounts.rest;
+    for ((pool_id, route), tick_arrays) in legs.iter().zip(leg_tick_arrays) {
+        let remaining_accounts = accounts.pool_accounts(*pool_id, tick_arrays)?;
+
+        let mut hops = Vec::with_capacity(route.hop_account_counts.len());
+        for count in &route.hop_account_counts {
+            let count = *count as usize;
+            require!(
+                count >= SWAP_HOP_ACCOUNTS_LEN && count <= rest.len(),
+                ErrorCode::InvalidRemainingAccounts
+            );
+            let (hop_accounts, next) = rest.split_at(count);
+            rest = next;
+            let (bitmap_extension, hop_tick_arrays) = split_pool_accounts(
+                &hop_accounts[SWAP_HOP_ACCOUNTS_LEN..],
+                &accounts.clmm_program,
+                hop_accounts[3].key()
+            )?;
+            hops.push(SwapHop {
+                input_token_account: &hop_accounts[0],
+                input_vault_mint: &hop_accounts[1],
+                amm_config: &hop_accounts[2],
+                pool_state: &hop_accounts[3],
+                input_vault: &hop_accounts[4],
+                output_vault: &hop_accounts[5],
+                output_vault_mint: &hop_accounts[6],
+                observation_state: &hop_accounts[7],
+                bitmap_extension,
+                tick_arrays: hop_tick_arrays,
+            });
+        }
+        result.push(SwapRouteAccounts { remaining_accounts, tick_arrays, hops });
+    }
+    require!(rest.is_empty(), ErrorCode::InvalidRemainingAccounts);
+    Ok(result)
+}
+
+/// Swaps `amount` through the first hop described by `first_hop` and then through `hops`, each hop
+/// swapping everything the previous one sent to its input token account. The output of the final
+/// hop goes to `first_hop.output_token_account` and must be at least `minimum_amount_out`; when
+/// there are more hops the first one outputs to the input token account and mint of the next one.
+///
+/// Hops after the first one are paid from token accounts of `hop_payer`. Returns the amount
+/// received from the final hop.
+pub fn swap_route<'info>(
+    mut first_hop: SwapArgs<'info>,
+    hops: &[SwapHop<'info>],
+    hop_payer: AccountInfo<'info>,
+    amount: u64,
+    minimum_amount_out: u64,
+    signer_seeds: &[&[&[u8]]]
+) -> Result<u64> {
+    if amount == 0 {
+        require!(minimum_amount_out == 0, ErrorCode::TooLittleOutputReceived);
+        return Ok(0);
+    }
+
+    let output_token_account = first_hop.output_token_account.clone();
+    if let Some(next) = hops.first() {
+        first_hop.output_token_account = next.input_token_account.clone();
+        first_hop.output_vault_mint = next.input_vault_mint.clone();
+    }
+    let clmm_program = first_hop.clmm_program.clone();
+    let token_program = first_hop.token_program.clone();
+    let token_program_2022 = first_hop.token_program_2022.clone();
+    let memo_program = first_hop.memo_program.clone();
+    let hop_output = first_hop.output_token_account.clone();
+
+    // Intermediate hops are unbounded, slippage is enforced on the final hop
+    let balance_before = token_account_amount(&hop_output)?;
+    RaydiumAdapter::swap(first_hop, amount, 0, 0, true, signer_seeds)?;
+    let mut amount_out = token_account_amount(&hop_output)?
+        .checked_sub(balance_before)
+        .unwrap();
+
+    for (index, hop) in hops.iter().enumerate() {
+        let hop_output = match hops.get(index + 1) {
+            Some(next) => next.input_token_account.clone(),
+            None => output_token_account.clone(),
+        };
+        let balance_before = token_account_amount(&hop_output)?;
+        RaydiumAdapter::swap(
+            SwapArgs {
+                clmm_program: clmm_program.clone(),
+                payer: hop_payer.clone(),
+                amm_config: hop.amm_config.clone(),
+                pool_state: hop.pool_state.clone(),
+                input_token_account: hop.input_token_account.clone(),
+                output_token_account: hop_output.clone(),
+                input_vault: hop.input_vault.clone(),
+                output_vault: hop.output_vault.clone(),
+                observation_state: hop.observation_state.clone(),
+                token_program: token_program.clone(),
+                token_program_2022: token_program_2022.clone(),
+                memo_program: memo_program.clone(),
+                input_vault_mint: hop.input_vault_mint.clone(),
+                output_vault_mint: hop.output_vault_mint.clone(),
+                remaining_accounts: hop.remaining_accounts(),
+            },
+            amount_out,
+            0,
+            0,
+            true,
+            signer_seeds
+        )?;
+        amount_out = token_account_amount(&hop_output)?.checked_sub(balance_before).unwrap();
+    }
+
+    require!(amount_out >= minimum_amount_out, ErrorCode::TooLittleOutputReceived);
+    Ok(amount_out)
+}
+
+fn token_account_amount(account_info: &AccountInfo) -> Result<u64> {
+    let data = account_info.try_borrow_data()?;
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+}
+
+/// Reads every hop of `route` into a `SwapRoute` starting from `input_mint` in `pool`, checking
+/// each hop pool trades the output of the previous one and the final hop outputs `output_mint`.
+pub fn load_swap_route<'info>(
+    clmm_program: &Pubkey,
+    pool: &SwapLegPool<'info>,
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    route: &SwapRouteAccounts<'info>
+) -> Result<SwapRoute> {
+    let mut hops = Vec::with_capacity(route.hops.len() + 1);
+    let leg = load_swap_leg(clmm_program, pool, input_mint, route.tick_arrays)?;
+    let mut hop_mint = swap_output_mint(&pool.pool_state, input_mint)?;
+    hops.push(leg);
+
+    for hop in &route.hops {
+        let hop_pool = SwapLegPool::try_from(
+            clmm_program,
+            hop.pool_state,
+            hop.amm_config,
+            hop.observation_state
+        )?;
+        hops.push(load_swap_leg(clmm_program, &hop_pool, hop_mint, hop.tick_arrays)?);
+        hop_mint = swap_output_mint(&hop_pool.pool_state, hop_mint)?;
+    }
+
+    require_keys_eq!(hop_mint, output_mint, ErrorCode::InvalidSwapRoute);
+    Ok(SwapRoute { hops })
+}
+
+fn swap_output_mint(pool_state: &ClmmLoader<PoolState>, input_mint: Pubkey) -> Result<Pubkey> {
+    let pool = pool_state.load()?;
+    if pool.token_mint_0 == input_mint {
+        Ok(pool.token_mint_1)
+    } else if pool.token_mint_1 == input_mint {
+        Ok(pool.token_mint_0)
+    } else {
+        err!(ErrorCode::InvalidSwapRoute)
     }
 }
+
+/// Reads the pool, its fee rate and the initialized ticks of the tick arrays passed for the swap.
+/// `tick_arrays` must have been checked to belong to the pool.
+pub fn load_swap_leg<'info>(
+    clmm_program: &Pubkey,
+    pool: &SwapLegPool<'info>,
+    input_mint: Pubkey,
+    tick_arrays: &'info [AccountInfo<'info>]
+) -> Result<SwapLeg> {
+    let mut ticks = Vec::new();
+    for account_info in tick_arrays {
+        let tick_array_loader = ClmmLoader::<TickArrayState>::try_from(
+            account_info,
+            clmm_program
+        )?;
+        let tick_array = tick_array_loader.load()?;
+        ticks.extend(
+            tick_array.ticks
+                .iter()
+                .filter(|tick| tick.liquidity_gross > 0)
+                .map(|tick| TickLiquidity { tick: tick.tick, liquidity_net: tick.liquidity_net })
+        );
+    }
+
+    let pool_state = pool.pool_state.load()?;
+    Ok(SwapLeg {
+        pool: SwapPoolSnapshot::new(
+            pool_state.sqrt_price_x64,
+            pool_state.tick_current,
+            pool_state.liquidity,
+            pool.amm_config.trade_fee_rate,
+            ticks
+        ),
+        zero_for_one: pool_state.token_mint_0 == input_mint,
+    })
+}
+
+/// Splits `amount` of the stable mint between both swap routes so the swapped tokens mint the most
+/// liquidity in `personal_position` at the current price of `pool_state`.
+pub fn calc_ratio_amounts<'info>(
+    amount: u64,
+    pool_state: &ClmmLoader<'info, PoolState>,
+    personal_position: &PersonalPositionState,
+    route_0: &SwapRoute,
+    route_1: &SwapRoute
+) -> Result<(u64, u64)> {
+    let target = RatioTarget {
+        sqrt_price_x64: pool_state.load()?.sqrt_price_x64,
+        sqrt_price_lower_x64: tick_math::get_sqrt_price_at_tick(
+            personal_position.tick_lower_index
+        )?,
+        sqrt_price_upper_x64: tick_math::get_sqrt_price_at_tick(
+            personal_position.tick_upper_index
+        )?,
+    };
+    let split = quote_optimal_split(amount, route_0, route_1, &target)?;
+    Ok((split.amount_0_in, split.amount_1_in))
+}
+
+/// `Some(true)` when the stable mint is token_0 of the position pool, `Some(false)` when it is
+/// token_1 and `None` when it is neither, in which case both tokens are bought with the stable mint.
+pub fn single_swap_side(pool_position_config: &PoolPositionConfig, stable_mint: Pubkey) -> Option<bool> {
+    if stable_mint == pool_position_config.vault_0_mint_key {
+        Some(true)
+    } else if stable_mint == pool_position_config.vault_1_mint_key {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Splits `amount` of the stable mint into the part swapped inside the target pool and the part
+/// deposited as is, returned as `(amount_swapped, amount_kept)`.
+pub fn calc_single_swap_amounts(
+    amount: u64,
+    personal_position: &PersonalPositionState,
+    pool_leg: &SwapLeg
+) -> Result<(u64, u64)> {
+    let split = quote_single_swap_split(
+        amount,
+        pool_leg,
+        tick_math::get_sqrt_price_at_tick(personal_position.tick_lower_index)?,
+        tick_math::get_sqrt_price_at_tick(personal_position.tick_upper_index)?
+    )?;
+    Ok((split.amount_swapped, split.amount_kept))
+}
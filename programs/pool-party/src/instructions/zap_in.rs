@@ -0,0 +1,732 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::spl_memo;
+use anchor_spl::token::Token;
+use anchor_spl::token_interface::{ Mint, Token2022, TokenAccount, TokenInterface };
+
+use crate::dex::{ DexAdapter, RaydiumAdapter, RaydiumPositionState };
+use crate::instructions::{
+    accrue_investor_fees,
+    calc_ratio_amounts,
+    calc_single_swap_amounts,
+    get_owed_fees,
+    load_swap_leg,
+    load_swap_route,
+    parse_swap_routes,
+    single_swap_side,
+    swap_route,
+    updated_liquidity_personal_position,
+    CollectFeesArgs,
+    IncreaseLiquidityArgs,
+    SwapArgs,
+    SwapLegPool,
+    SwapRouteAccounts,
+    SwapRouteArgs,
+};
+use crate::libraries::{
+    check_liquidity_caps,
+    check_min_deposit,
+    check_min_liquidity_out,
+    check_price_deviation,
+    received_amount,
+    transfer_sol,
+    transfer_token,
+    weighted_deposited_at,
+    RemainingAccountsLayout,
+};
+use crate::errors::ErrorCode;
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig, ProtocolConfig };
+
+/// Accounts for `deposit` + `swap_to_ratio_deposit` + `increase_liquidity` in a single
+/// instruction. Every account except the signer is static per pool position, so the whole
+/// layout can be served from an address lookup table.
+///
+/// Remaining accounts follow `RemainingAccountsLayout`, with the extra hops of both routes at the
+/// end. When the stable mint is one side of the pool a single swap through the pool itself
+/// (passed as `pool_state_0`) is done and only route 0 is read.
+#[derive(Accounts)]
+pub struct ZapInCtx<'info> {
+    #[account(mut)]
+    pub investor: Signer<'info>,
+
+    #[account(constraint = !pool_position_config.paused @ ErrorCode::PositionPaused)]
+    pub pool_position_config: Box<Account<'info, PoolPositionConfig>>,
+
+    #[account(
+        mut,
+        seeds = [PoolPosition::POOL_POSITION_SEED.as_bytes(), pool_position_config.key().as_ref()],
+        bump,
+    )]
+    pub pool_position: AccountLoader<'info, PoolPosition>,
+
+    #[account(
+        mut,
+        seeds = [
+            Investor::INVESTOR_SEED.as_bytes(),
+            pool_position_config.key().as_ref(),
+            investor.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub investor_account: AccountLoader<'info, Investor>,
+
+    #[account(
+        init_if_needed,
+        payer = investor,
+        seeds = [
+            Investor::INVESTOR_DEPOSIT_STABLE_TOKEN_ACCOUNT_SEED.as_bytes(),
+            investor_account.key().as_ref(),
+        ],
+        bump,
+        token::mint = pool_vault_deposit_stable_mint,
+        token::authority = investor_deposit_stable_token_account
+    )]
+    pub investor_deposit_stable_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            Investor::INVESTOR_DEPOSIT_TOKEN_0_ACCOUNT_SEED.as_bytes(),
+            investor_account.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub investor_deposit_token_0_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            Investor::INVESTOR_DEPOSIT_TOKEN_1_ACCOUNT_SEED.as_bytes(),
+            investor_account.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub investor_deposit_token_1_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [PoolPosition::POOL_POSITION_VAULT_0_SEED.as_bytes(), pool_position.key().as_ref()],
+        bump,
+    )]
+    pub pool_position_vault_0_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [PoolPosition::POOL_POSITION_VAULT_1_SEED.as_bytes(), pool_position.key().as_ref()],
+        bump,
+    )]
+    pub pool_position_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PoolPosition::POOL_POSITION_FEES_VAULT_0_SEED.as_bytes(),
+            pool_position.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub fees_vault_0_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [
+            PoolPosition::POOL_POSITION_FEES_VAULT_1_SEED.as_bytes(),
+            pool_position.key().as_ref(),
+        ],
+        bump,
+    )]
+    pub fees_vault_1_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [ProtocolConfig::PROTOCOL_CONFIG_SEED.as_bytes()],
+        bump = protocol_config.bump,
+    )]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+
+    /// CHECK: a CLMM program of the protocol config allowlist
+    #[account(
+        executable,
+        constraint = protocol_config.is_clmm_program(clmm_program.key)
+            @ ErrorCode::ClmmProgramNotAllowed
+    )]
+    pub clmm_program: UncheckedAccount<'info>,
+
+    /// The token account for nft
+    #[account(token::token_program = token_program)]
+    pub position_nft_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The pool the position provides liquidity to
+    /// CHECK: a pool of `clmm_program`, read by `RaydiumPositionState`
+    #[account(mut, address = pool_position_config.pool_key)]
+    pub pool_state: UncheckedAccount<'info>,
+
+    /// CHECK: a protocol position of `pool_state`, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub protocol_position: UncheckedAccount<'info>,
+
+    /// Increase liquidity for this position
+    /// CHECK: a personal position of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub personal_position: UncheckedAccount<'info>,
+
+    /// Stores init state for the lower tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+
+    /// Stores init state for the upper tick
+    /// CHECK: a tick array of `pool_state`, read by `RaydiumPositionState`
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    /// The address that holds pool tokens for token_0, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub token_vault_0: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The address that holds pool tokens for token_1, checked by `RaydiumPositionState`
+    #[account(mut)]
+    pub token_vault_1: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The factory state to read protocol fees
+    /// CHECK: the AMM config of `pool_state_0`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub amm_config_0: UncheckedAccount<'info>,
+
+    /// The pool swapping the stable mint into token_0
+    /// CHECK: a pool of `clmm_program`, read by `SwapLegPool`
+    #[account(mut)]
+    pub pool_state_0: UncheckedAccount<'info>,
+
+    /// The program account for the most recent oracle observation
+    /// CHECK: the observation state of `pool_state_0`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub observation_state_0: UncheckedAccount<'info>,
+
+    /// The factory state to read protocol fees
+    /// CHECK: the AMM config of `pool_state_1`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub amm_config_1: UncheckedAccount<'info>,
+
+    /// The pool swapping the stable mint into token_1
+    /// CHECK: a pool of `clmm_program`, read by `SwapLegPool`
+    #[account(mut)]
+    pub pool_state_1: UncheckedAccount<'info>,
+
+    /// The program account for the most recent oracle observation
+    /// CHECK: the observation state of `pool_state_1`, checked by `SwapLegPool`
+    #[account(mut)]
+    pub observation_state_1: UncheckedAccount<'info>,
+
+    /// The vault of `pool_state_0` receiving the stable mint
+    #[account(mut)]
+    pub pool_vault_0_input: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault of `pool_state_1` receiving the stable mint
+    #[account(mut)]
+    pub pool_vault_1_input: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault of `pool_state_0` paying out token_0
+    #[account(mut)]
+    pub pool_vault_token_0_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault of `pool_state_1` paying out token_1
+    #[account(mut)]
+    pub pool_vault_token_1_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The mint of the deposited stable token
+    #[account(mint::token_program = token_program)]
+    pub pool_vault_deposit_stable_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of token vault 0
+    #[account(address = token_vault_0.mint, mint::token_program = token_program_0)]
+    pub pool_vault_token_0_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of token vault 1
+    #[account(address = token_vault_1.mint, mint::token_program = token_program_1)]
+    pub pool_vault_token_1_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// SPL program for token transfers
+    pub token_program: Program<'info, Token>,
+
+    /// SPL program 2022 for token transfers
+    pub token_program_2022: Program<'info, Token2022>,
+
+    /// Token program of the mint of token vault 0, legacy or Token-2022
+    pub token_program_0: Interface<'info, TokenInterface>,
+
+    /// Token program of the mint of token vault 1
+    pub token_program_1: Interface<'info, TokenInterface>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// memo program
+    /// CHECK:
+    #[account(address = spl_memo::id())]
+    pub memo_program: UncheckedAccount<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ZapInCtx<'info> {
+    pub fn zap_in<'a, 'b, 'c: 'info>(
+        &mut self,
+        amount: u64,
+        route_0: SwapRouteArgs,
+        route_1: SwapRouteArgs,
+        min_liquidity_out: u128,
+        expected_sqrt_price_x64: u128,
+        max_price_deviation: u16,
+        remaining_accounts_layout: RemainingAccountsLayout,
+        remaining_accounts: &'c [AccountInfo<'info>],
+        bumps: &ZapInCtxBumps
+    ) -> Result<()> {
+        // Positions with ladder ranges deposit and call `increase_liquidity` separately
+        let (position_nft_mint_key, ladder_ranges, pool_position_bump_seed) = {
+            let pool_position = self.pool_position.load()?;
+            (pool_position.position_nft_mint_key, pool_position.ladder_ranges, pool_position.bump)
+        };
+        require!(ladder_ranges == 0, ErrorCode::LadderNotSupported);
+        let position = self.raydium_position()?;
+        require_keys_eq!(
+            position.personal_position.nft_mint,
+            position_nft_mint_key,
+            ErrorCode::InvalidClmmAccount
+        );
+        let sqrt_price_x64 = position.pool_state.load()?.sqrt_price_x64;
+        check_price_deviation(sqrt_price_x64, expected_sqrt_price_x64, max_price_deviation)?;
+
+        let stable_mint = self.pool_vault_deposit_stable_mint.key();
+        let single_swap = single_swap_side(&self.pool_position_config, stable_mint);
+
+        if amount > 0 {
+            check_min_deposit(amount, self.pool_position_config.min_deposit)?;
+            transfer_sol(
+                &self.investor.to_account_info(),
+                &self.investor_deposit_stable_token_account.to_account_info(),
+                &self.token_program,
+                &self.system_program,
+                amount
+            )?;
+            self.investor_deposit_stable_token_account.reload()?;
+        }
+
+        let accounts = remaining_accounts_layout.parse(remaining_accounts, self.clmm_program.key)?;
+        match single_swap {
+            Some(stable_is_token_0) => {
+                let routes = parse_swap_routes(&accounts, &[(self.pool_state_0.key(), &route_0)])?;
+                self.single_swap_to_ratio(
+                    stable_is_token_0,
+                    &position,
+                    &routes[0],
+                    route_0.minimum_amount_out,
+                    bumps
+                )?;
+            }
+            None => {
+                let routes = parse_swap_routes(
+                    &accounts,
+                    &[
+                        (self.pool_state_0.key(), &route_0),
+                        (self.pool_state_1.key(), &route_1),
+                    ]
+                )?;
+                self.swap_to_ratio(
+                    &position,
+                    &routes[0],
+                    &routes[1],
+                    route_0.minimum_amount_out,
+                    route_1.minimum_amount_out,
+                    bumps
+                )?;
+            }
+        }
+        let liquidity_accounts = accounts.liquidity_accounts(self.pool_state.key())?;
+
+        self.investor_deposit_token_0_account.reload()?;
+        self.investor_deposit_token_1_account.reload()?;
+        let amount_0 = self.investor_deposit_token_0_account.amount;
+        let amount_1 = self.investor_deposit_token_1_account.amount;
+
+        // With a transfer fee the vaults receive less than the deposits, only that is added
+        let vault_0_before = self.pool_position_vault_0_token_account.amount;
+        let vault_1_before = self.pool_position_vault_1_token_account.amount;
+        self.transfer_tokens(amount_0, amount_1, bumps)?;
+        let amount_0_max = received_amount(
+            &mut self.pool_position_vault_0_token_account,
+            vault_0_before
+        )?;
+        let amount_1_max = received_amount(
+            &mut self.pool_position_vault_1_token_account,
+            vault_1_before
+        )?;
+
+        let (fees_owed0, fees_owed1) = get_owed_fees(
+            position.tick_array_lower.clone(),
+            position.tick_array_upper.clone(),
+            &position.personal_position,
+            &position.pool_state
+        );
+
+        let liquidity = position.personal_position.liquidity;
+
+        let pool_position_config_key = self.pool_position_config.key();
+        let signer_seeds: &[&[&[u8]]] = &[
+            &[
+                PoolPosition::POOL_POSITION_SEED.as_bytes(),
+                pool_position_config_key.as_ref(),
+                &[pool_position_bump_seed],
+            ],
+        ];
+
+        let fees_0_before = self.fees_vault_0_token_account.amount;
+        let fees_1_before = self.fees_vault_1_token_account.amount;
+        if fees_owed0 > 0 || fees_owed1 > 0 {
+            RaydiumAdapter::collect_fees(
+                CollectFeesArgs {
+                    clmm_program: self.clmm_program.to_account_info(),
+                    nft_owner: self.pool_position.to_account_info(),
+                    nft_account: self.position_nft_account.to_account_info(),
+                    pool_state: self.pool_state.to_account_info(),
+                    protocol_position: self.protocol_position.to_account_info(),
+                    personal_position: self.personal_position.to_account_info(),
+                    tick_array_lower: self.tick_array_lower.to_account_info(),
+                    tick_array_upper: self.tick_array_upper.to_account_info(),
+                    recipient_token_account_0: self.fees_vault_0_token_account.to_account_info(),
+                    recipient_token_account_1: self.fees_vault_1_token_account.to_account_info(),
+                    token_vault_0: self.token_vault_0.to_account_info(),
+                    token_vault_1: self.token_vault_1.to_account_info(),
+                    token_program: self.token_program.to_account_info(),
+                    token_program_2022: self.token_program_2022.to_account_info(),
+                    vault_0_mint: self.pool_vault_token_0_mint.to_account_info(),
+                    vault_1_mint: self.pool_vault_token_1_mint.to_account_info(),
+                    memo_program: self.memo_program.to_account_info(),
+                    remaining_accounts: liquidity_accounts.clone(),
+                },
+                signer_seeds
+            )?;
+        }
+        // The investors share the fees the vaults received, net of the transfer fees
+        let fees_received0 = received_amount(&mut self.fees_vault_0_token_account, fees_0_before)?;
+        let fees_received1 = received_amount(&mut self.fees_vault_1_token_account, fees_1_before)?;
+        let (fees_index0, fees_index1) = accrue_investor_fees(
+            &*self.pool_position.load()?,
+            &mut *self.investor_account.load_mut()?,
+            liquidity,
+            fees_received0,
+            fees_received1
+        );
+
+        RaydiumAdapter::increase_liquidity(
+            IncreaseLiquidityArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                nft_owner: self.pool_position.to_account_info(),
+                nft_account: self.position_nft_account.to_account_info(),
+                pool_state: self.pool_state.to_account_info(),
+                protocol_position: self.protocol_position.to_account_info(),
+                personal_position: self.personal_position.to_account_info(),
+                tick_array_lower: self.tick_array_lower.to_account_info(),
+                tick_array_upper: self.tick_array_upper.to_account_info(),
+                token_account_0: self.pool_position_vault_0_token_account.to_account_info(),
+                token_account_1: self.pool_position_vault_1_token_account.to_account_info(),
+                token_vault_0: self.token_vault_0.to_account_info(),
+                token_vault_1: self.token_vault_1.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                vault_0_mint: self.pool_vault_token_0_mint.to_account_info(),
+                vault_1_mint: self.pool_vault_token_1_mint.to_account_info(),
+                remaining_accounts: liquidity_accounts,
+            },
+            0,
+            amount_0_max,
+            amount_1_max,
+            signer_seeds
+        )?;
+
+        let liquidity_after = updated_liquidity_personal_position(
+            self.personal_position.to_account_info()
+        )?;
+
+        let liquidity_delta = liquidity_after.checked_sub(liquidity).unwrap();
+        check_min_liquidity_out(liquidity_delta, min_liquidity_out)?;
+
+        let mut investor_account = self.investor_account.load_mut()?;
+        investor_account.deposited_at = weighted_deposited_at(
+            investor_account.deposited_at,
+            investor_account.liquidity,
+            Clock::get()?.unix_timestamp,
+            liquidity_delta
+        );
+        investor_account.liquidity = investor_account.liquidity
+            .checked_add(liquidity_delta)
+            .unwrap();
+        check_liquidity_caps(
+            investor_account.liquidity,
+            liquidity_after,
+            self.pool_position_config.max_investor_liquidity,
+            self.pool_position_config.max_total_liquidity
+        )?;
+
+        let mut pool_position = self.pool_position.load_mut()?;
+        pool_position.liquidity = liquidity_after;
+        pool_position.fees_index0 = fees_index0;
+        pool_position.fees_index1 = fees_index1;
+
+        Ok(())
+    }
+
+    fn swap_to_ratio(
+        &self,
+        position: &RaydiumPositionState<'info>,
+        route_0: &SwapRouteAccounts<'info>,
+        route_1: &SwapRouteAccounts<'info>,
+        minimum_amount_out_0: u64,
+        minimum_amount_out_1: u64,
+        bumps: &ZapInCtxBumps
+    ) -> Result<()> {
+        let amount = self.investor_deposit_stable_token_account.amount;
+        let stable_mint = self.pool_vault_deposit_stable_mint.key();
+        let clmm_program = self.clmm_program.key;
+        let leg_0 = SwapLegPool::try_from(
+            clmm_program,
+            &self.pool_state_0,
+            &self.amm_config_0,
+            &self.observation_state_0
+        )?;
+        let leg_1 = SwapLegPool::try_from(
+            clmm_program,
+            &self.pool_state_1,
+            &self.amm_config_1,
+            &self.observation_state_1
+        )?;
+        let quote_route_0 = load_swap_route(
+            clmm_program,
+            &leg_0,
+            stable_mint,
+            self.investor_deposit_token_0_account.mint,
+            route_0
+        )?;
+        let quote_route_1 = load_swap_route(
+            clmm_program,
+            &leg_1,
+            stable_mint,
+            self.investor_deposit_token_1_account.mint,
+            route_1
+        )?;
+        let (amount_a, amount_b) = calc_ratio_amounts(
+            amount,
+            &position.pool_state,
+            &position.personal_position,
+            &quote_route_0,
+            &quote_route_1
+        )?;
+
+        let investor_account_key = self.investor_account.key();
+        let signer_seeds: &[&[&[u8]]] = &[
+            &[
+                Investor::INVESTOR_DEPOSIT_STABLE_TOKEN_ACCOUNT_SEED.as_bytes(),
+                investor_account_key.as_ref(),
+                &[bumps.investor_deposit_stable_token_account],
+            ],
+        ];
+
+        swap_route(
+            SwapArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                payer: self.investor_deposit_stable_token_account.to_account_info(),
+                amm_config: self.amm_config_0.to_account_info(),
+                pool_state: self.pool_state_0.to_account_info(),
+                input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
+                output_token_account: self.investor_deposit_token_0_account.to_account_info(),
+                input_vault: self.pool_vault_0_input.to_account_info(),
+                output_vault: self.pool_vault_token_0_account.to_account_info(),
+                observation_state: self.observation_state_0.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
+                output_vault_mint: self.pool_vault_token_0_mint.to_account_info(),
+                remaining_accounts: route_0.remaining_accounts.clone(),
+            },
+            &route_0.hops,
+            self.investor.to_account_info(),
+            amount_a,
+            minimum_amount_out_0,
+            signer_seeds
+        )?;
+
+        swap_route(
+            SwapArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                payer: self.investor_deposit_stable_token_account.to_account_info(),
+                amm_config: self.amm_config_1.to_account_info(),
+                pool_state: self.pool_state_1.to_account_info(),
+                input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
+                output_token_account: self.investor_deposit_token_1_account.to_account_info(),
+                input_vault: self.pool_vault_1_input.to_account_info(),
+                output_vault: self.pool_vault_token_1_account.to_account_info(),
+                observation_state: self.observation_state_1.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
+                output_vault_mint: self.pool_vault_token_1_mint.to_account_info(),
+                remaining_accounts: route_1.remaining_accounts.clone(),
+            },
+            &route_1.hops,
+            self.investor.to_account_info(),
+            amount_b,
+            minimum_amount_out_1,
+            signer_seeds
+        )?;
+        Ok(())
+    }
+
+    fn single_swap_to_ratio(
+        &self,
+        stable_is_token_0: bool,
+        position: &RaydiumPositionState<'info>,
+        route: &SwapRouteAccounts<'info>,
+        minimum_amount_out: u64,
+        bumps: &ZapInCtxBumps
+    ) -> Result<()> {
+        require_keys_eq!(self.pool_state_0.key(), self.pool_state.key(), ErrorCode::InvalidSwapPool);
+        require!(route.hops.is_empty(), ErrorCode::InvalidSwapRoute);
+
+        let amount = self.investor_deposit_stable_token_account.amount;
+        let stable_mint = self.pool_vault_deposit_stable_mint.key();
+        let pool = SwapLegPool::try_from(
+            self.clmm_program.key,
+            &self.pool_state_0,
+            &self.amm_config_0,
+            &self.observation_state_0
+        )?;
+        let leg = load_swap_leg(self.clmm_program.key, &pool, stable_mint, route.tick_arrays)?;
+        let (amount_swapped, amount_kept) = calc_single_swap_amounts(
+            amount,
+            &position.personal_position,
+            &leg
+        )?;
+
+        let (kept_token_account, output_token_account, output_vault_mint) = if stable_is_token_0 {
+            (
+                &self.investor_deposit_token_0_account,
+                &self.investor_deposit_token_1_account,
+                &self.pool_vault_token_1_mint,
+            )
+        } else {
+            (
+                &self.investor_deposit_token_1_account,
+                &self.investor_deposit_token_0_account,
+                &self.pool_vault_token_0_mint,
+            )
+        };
+
+        let investor_account_key = self.investor_account.key();
+        let signer_seeds: &[&[&[u8]]] = &[
+            &[
+                Investor::INVESTOR_DEPOSIT_STABLE_TOKEN_ACCOUNT_SEED.as_bytes(),
+                investor_account_key.as_ref(),
+                &[bumps.investor_deposit_stable_token_account],
+            ],
+        ];
+
+        swap_route(
+            SwapArgs {
+                clmm_program: self.clmm_program.to_account_info(),
+                payer: self.investor_deposit_stable_token_account.to_account_info(),
+                amm_config: self.amm_config_0.to_account_info(),
+                pool_state: self.pool_state_0.to_account_info(),
+                input_token_account: self.investor_deposit_stable_token_account.to_account_info(),
+                output_token_account: output_token_account.to_account_info(),
+                input_vault: self.pool_vault_0_input.to_account_info(),
+                output_vault: self.pool_vault_token_0_account.to_account_info(),
+                observation_state: self.observation_state_0.to_account_info(),
+                token_program: self.token_program.to_account_info(),
+                token_program_2022: self.token_program_2022.to_account_info(),
+                memo_program: self.memo_program.to_account_info(),
+                input_vault_mint: self.pool_vault_deposit_stable_mint.to_account_info(),
+                output_vault_mint: output_vault_mint.to_account_info(),
+                remaining_accounts: route.remaining_accounts.clone(),
+            },
+            &[],
+            self.investor.to_account_info(),
+            amount_swapped,
+            minimum_amount_out,
+            signer_seeds
+        )?;
+
+        if amount_kept > 0 {
+            transfer_token(
+                &self.investor_deposit_stable_token_account,
+                kept_token_account,
+                &amount_kept,
+                &self.pool_vault_deposit_stable_mint,
+                &self.investor_deposit_stable_token_account.to_account_info(),
+                &self.token_program,
+                Some(signer_seeds)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The Raydium position of the pool position, checked against the accounts of its CPIs
+    fn raydium_position(&self) -> Result<RaydiumPositionState<'info>> {
+        let position = RaydiumPositionState::try_from(
+            self.clmm_program.key,
+            &self.pool_state,
+            &self.personal_position,
+            &self.tick_array_lower,
+            &self.tick_array_upper
+        )?;
+        position.check_cpi_accounts(
+            self.clmm_program.key,
+            &self.protocol_position,
+            &self.position_nft_account,
+            self.token_vault_0.key(),
+            self.token_vault_1.key()
+        )?;
+        Ok(position)
+    }
+
+    fn transfer_tokens(
+        &self,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        bumps: &ZapInCtxBumps
+    ) -> Result<()> {
+        let investor_account_key = self.investor_account.key();
+        let a_seeds: &[&[&[u8]]] = &[
+            &[
+                Investor::INVESTOR_DEPOSIT_TOKEN_0_ACCOUNT_SEED.as_bytes(),
+                investor_account_key.as_ref(),
+                &[bumps.investor_deposit_token_0_account],
+            ],
+        ];
+        transfer_token(
+            &self.investor_deposit_token_0_account,
+            &self.pool_position_vault_0_token_account,
+            &amount_0_max,
+            &self.pool_vault_token_0_mint,
+            &self.investor_deposit_token_0_account.to_account_info(),
+            &self.token_program_0,
+            Some(a_seeds)
+        )?;
+        let b_seeds: &[&[&[u8]]] = &[
+            &[
+                Investor::INVESTOR_DEPOSIT_TOKEN_1_ACCOUNT_SEED.as_bytes(),
+                investor_account_key.as_ref(),
+                &[bumps.investor_deposit_token_1_account],
+            ],
+        ];
+        transfer_token(
+            &self.investor_deposit_token_1_account,
+            &self.pool_position_vault_1_token_account,
+            &amount_1_max,
+            &self.pool_vault_token_1_mint,
+            &self.investor_deposit_token_1_account.to_account_info(),
+            &self.token_program_1,
+            Some(b_seeds)
+        )
+    }
+}
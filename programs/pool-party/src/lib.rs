@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 
 pub mod constants;
+pub mod dex;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod state;
 pub mod libraries;
@@ -15,6 +17,10 @@ use constants::*;
 use errors::*;
 #[allow(unused_imports)]
 use state::*;
+#[allow(unused_imports)]
+use libraries::{ PriceSource, RemainingAccountsLayout };
+#[allow(unused_imports)]
+use dex::Dex;
 
 declare_id!("3inmw7qcywQirQoNSL54MhqoG7CJ58ZYwVCYSmC1TTB4");
 
@@ -22,6 +28,8 @@ declare_id!("3inmw7qcywQirQoNSL54MhqoG7CJ58ZYwVCYSmC1TTB4");
 pub mod pool_party {
     use super::*;
 
+    /// Creates the position config, the position and the manager account, for a position whose
+    /// liquidity `dex` will hold. Only Raydium is supported for now.
     pub fn create_position<'info>(
         ctx: Context<CreatePositionCtx<'info>>,
         name: String,
@@ -31,7 +39,8 @@ pub mod pool_party {
         token_vault_0_key: Pubkey,
         token_vault_1_key: Pubkey,
         vault_0_mint_key: Pubkey,
-        vault_1_mint_key: Pubkey
+        vault_1_mint_key: Pubkey,
+        dex: Dex
     ) -> Result<()> {
         ctx.accounts.create_position(
             name,
@@ -42,6 +51,7 @@ pub mod pool_party {
             token_vault_1_key,
             vault_0_mint_key,
             vault_1_mint_key,
+            dex,
             &ctx.bumps
         )
     }
@@ -59,7 +69,8 @@ pub mod pool_party {
         tick_lower_index: i32,
         tick_upper_index: i32,
         tick_array_lower_start_index: i32,
-        tick_array_upper_start_index: i32
+        tick_array_upper_start_index: i32,
+        remaining_accounts_layout: RemainingAccountsLayout
     ) -> Result<()> {
         ctx.accounts.open_position(
             amount_0_max,
@@ -68,14 +79,17 @@ pub mod pool_party {
             tick_upper_index,
             tick_array_lower_start_index,
             tick_array_upper_start_index,
+            remaining_accounts_layout,
             ctx.remaining_accounts
         )
     }
 
+    /// `allowlist_proof` is the Merkle proof of the investor when the position has an allowlist
     pub fn create_investor_position<'a, 'b, 'c: 'info, 'info>(
-        ctx: Context<'a, 'b, 'c, 'info, CreateInvestorPositionCtx<'info>>
+        ctx: Context<'a, 'b, 'c, 'info, CreateInvestorPositionCtx<'info>>,
+        allowlist_proof: Vec<[u8; 32]>
     ) -> Result<()> {
-        ctx.accounts.create_investor_position(&ctx.bumps)
+        ctx.accounts.create_investor_position(allowlist_proof, &ctx.bumps)
     }
 
     pub fn deposit<'a, 'b, 'c: 'info, 'info>(
@@ -87,28 +101,186 @@ pub mod pool_party {
 
     pub fn swap_to_ratio_deposit<'a, 'b, 'c: 'info, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, SwapToRatioDepositCtx<'info>>,
-        other_amount_threshold: u64,
-        sqrt_price_limit_x64: u128,
-        is_base_input: bool
+        route_0: SwapRouteArgs,
+        route_1: SwapRouteArgs,
+        remaining_accounts_layout: RemainingAccountsLayout
     ) -> Result<()> {
         ctx.accounts.swap_to_ratio_deposit(
-            other_amount_threshold,
-            sqrt_price_limit_x64,
-            is_base_input,
+            route_0,
+            route_1,
+            remaining_accounts_layout,
             ctx.remaining_accounts,
             &ctx.bumps
         )
     }
 
     pub fn increase_liquidity<'a, 'b, 'c: 'info, 'info>(
-        ctx: Context<'a, 'b, 'c, 'info, IncreaseLiquidityCtx<'info>>
+        ctx: Context<'a, 'b, 'c, 'info, IncreaseLiquidityCtx<'info>>,
+        min_liquidity_out: u128,
+        expected_sqrt_price_x64: u128,
+        max_price_deviation: u16,
+        remaining_accounts_layout: RemainingAccountsLayout
     ) -> Result<()> {
-        ctx.accounts.increase_liquidity(ctx.remaining_accounts, &ctx.bumps)
+        ctx.accounts.increase_liquidity(
+            min_liquidity_out,
+            expected_sqrt_price_x64,
+            max_price_deviation,
+            remaining_accounts_layout,
+            ctx.remaining_accounts,
+            &ctx.bumps
+        )
+    }
+
+    /// Not available on positions with ladder ranges, which fails with `LadderNotSupported`: their
+    /// investors `deposit`, `swap_to_ratio_deposit` and `increase_liquidity` separately
+    pub fn zap_in<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, ZapInCtx<'info>>,
+        amount: u64,
+        route_0: SwapRouteArgs,
+        route_1: SwapRouteArgs,
+        min_liquidity_out: u128,
+        expected_sqrt_price_x64: u128,
+        max_price_deviation: u16,
+        remaining_accounts_layout: RemainingAccountsLayout
+    ) -> Result<()> {
+        ctx.accounts.zap_in(
+            amount,
+            route_0,
+            route_1,
+            min_liquidity_out,
+            expected_sqrt_price_x64,
+            max_price_deviation,
+            remaining_accounts_layout,
+            ctx.remaining_accounts,
+            &ctx.bumps
+        )
     }
 
     pub fn collect_fees<'a, 'b, 'c: 'info, 'info>(
-        ctx: Context<'a, 'b, 'c, 'info, CollectFeesCtx<'info>>
+        ctx: Context<'a, 'b, 'c, 'info, CollectFeesCtx<'info>>,
+        remaining_accounts_layout: RemainingAccountsLayout
+    ) -> Result<()> {
+        ctx.accounts.collect_fees(remaining_accounts_layout, ctx.remaining_accounts, &ctx.bumps)
+    }
+
+    /// Removes liquidity of the investor and pays out its tokens, subject to the lockup of the
+    /// position
+    pub fn decrease_liquidity<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, DecreaseLiquidityCtx<'info>>,
+        liquidity: u128,
+        amount_0_min: u64,
+        amount_1_min: u64,
+        remaining_accounts_layout: RemainingAccountsLayout
+    ) -> Result<()> {
+        ctx.accounts.decrease_liquidity(
+            liquidity,
+            amount_0_min,
+            amount_1_min,
+            remaining_accounts_layout,
+            ctx.remaining_accounts
+        )
+    }
+
+    /// Values the position of an investor without writing any account. The `InvestorValue` is
+    /// set as return data, for clients to read it with `simulateTransaction`.
+    pub fn get_investor_value<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, GetInvestorValueCtx<'info>>
+    ) -> Result<InvestorValue> {
+        ctx.accounts.get_investor_value(ctx.remaining_accounts)
+    }
+
+    /// Records the NAV of the pool position in the stable mint, priced at spot or TWAP
+    pub fn snapshot_nav<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, SnapshotNavCtx<'info>>,
+        price_source: PriceSource
+    ) -> Result<NavSnapshot> {
+        ctx.accounts.snapshot_nav(price_source, ctx.remaining_accounts)
+    }
+
+    /// Makes the proposed manager the manager of the position
+    pub fn accept_manager(ctx: Context<AcceptManagerCtx>) -> Result<()> {
+        ctx.accounts.accept_manager(&ctx.bumps)
+    }
+
+    /// Pauses or unpauses the deposits of the position, signed by the guardian
+    pub fn set_paused(ctx: Context<SetPausedCtx>, paused: bool) -> Result<()> {
+        ctx.accounts.set_paused(paused)
+    }
+
+    /// Queues a change of the position config, executable once the timelock has elapsed
+    pub fn queue_config_change(
+        ctx: Context<QueueConfigChangeCtx>,
+        change: ConfigChange
+    ) -> Result<()> {
+        ctx.accounts.queue_config_change(change, &ctx.bumps)
+    }
+
+    /// Applies a queued config change whose timelock has elapsed
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChangeCtx>) -> Result<()> {
+        ctx.accounts.execute_config_change()
+    }
+
+    /// Drops a queued config change, signed by its proposer or the guardian
+    pub fn cancel_config_change(ctx: Context<CancelConfigChangeCtx>) -> Result<()> {
+        ctx.accounts.cancel_config_change()
+    }
+
+    /// Opens a range of the ladder of the position holding `weight_bps` of the core liquidity,
+    /// while the manager holds all the liquidity
+    pub fn open_ladder_range<'a, 'b, 'c: 'info, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, OpenLadderRangeCtx<'info>>,
+        tick_lower_index: i32,
+        tick_upper_index: i32,
+        tick_array_lower_start_index: i32,
+        tick_array_upper_start_index: i32,
+        weight_bps: u32,
+        amount_0_max: u64,
+        amount_1_max: u64,
+        remaining_accounts_layout: RemainingAccountsLayout
+    ) -> Result<()> {
+        ctx.accounts.open_ladder_range(
+            tick_lower_index,
+            tick_upper_index,
+            tick_array_lower_start_index,
+            tick_array_upper_start_index,
+            weight_bps,
+            amount_0_max,
+            amount_1_max,
+            remaining_accounts_layout,
+            ctx.remaining_accounts,
+            &ctx.bumps
+        )
+    }
+
+    /// Creates the protocol config with the CLMM programs positions may use, signed by the
+    /// upgrade authority of the program
+    pub fn initialize_protocol_config(
+        ctx: Context<InitializeProtocolConfigCtx>,
+        clmm_programs: Vec<Pubkey>
     ) -> Result<()> {
-        ctx.accounts.collect_fees(ctx.remaining_accounts, &ctx.bumps)
+        ctx.accounts.initialize_protocol_config(clmm_programs, &ctx.bumps)
+    }
+
+    /// Replaces the CLMM programs allowlist of the protocol config, signed by its admin
+    pub fn set_clmm_programs(
+        ctx: Context<SetClmmProgramsCtx>,
+        clmm_programs: Vec<Pubkey>
+    ) -> Result<()> {
+        ctx.accounts.set_clmm_programs(clmm_programs)
+    }
+
+    /// Upgrades an investor account to the current layout, growing it at the expense of `payer`
+    pub fn migrate_investor(ctx: Context<MigrateInvestorCtx>) -> Result<()> {
+        ctx.accounts.migrate_investor()
+    }
+
+    /// Upgrades a pool position to the current layout, growing it at the expense of `payer`
+    pub fn migrate_pool_position(ctx: Context<MigratePoolPositionCtx>) -> Result<()> {
+        ctx.accounts.migrate_pool_position()
+    }
+
+    /// Upgrades a position config to the current layout, growing it at the expense of `payer`
+    pub fn migrate_pool_position_config(ctx: Context<MigratePoolPositionConfigCtx>) -> Result<()> {
+        ctx.accounts.migrate_pool_position_config()
     }
 }
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+/// Prefixes of the hashed leaves and nodes, so an inner node can never pass for a leaf
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf of an allowlisted investor in the allowlist Merkle tree
+pub fn allowlist_leaf(investor: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, investor.as_ref()]).to_bytes()
+}
+
+/// Parent of two nodes. The pair is sorted, so proofs do not need the side of each sibling.
+pub fn allowlist_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Whether `proof`, the siblings from the leaf up, links the leaf of `investor` to `root`
+pub fn verify_allowlist_proof(root: &[u8; 32], proof: &[[u8; 32]], investor: &Pubkey) -> bool {
+    let node = proof
+        .iter()
+        .fold(allowlist_leaf(investor), |node, sibling| allowlist_node(&node, sibling));
+    node == *root
+}
+
+#[cfg(test)]
+mod allowlist_test {
+    use super::*;
+
+    #[test]
+    fn single_investor_root_is_its_leaf() {
+        let investor = Pubkey::new_unique();
+        let root = allowlist_leaf(&investor);
+        assert!(verify_allowlist_proof(&root, &[], &investor));
+        assert!(!verify_allowlist_proof(&root, &[], &Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn proof_verifies_either_side() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let ab = allowlist_node(&allowlist_leaf(&a), &allowlist_leaf(&b));
+        let root = allowlist_node(&ab, &allowlist_leaf(&c));
+
+        assert!(verify_allowlist_proof(&root, &[allowlist_leaf(&b), allowlist_leaf(&c)], &a));
+        assert!(verify_allowlist_proof(&root, &[allowlist_leaf(&a), allowlist_leaf(&c)], &b));
+        assert!(verify_allowlist_proof(&root, &[ab], &c));
+        assert!(!verify_allowlist_proof(&root, &[ab], &a));
+    }
+
+    #[test]
+    fn inner_node_is_not_a_leaf() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let ab = allowlist_node(&allowlist_leaf(&a), &allowlist_leaf(&b));
+        assert_ne!(ab, allowlist_leaf(&Pubkey::new_from_array(ab)));
+    }
+}
@@ -0,0 +1,138 @@
+//! Accounts of the Raydium CLMM program a position uses.
+//!
+//! The account types of `raydium_clmm_cpi` only accept accounts owned by the program id the crate
+//! was built for, while the CLMM program is any program of the `ProtocolConfig` allowlist. Its
+//! states are read with `ClmmLoader` and `ClmmAccount` instead, which check the owner against the
+//! CLMM program passed to the instruction, and the discriminator of the state.
+
+use std::cell::Ref;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::Deref;
+
+use anchor_lang::{ __private::bytemuck, prelude::*, ZeroCopy };
+
+use crate::errors::ErrorCode;
+
+/// Zero-copy state of the CLMM program, the counterpart of `AccountLoader`
+#[derive(Clone)]
+pub struct ClmmLoader<'info, T: ZeroCopy> {
+    info: AccountInfo<'info>,
+    phantom: PhantomData<T>,
+}
+
+impl<'info, T: ZeroCopy> ClmmLoader<'info, T> {
+    /// Reads `info` as a `T` of `clmm_program`
+    pub fn try_from(info: &AccountInfo<'info>, clmm_program: &Pubkey) -> Result<Self> {
+        require_keys_eq!(*info.owner, *clmm_program, ErrorCode::InvalidClmmAccount);
+        let data = info.try_borrow_data()?;
+        require!(
+            data.len() >= T::DISCRIMINATOR.len() + size_of::<T>() &&
+                data.starts_with(T::DISCRIMINATOR),
+            ErrorCode::InvalidClmmAccount
+        );
+        Ok(ClmmLoader { info: info.clone(), phantom: PhantomData })
+    }
+
+    pub fn load(&self) -> Result<Ref<'_, T>> {
+        let data = self.info.try_borrow_data()?;
+        let offset = T::DISCRIMINATOR.len();
+        Ok(Ref::map(data, |data| bytemuck::from_bytes(&data[offset..offset + size_of::<T>()])))
+    }
+}
+
+impl<'info, T: ZeroCopy> AsRef<AccountInfo<'info>> for ClmmLoader<'info, T> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        &self.info
+    }
+}
+
+impl<'info, T: ZeroCopy> Key for ClmmLoader<'info, T> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
+
+/// Deserialized state of the CLMM program, the counterpart of `Account`
+#[derive(Clone)]
+pub struct ClmmAccount<'info, T: AccountDeserialize + Clone> {
+    info: AccountInfo<'info>,
+    account: T,
+}
+
+impl<'info, T: AccountDeserialize + Clone> ClmmAccount<'info, T> {
+    /// Deserializes `info` as a `T` of `clmm_program`
+    pub fn try_from(info: &AccountInfo<'info>, clmm_program: &Pubkey) -> Result<Self> {
+        require_keys_eq!(*info.owner, *clmm_program, ErrorCode::InvalidClmmAccount);
+        let account = T::try_deserialize(&mut &info.try_borrow_data()?[..])
+            .map_err(|_| error!(ErrorCode::InvalidClmmAccount))?;
+        Ok(ClmmAccount { info: info.clone(), account })
+    }
+}
+
+impl<'info, T: AccountDeserialize + Clone> Deref for ClmmAccount<'info, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.account
+    }
+}
+
+impl<'info, T: AccountDeserialize + Clone> AsRef<AccountInfo<'info>> for ClmmAccount<'info, T> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        &self.info
+    }
+}
+
+impl<'info, T: AccountDeserialize + Clone> Key for ClmmAccount<'info, T> {
+    fn key(&self) -> Pubkey {
+        *self.info.key
+    }
+}
+
+#[cfg(test)]
+mod clmm_accounts_test {
+    use anchor_lang::Discriminator;
+    use raydium_clmm_cpi::states::TickArrayState;
+
+    use super::*;
+
+    fn tick_array_data(pool_id: Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; 8 + size_of::<TickArrayState>()];
+        data[..8].copy_from_slice(TickArrayState::DISCRIMINATOR);
+        data[8..40].copy_from_slice(pool_id.as_ref());
+        data
+    }
+
+    #[test]
+    fn loader_accepts_any_given_clmm_program() {
+        let (key, pool_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let fork = Pubkey::new_unique();
+        let mut lamports = 1;
+        let mut data = tick_array_data(pool_id);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &fork, false, 0);
+
+        let tick_array = ClmmLoader::<TickArrayState>::try_from(&info, &fork).unwrap();
+        assert_eq!(tick_array.key(), key);
+        assert_eq!(tick_array.load().unwrap().pool_id, pool_id);
+
+        // owned by a program other than the one given
+        assert!(ClmmLoader::<TickArrayState>::try_from(&info, &raydium_clmm_cpi::ID).is_err());
+    }
+
+    #[test]
+    fn loader_rejects_other_states() {
+        let (key, fork) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut lamports = 1;
+        let mut data = tick_array_data(Pubkey::new_unique());
+        data[..8].copy_from_slice(&[1u8; 8]);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &fork, false, 0);
+        assert!(ClmmLoader::<TickArrayState>::try_from(&info, &fork).is_err());
+
+        let mut data = tick_array_data(Pubkey::new_unique());
+        data.truncate(40);
+        let mut lamports = 1;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &fork, false, 0);
+        assert!(ClmmLoader::<TickArrayState>::try_from(&info, &fork).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+
+/// Fails if `amount` deposited in the stable mint is below `min_deposit`.
+pub fn check_min_deposit(amount: u64, min_deposit: u64) -> Result<()> {
+    require!(amount >= min_deposit, ErrorCode::DepositBelowMinimum);
+    Ok(())
+}
+
+/// Fails if the liquidity of the investor or of the whole position after an increase is above
+/// its cap. A cap of zero leaves the liquidity uncapped.
+pub fn check_liquidity_caps(
+    investor_liquidity: u128,
+    total_liquidity: u128,
+    max_investor_liquidity: u128,
+    max_total_liquidity: u128
+) -> Result<()> {
+    require!(
+        max_investor_liquidity == 0 || investor_liquidity <= max_investor_liquidity,
+        ErrorCode::InvestorLiquidityCapExceeded
+    );
+    require!(
+        max_total_liquidity == 0 || total_liquidity <= max_total_liquidity,
+        ErrorCode::TotalLiquidityCapExceeded
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod deposit_limits_test {
+    use super::*;
+
+    #[test]
+    fn check_min_deposit_bounds() {
+        assert!(check_min_deposit(10, 10).is_ok());
+        assert!(check_min_deposit(9, 10).is_err());
+        assert!(check_min_deposit(0, 0).is_ok());
+    }
+
+    #[test]
+    fn check_liquidity_caps_bounds() {
+        assert!(check_liquidity_caps(10, 100, 10, 100).is_ok());
+        assert_eq!(
+            check_liquidity_caps(11, 100, 10, 100).unwrap_err(),
+            ErrorCode::InvestorLiquidityCapExceeded.into()
+        );
+        assert_eq!(
+            check_liquidity_caps(10, 101, 10, 100).unwrap_err(),
+            ErrorCode::TotalLiquidityCapExceeded.into()
+        );
+    }
+
+    #[test]
+    fn zero_caps_are_uncapped() {
+        assert!(check_liquidity_caps(u128::MAX, u128::MAX, 0, 0).is_ok());
+        assert!(check_liquidity_caps(u128::MAX, 100, 0, 100).is_ok());
+    }
+}
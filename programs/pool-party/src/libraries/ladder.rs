@@ -0,0 +1,356 @@
+//! Ladder ranges of a pool position.
+//!
+//! A ladder range is a Raydium position besides the core range of the pool position, holding
+//! `weight_bps` of the core liquidity. Instructions moving liquidity or fees read every range of
+//! the position from the accounts following the `RemainingAccountsLayout` groups, as consecutive
+//! groups of `LADDER_RANGE_ACCOUNTS_LEN` accounts in the order of their index:
+//! `[ladder range] [position nft account] [protocol position] [personal position]
+//! [tick array lower] [tick array upper]`.
+
+use anchor_lang::prelude::*;
+use raydium_clmm_cpi::states::{ PersonalPositionState, PoolState, TickArrayState };
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::dex::{ DexAdapter, RaydiumAdapter };
+use crate::errors::ErrorCode;
+use crate::instructions::{
+    get_owed_fees,
+    updated_liquidity_personal_position,
+    CollectFeesArgs,
+    IncreaseLiquidityArgs,
+};
+use crate::libraries::{
+    check_tick_arrays,
+    liquidity_math,
+    ClmmAccount,
+    ClmmLoader,
+    tick_math,
+    MulDiv,
+    U128,
+};
+use crate::state::LadderRange;
+
+/// Accounts passed for each ladder range
+pub const LADDER_RANGE_ACCOUNTS_LEN: usize = 6;
+
+pub struct LadderRangeAccounts<'info> {
+    pub ladder_range: Box<Account<'info, LadderRange>>,
+    pub position_nft_account: AccountInfo<'info>,
+    pub protocol_position: AccountInfo<'info>,
+    pub personal_position: Box<ClmmAccount<'info, PersonalPositionState>>,
+    pub tick_array_lower: ClmmLoader<'info, TickArrayState>,
+    pub tick_array_upper: ClmmLoader<'info, TickArrayState>,
+}
+
+/// A range of a ladder, by its price bounds and weight
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LadderLeg {
+    pub sqrt_price_lower_x64: u128,
+    pub sqrt_price_upper_x64: u128,
+    pub weight_bps: u32,
+}
+
+/// Parses the `ladder_ranges` ranges of the pool position `pool_position_key` on `pool_id`, a
+/// pool of `clmm_program`. Every range must be passed, so that none is left out of the fees and
+/// liquidity.
+pub fn parse_ladder_ranges<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    clmm_program: &Pubkey,
+    pool_position_key: Pubkey,
+    ladder_ranges: u8,
+    pool_id: Pubkey
+) -> Result<Vec<LadderRangeAccounts<'info>>> {
+    require!(
+        accounts.len() == (ladder_ranges as usize) * LADDER_RANGE_ACCOUNTS_LEN,
+        ErrorCode::InvalidRemainingAccounts
+    );
+    accounts
+        .chunks(LADDER_RANGE_ACCOUNTS_LEN)
+        .enumerate()
+        .map(|(index, group)| {
+            let ladder_range = Box::new(Account::<LadderRange>::try_from(&group[0])?);
+            require_keys_eq!(
+                ladder_range.pool_position_key,
+                pool_position_key,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            require!(
+                (ladder_range.index as usize) == index,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            let personal_position = Box::new(
+                ClmmAccount::<PersonalPositionState>::try_from(&group[3], clmm_program)?
+            );
+            require_keys_eq!(
+                personal_position.nft_mint,
+                ladder_range.position_nft_mint_key,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            require_keys_eq!(
+                personal_position.pool_id,
+                pool_id,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            check_tick_arrays(&group[4..], clmm_program, pool_id)?;
+
+            Ok(LadderRangeAccounts {
+                ladder_range,
+                position_nft_account: group[1].clone(),
+                protocol_position: group[2].clone(),
+                personal_position,
+                tick_array_lower: ClmmLoader::try_from(&group[4], clmm_program)?,
+                tick_array_upper: ClmmLoader::try_from(&group[5], clmm_program)?,
+            })
+        })
+        .collect()
+}
+
+impl<'info> LadderRangeAccounts<'info> {
+    pub fn leg(&self) -> Result<LadderLeg> {
+        Ok(LadderLeg {
+            sqrt_price_lower_x64: tick_math::get_sqrt_price_at_tick(
+                self.ladder_range.tick_lower_index
+            )?,
+            sqrt_price_upper_x64: tick_math::get_sqrt_price_at_tick(
+                self.ladder_range.tick_upper_index
+            )?,
+            weight_bps: self.ladder_range.weight_bps,
+        })
+    }
+
+    /// Token amounts backing `liquidity` in the range at `sqrt_price_x64`, rounded down
+    pub fn amounts_for_liquidity(
+        &self,
+        sqrt_price_x64: u128,
+        liquidity: u128
+    ) -> Result<(u64, u64)> {
+        let leg = self.leg()?;
+        liquidity_math::get_amounts_for_liquidity(
+            sqrt_price_x64,
+            leg.sqrt_price_lower_x64,
+            leg.sqrt_price_upper_x64,
+            liquidity,
+            false
+        )
+    }
+
+    /// Liquidity of the range as written by the latest CPI
+    pub fn updated_liquidity(&self) -> Result<u128> {
+        updated_liquidity_personal_position(self.personal_position.to_account_info())
+    }
+
+    /// The accounts of `core` with the Raydium position of the range
+    pub fn collect_fees_args(&self, core: &CollectFeesArgs<'info>) -> CollectFeesArgs<'info> {
+        CollectFeesArgs {
+            nft_account: self.position_nft_account.clone(),
+            protocol_position: self.protocol_position.clone(),
+            personal_position: self.personal_position.to_account_info(),
+            tick_array_lower: self.tick_array_lower.to_account_info(),
+            tick_array_upper: self.tick_array_upper.to_account_info(),
+            ..core.clone()
+        }
+    }
+
+    /// The accounts of `core` with the Raydium position of the range
+    pub fn increase_liquidity_args(
+        &self,
+        core: &IncreaseLiquidityArgs<'info>
+    ) -> IncreaseLiquidityArgs<'info> {
+        IncreaseLiquidityArgs {
+            nft_account: self.position_nft_account.clone(),
+            protocol_position: self.protocol_position.clone(),
+            personal_position: self.personal_position.to_account_info(),
+            tick_array_lower: self.tick_array_lower.to_account_info(),
+            tick_array_upper: self.tick_array_upper.to_account_info(),
+            ..core.clone()
+        }
+    }
+}
+
+/// Liquidity held by the ranges
+pub fn ladder_liquidity(ranges: &[LadderRangeAccounts]) -> u128 {
+    ranges
+        .iter()
+        .map(|range| range.personal_position.liquidity)
+        .sum()
+}
+
+/// Fees owed by Raydium to the ranges
+pub fn ladder_owed_fees<'info>(
+    ranges: &[LadderRangeAccounts<'info>],
+    pool_state: &ClmmLoader<'info, PoolState>
+) -> (u64, u64) {
+    ranges.iter().fold((0, 0), |(fees_owed0, fees_owed1), range| {
+        let (owed0, owed1) = get_owed_fees(
+            range.tick_array_lower.clone(),
+            range.tick_array_upper.clone(),
+            &range.personal_position,
+            pool_state
+        );
+        (fees_owed0.checked_add(owed0).unwrap(), fees_owed1.checked_add(owed1).unwrap())
+    })
+}
+
+/// Harvests the fees owed to the ranges into the recipient accounts of `core`
+pub fn harvest_ladder_fees<'info>(
+    ranges: &[LadderRangeAccounts<'info>],
+    pool_state: &ClmmLoader<'info, PoolState>,
+    core: &CollectFeesArgs<'info>,
+    signer_seeds: &[&[&[u8]]]
+) -> Result<()> {
+    for range in ranges {
+        let (fees_owed0, fees_owed1) = ladder_owed_fees(std::slice::from_ref(range), pool_state);
+        if fees_owed0 > 0 || fees_owed1 > 0 {
+            RaydiumAdapter::collect_fees(range.collect_fees_args(core), signer_seeds)?;
+        }
+    }
+    Ok(())
+}
+
+/// Liquidity a range of `weight_bps` holds for `core_liquidity` in the core range
+pub fn ladder_range_liquidity(core_liquidity: u128, weight_bps: u32) -> u128 {
+    U128::from(core_liquidity)
+        .mul_div_floor(U128::from(weight_bps), U128::from(BPS_DENOMINATOR))
+        .unwrap()
+        .as_u128()
+}
+
+/// Part of `range_liquidity` backing `liquidity` out of the `total_liquidity` of the position,
+/// rounded down
+pub fn range_share(range_liquidity: u128, liquidity: u128, total_liquidity: u128) -> u128 {
+    if total_liquidity == 0 {
+        return 0;
+    }
+    U128::from(range_liquidity)
+        .mul_div_floor(U128::from(liquidity), U128::from(total_liquidity))
+        .unwrap()
+        .as_u128()
+}
+
+/// Token amounts paid for `core_liquidity` in the core range and its share in every other leg,
+/// rounded up as Raydium charges them
+pub fn ladder_amounts(
+    sqrt_price_x64: u128,
+    legs: &[LadderLeg],
+    core_liquidity: u128
+) -> Result<(u128, u128)> {
+    let mut amount_0 = 0u128;
+    let mut amount_1 = 0u128;
+    for leg in legs {
+        let (leg_amount_0, leg_amount_1) = liquidity_math::get_amounts_for_liquidity(
+            sqrt_price_x64,
+            leg.sqrt_price_lower_x64,
+            leg.sqrt_price_upper_x64,
+            ladder_range_liquidity(core_liquidity, leg.weight_bps),
+            true
+        )?;
+        amount_0 += u128::from(leg_amount_0);
+        amount_1 += u128::from(leg_amount_1);
+    }
+    Ok((amount_0, amount_1))
+}
+
+/// Largest core liquidity whose legs can all be paid with `amount_0` and `amount_1`. `legs`
+/// starts with the core range at `BPS_DENOMINATOR`.
+///
+/// The amounts of the legs are linear in the core liquidity, so they are measured once at a
+/// probe liquidity and scaled. Every leg rounds its amounts up by at most one token, and its
+/// liquidity down, which the slack of two tokens per leg covers.
+pub fn ladder_core_liquidity(
+    sqrt_price_x64: u128,
+    legs: &[LadderLeg],
+    amount_0: u64,
+    amount_1: u64
+) -> Result<u128> {
+    let Some(core) = legs.first() else {
+        return Ok(0);
+    };
+    let total_weight_bps: u128 = legs
+        .iter()
+        .map(|leg| u128::from(leg.weight_bps))
+        .sum();
+    let probe = U128::from(
+        liquidity_math::get_liquidity_from_amounts(
+            sqrt_price_x64,
+            core.sqrt_price_lower_x64,
+            core.sqrt_price_upper_x64,
+            amount_0,
+            amount_1
+        )
+    )
+        .mul_div_floor(U128::from(BPS_DENOMINATOR), U128::from(total_weight_bps))
+        .unwrap()
+        .as_u128();
+    if probe == 0 {
+        return Ok(0);
+    }
+
+    let (need_0, need_1) = ladder_amounts(sqrt_price_x64, legs, probe)?;
+    let slack = 2 * (legs.len() as u64);
+    let scaled = |amount: u64, need: u128| -> u128 {
+        if need == 0 {
+            return u128::MAX;
+        }
+        U128::from(probe)
+            .mul_div_floor(U128::from(amount.saturating_sub(slack)), U128::from(need))
+            .map(|liquidity| liquidity.as_u128())
+            .unwrap_or(u128::MAX)
+    };
+    let liquidity = u128::min(scaled(amount_0, need_0), scaled(amount_1, need_1));
+    Ok(if liquidity == u128::MAX { 0 } else { liquidity })
+}
+
+#[cfg(test)]
+mod ladder_test {
+    use super::*;
+
+    fn leg(tick_lower: i32, tick_upper: i32, weight_bps: u32) -> LadderLeg {
+        LadderLeg {
+            sqrt_price_lower_x64: tick_math::get_sqrt_price_at_tick(tick_lower).unwrap(),
+            sqrt_price_upper_x64: tick_math::get_sqrt_price_at_tick(tick_upper).unwrap(),
+            weight_bps,
+        }
+    }
+
+    #[test]
+    fn core_liquidity_pays_every_leg_within_the_amounts() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(100).unwrap();
+        let ladders = [
+            vec![leg(-1200, 1200, 10_000)],
+            vec![leg(-1200, 1200, 10_000), leg(-6000, 6000, 5_000)],
+            // one range above the price holds token_0 only, one below token_1 only
+            vec![leg(-1200, 1200, 10_000), leg(600, 3000, 20_000), leg(-3000, -600, 2_500)],
+        ];
+        for legs in ladders.iter() {
+            let (amount_0, amount_1) = (1_000_000_000, 3_000_000_000);
+            let liquidity = ladder_core_liquidity(sqrt_price_x64, legs, amount_0, amount_1)
+                .unwrap();
+            assert!(liquidity > 0);
+
+            let (paid_0, paid_1) = ladder_amounts(sqrt_price_x64, legs, liquidity).unwrap();
+            assert!(paid_0 <= u128::from(amount_0) && paid_1 <= u128::from(amount_1));
+            // one of the tokens is almost entirely used
+            let left_0 = u128::from(amount_0) - paid_0;
+            let left_1 = u128::from(amount_1) - paid_1;
+            assert!(left_0 * 1000 < u128::from(amount_0) || left_1 * 1000 < u128::from(amount_1));
+        }
+    }
+
+    #[test]
+    fn core_liquidity_is_zero_without_amounts() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let legs = [leg(-1200, 1200, 10_000), leg(-2400, 2400, 10_000)];
+        assert_eq!(ladder_core_liquidity(sqrt_price_x64, &legs, 0, 0).unwrap(), 0);
+        assert_eq!(ladder_core_liquidity(sqrt_price_x64, &legs, 3, 3).unwrap(), 0);
+        assert_eq!(ladder_core_liquidity(sqrt_price_x64, &[], 1_000, 1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn range_shares_follow_the_weights() {
+        assert_eq!(ladder_range_liquidity(1_000_000, 2_500), 250_000);
+        assert_eq!(ladder_range_liquidity(1_000_000, 100_000), 10_000_000);
+        assert_eq!(range_share(250_000, 300, 1_250_000), 60);
+        assert_eq!(range_share(250_000, 1_250_000, 1_250_000), 250_000);
+        assert_eq!(range_share(250_000, 1, 0), 0);
+    }
+}
@@ -0,0 +1,218 @@
+use anchor_lang::prelude::*;
+
+use crate::libraries::{ big_num::U128, fixed_point_64, full_math::MulDiv };
+use crate::libraries::sqrt_price_math::{ get_delta_amount_0_unsigned, get_delta_amount_1_unsigned };
+
+/// Computes the amount of liquidity received for a given amount of token_0 and price range
+/// Calculates ΔL = Δx (√P_upper x √P_lower)/(√P_upper - √P_lower)
+pub fn get_liquidity_from_amount_0(
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    amount_0: u64
+) -> u128 {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+    let intermediate = U128::from(sqrt_ratio_a_x64)
+        .mul_div_floor(U128::from(sqrt_ratio_b_x64), U128::from(fixed_point_64::Q64))
+        .unwrap();
+
+    U128::from(amount_0)
+        .mul_div_floor(intermediate, U128::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64))
+        .unwrap()
+        .as_u128()
+}
+
+/// Computes the amount of liquidity received for a given amount of token_1 and price range
+/// Calculates ΔL = Δy / (√P_upper - √P_lower)
+pub fn get_liquidity_from_amount_1(
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    amount_1: u64
+) -> u128 {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+
+    U128::from(amount_1)
+        .mul_div_floor(
+            U128::from(fixed_point_64::Q64),
+            U128::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64)
+        )
+        .unwrap()
+        .as_u128()
+}
+
+/// Computes the maximum amount of liquidity received for a given amount of token_0, token_1, the current
+/// pool prices and the prices at the tick boundaries
+pub fn get_liquidity_from_amounts(
+    sqrt_ratio_x64: u128,
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    amount_0: u64,
+    amount_1: u64
+) -> u128 {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+
+    if sqrt_ratio_x64 <= sqrt_ratio_a_x64 {
+        // If P ≤ P_lower, only token_0 liquidity is active
+        get_liquidity_from_amount_0(sqrt_ratio_a_x64, sqrt_ratio_b_x64, amount_0)
+    } else if sqrt_ratio_x64 < sqrt_ratio_b_x64 {
+        // If P_lower < P < P_upper, active liquidity is the minimum of the liquidity provided
+        // by token_0 and token_1
+        u128::min(
+            get_liquidity_from_amount_0(sqrt_ratio_x64, sqrt_ratio_b_x64, amount_0),
+            get_liquidity_from_amount_1(sqrt_ratio_a_x64, sqrt_ratio_x64, amount_1)
+        )
+    } else {
+        // If P ≥ P_upper, only token_1 liquidity is active
+        get_liquidity_from_amount_1(sqrt_ratio_a_x64, sqrt_ratio_b_x64, amount_1)
+    }
+}
+
+/// Add a signed liquidity delta to liquidity, returns `None` on overflow or underflow
+pub fn add_delta(x: u128, y: i128) -> Option<u128> {
+    if y < 0 {
+        x.checked_sub(y.unsigned_abs())
+    } else {
+        x.checked_add(y as u128)
+    }
+}
+
+/// Computes the amount of token_0 held by a given amount of liquidity between two prices
+/// Calculates Δx = ΔL (√P_upper - √P_lower) / (√P_upper x √P_lower)
+pub fn get_amount_0_for_liquidity(
+    sqrt_ratio_a_x64: u128,
+    sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool
+) -> Result<u64> {
+    get_delta_amount_0_unsigned(sqrt_ratio_a_x64, sqrt_ratio_b_x64, liquidity, round_up)
+}
+
+/// Computes the amount of token_1 held by a given amount of liquidity between two prices
+/// Calculates Δy = ΔL (√P_upper - √P_lower)
+pub fn get_amount_1_for_liquidity(
+    sqrt_ratio_a_x64: u128,
+    sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool
+) -> Result<u64> {
+    get_delta_amount_1_unsigned(sqrt_ratio_a_x64, sqrt_ratio_b_x64, liquidity, round_up)
+}
+
+/// Computes the amounts of token_0 and token_1 held by a given amount of liquidity, the current
+/// pool price and the prices at the tick boundaries. Amounts are rounded down when valuing a
+/// position and up when paying for new liquidity.
+pub fn get_amounts_for_liquidity(
+    sqrt_ratio_x64: u128,
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool
+) -> Result<(u64, u64)> {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+
+    if sqrt_ratio_x64 <= sqrt_ratio_a_x64 {
+        // If P ≤ P_lower, the liquidity is all in token_0
+        let amount_0 = get_amount_0_for_liquidity(
+            sqrt_ratio_a_x64,
+            sqrt_ratio_b_x64,
+            liquidity,
+            round_up
+        )?;
+        Ok((amount_0, 0))
+    } else if sqrt_ratio_x64 < sqrt_ratio_b_x64 {
+        // If P_lower < P < P_upper, token_0 is held above the price and token_1 below it
+        Ok((
+            get_amount_0_for_liquidity(sqrt_ratio_x64, sqrt_ratio_b_x64, liquidity, round_up)?,
+            get_amount_1_for_liquidity(sqrt_ratio_a_x64, sqrt_ratio_x64, liquidity, round_up)?,
+        ))
+    } else {
+        // If P ≥ P_upper, the liquidity is all in token_1
+        let amount_1 = get_amount_1_for_liquidity(
+            sqrt_ratio_a_x64,
+            sqrt_ratio_b_x64,
+            liquidity,
+            round_up
+        )?;
+        Ok((0, amount_1))
+    }
+}
+
+#[cfg(test)]
+mod liquidity_math_test {
+    use super::*;
+    use crate::libraries::tick_math;
+
+    #[test]
+    fn amounts_for_liquidity_round_trip_below_the_deposited_amounts() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(100).unwrap();
+        let sqrt_price_a_x64 = tick_math::get_sqrt_price_at_tick(-1200).unwrap();
+        let sqrt_price_b_x64 = tick_math::get_sqrt_price_at_tick(1200).unwrap();
+        let (amount_0, amount_1) = (1_000_000_000, 2_000_000_000);
+
+        let liquidity = get_liquidity_from_amounts(
+            sqrt_price_x64,
+            sqrt_price_a_x64,
+            sqrt_price_b_x64,
+            amount_0,
+            amount_1
+        );
+        let (held_0, held_1) = get_amounts_for_liquidity(
+            sqrt_price_x64,
+            sqrt_price_a_x64,
+            sqrt_price_b_x64,
+            liquidity,
+            false
+        ).unwrap();
+
+        assert!(held_0 <= amount_0 && held_1 <= amount_1);
+        // The liquidity is bound by token_0, which is almost entirely used
+        assert!(amount_0 - held_0 <= 2);
+        let (paid_0, paid_1) = get_amounts_for_liquidity(
+            sqrt_price_x64,
+            sqrt_price_a_x64,
+            sqrt_price_b_x64,
+            liquidity,
+            true
+        ).unwrap();
+        assert!(paid_0 >= held_0 && paid_0 - held_0 <= 1);
+        assert!(paid_1 >= held_1 && paid_1 - held_1 <= 1);
+    }
+
+    #[test]
+    fn amounts_for_liquidity_out_of_range_are_single_sided() {
+        let sqrt_price_a_x64 = tick_math::get_sqrt_price_at_tick(-1200).unwrap();
+        let sqrt_price_b_x64 = tick_math::get_sqrt_price_at_tick(1200).unwrap();
+        let below = tick_math::get_sqrt_price_at_tick(-2000).unwrap();
+        let above = tick_math::get_sqrt_price_at_tick(2000).unwrap();
+
+        let (amount_0, amount_1) = get_amounts_for_liquidity(
+            below,
+            sqrt_price_a_x64,
+            sqrt_price_b_x64,
+            1_000_000,
+            false
+        ).unwrap();
+        assert!(amount_0 > 0 && amount_1 == 0);
+
+        // The bounds may be given in any order
+        let (amount_0, amount_1) = get_amounts_for_liquidity(
+            above,
+            sqrt_price_b_x64,
+            sqrt_price_a_x64,
+            1_000_000,
+            false
+        ).unwrap();
+        assert!(amount_0 == 0 && amount_1 > 0);
+    }
+}
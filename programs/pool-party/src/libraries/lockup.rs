@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::BPS_DENOMINATOR;
+use crate::errors::ErrorCode;
+
+/// Deposit time of an investor holding `liquidity` deposited at `deposited_at` after it adds
+/// `liquidity_delta` at `now`: the liquidity-weighted average of both, so adding to a position
+/// extends its lockup pro rata instead of restarting it.
+pub fn weighted_deposited_at(
+    deposited_at: i64,
+    liquidity: u128,
+    now: i64,
+    liquidity_delta: u128
+) -> i64 {
+    let total = liquidity.saturating_add(liquidity_delta);
+    if total == 0 {
+        return now;
+    }
+    // Averaged as an offset to `deposited_at`, positive since time only moves forward
+    let elapsed = u128::try_from(now.saturating_sub(deposited_at)).unwrap_or(0);
+    let offset = (elapsed.saturating_mul(liquidity_delta) / total) as i64;
+    deposited_at + offset
+}
+
+/// Penalty in basis points due by an investor who deposited at `deposited_at` and exits at
+/// `now`: zero once the lockup is over, `early_exit_penalty_bps` before. Fails before the end
+/// of the lockup when there is no penalty, the lock then being strict.
+pub fn exit_penalty_bps(
+    deposited_at: i64,
+    lockup_seconds: i64,
+    early_exit_penalty_bps: u16,
+    now: i64
+) -> Result<u16> {
+    if now >= deposited_at.saturating_add(lockup_seconds) {
+        return Ok(0);
+    }
+    require!(early_exit_penalty_bps > 0, ErrorCode::LiquidityLocked);
+    Ok(early_exit_penalty_bps)
+}
+
+/// Share of `amount` withheld by a penalty of `penalty_bps`, rounded up
+pub fn early_exit_penalty(amount: u64, penalty_bps: u16) -> u64 {
+    let penalty = (u128::from(amount) * u128::from(penalty_bps)).div_ceil(
+        u128::from(BPS_DENOMINATOR)
+    );
+    penalty.min(u128::from(amount)) as u64
+}
+
+#[cfg(test)]
+mod lockup_test {
+    use super::*;
+
+    #[test]
+    fn weighted_deposited_at_averages_by_liquidity() {
+        assert_eq!(weighted_deposited_at(0, 0, 1_000, 10), 1_000);
+        assert_eq!(weighted_deposited_at(1_000, 100, 2_000, 100), 1_500);
+        assert_eq!(weighted_deposited_at(1_000, 300, 2_000, 100), 1_250);
+        assert_eq!(weighted_deposited_at(1_000, 100, 2_000, 0), 1_000);
+        assert_eq!(weighted_deposited_at(1_000, 0, 2_000, 0), 2_000);
+    }
+
+    #[test]
+    fn weighted_deposited_at_does_not_overflow() {
+        let deposited_at = weighted_deposited_at(0, u128::MAX, i64::MAX, u128::MAX);
+        assert!(deposited_at >= 0);
+    }
+
+    #[test]
+    fn exit_penalty_applies_during_the_lockup_only() {
+        assert_eq!(exit_penalty_bps(1_000, 100, 50, 1_099).unwrap(), 50);
+        assert_eq!(exit_penalty_bps(1_000, 100, 50, 1_100).unwrap(), 0);
+        assert_eq!(exit_penalty_bps(1_000, 0, 0, 1_000).unwrap(), 0);
+        assert_eq!(
+            exit_penalty_bps(1_000, 100, 0, 1_099).unwrap_err(),
+            ErrorCode::LiquidityLocked.into()
+        );
+    }
+
+    #[test]
+    fn early_exit_penalty_rounds_up() {
+        assert_eq!(early_exit_penalty(10_000, 50), 50);
+        assert_eq!(early_exit_penalty(10_001, 50), 51);
+        assert_eq!(early_exit_penalty(0, 50), 0);
+        assert_eq!(early_exit_penalty(u64::MAX, 10_000), u64::MAX);
+        assert_eq!(early_exit_penalty(1_000, 0), 0);
+    }
+}
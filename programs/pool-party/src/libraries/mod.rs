@@ -3,9 +3,32 @@ pub mod fixed_point_64;
 pub mod big_num;
 pub mod tick_math;
 pub mod transfer;
+pub mod slippage;
+pub mod sqrt_price_math;
+pub mod liquidity_math;
+pub mod swap_math;
+pub mod swap_simulation;
+pub mod remaining_accounts;
+pub mod pricing;
+pub mod nav;
+pub mod deposit_limits;
+pub mod allowlist;
+pub mod lockup;
+pub mod ladder;
+pub mod clmm_accounts;
 
 pub use full_math::*;
 pub use fixed_point_64::*;
 pub use big_num::*;
 pub use tick_math::*;
 pub use transfer::*;
+pub use slippage::*;
+pub use swap_simulation::*;
+pub use remaining_accounts::*;
+pub use pricing::*;
+pub use nav::*;
+pub use deposit_limits::*;
+pub use allowlist::*;
+pub use lockup::*;
+pub use ladder::*;
+pub use clmm_accounts::*;
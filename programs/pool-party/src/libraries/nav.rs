@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+
+use crate::{ errors::ErrorCode, libraries::{ fixed_point_64, MulDiv, U256 } };
+
+/// Token amounts a pool position holds, by where they sit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PositionHoldings {
+    /// Backing the Raydium liquidity at the current pool price
+    pub liquidity_amount_0: u64,
+    pub liquidity_amount_1: u64,
+    /// Owed by Raydium and not harvested yet
+    pub uncollected_fees_0: u64,
+    pub uncollected_fees_1: u64,
+    /// Harvested into the fees vaults and not paid out yet
+    pub fees_vault_0: u64,
+    pub fees_vault_1: u64,
+    /// Sitting in the position vaults outside of Raydium
+    pub idle_0: u64,
+    pub idle_1: u64,
+}
+
+impl PositionHoldings {
+    pub fn total_0(&self) -> Result<u64> {
+        total(&[
+            self.liquidity_amount_0,
+            self.uncollected_fees_0,
+            self.fees_vault_0,
+            self.idle_0,
+        ])
+    }
+
+    pub fn total_1(&self) -> Result<u64> {
+        total(&[
+            self.liquidity_amount_1,
+            self.uncollected_fees_1,
+            self.fees_vault_1,
+            self.idle_1,
+        ])
+    }
+}
+
+fn total(amounts: &[u64]) -> Result<u64> {
+    amounts
+        .iter()
+        .try_fold(0u64, |total, amount| total.checked_add(*amount))
+        .ok_or(error!(ErrorCode::MaxTokenOverflow))
+}
+
+/// NAV of one unit of liquidity, as a Q64.64 amount of the stable mint. Zero without liquidity.
+pub fn nav_per_liquidity_x64(nav: u64, liquidity: u128) -> u128 {
+    if liquidity == 0 {
+        return 0;
+    }
+    U256::from(nav)
+        .mul_div_floor(U256::from(fixed_point_64::Q64), U256::from(liquidity))
+        .map(|nav_per_liquidity| {
+            if nav_per_liquidity > U256::from(u128::MAX) {
+                u128::MAX
+            } else {
+                nav_per_liquidity.as_u128()
+            }
+        })
+        .unwrap_or(u128::MAX)
+}
+
+/// Stable mint amount worth `liquidity` units at `nav_per_liquidity_x64`, rounded down
+pub fn liquidity_value(liquidity: u128, nav_per_liquidity_x64: u128) -> Result<u64> {
+    let value = U256::from(liquidity)
+        .mul_div_floor(U256::from(nav_per_liquidity_x64), U256::from(fixed_point_64::Q64))
+        .ok_or(ErrorCode::MaxTokenOverflow)?;
+    require!(value <= U256::from(u64::MAX), ErrorCode::MaxTokenOverflow);
+    Ok(value.as_u64())
+}
+
+#[cfg(test)]
+mod nav_test {
+    use super::*;
+
+    #[test]
+    fn holdings_add_up_every_place() {
+        let holdings = PositionHoldings {
+            liquidity_amount_0: 1_000,
+            liquidity_amount_1: 2_000,
+            uncollected_fees_0: 10,
+            uncollected_fees_1: 20,
+            fees_vault_0: 1,
+            fees_vault_1: 2,
+            idle_0: 100,
+            idle_1: 200,
+        };
+        assert_eq!(holdings.total_0().unwrap(), 1_111);
+        assert_eq!(holdings.total_1().unwrap(), 2_222);
+
+        let overflowing = PositionHoldings { idle_0: u64::MAX, ..holdings };
+        assert!(overflowing.total_0().is_err());
+    }
+
+    #[test]
+    fn nav_per_liquidity_values_liquidity_back() {
+        let nav = 1_234_567_890;
+        let liquidity = 987_654_321_000;
+        let nav_per_liquidity = nav_per_liquidity_x64(nav, liquidity);
+
+        assert_eq!(liquidity_value(liquidity, nav_per_liquidity).unwrap(), nav - 1);
+        assert_eq!(liquidity_value(liquidity / 2, nav_per_liquidity).unwrap(), nav / 2 - 1);
+        assert_eq!(nav_per_liquidity_x64(nav, 0), 0);
+    }
+}
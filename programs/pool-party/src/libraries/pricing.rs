@@ -0,0 +1,177 @@
+use anchor_lang::prelude::*;
+use raydium_clmm_cpi::states::{ ObservationState, PoolState, OBSERVATION_NUM };
+
+use crate::{ errors::ErrorCode, libraries::{ fixed_point_64, tick_math, MulDiv, U256 } };
+
+/// Price used to value tokens in the stable mint
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceSource {
+    /// Current price of the pool
+    Spot,
+    /// Time weighted average price of the pool over at least the last `window_seconds`, from its
+    /// oracle observations
+    Twap {
+        window_seconds: u32,
+    },
+}
+
+/// Converts `amount` of one token of a pool into the other token at `sqrt_price_x64`, rounded
+/// down. The price P = (√P)² is the amount of token_1 per token_0.
+pub fn quote_at_sqrt_price(amount: u64, sqrt_price_x64: u128, zero_for_one: bool) -> Result<u64> {
+    require!(sqrt_price_x64 > 0, ErrorCode::SqrtPriceX64);
+    let q64 = U256::from(fixed_point_64::Q64);
+    let sqrt_price_x64 = U256::from(sqrt_price_x64);
+    let quote = if zero_for_one {
+        U256::from(amount)
+            .mul_div_floor(sqrt_price_x64, q64)
+            .and_then(|amount| amount.mul_div_floor(sqrt_price_x64, q64))
+    } else {
+        U256::from(amount)
+            .mul_div_floor(q64, sqrt_price_x64)
+            .and_then(|amount| amount.mul_div_floor(q64, sqrt_price_x64))
+    }.ok_or(ErrorCode::MaxTokenOverflow)?;
+    require!(quote <= U256::from(u64::MAX), ErrorCode::MaxTokenOverflow);
+    Ok(quote.as_u64())
+}
+
+/// Values `amount` of `mint` in `stable_mint` at `sqrt_price_x64` of `pool_state`, a pool pairing
+/// the two mints. The pool is ignored when `mint` is the stable mint.
+pub fn value_in_stable(
+    amount: u64,
+    mint: Pubkey,
+    stable_mint: Pubkey,
+    pool_state: &PoolState,
+    sqrt_price_x64: u128
+) -> Result<u64> {
+    if mint == stable_mint {
+        return Ok(amount);
+    }
+    let zero_for_one = match (pool_state.token_mint_0, pool_state.token_mint_1) {
+        (mint_0, mint_1) if mint_0 == mint && mint_1 == stable_mint => true,
+        (mint_0, mint_1) if mint_0 == stable_mint && mint_1 == mint => false,
+        _ => {
+            return err!(ErrorCode::InvalidPricePool);
+        }
+    };
+    quote_at_sqrt_price(amount, sqrt_price_x64, zero_for_one)
+}
+
+/// Sqrt price of `pool_state` given by `price_source`, `observation_state` being its oracle
+pub fn source_sqrt_price_x64(
+    price_source: PriceSource,
+    pool_state: &PoolState,
+    observation_state: &ObservationState,
+    now: u32
+) -> Result<u128> {
+    match price_source {
+        PriceSource::Spot => Ok(pool_state.sqrt_price_x64),
+        PriceSource::Twap { window_seconds } => {
+            // The account is packed: observations are copied out one by one
+            let observations: Vec<(u32, i64)> = (0..OBSERVATION_NUM)
+                .map(|index| {
+                    let observation = observation_state.observations[index];
+                    (observation.block_timestamp, observation.tick_cumulative)
+                })
+                .collect();
+            let tick = twap_tick(
+                &observations,
+                usize::from(observation_state.observation_index),
+                pool_state.tick_current,
+                now,
+                window_seconds
+            )?;
+            tick_math::get_sqrt_price_at_tick(tick)
+        }
+    }
+}
+
+/// Time weighted average tick over at least the last `window_seconds`, from the oracle
+/// observations `(block_timestamp, tick_cumulative)` in storage order, the newest one at
+/// `observation_index`. The pool is assumed at `tick_current` since the newest observation.
+pub fn twap_tick(
+    observations: &[(u32, i64)],
+    observation_index: usize,
+    tick_current: i32,
+    now: u32,
+    window_seconds: u32
+) -> Result<i32> {
+    require!(window_seconds > 0, ErrorCode::InvalidTwapWindow);
+    let target = now.checked_sub(window_seconds).ok_or(ErrorCode::InvalidTwapWindow)?;
+
+    let (newest_timestamp, newest_tick_cumulative) = observations[observation_index];
+    require!(newest_timestamp > 0, ErrorCode::NotEnoughObservations);
+    let tick_cumulative_now = newest_tick_cumulative +
+        i64::from(tick_current) * i64::from(now.saturating_sub(newest_timestamp));
+
+    // Walk back from the newest observation to the first one old enough, unwritten slots have
+    // a zero timestamp
+    let len = observations.len();
+    let (timestamp, tick_cumulative) = (0..len)
+        .map(|back| observations[(observation_index + len - back) % len])
+        .take_while(|(timestamp, _)| *timestamp > 0)
+        .find(|(timestamp, _)| *timestamp <= target)
+        .ok_or(ErrorCode::NotEnoughObservations)?;
+
+    let elapsed = i64::from(now - timestamp);
+    Ok((tick_cumulative_now - tick_cumulative).div_euclid(elapsed) as i32)
+}
+
+#[cfg(test)]
+mod pricing_test {
+    use super::*;
+    use crate::libraries::tick_math;
+
+    #[test]
+    fn quote_at_price_one_is_the_amount() {
+        let sqrt_price_x64 = fixed_point_64::Q64;
+        assert_eq!(quote_at_sqrt_price(1_000_000, sqrt_price_x64, true).unwrap(), 1_000_000);
+        assert_eq!(quote_at_sqrt_price(1_000_000, sqrt_price_x64, false).unwrap(), 1_000_000);
+    }
+
+    #[test]
+    fn quotes_both_ways_round_down() {
+        // P = 1.0001^6932 ≈ 2
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(6932).unwrap();
+        let amount_1 = quote_at_sqrt_price(1_000_000_000, sqrt_price_x64, true).unwrap();
+        assert!(amount_1 > 1_999_000_000 && amount_1 < 2_001_000_000);
+
+        let amount_0 = quote_at_sqrt_price(amount_1, sqrt_price_x64, false).unwrap();
+        assert!(amount_0 <= 1_000_000_000 && 1_000_000_000 - amount_0 <= 2);
+    }
+
+    #[test]
+    fn twap_tick_averages_over_the_window() {
+        // Tick 100 from t=1000 to t=1100, then tick -50 until the newest observation at t=1300
+        let mut observations = vec![(0u32, 0i64); 8];
+        observations[5] = (1000, 0);
+        observations[6] = (1100, 100 * 100);
+        observations[7] = (1200, 100 * 100 - 50 * 100);
+        observations[0] = (1300, 100 * 100 - 50 * 200);
+
+        // Since the newest observation the pool sat at tick 20
+        assert_eq!(twap_tick(&observations, 0, 20, 1300, 100).unwrap(), -50);
+        assert_eq!(twap_tick(&observations, 0, 20, 1400, 200).unwrap(), -15);
+        // The window starts at the first observation old enough, here t=1000
+        assert_eq!(twap_tick(&observations, 0, 20, 1300, 250).unwrap(), 0);
+        // Averages round towards negative infinity
+        assert_eq!(twap_tick(&observations, 0, -51, 1301, 101).unwrap(), -51);
+    }
+
+    #[test]
+    fn twap_tick_needs_old_enough_observations() {
+        let mut observations = vec![(0u32, 0i64); 4];
+        observations[0] = (1000, 0);
+        observations[1] = (1100, 100);
+        assert!(twap_tick(&observations, 1, 1, 1150, 100).is_ok());
+        assert!(twap_tick(&observations, 1, 1, 1150, 200).is_err());
+        assert!(twap_tick(&observations, 1, 1, 1150, 0).is_err());
+        assert!(twap_tick(&[(0, 0); 4], 0, 1, 1150, 10).is_err());
+    }
+
+    #[test]
+    fn quote_overflow_is_an_error() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(100_000).unwrap();
+        assert!(quote_at_sqrt_price(u64::MAX, sqrt_price_x64, true).is_err());
+        assert!(quote_at_sqrt_price(1, 0, true).is_err());
+    }
+}
@@ -0,0 +1,404 @@
+//! Typed layout of the remaining accounts passed to the Raydium CPIs.
+//!
+//! `remaining_accounts` is read as consecutive groups whose lengths are given by the instruction
+//! arguments: `[bitmap extensions] [leg 0 tick arrays] [leg 1 tick arrays] [liquidity tick arrays]`,
+//! followed by the accounts specific to the instruction (e.g. the extra hops of a swap route or
+//! the ladder ranges of the position).
+
+use anchor_lang::{ prelude::*, Discriminator };
+use raydium_clmm_cpi::states::{ TickArrayBitmapExtension, TickArrayState };
+
+use crate::errors::ErrorCode;
+use crate::libraries::{ parse_ladder_ranges, ClmmLoader, LadderRangeAccounts };
+
+/// Number of accounts of each group, in the order they appear in `remaining_accounts`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RemainingAccountsLayout {
+    /// `TickArrayBitmapExtension` accounts, at most one per pool
+    pub bitmap_extensions: u8,
+    /// Tick arrays of the first pool of swap leg 0
+    pub leg_0_tick_arrays: u8,
+    /// Tick arrays of the first pool of swap leg 1
+    pub leg_1_tick_arrays: u8,
+    /// Tick arrays of the position pool
+    pub liquidity_tick_arrays: u8,
+}
+
+pub struct RemainingAccounts<'info> {
+    /// CLMM program owning the bitmap extensions and tick arrays
+    pub clmm_program: Pubkey,
+    pub bitmap_extensions: &'info [AccountInfo<'info>],
+    pub leg_0_tick_arrays: &'info [AccountInfo<'info>],
+    pub leg_1_tick_arrays: &'info [AccountInfo<'info>],
+    pub liquidity_tick_arrays: &'info [AccountInfo<'info>],
+    /// Accounts following the described groups
+    pub rest: &'info [AccountInfo<'info>],
+}
+
+impl RemainingAccountsLayout {
+    /// Splits `remaining_accounts` into its groups, checking every bitmap extension and tick array
+    /// is owned by `clmm_program` and has the discriminator of its state.
+    pub fn parse<'info>(
+        &self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        clmm_program: &Pubkey
+    ) -> Result<RemainingAccounts<'info>> {
+        let mut rest = remaining_accounts;
+        let bitmap_extensions = take_group(&mut rest, self.bitmap_extensions)?;
+        let leg_0_tick_arrays = take_group(&mut rest, self.leg_0_tick_arrays)?;
+        let leg_1_tick_arrays = take_group(&mut rest, self.leg_1_tick_arrays)?;
+        let liquidity_tick_arrays = take_group(&mut rest, self.liquidity_tick_arrays)?;
+
+        for account_info in bitmap_extensions {
+            require!(
+                is_bitmap_extension(account_info, clmm_program),
+                ErrorCode::InvalidRemainingAccounts
+            );
+        }
+        for account_info in leg_0_tick_arrays
+            .iter()
+            .chain(leg_1_tick_arrays)
+            .chain(liquidity_tick_arrays) {
+            require!(is_tick_array(account_info, clmm_program), ErrorCode::InvalidTickArray);
+        }
+
+        Ok(RemainingAccounts {
+            clmm_program: *clmm_program,
+            bitmap_extensions,
+            leg_0_tick_arrays,
+            leg_1_tick_arrays,
+            liquidity_tick_arrays,
+            rest,
+        })
+    }
+
+    /// Parses the remaining accounts of an instruction that only touches the position pool and
+    /// returns the accounts to forward to its liquidity CPIs.
+    pub fn parse_liquidity<'info>(
+        &self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        clmm_program: &Pubkey,
+        pool_id: Pubkey
+    ) -> Result<Vec<AccountInfo<'info>>> {
+        let accounts = self.parse(remaining_accounts, clmm_program)?;
+        require!(
+            accounts.leg_0_tick_arrays.is_empty() &&
+                accounts.leg_1_tick_arrays.is_empty() &&
+                accounts.rest.is_empty(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        accounts.liquidity_accounts(pool_id)
+    }
+
+    /// Parses the remaining accounts of a liquidity instruction on a pool position with ladder
+    /// ranges, which follow the described groups, and returns the accounts to forward to its
+    /// liquidity CPIs along with the ranges.
+    pub fn parse_liquidity_ladder<'info>(
+        &self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        clmm_program: &Pubkey,
+        pool_id: Pubkey,
+        pool_position_key: Pubkey,
+        ladder_ranges: u8
+    ) -> Result<(Vec<AccountInfo<'info>>, Vec<LadderRangeAccounts<'info>>)> {
+        let accounts = self.parse(remaining_accounts, clmm_program)?;
+        require!(
+            accounts.leg_0_tick_arrays.is_empty() && accounts.leg_1_tick_arrays.is_empty(),
+            ErrorCode::InvalidRemainingAccounts
+        );
+        let ranges = parse_ladder_ranges(
+            accounts.rest,
+            clmm_program,
+            pool_position_key,
+            ladder_ranges,
+            pool_id
+        )?;
+        Ok((accounts.liquidity_accounts(pool_id)?, ranges))
+    }
+}
+
+impl<'info> RemainingAccounts<'info> {
+    /// The bitmap extension passed for `pool_id`, if any
+    pub fn bitmap_extension(&self, pool_id: Pubkey) -> Result<Option<&'info AccountInfo<'info>>> {
+        for account_info in self.bitmap_extensions {
+            if bitmap_extension_pool_id(account_info, &self.clmm_program)? == pool_id {
+                return Ok(Some(account_info));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Accounts forwarded to a Raydium CPI on `pool_id`: its bitmap extension, if any, followed by
+    /// `tick_arrays`, which must all belong to `pool_id`.
+    pub fn pool_accounts(
+        &self,
+        pool_id: Pubkey,
+        tick_arrays: &'info [AccountInfo<'info>]
+    ) -> Result<Vec<AccountInfo<'info>>> {
+        check_tick_arrays(tick_arrays, &self.clmm_program, pool_id)?;
+        let mut accounts = Vec::with_capacity(tick_arrays.len() + 1);
+        if let Some(bitmap_extension) = self.bitmap_extension(pool_id)? {
+            accounts.push(bitmap_extension.clone());
+        }
+        accounts.extend_from_slice(tick_arrays);
+        Ok(accounts)
+    }
+
+    /// Accounts forwarded to the liquidity CPIs on the position pool `pool_id`
+    pub fn liquidity_accounts(&self, pool_id: Pubkey) -> Result<Vec<AccountInfo<'info>>> {
+        self.pool_accounts(pool_id, self.liquidity_tick_arrays)
+    }
+}
+
+/// Splits the accounts of a single pool of `clmm_program`, an optional leading bitmap extension
+/// followed by tick arrays, checking they all belong to `pool_id`.
+pub fn split_pool_accounts<'info>(
+    accounts: &'info [AccountInfo<'info>],
+    clmm_program: &Pubkey,
+    pool_id: Pubkey
+) -> Result<(Option<&'info AccountInfo<'info>>, &'info [AccountInfo<'info>])> {
+    let (bitmap_extension, tick_arrays) = match accounts.split_first() {
+        Some((first, tick_arrays)) if is_bitmap_extension(first, clmm_program) => {
+            require_keys_eq!(
+                bitmap_extension_pool_id(first, clmm_program)?,
+                pool_id,
+                ErrorCode::InvalidRemainingAccounts
+            );
+            (Some(first), tick_arrays)
+        }
+        _ => (None, accounts),
+    };
+    check_tick_arrays(tick_arrays, clmm_program, pool_id)?;
+    Ok((bitmap_extension, tick_arrays))
+}
+
+/// Checks every account of `tick_arrays` is a tick array of `clmm_program` on `pool_id`
+pub fn check_tick_arrays<'info>(
+    tick_arrays: &'info [AccountInfo<'info>],
+    clmm_program: &Pubkey,
+    pool_id: Pubkey
+) -> Result<()> {
+    for account_info in tick_arrays {
+        require!(is_tick_array(account_info, clmm_program), ErrorCode::InvalidTickArray);
+        let tick_array = ClmmLoader::<TickArrayState>::try_from(account_info, clmm_program)?;
+        let tick_array_pool_id = tick_array.load()?.pool_id;
+        require_keys_eq!(tick_array_pool_id, pool_id, ErrorCode::InvalidTickArray);
+    }
+    Ok(())
+}
+
+pub fn is_tick_array(account_info: &AccountInfo, clmm_program: &Pubkey) -> bool {
+    is_clmm_account(account_info, clmm_program, TickArrayState::DISCRIMINATOR)
+}
+
+pub fn is_bitmap_extension(account_info: &AccountInfo, clmm_program: &Pubkey) -> bool {
+    is_clmm_account(account_info, clmm_program, TickArrayBitmapExtension::DISCRIMINATOR)
+}
+
+fn is_clmm_account(
+    account_info: &AccountInfo,
+    clmm_program: &Pubkey,
+    discriminator: &[u8]
+) -> bool {
+    account_info.owner == clmm_program &&
+        account_info
+            .try_borrow_data()
+            .map(|data| data.starts_with(discriminator))
+            .unwrap_or(false)
+}
+
+fn bitmap_extension_pool_id<'info>(
+    account_info: &'info AccountInfo<'info>,
+    clmm_program: &Pubkey
+) -> Result<Pubkey> {
+    let bitmap_extension = ClmmLoader::<TickArrayBitmapExtension>::try_from(
+        account_info,
+        clmm_program
+    )?;
+    let pool_id = bitmap_extension.load()?.pool_id;
+    Ok(pool_id)
+}
+
+fn take_group<'info>(
+    rest: &mut &'info [AccountInfo<'info>],
+    count: u8
+) -> Result<&'info [AccountInfo<'info>]> {
+    let count = count as usize;
+    require!(count <= rest.len(), ErrorCode::InvalidRemainingAccounts);
+    let (group, next) = rest.split_at(count);
+    *rest = next;
+    Ok(group)
+}
+
+#[cfg(test)]
+mod remaining_accounts_test {
+    use raydium_clmm_cpi::ID as RAYDIUM_CLMM_ID;
+
+    use super::*;
+
+    struct TestAccount {
+        key: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        owner: Pubkey,
+    }
+
+    impl TestAccount {
+        fn new(owner: Pubkey, discriminator: &[u8], size: usize, pool_id: Pubkey) -> Self {
+            let mut data = vec![0u8; 8 + size];
+            data[..8].copy_from_slice(discriminator);
+            data[8..40].copy_from_slice(pool_id.as_ref());
+            TestAccount { key: Pubkey::new_unique(), lamports: 1, data, owner }
+        }
+
+        fn tick_array(pool_id: Pubkey) -> Self {
+            Self::new(
+                RAYDIUM_CLMM_ID,
+                TickArrayState::DISCRIMINATOR,
+                std::mem::size_of::<TickArrayState>(),
+                pool_id
+            )
+        }
+
+        fn bitmap_extension(pool_id: Pubkey) -> Self {
+            Self::new(
+                RAYDIUM_CLMM_ID,
+                TickArrayBitmapExtension::DISCRIMINATOR,
+                std::mem::size_of::<TickArrayBitmapExtension>(),
+                pool_id
+            )
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                false,
+                true,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0
+            )
+        }
+    }
+
+    fn layout(bitmap_extensions: u8, leg_0: u8, leg_1: u8, liquidity: u8) -> RemainingAccountsLayout {
+        RemainingAccountsLayout {
+            bitmap_extensions,
+            leg_0_tick_arrays: leg_0,
+            leg_1_tick_arrays: leg_1,
+            liquidity_tick_arrays: liquidity,
+        }
+    }
+
+    #[test]
+    fn parse_splits_groups_in_order() {
+        let pool_id = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::bitmap_extension(pool_id),
+            TestAccount::tick_array(pool_id),
+            TestAccount::tick_array(pool_id),
+            TestAccount::tick_array(pool_id),
+            TestAccount::tick_array(pool_id),
+            TestAccount::new(Pubkey::new_unique(), &[0u8; 8], 32, pool_id)
+        ];
+        let infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .map(|account| account.info())
+            .collect();
+
+        let parsed = layout(1, 2, 1, 1).parse(&infos, &RAYDIUM_CLMM_ID).unwrap();
+        assert_eq!(parsed.bitmap_extensions.len(), 1);
+        assert_eq!(parsed.leg_0_tick_arrays[0].key, infos[1].key);
+        assert_eq!(parsed.leg_0_tick_arrays.len(), 2);
+        assert_eq!(parsed.leg_1_tick_arrays[0].key, infos[3].key);
+        assert_eq!(parsed.liquidity_tick_arrays[0].key, infos[4].key);
+        assert_eq!(parsed.rest.len(), 1);
+    }
+
+    #[test]
+    fn parse_rejects_wrong_owner_discriminator_and_short_lists() {
+        let pool_id = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::tick_array(pool_id),
+            TestAccount::new(Pubkey::new_unique(), TickArrayState::DISCRIMINATOR, 32, pool_id)
+        ];
+        let infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .map(|account| account.info())
+            .collect();
+
+        // a tick array is not a bitmap extension
+        assert!(layout(1, 0, 0, 0).parse(&infos[..1], &RAYDIUM_CLMM_ID).is_err());
+        // a tick array owned by another program
+        assert!(layout(0, 0, 0, 1).parse(&infos[1..], &RAYDIUM_CLMM_ID).is_err());
+        // more accounts announced than passed
+        assert!(layout(0, 3, 0, 0).parse(&infos, &RAYDIUM_CLMM_ID).is_err());
+    }
+
+    #[test]
+    fn liquidity_accounts_prepend_the_pool_bitmap_extension() {
+        let pool_id = Pubkey::new_unique();
+        let other_pool_id = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::bitmap_extension(other_pool_id),
+            TestAccount::bitmap_extension(pool_id),
+            TestAccount::tick_array(pool_id)
+        ];
+        let infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .map(|account| account.info())
+            .collect();
+
+        let liquidity_accounts = layout(2, 0, 0, 1)
+            .parse_liquidity(&infos, &RAYDIUM_CLMM_ID, pool_id)
+            .unwrap();
+        assert_eq!(liquidity_accounts.len(), 2);
+        assert_eq!(liquidity_accounts[0].key, infos[1].key);
+        assert_eq!(liquidity_accounts[1].key, infos[2].key);
+
+        // the tick array belongs to `pool_id` only
+        assert!(
+            layout(2, 0, 0, 1).parse_liquidity(&infos, &RAYDIUM_CLMM_ID, other_pool_id).is_err()
+        );
+        // leg tick arrays are not expected by liquidity only instructions
+        assert!(layout(2, 1, 0, 0).parse_liquidity(&infos, &RAYDIUM_CLMM_ID, pool_id).is_err());
+    }
+
+    #[test]
+    fn parse_checks_the_owner_against_the_given_clmm_program() {
+        let pool_id = Pubkey::new_unique();
+        let fork = Pubkey::new_unique();
+        let mut accounts = vec![
+            TestAccount::new(
+                fork,
+                TickArrayBitmapExtension::DISCRIMINATOR,
+                std::mem::size_of::<TickArrayBitmapExtension>(),
+                pool_id
+            ),
+            TestAccount::new(
+                fork,
+                TickArrayState::DISCRIMINATOR,
+                std::mem::size_of::<TickArrayState>(),
+                pool_id
+            )
+        ];
+        let infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .map(|account| account.info())
+            .collect();
+
+        let liquidity_accounts = layout(1, 0, 0, 1)
+            .parse_liquidity(&infos, &fork, pool_id)
+            .unwrap();
+        assert_eq!(liquidity_accounts.len(), 2);
+        let (bitmap_extension, tick_arrays) = split_pool_accounts(&infos, &fork, pool_id).unwrap();
+        assert_eq!(bitmap_extension.unwrap().key, infos[0].key);
+        assert_eq!(tick_arrays.len(), 1);
+
+        // the accounts of a fork are not those of the Raydium deployment
+        assert!(layout(1, 0, 0, 1).parse(&infos, &RAYDIUM_CLMM_ID).is_err());
+        assert!(split_pool_accounts(&infos[1..], &RAYDIUM_CLMM_ID, pool_id).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::{ constants::BPS_DENOMINATOR, errors::ErrorCode, libraries::{ MulDiv, U256 } };
+
+/// Deviation of the price of `current_sqrt_price_x64` from the price of `expected_sqrt_price_x64`,
+/// in basis points of the expected price. Both square roots are squared first, so the bound applies
+/// to the price itself. Rounds up so a deviation is never under-reported.
+pub fn price_deviation_bps(current_sqrt_price_x64: u128, expected_sqrt_price_x64: u128) -> u64 {
+    if expected_sqrt_price_x64 == 0 {
+        return u64::MAX;
+    }
+    let current_price = U256::from(current_sqrt_price_x64) * U256::from(current_sqrt_price_x64);
+    let expected_price = U256::from(expected_sqrt_price_x64) * U256::from(expected_sqrt_price_x64);
+    let diff = if current_price > expected_price {
+        current_price - expected_price
+    } else {
+        expected_price - current_price
+    };
+    diff
+        .mul_div_ceil(U256::from(BPS_DENOMINATOR), expected_price)
+        .map(|bps| if bps > U256::from(u64::MAX) { u64::MAX } else { bps.as_u64() })
+        .unwrap_or(u64::MAX)
+}
+
+/// Fails if the pool price moved more than `max_price_deviation` bps away from the
+/// price the client quoted against.
+pub fn check_price_deviation(
+    current_sqrt_price_x64: u128,
+    expected_sqrt_price_x64: u128,
+    max_price_deviation: u16
+) -> Result<()> {
+    let deviation = price_deviation_bps(current_sqrt_price_x64, expected_sqrt_price_x64);
+    require!(deviation <= u64::from(max_price_deviation), ErrorCode::PriceDeviationExceeded);
+    Ok(())
+}
+
+/// Fails if the liquidity minted by the increase is below `min_liquidity_out`.
+pub fn check_min_liquidity_out(liquidity_delta: u128, min_liquidity_out: u128) -> Result<()> {
+    require!(liquidity_delta >= min_liquidity_out, ErrorCode::LiquiditySlippageExceeded);
+    Ok(())
+}
+
+#[cfg(test)]
+mod slippage_test {
+    use super::*;
+
+    #[test]
+    fn price_deviation_is_measured_on_the_price_and_rounds_up() {
+        let expected: u128 = 1 << 64;
+        assert_eq!(price_deviation_bps(expected, expected), 0);
+        // A 1% move of the square root is a 2.01% move up and a 1.99% move down of the price
+        assert_eq!(price_deviation_bps(expected + expected / 100, expected), 201);
+        assert_eq!(price_deviation_bps(expected - expected / 100, expected), 199);
+        assert_eq!(price_deviation_bps(expected + 1, expected), 1);
+        assert_eq!(price_deviation_bps(expected, 0), u64::MAX);
+    }
+
+    #[test]
+    fn check_price_deviation_bounds() {
+        let expected: u128 = 1 << 64;
+        assert!(check_price_deviation(expected + expected / 100, expected, 201).is_ok());
+        assert!(check_price_deviation(expected + expected / 100, expected, 200).is_err());
+    }
+
+    #[test]
+    fn check_min_liquidity_out_bounds() {
+        assert!(check_min_liquidity_out(10, 10).is_ok());
+        assert!(check_min_liquidity_out(9, 10).is_err());
+    }
+}
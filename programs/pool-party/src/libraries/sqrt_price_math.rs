@@ -0,0 +1,238 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::ErrorCode,
+    libraries::{ big_num::U256, fixed_point_64, full_math::MulDiv },
+};
+
+/// `ceil(numerator / denominator)` without going through floating point
+fn div_rounding_up(numerator: U256, denominator: U256) -> U256 {
+    let quotient = numerator / denominator;
+    if numerator % denominator > U256::default() {
+        quotient + U256::from(1)
+    } else {
+        quotient
+    }
+}
+
+/// Gets the next sqrt price √P' given a delta of token_0
+///
+/// Always round up because
+/// 1. In the exact output case, token 0 supply decreases leading to price increase.
+/// Move price up so that exact output is met.
+/// 2. In the exact input case, token 0 supply increases leading to price decrease.
+/// Do not round down to minimize price impact. We only need to meet input
+/// change and not guarantee exact output.
+///
+/// Use function for exact input or exact output swaps for token 0
+///
+/// # Formula
+///
+/// * `√P' = √P * L / (L + Δx * √P)`
+/// * If Δx * √P overflows, use alternate form `√P' = L / (L/√P + Δx)`
+pub fn get_next_sqrt_price_from_amount_0_rounding_up(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount: u64,
+    add: bool
+) -> u128 {
+    if amount == 0 {
+        return sqrt_price_x64;
+    }
+    let numerator_1 = U256::from(liquidity) << fixed_point_64::RESOLUTION;
+
+    if add {
+        if let Some(product) = U256::from(amount).checked_mul(U256::from(sqrt_price_x64)) {
+            let denominator = numerator_1 + product;
+            if denominator >= numerator_1 {
+                return numerator_1
+                    .mul_div_ceil(U256::from(sqrt_price_x64), denominator)
+                    .unwrap()
+                    .as_u128();
+            }
+        }
+
+        div_rounding_up(
+            numerator_1,
+            (numerator_1 / U256::from(sqrt_price_x64)).checked_add(U256::from(amount)).unwrap()
+        ).as_u128()
+    } else {
+        let product = U256::from(amount).checked_mul(U256::from(sqrt_price_x64)).unwrap();
+        let denominator = numerator_1.checked_sub(product).unwrap();
+        numerator_1.mul_div_ceil(U256::from(sqrt_price_x64), denominator).unwrap().as_u128()
+    }
+}
+
+/// Gets the next sqrt price given a delta of token_1
+///
+/// Always round down because
+/// 1. In the exact output case, token 1 supply decreases leading to price decrease.
+/// Move price down by rounding down so that exact output of token 0 is met.
+/// 2. In the exact input case, token 1 supply increases leading to price increase.
+/// Do not round down to minimize price impact. We only need to meet input
+/// change and not guarantee exact output for token 0.
+///
+/// # Formula
+///
+/// * `√P' = √P + Δy / L`
+pub fn get_next_sqrt_price_from_amount_1_rounding_down(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount: u64,
+    add: bool
+) -> u128 {
+    if add {
+        let quotient = U256::from((amount as u128) << fixed_point_64::RESOLUTION) /
+        U256::from(liquidity);
+        sqrt_price_x64.checked_add(quotient.as_u128()).unwrap()
+    } else {
+        let quotient = div_rounding_up(
+            U256::from((amount as u128) << fixed_point_64::RESOLUTION),
+            U256::from(liquidity)
+        );
+        sqrt_price_x64.checked_sub(quotient.as_u128()).unwrap()
+    }
+}
+
+/// Gets the next sqrt price given an input amount of token_0 or token_1
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_in: u64,
+    zero_for_one: bool
+) -> u128 {
+    assert!(sqrt_price_x64 > 0);
+    assert!(liquidity > 0);
+
+    // round to make sure that we don't pass the target price
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64, liquidity, amount_in, true)
+    }
+}
+
+/// Gets the next sqrt price given an output amount of token0 or token1
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_out: u64,
+    zero_for_one: bool
+) -> u128 {
+    assert!(sqrt_price_x64 > 0);
+    assert!(liquidity > 0);
+
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_1_rounding_down(
+            sqrt_price_x64,
+            liquidity,
+            amount_out,
+            false
+        )
+    } else {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_out, false)
+    }
+}
+
+/// Gets the amount_0 delta between two prices, for given amount of liquidity
+///
+/// # Formula
+///
+/// * `Δx = L * (1 / √P_lower - 1 / √P_upper)`
+/// * i.e. `L * (√P_upper - √P_lower) / (√P_upper * √P_lower)`
+pub fn get_delta_amount_0_unsigned(
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool
+) -> Result<u64> {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+
+    let numerator_1 = U256::from(liquidity) << fixed_point_64::RESOLUTION;
+    let numerator_2 = U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64);
+
+    assert!(sqrt_ratio_a_x64 > 0);
+
+    let result = if round_up {
+        div_rounding_up(
+            numerator_1.mul_div_ceil(numerator_2, U256::from(sqrt_ratio_b_x64)).unwrap(),
+            U256::from(sqrt_ratio_a_x64)
+        )
+    } else {
+        numerator_1.mul_div_floor(numerator_2, U256::from(sqrt_ratio_b_x64)).unwrap() /
+            U256::from(sqrt_ratio_a_x64)
+    };
+    if result > U256::from(u64::MAX) {
+        return err!(ErrorCode::MaxTokenOverflow);
+    }
+    Ok(result.as_u64())
+}
+
+/// Gets the amount_1 delta between two prices, for given amount of liquidity
+///
+/// # Formula
+///
+/// * `Δy = L (√P_upper - √P_lower)`
+pub fn get_delta_amount_1_unsigned(
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool
+) -> Result<u64> {
+    // sqrt_ratio_a_x64 should hold the smaller value
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+
+    let result = if round_up {
+        U256::from(liquidity).mul_div_ceil(
+            U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64),
+            U256::from(fixed_point_64::Q64)
+        )
+    } else {
+        U256::from(liquidity).mul_div_floor(
+            U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64),
+            U256::from(fixed_point_64::Q64)
+        )
+    }.unwrap();
+    if result > U256::from(u64::MAX) {
+        return err!(ErrorCode::MaxTokenOverflow);
+    }
+    Ok(result.as_u64())
+}
+
+#[cfg(test)]
+mod sqrt_price_math_test {
+    use super::*;
+    use crate::libraries::tick_math;
+
+    #[test]
+    fn next_sqrt_price_moves_in_swap_direction() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let liquidity = 1_000_000_000_000u128;
+
+        let down = get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, 1_000_000, true);
+        assert!(down < sqrt_price_x64);
+        let up = get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, 1_000_000, false);
+        assert!(up > sqrt_price_x64);
+        assert_eq!(get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, 0, true), sqrt_price_x64);
+    }
+
+    #[test]
+    fn delta_amounts_round_trip_next_sqrt_price() {
+        let sqrt_price_x64 = tick_math::get_sqrt_price_at_tick(-1000).unwrap();
+        let liquidity = 5_000_000_000_000u128;
+        let amount_in = 123_456_789u64;
+
+        let next = get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, amount_in, true);
+        let amount_0 = get_delta_amount_0_unsigned(next, sqrt_price_x64, liquidity, true).unwrap();
+        assert!(amount_0 <= amount_in);
+
+        let next = get_next_sqrt_price_from_input(sqrt_price_x64, liquidity, amount_in, false);
+        let amount_1 = get_delta_amount_1_unsigned(sqrt_price_x64, next, liquidity, true).unwrap();
+        assert!(amount_1 <= amount_in);
+    }
+}
@@ -0,0 +1,242 @@
+use anchor_lang::prelude::*;
+
+use crate::libraries::{ full_math::MulDiv, sqrt_price_math };
+
+/// Denominator of the `trade_fee_rate` stored on Raydium's `AmmConfig` (hundredths of a bip)
+pub const FEE_RATE_DENOMINATOR_VALUE: u32 = 1_000_000;
+
+/// Result of a swap step
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    /// The price after swapping the amount in/out, not to exceed the price target
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Computes the result of swapping some amount in, or amount out, given the parameters of the swap
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u32,
+    is_base_input: bool,
+    zero_for_one: bool
+) -> Result<SwapStep> {
+    let mut swap_step = SwapStep::default();
+
+    // `None` when the amount needed to reach the target does not fit in a u64
+    let amount_in_range = calculate_amount_in_range(
+        sqrt_price_current_x64,
+        sqrt_price_target_x64,
+        liquidity,
+        zero_for_one,
+        is_base_input
+    );
+
+    if is_base_input {
+        let amount_remaining_less_fee = amount_remaining
+            .mul_div_floor(
+                u64::from(FEE_RATE_DENOMINATOR_VALUE - fee_rate),
+                u64::from(FEE_RATE_DENOMINATOR_VALUE)
+            )
+            .unwrap();
+        swap_step.sqrt_price_next_x64 = match amount_in_range {
+            Some(amount_in) if amount_remaining_less_fee >= amount_in => sqrt_price_target_x64,
+            _ =>
+                sqrt_price_math::get_next_sqrt_price_from_input(
+                    sqrt_price_current_x64,
+                    liquidity,
+                    amount_remaining_less_fee,
+                    zero_for_one
+                ),
+        };
+    } else {
+        swap_step.sqrt_price_next_x64 = match amount_in_range {
+            Some(amount_out) if amount_remaining >= amount_out => sqrt_price_target_x64,
+            _ =>
+                sqrt_price_math::get_next_sqrt_price_from_output(
+                    sqrt_price_current_x64,
+                    liquidity,
+                    amount_remaining,
+                    zero_for_one
+                ),
+        };
+    }
+
+    // whether we reached the max possible price for the given ticks
+    let max = sqrt_price_target_x64 == swap_step.sqrt_price_next_x64;
+
+    // get the input / output amounts when target price is not reached
+    if zero_for_one {
+        swap_step.amount_in = match amount_in_range {
+            Some(amount_in) if max && is_base_input => amount_in,
+            _ =>
+                sqrt_price_math::get_delta_amount_0_unsigned(
+                    swap_step.sqrt_price_next_x64,
+                    sqrt_price_current_x64,
+                    liquidity,
+                    true
+                )?,
+        };
+        swap_step.amount_out = match amount_in_range {
+            Some(amount_out) if max && !is_base_input => amount_out,
+            _ =>
+                sqrt_price_math::get_delta_amount_1_unsigned(
+                    swap_step.sqrt_price_next_x64,
+                    sqrt_price_current_x64,
+                    liquidity,
+                    false
+                )?,
+        };
+    } else {
+        swap_step.amount_in = match amount_in_range {
+            Some(amount_in) if max && is_base_input => amount_in,
+            _ =>
+                sqrt_price_math::get_delta_amount_1_unsigned(
+                    sqrt_price_current_x64,
+                    swap_step.sqrt_price_next_x64,
+                    liquidity,
+                    true
+                )?,
+        };
+        swap_step.amount_out = match amount_in_range {
+            Some(amount_out) if max && !is_base_input => amount_out,
+            _ =>
+                sqrt_price_math::get_delta_amount_0_unsigned(
+                    sqrt_price_current_x64,
+                    swap_step.sqrt_price_next_x64,
+                    liquidity,
+                    false
+                )?,
+        };
+    }
+
+    // For exact output case, cap the output amount to not exceed the remaining output amount
+    if !is_base_input && swap_step.amount_out > amount_remaining {
+        swap_step.amount_out = amount_remaining;
+    }
+
+    swap_step.fee_amount = if
+        is_base_input &&
+        swap_step.sqrt_price_next_x64 != sqrt_price_target_x64
+    {
+        // we didn't reach the target, so take the remainder of the maximum input as fee
+        // swap dust is granted as fee
+        amount_remaining.checked_sub(swap_step.amount_in).unwrap()
+    } else {
+        // take pip percentage as fee
+        swap_step.amount_in
+            .mul_div_ceil(
+                u64::from(fee_rate),
+                u64::from(FEE_RATE_DENOMINATOR_VALUE - fee_rate)
+            )
+            .unwrap()
+    };
+
+    Ok(swap_step)
+}
+
+/// Pre calculate amount_in or amount_out for the specified price range
+/// The amount may overflow u64 due to the `sqrt_price_target_x64`
+fn calculate_amount_in_range(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    zero_for_one: bool,
+    is_base_input: bool
+) -> Option<u64> {
+    let result = if is_base_input {
+        if zero_for_one {
+            sqrt_price_math::get_delta_amount_0_unsigned(
+                sqrt_price_target_x64,
+                sqrt_price_current_x64,
+                liquidity,
+                true
+            )
+        } else {
+            sqrt_price_math::get_delta_amount_1_unsigned(
+                sqrt_price_current_x64,
+                sqrt_price_target_x64,
+                liquidity,
+                true
+            )
+        }
+    } else if zero_for_one {
+        sqrt_price_math::get_delta_amount_1_unsigned(
+            sqrt_price_target_x64,
+            sqrt_price_current_x64,
+            liquidity,
+            false
+        )
+    } else {
+        sqrt_price_math::get_delta_amount_0_unsigned(
+            sqrt_price_current_x64,
+            sqrt_price_target_x64,
+            liquidity,
+            false
+        )
+    };
+    result.ok()
+}
+
+#[cfg(test)]
+mod swap_math_test {
+    use super::*;
+    use crate::libraries::tick_math;
+
+    #[test]
+    fn exact_input_step_consumes_whole_amount_before_target() {
+        let current = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let target = tick_math::get_sqrt_price_at_tick(-10_000).unwrap();
+        let amount_remaining = 1_000_000u64;
+
+        let step = compute_swap_step(
+            current,
+            target,
+            10_000_000_000_000,
+            amount_remaining,
+            2500,
+            true,
+            true
+        ).unwrap();
+
+        assert!(step.sqrt_price_next_x64 < current);
+        assert!(step.sqrt_price_next_x64 > target);
+        assert_eq!(step.amount_in + step.fee_amount, amount_remaining);
+        assert!(step.amount_out > 0);
+    }
+
+    #[test]
+    fn exact_input_step_stops_at_target() {
+        let current = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let target = tick_math::get_sqrt_price_at_tick(10).unwrap();
+        let amount_remaining = u64::MAX / 2;
+
+        let step = compute_swap_step(current, target, 1_000_000, amount_remaining, 2500, true, false).unwrap();
+
+        assert_eq!(step.sqrt_price_next_x64, target);
+        assert!(step.amount_in + step.fee_amount < amount_remaining);
+    }
+
+    #[test]
+    fn exact_output_step_caps_output() {
+        let current = tick_math::get_sqrt_price_at_tick(0).unwrap();
+        let target = tick_math::get_sqrt_price_at_tick(-10_000).unwrap();
+
+        let step = compute_swap_step(
+            current,
+            target,
+            10_000_000_000_000,
+            1_000,
+            2500,
+            false,
+            true
+        ).unwrap();
+
+        assert!(step.amount_out <= 1_000);
+        assert!(step.amount_in > 0);
+    }
+}
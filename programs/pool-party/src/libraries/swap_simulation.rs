@@ -0,0 +1,589 @@
+//! Off-chain and on-chain simulation of Raydium CLMM swaps, used to split a stable deposit
+//! between the two swap legs so that the resulting token_0/token_1 mix mints the most liquidity.
+
+use anchor_lang::prelude::*;
+
+use crate::{
+    errors::ErrorCode,
+    libraries::{ liquidity_math, swap_math, tick_math },
+};
+
+/// Upper bound of bisection steps when solving the split; each step simulates both legs
+pub const MAX_SPLIT_ITERATIONS: u32 = 32;
+
+/// An initialized tick of a pool, as read from its tick arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickLiquidity {
+    pub tick: i32,
+    pub liquidity_net: i128,
+}
+
+/// The subset of a Raydium pool needed to simulate a swap
+#[derive(Debug, Clone, Default)]
+pub struct SwapPoolSnapshot {
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    /// `AmmConfig::trade_fee_rate`, in hundredths of a bip
+    pub trade_fee_rate: u32,
+    /// Initialized ticks of the provided tick arrays, sorted ascending
+    pub ticks: Vec<TickLiquidity>,
+}
+
+/// One stable-to-token swap of the deposit split
+#[derive(Debug, Clone, Default)]
+pub struct SwapLeg {
+    pub pool: SwapPoolSnapshot,
+    /// Whether the stable mint is token_0 of `pool`
+    pub zero_for_one: bool,
+}
+
+/// A stable-to-token leg made of one or more swaps, each hop swapping the output of the previous
+/// one
+#[derive(Debug, Clone, Default)]
+pub struct SwapRoute {
+    pub hops: Vec<SwapLeg>,
+}
+
+/// The position the swapped tokens are deposited into
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RatioTarget {
+    pub sqrt_price_x64: u128,
+    pub sqrt_price_lower_x64: u128,
+    pub sqrt_price_upper_x64: u128,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub sqrt_price_x64: u128,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatioSplit {
+    /// Stable amount swapped into token_0
+    pub amount_0_in: u64,
+    /// Stable amount swapped into token_1
+    pub amount_1_in: u64,
+    pub amount_0_out: u64,
+    pub amount_1_out: u64,
+    /// Liquidity minted by depositing both outputs at the target price
+    pub liquidity: u128,
+}
+
+impl SwapPoolSnapshot {
+    /// Builds a snapshot keeping only initialized ticks, sorted and deduplicated
+    pub fn new(
+        sqrt_price_x64: u128,
+        tick_current: i32,
+        liquidity: u128,
+        trade_fee_rate: u32,
+        mut ticks: Vec<TickLiquidity>
+    ) -> Self {
+        ticks.retain(|tick| tick.liquidity_net != 0);
+        ticks.sort_by_key(|tick| tick.tick);
+        ticks.dedup_by_key(|tick| tick.tick);
+        Self { sqrt_price_x64, tick_current, liquidity, trade_fee_rate, ticks }
+    }
+
+    fn next_initialized_tick(&self, tick_current: i32, zero_for_one: bool) -> Option<TickLiquidity> {
+        if zero_for_one {
+            self.ticks
+                .iter()
+                .rev()
+                .find(|tick| tick.tick <= tick_current)
+                .copied()
+        } else {
+            self.ticks
+                .iter()
+                .find(|tick| tick.tick > tick_current)
+                .copied()
+        }
+    }
+
+    /// Simulates an exact input swap the same way Raydium's `swap_internal` does. Past the last
+    /// provided tick the swap continues with the current liquidity up to the price limit, so the
+    /// quote is only exact while the swap stays within the provided tick arrays.
+    pub fn simulate_exact_in(&self, amount_in: u64, zero_for_one: bool) -> Result<SwapQuote> {
+        let sqrt_price_limit_x64 = if zero_for_one {
+            tick_math::MIN_SQRT_PRICE_X64 + 1
+        } else {
+            tick_math::MAX_SQRT_PRICE_X64 - 1
+        };
+
+        let mut amount_remaining = amount_in;
+        let mut quote = SwapQuote { sqrt_price_x64: self.sqrt_price_x64, ..Default::default() };
+        let mut tick_current = self.tick_current;
+        let mut liquidity = self.liquidity;
+
+        while amount_remaining > 0 && quote.sqrt_price_x64 != sqrt_price_limit_x64 {
+            let next_tick = self.next_initialized_tick(tick_current, zero_for_one);
+            let sqrt_price_target_x64 = match next_tick {
+                Some(next) => {
+                    let sqrt_price_next_x64 = tick_math::get_sqrt_price_at_tick(next.tick)?;
+                    if zero_for_one {
+                        sqrt_price_next_x64.max(sqrt_price_limit_x64)
+                    } else {
+                        sqrt_price_next_x64.min(sqrt_price_limit_x64)
+                    }
+                }
+                None => sqrt_price_limit_x64,
+            };
+
+            if liquidity == 0 && next_tick.is_none() {
+                break;
+            }
+
+            let step = if liquidity == 0 {
+                swap_math::SwapStep { sqrt_price_next_x64: sqrt_price_target_x64, ..Default::default() }
+            } else {
+                swap_math::compute_swap_step(
+                    quote.sqrt_price_x64,
+                    sqrt_price_target_x64,
+                    liquidity,
+                    amount_remaining,
+                    self.trade_fee_rate,
+                    true,
+                    zero_for_one
+                )?
+            };
+
+            let consumed = step.amount_in.checked_add(step.fee_amount).unwrap();
+            amount_remaining = amount_remaining.checked_sub(consumed).unwrap();
+            quote.amount_in = quote.amount_in.checked_add(step.amount_in).unwrap();
+            quote.fee_amount = quote.fee_amount.checked_add(step.fee_amount).unwrap();
+            quote.amount_out = quote.amount_out.checked_add(step.amount_out).unwrap();
+            quote.sqrt_price_x64 = step.sqrt_price_next_x64;
+
+            match next_tick {
+                Some(next) if step.sqrt_price_next_x64 == sqrt_price_target_x64 => {
+                    let liquidity_net = if zero_for_one {
+                        -next.liquidity_net
+                    } else {
+                        next.liquidity_net
+                    };
+                    liquidity = liquidity_math
+                        ::add_delta(liquidity, liquidity_net)
+                        .ok_or(ErrorCode::LiquidityOverflow)?;
+                    tick_current = if zero_for_one { next.tick - 1 } else { next.tick };
+                }
+                _ => {
+                    if consumed == 0 {
+                        break;
+                    }
+                    tick_current = tick_math::get_tick_at_sqrt_price(quote.sqrt_price_x64)?;
+                }
+            }
+        }
+
+        Ok(quote)
+    }
+}
+
+impl SwapLeg {
+    pub fn quote(&self, amount_in: u64) -> Result<SwapQuote> {
+        if amount_in == 0 {
+            return Ok(SwapQuote { sqrt_price_x64: self.pool.sqrt_price_x64, ..Default::default() });
+        }
+        self.pool.simulate_exact_in(amount_in, self.zero_for_one)
+    }
+}
+
+impl SwapRoute {
+    /// Simulates the hops in order. The returned quote holds the route input, the output of the
+    /// final hop, the fees of every hop in their own input token and the final hop price.
+    pub fn quote(&self, amount_in: u64) -> Result<SwapQuote> {
+        let mut quote = SwapQuote { amount_out: amount_in, ..Default::default() };
+        let mut fee_amount = 0u64;
+        for hop in &self.hops {
+            quote = hop.quote(quote.amount_out)?;
+            fee_amount = fee_amount.saturating_add(quote.fee_amount);
+        }
+        Ok(SwapQuote { amount_in, fee_amount, ..quote })
+    }
+}
+
+impl From<SwapLeg> for SwapRoute {
+    fn from(leg: SwapLeg) -> Self {
+        SwapRoute { hops: vec![leg] }
+    }
+}
+
+impl RatioTarget {
+    fn liquidity_0(&self, amount_0: u64) -> u128 {
+        if self.sqrt_price_x64 >= self.sqrt_price_upper_x64 {
+            return 0;
+        }
+        let sqrt_price_x64 = self.sqrt_price_x64.max(self.sqrt_price_lower_x64);
+        liquidity_math::get_liquidity_from_amount_0(
+            sqrt_price_x64,
+            self.sqrt_price_upper_x64,
+            amount_0
+        )
+    }
+
+    fn liquidity_1(&self, amount_1: u64) -> u128 {
+        if self.sqrt_price_x64 <= self.sqrt_price_lower_x64 {
+            return 0;
+        }
+        let sqrt_price_x64 = self.sqrt_price_x64.min(self.sqrt_price_upper_x64);
+        liquidity_math::get_liquidity_from_amount_1(
+            self.sqrt_price_lower_x64,
+            sqrt_price_x64,
+            amount_1
+        )
+    }
+
+    fn liquidity(&self, liquidity_0: u128, liquidity_1: u128) -> u128 {
+        if self.sqrt_price_x64 <= self.sqrt_price_lower_x64 {
+            liquidity_0
+        } else if self.sqrt_price_x64 >= self.sqrt_price_upper_x64 {
+            liquidity_1
+        } else {
+            liquidity_0.min(liquidity_1)
+        }
+    }
+}
+
+fn evaluate_split(
+    amount: u64,
+    amount_0_in: u64,
+    route_0: &SwapRoute,
+    route_1: &SwapRoute,
+    target: &RatioTarget
+) -> Result<(RatioSplit, u128, u128)> {
+    let amount_1_in = amount - amount_0_in;
+    let amount_0_out = route_0.quote(amount_0_in)?.amount_out;
+    let amount_1_out = route_1.quote(amount_1_in)?.amount_out;
+    let liquidity_0 = target.liquidity_0(amount_0_out);
+    let liquidity_1 = target.liquidity_1(amount_1_out);
+    Ok((
+        RatioSplit {
+            amount_0_in,
+            amount_1_in,
+            amount_0_out,
+            amount_1_out,
+            liquidity: target.liquidity(liquidity_0, liquidity_1),
+        },
+        liquidity_0,
+        liquidity_1,
+    ))
+}
+
+/// Finds how much of `amount` to swap through `route_0` (into token_0) and `route_1` (into token_1)
+/// so that depositing both outputs at `target` mints the most liquidity.
+///
+/// Liquidity minted from token_0 grows with the amount sent through `route_0` while the liquidity
+/// minted from token_1 shrinks, so the optimum is where both cross. It is found by bisection on
+/// simulated swap outputs, which accounts for price impact and trade fees of every hop.
+pub fn quote_optimal_split(
+    amount: u64,
+    route_0: &SwapRoute,
+    route_1: &SwapRoute,
+    target: &RatioTarget
+) -> Result<RatioSplit> {
+    require!(
+        target.sqrt_price_lower_x64 < target.sqrt_price_upper_x64,
+        ErrorCode::InvalidTickRange
+    );
+
+    // Out of range positions only take one of the tokens
+    if target.sqrt_price_x64 <= target.sqrt_price_lower_x64 {
+        return Ok(evaluate_split(amount, amount, route_0, route_1, target)?.0);
+    }
+    if target.sqrt_price_x64 >= target.sqrt_price_upper_x64 {
+        return Ok(evaluate_split(amount, 0, route_0, route_1, target)?.0);
+    }
+
+    // Smallest amount_0_in where token_0 stops being the limiting side
+    let mut low = 0u64;
+    let mut high = amount;
+    let mut iterations = 0;
+    while low < high && iterations < MAX_SPLIT_ITERATIONS {
+        let mid = low + (high - low) / 2;
+        let (_, liquidity_0, liquidity_1) = evaluate_split(amount, mid, route_0, route_1, target)?;
+        if liquidity_0 >= liquidity_1 {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+        iterations += 1;
+    }
+
+    let (best, _, _) = evaluate_split(amount, high, route_0, route_1, target)?;
+    if high == 0 {
+        return Ok(best);
+    }
+    let (below, _, _) = evaluate_split(amount, high - 1, route_0, route_1, target)?;
+    Ok(if below.liquidity > best.liquidity { below } else { best })
+}
+
+/// Split of a deposit whose stable mint is already one side of the target pool: part of it is
+/// swapped inside the target pool and the rest is deposited as is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SingleSwapSplit {
+    /// Stable amount swapped into the other token of the pool
+    pub amount_swapped: u64,
+    /// Stable amount kept and deposited as is
+    pub amount_kept: u64,
+    pub amount_out: u64,
+    /// Liquidity minted at the pool price after the swap
+    pub liquidity: u128,
+}
+
+fn evaluate_single_swap(
+    amount: u64,
+    amount_swapped: u64,
+    pool_leg: &SwapLeg,
+    sqrt_price_lower_x64: u128,
+    sqrt_price_upper_x64: u128
+) -> Result<(SingleSwapSplit, u128, u128)> {
+    let amount_kept = amount - amount_swapped;
+    let quote = pool_leg.quote(amount_swapped)?;
+    // Swapping through the target pool moves the price the liquidity is minted at
+    let target = RatioTarget {
+        sqrt_price_x64: quote.sqrt_price_x64,
+        sqrt_price_lower_x64,
+        sqrt_price_upper_x64,
+    };
+    let (liquidity_0, liquidity_1) = if pool_leg.zero_for_one {
+        (target.liquidity_0(amount_kept), target.liquidity_1(quote.amount_out))
+    } else {
+        (target.liquidity_0(quote.amount_out), target.liquidity_1(amount_kept))
+    };
+    let (liquidity_kept, liquidity_out) = if pool_leg.zero_for_one {
+        (liquidity_0, liquidity_1)
+    } else {
+        (liquidity_1, liquidity_0)
+    };
+    Ok((
+        SingleSwapSplit {
+            amount_swapped,
+            amount_kept,
+            amount_out: quote.amount_out,
+            liquidity: target.liquidity(liquidity_0, liquidity_1),
+        },
+        liquidity_kept,
+        liquidity_out,
+    ))
+}
+
+/// Finds how much of `amount` to swap inside the target pool itself when the stable mint is one
+/// of its tokens. `pool_leg` is the target pool, swapping from the stable mint.
+pub fn quote_single_swap_split(
+    amount: u64,
+    pool_leg: &SwapLeg,
+    sqrt_price_lower_x64: u128,
+    sqrt_price_upper_x64: u128
+) -> Result<SingleSwapSplit> {
+    require!(sqrt_price_lower_x64 < sqrt_price_upper_x64, ErrorCode::InvalidTickRange);
+
+    let sqrt_price_x64 = pool_leg.pool.sqrt_price_x64;
+    let stable_is_token_0 = pool_leg.zero_for_one;
+    // Out of range positions only take one of the tokens
+    if sqrt_price_x64 <= sqrt_price_lower_x64 || sqrt_price_x64 >= sqrt_price_upper_x64 {
+        let needs_token_0 = sqrt_price_x64 <= sqrt_price_lower_x64;
+        let amount_swapped = if needs_token_0 == stable_is_token_0 { 0 } else { amount };
+        return Ok(
+            evaluate_single_swap(
+                amount,
+                amount_swapped,
+                pool_leg,
+                sqrt_price_lower_x64,
+                sqrt_price_upper_x64
+            )?.0
+        );
+    }
+
+    // Smallest swapped amount where the kept token stops being the limiting side
+    let mut low = 0u64;
+    let mut high = amount;
+    let mut iterations = 0;
+    while low < high && iterations < MAX_SPLIT_ITERATIONS {
+        let mid = low + (high - low) / 2;
+        let (_, liquidity_kept, liquidity_out) = evaluate_single_swap(
+            amount,
+            mid,
+            pool_leg,
+            sqrt_price_lower_x64,
+            sqrt_price_upper_x64
+        )?;
+        if liquidity_out >= liquidity_kept {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+        iterations += 1;
+    }
+
+    let (best, _, _) = evaluate_single_swap(
+        amount,
+        high,
+        pool_leg,
+        sqrt_price_lower_x64,
+        sqrt_price_upper_x64
+    )?;
+    if high == 0 {
+        return Ok(best);
+    }
+    let (below, _, _) = evaluate_single_swap(
+        amount,
+        high - 1,
+        pool_leg,
+        sqrt_price_lower_x64,
+        sqrt_price_upper_x64
+    )?;
+    Ok(if below.liquidity > best.liquidity { below } else { best })
+}
+
+#[cfg(test)]
+mod swap_simulation_test {
+    use super::*;
+
+    fn full_range_pool(tick_current: i32, liquidity: u128, trade_fee_rate: u32) -> SwapPoolSnapshot {
+        SwapPoolSnapshot::new(
+            tick_math::get_sqrt_price_at_tick(tick_current).unwrap(),
+            tick_current,
+            liquidity,
+            trade_fee_rate,
+            vec![
+                TickLiquidity { tick: -443580, liquidity_net: liquidity as i128 },
+                TickLiquidity { tick: 443580, liquidity_net: -(liquidity as i128) }
+            ]
+        )
+    }
+
+    #[test]
+    fn simulate_exact_in_charges_fee_and_moves_price() {
+        let pool = full_range_pool(0, 1_000_000_000_000, 2500);
+        let quote = pool.simulate_exact_in(1_000_000, true).unwrap();
+
+        assert_eq!(quote.amount_in + quote.fee_amount, 1_000_000);
+        assert!(quote.fee_amount >= 2500);
+        assert!(quote.amount_out < quote.amount_in);
+        assert!(quote.sqrt_price_x64 < pool.sqrt_price_x64);
+    }
+
+    #[test]
+    fn simulate_exact_in_crosses_ticks() {
+        let liquidity = 1_000_000_000u128;
+        let pool = SwapPoolSnapshot::new(
+            tick_math::get_sqrt_price_at_tick(0).unwrap(),
+            0,
+            liquidity,
+            0,
+            vec![
+                TickLiquidity { tick: -10, liquidity_net: (liquidity as i128) / 2 },
+                TickLiquidity { tick: -443580, liquidity_net: (liquidity as i128) / 2 }
+            ]
+        );
+        let small = pool.simulate_exact_in(1_000, true).unwrap();
+        let large = pool.simulate_exact_in(10_000_000, true).unwrap();
+
+        assert!(small.sqrt_price_x64 > tick_math::get_sqrt_price_at_tick(-10).unwrap());
+        assert!(large.sqrt_price_x64 < tick_math::get_sqrt_price_at_tick(-10).unwrap());
+        assert!(large.amount_out > small.amount_out);
+    }
+
+    #[test]
+    fn optimal_split_beats_naive_halves() {
+        let route_0 = SwapRoute::from(SwapLeg {
+            pool: full_range_pool(0, 10_000_000_000, 2500),
+            zero_for_one: true,
+        });
+        let route_1 = SwapRoute::from(SwapLeg {
+            pool: full_range_pool(0, 50_000_000_000, 100),
+            zero_for_one: true,
+        });
+        let target = RatioTarget {
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(0).unwrap(),
+            sqrt_price_lower_x64: tick_math::get_sqrt_price_at_tick(-1000).unwrap(),
+            sqrt_price_upper_x64: tick_math::get_sqrt_price_at_tick(1000).unwrap(),
+        };
+        let amount = 100_000_000u64;
+
+        let split = quote_optimal_split(amount, &route_0, &route_1, &target).unwrap();
+        let (halves, _, _) = evaluate_split(amount, amount / 2, &route_0, &route_1, &target).unwrap();
+
+        assert_eq!(split.amount_0_in + split.amount_1_in, amount);
+        assert!(split.liquidity >= halves.liquidity);
+    }
+
+    #[test]
+    fn optimal_split_out_of_range_uses_single_leg() {
+        let route = SwapRoute::from(SwapLeg {
+            pool: full_range_pool(0, 10_000_000_000, 2500),
+            zero_for_one: true,
+        });
+        let target = RatioTarget {
+            sqrt_price_x64: tick_math::get_sqrt_price_at_tick(-2000).unwrap(),
+            sqrt_price_lower_x64: tick_math::get_sqrt_price_at_tick(-1000).unwrap(),
+            sqrt_price_upper_x64: tick_math::get_sqrt_price_at_tick(1000).unwrap(),
+        };
+
+        let split = quote_optimal_split(1_000_000, &route, &route, &target).unwrap();
+        assert_eq!(split.amount_0_in, 1_000_000);
+        assert_eq!(split.amount_1_in, 0);
+        assert!(split.liquidity > 0);
+    }
+
+    #[test]
+    fn route_quote_chains_hops() {
+        let hop_0 = SwapLeg { pool: full_range_pool(0, 10_000_000_000, 2500), zero_for_one: true };
+        let hop_1 = SwapLeg { pool: full_range_pool(0, 20_000_000_000, 500), zero_for_one: false };
+        let route = SwapRoute { hops: vec![hop_0.clone(), hop_1.clone()] };
+        let amount = 1_000_000u64;
+
+        let quote = route.quote(amount).unwrap();
+        let first = hop_0.quote(amount).unwrap();
+        let second = hop_1.quote(first.amount_out).unwrap();
+
+        assert_eq!(quote.amount_in, amount);
+        assert_eq!(quote.amount_out, second.amount_out);
+        assert_eq!(quote.fee_amount, first.fee_amount + second.fee_amount);
+        assert_eq!(quote.sqrt_price_x64, second.sqrt_price_x64);
+        // every extra hop pays fees and price impact
+        assert!(quote.amount_out < hop_0.quote(amount).unwrap().amount_out);
+    }
+
+    #[test]
+    fn single_swap_split_balances_kept_and_swapped_sides() {
+        let pool = full_range_pool(0, 10_000_000_000, 2500);
+        let sqrt_price_lower_x64 = tick_math::get_sqrt_price_at_tick(-1000).unwrap();
+        let sqrt_price_upper_x64 = tick_math::get_sqrt_price_at_tick(1000).unwrap();
+        let amount = 10_000_000u64;
+
+        for zero_for_one in [true, false] {
+            let leg = SwapLeg { pool: pool.clone(), zero_for_one };
+            let split = quote_single_swap_split(
+                amount,
+                &leg,
+                sqrt_price_lower_x64,
+                sqrt_price_upper_x64
+            ).unwrap();
+
+            assert_eq!(split.amount_swapped + split.amount_kept, amount);
+            // symmetric range around the current price, so roughly half is swapped
+            assert!(split.amount_swapped > amount / 3 && split.amount_swapped < (amount * 2) / 3);
+            assert!(split.liquidity > 0);
+        }
+    }
+
+    #[test]
+    fn single_swap_split_out_of_range() {
+        let pool = full_range_pool(0, 10_000_000_000, 2500);
+        let sqrt_price_lower_x64 = tick_math::get_sqrt_price_at_tick(1000).unwrap();
+        let sqrt_price_upper_x64 = tick_math::get_sqrt_price_at_tick(2000).unwrap();
+
+        // price below the range: only token_0 is needed
+        let keep = SwapLeg { pool: pool.clone(), zero_for_one: true };
+        let split = quote_single_swap_split(1_000, &keep, sqrt_price_lower_x64, sqrt_price_upper_x64).unwrap();
+        assert_eq!(split.amount_swapped, 0);
+
+        let swap_all = SwapLeg { pool, zero_for_one: false };
+        let split = quote_single_swap_split(1_000, &swap_all, sqrt_price_lower_x64, sqrt_price_upper_x64).unwrap();
+        assert_eq!(split.amount_kept, 0);
+    }
+}
@@ -1,17 +1,36 @@
 use anchor_lang::{ prelude::*, system_program };
-use anchor_spl::token::{ self, Token };
-use anchor_spl::token_2022::{ transfer_checked, TransferChecked };
-use anchor_spl::token_interface::{ Mint, TokenAccount };
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{ transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions },
+};
+use anchor_spl::token_interface::{
+    self,
+    transfer_checked,
+    Mint,
+    TokenAccount,
+    TransferChecked,
+};
 
+use crate::errors::ErrorCode;
+
+/// Transfers `amount` of `mint` with the token program owning it, legacy or Token-2022. With a
+/// transfer fee, `to` receives `amount` less the fee.
 pub fn transfer_token<'info>(
     from: &InterfaceAccount<'info, TokenAccount>,
     to: &InterfaceAccount<'info, TokenAccount>,
     amount: &u64,
     mint: &InterfaceAccount<'info, Mint>,
     authority: &AccountInfo<'info>,
-    token_program: &Program<'info, Token>,
+    token_program: &impl ToAccountInfo<'info>,
     signer_seeds: Option<&[&[&[u8]]]>
 ) -> Result<()> {
+    let token_program = token_program.to_account_info();
+    require_keys_eq!(
+        token_program.key(),
+        *mint.to_account_info().owner,
+        ErrorCode::InvalidTokenProgram
+    );
     let transfer_accounts_options = TransferChecked {
         mint: mint.to_account_info(),
         from: from.to_account_info(),
@@ -19,23 +38,60 @@ pub fn transfer_token<'info>(
         authority: authority.to_account_info(),
     };
 
-    let mut cpi_context = CpiContext::new(
-        token_program.to_account_info(),
-        transfer_accounts_options
-    );
+    let mut cpi_context = CpiContext::new(token_program, transfer_accounts_options);
     if let Some(seeds) = signer_seeds {
-        let seeds_slice = seeds;
-        cpi_context = cpi_context.with_signer(seeds_slice);
-        transfer_checked(cpi_context, *amount, mint.decimals)
-    } else {
-        transfer_checked(cpi_context, *amount, mint.decimals)
+        cpi_context = cpi_context.with_signer(seeds);
     }
+    transfer_checked(cpi_context, *amount, mint.decimals)
+}
+
+/// Fee the transfer-fee extension of `mint` withholds from a transfer of `pre_fee_amount` in the
+/// current epoch, zero for legacy mints and mints without the extension
+pub fn get_transfer_fee(mint: &InterfaceAccount<'_, Mint>, pre_fee_amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    let mint_data = mint_info.try_borrow_data()?;
+    transfer_fee_at_epoch(mint_info.owner, &mint_data, Clock::get()?.epoch, pre_fee_amount)
+}
+
+/// Amount received from a transfer of `amount` of `mint`, net of its transfer fee
+pub fn amount_after_transfer_fee(mint: &InterfaceAccount<'_, Mint>, amount: u64) -> Result<u64> {
+    Ok(amount.checked_sub(get_transfer_fee(mint, amount)?).unwrap())
+}
+
+/// Amount `token_account` received since it held `amount_before`, reloading it
+pub fn received_amount(
+    token_account: &mut InterfaceAccount<'_, TokenAccount>,
+    amount_before: u64
+) -> Result<u64> {
+    token_account.reload()?;
+    Ok(token_account.amount.checked_sub(amount_before).unwrap())
+}
+
+/// Transfer fee of the mint account owned by `owner` and holding `mint_data` at `epoch`
+pub fn transfer_fee_at_epoch(
+    owner: &Pubkey,
+    mint_data: &[u8],
+    epoch: u64,
+    pre_fee_amount: u64
+) -> Result<u64> {
+    if *owner == Token::id() {
+        return Ok(0);
+    }
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(transfer_fee_config) =>
+            transfer_fee_config
+                .calculate_epoch_fee(epoch, pre_fee_amount)
+                .ok_or(ErrorCode::MaxTokenOverflow)?,
+        Err(_) => 0,
+    };
+    Ok(fee)
 }
 
 pub fn transfer_sol<'info>(
     from: &AccountInfo<'info>,
     to: &AccountInfo<'info>,
-    token_program: &Program<'info, Token>,
+    token_program: &impl ToAccountInfo<'info>,
     system_program: &Program<'info, System>,
     amount: u64
 ) -> Result<()> {
@@ -47,11 +103,97 @@ pub fn transfer_sol<'info>(
     system_program::transfer(cpi_context, amount)?;
 
     // Sync the native token to reflect the new SOL balance as wSOL
-    let cpi_accounts = token::SyncNative {
+    let cpi_accounts = token_interface::SyncNative {
         account: to.to_account_info(),
     };
     let cpi_program = token_program.to_account_info();
     let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-    token::sync_native(cpi_ctx)?;
+    token_interface::sync_native(cpi_ctx)?;
+    Ok(())
+}
+
+/// Grows `account` to `len` bytes, the new bytes zeroed, with `payer` topping up its rent
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    len: usize
+) -> Result<()> {
+    let lamports = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+    if lamports > 0 {
+        let transfer_accounts = system_program::Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        let cpi_context = CpiContext::new(system_program.to_account_info(), transfer_accounts);
+        system_program::transfer(cpi_context, lamports)?;
+    }
+    account.realloc(len, true)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod transfer_test {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use spl_token_2022::extension::{
+        transfer_fee::TransferFee,
+        ExtensionType,
+        StateWithExtensionsMut,
+    };
+
+    fn mint_with_transfer_fee(basis_points: u16, maximum_fee: u64) -> Vec<u8> {
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(
+            &[ExtensionType::TransferFeeConfig]
+        ).unwrap();
+        let mut data = vec![0; space];
+        let mut state = StateWithExtensionsMut::<spl_token_2022::state::Mint>
+            ::unpack_uninitialized(&mut data)
+            .unwrap();
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        };
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = fee;
+        config.newer_transfer_fee = fee;
+        state.base = spl_token_2022::state::Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn legacy_mints_have_no_transfer_fee() {
+        assert_eq!(transfer_fee_at_epoch(&Token::id(), &[], 0, 1_000_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn transfer_fee_is_charged_up_to_the_maximum() {
+        let data = mint_with_transfer_fee(100, 5_000);
+        let owner = spl_token_2022::id();
+        assert_eq!(transfer_fee_at_epoch(&owner, &data, 3, 10_000).unwrap(), 100);
+        // The fee rounds up
+        assert_eq!(transfer_fee_at_epoch(&owner, &data, 3, 101).unwrap(), 2);
+        assert_eq!(transfer_fee_at_epoch(&owner, &data, 3, 1_000_000).unwrap(), 5_000);
+        assert_eq!(transfer_fee_at_epoch(&owner, &data, 3, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn token_2022_mints_without_the_extension_have_no_transfer_fee() {
+        let mut data = vec![0; spl_token_2022::state::Mint::LEN];
+        let mint = spl_token_2022::state::Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        spl_token_2022::state::Mint::pack(mint, &mut data).unwrap();
+        let owner = spl_token_2022::id();
+        assert_eq!(transfer_fee_at_epoch(&owner, &data, 3, 10_000).unwrap(), 0);
+    }
+}
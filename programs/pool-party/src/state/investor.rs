@@ -1,23 +1,35 @@
 use anchor_lang::prelude::*;
 
-#[account]
-#[derive(InitSpace)]
+/// Zero-copy: the fields are ordered by alignment so that the layout has no padding
+#[account(zero_copy)]
+#[derive(Debug)]
 pub struct Investor {
-    pub bump: u8,
-    pub authority: Pubkey,
-    pub pool_position_key: Pubkey,
     pub init_liquidity: u128,
     pub liquidity: u128,
-    pub fees_earned0: u64,
-    pub fees_earned1: u64,
     pub fees_index0: u128,
     pub fees_index1: u128,
-    pub is_manager: bool,
+    pub fees_earned0: u64,
+    pub fees_earned1: u64,
+    /// Liquidity-weighted average time of the deposits, start of the lockup
+    pub deposited_at: i64,
+    pub authority: Pubkey,
+    pub pool_position_key: Pubkey,
+    pub bump: u8,
+    /// Non-zero for the account of the manager
+    pub is_manager: u8,
+    /// Layout version, `Investor::VERSION` once created or migrated
+    pub version: u8,
+    pub padding: [u8; 5],
+    /// Room for the fields of later versions
+    pub reserved: [u8; 64],
 }
 
 impl Investor {
     pub const LEN: usize = 8 + std::mem::size_of::<Investor>();
 
+    /// Version of the current layout. Version 0 is the Borsh `InvestorV0`.
+    pub const VERSION: u8 = 1;
+
     /// Seed to derive account address and signature
     pub const INVESTOR_SEED: &'static str = "investor:";
 
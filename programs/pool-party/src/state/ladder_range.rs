@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+/// A range of a pool position besides its core range, with its own Raydium position NFT. Its
+/// liquidity is kept at `weight_bps` of the liquidity of the core range, so every investor holds
+/// the same share of each range.
+#[account]
+#[derive(InitSpace)]
+pub struct LadderRange {
+    pub bump: u8,
+
+    pub pool_position_key: Pubkey,
+
+    /// Position of the range in the ladder, part of the address
+    pub index: u8,
+
+    pub position_nft_mint_key: Pubkey,
+
+    pub tick_lower_index: i32,
+
+    pub tick_upper_index: i32,
+
+    /// Liquidity of the range per unit of liquidity of the core range, in basis points
+    pub weight_bps: u32,
+}
+
+impl LadderRange {
+    pub const LEN: usize = 8 + LadderRange::INIT_SPACE;
+
+    /// Seed to derive account address and signature
+    pub const LADDER_RANGE_SEED: &'static str = "ladder_range:";
+}
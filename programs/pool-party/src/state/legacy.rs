@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::MIN_TIMELOCK_SECONDS;
+use crate::dex::Dex;
+use crate::state::{ Investor, PoolPosition, PoolPositionConfig, PositionRoles };
+
+/// Borsh layout of `PoolPositionConfig` before it was versioned, read once by
+/// `migrate_pool_position_config`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, InitSpace)]
+pub struct PoolPositionConfigV0 {
+    pub bump: u8,
+    pub pda_bump: u8,
+    pub tick_lower_index: i32,
+    pub tick_upper_index: i32,
+    pub pool_key: Pubkey,
+    pub pool_position_key: Pubkey,
+    pub manager_key: Pubkey,
+    pub token_vault_0_key: Pubkey,
+    pub token_vault_1_key: Pubkey,
+    pub vault_0_mint_key: Pubkey,
+    pub vault_1_mint_key: Pubkey,
+    #[max_len(32)]
+    pub name: String,
+}
+
+impl PoolPositionConfigV0 {
+    pub const LEN: usize = 8 + PoolPositionConfigV0::INIT_SPACE;
+
+    /// The config in the current layout. The fields added since take the values of a new
+    /// position, every role going to the manager.
+    pub fn migrate(&self) -> PoolPositionConfig {
+        PoolPositionConfig {
+            bump: self.bump,
+            pda_bump: self.pda_bump,
+            tick_lower_index: self.tick_lower_index,
+            tick_upper_index: self.tick_upper_index,
+            pool_key: self.pool_key,
+            pool_position_key: self.pool_position_key,
+            manager_key: self.manager_key,
+            token_vault_0_key: self.token_vault_0_key,
+            token_vault_1_key: self.token_vault_1_key,
+            vault_0_mint_key: self.vault_0_mint_key,
+            vault_1_mint_key: self.vault_1_mint_key,
+            name: self.name.clone(),
+            max_total_liquidity: 0,
+            max_investor_liquidity: 0,
+            min_deposit: 0,
+            allowlist_root: None,
+            lockup_seconds: 0,
+            early_exit_penalty_bps: 0,
+            roles: PositionRoles {
+                strategist: self.manager_key,
+                guardian: self.manager_key,
+                fee_recipient: self.manager_key,
+            },
+            paused: false,
+            timelock_seconds: MIN_TIMELOCK_SECONDS,
+            next_config_change_id: 0,
+            dex: Dex::RaydiumClmm,
+            pending_manager_key: None,
+            version: PoolPositionConfig::VERSION,
+            reserved: [0; 64],
+        }
+    }
+}
+
+/// Borsh layout of `Investor` before it was versioned, read once by `migrate_investor`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct InvestorV0 {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub pool_position_key: Pubkey,
+    pub init_liquidity: u128,
+    pub liquidity: u128,
+    pub fees_earned0: u64,
+    pub fees_earned1: u64,
+    pub fees_index0: u128,
+    pub fees_index1: u128,
+    pub is_manager: bool,
+}
+
+impl InvestorV0 {
+    /// Copies the fields into the zeroed `investor` of the current layout. The liquidity has no
+    /// deposit time, so it is past any lockup.
+    pub fn migrate(&self, investor: &mut Investor) {
+        investor.init_liquidity = self.init_liquidity;
+        investor.liquidity = self.liquidity;
+        investor.fees_index0 = self.fees_index0;
+        investor.fees_index1 = self.fees_index1;
+        investor.fees_earned0 = self.fees_earned0;
+        investor.fees_earned1 = self.fees_earned1;
+        investor.authority = self.authority;
+        investor.pool_position_key = self.pool_position_key;
+        investor.bump = self.bump;
+        investor.is_manager = u8::from(self.is_manager);
+        investor.version = Investor::VERSION;
+    }
+}
+
+/// Borsh layout of `PoolPosition` before it was versioned, read once by `migrate_pool_position`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, InitSpace)]
+pub struct PoolPositionV0 {
+    pub bump: u8,
+    pub pool_position_nft_account_bump: u8,
+    pub pool_position_config_key: Pubkey,
+    /// Never written by the program, the manager is the one of the config
+    pub manager_key: Pubkey,
+    pub pool_position_nft_key: Pubkey,
+    pub position_nft_mint_key: Pubkey,
+    pub position_nft_account_key: Pubkey,
+    #[max_len(32)]
+    pub name: String,
+    pub fees_index0: u128,
+    pub fees_index1: u128,
+    pub liquidity: u128,
+    pub vaults_initialized: bool,
+    pub created_at: u64,
+}
+
+impl PoolPositionV0 {
+    pub const LEN: usize = 8 + PoolPositionV0::INIT_SPACE;
+
+    /// Copies the fields into the zeroed `pool_position` of the current layout, which has no NAV
+    /// snapshot or ladder range yet
+    pub fn migrate(&self, pool_position: &mut PoolPosition) -> Result<()> {
+        pool_position.fees_index0 = self.fees_index0;
+        pool_position.fees_index1 = self.fees_index1;
+        pool_position.liquidity = self.liquidity;
+        pool_position.created_at = self.created_at;
+        pool_position.pool_position_config_key = self.pool_position_config_key;
+        pool_position.pool_position_nft_key = self.pool_position_nft_key;
+        pool_position.position_nft_mint_key = self.position_nft_mint_key;
+        pool_position.position_nft_account_key = self.position_nft_account_key;
+        pool_position.set_name(&self.name)?;
+        pool_position.bump = self.bump;
+        pool_position.pool_position_nft_account_bump = self.pool_position_nft_account_bump;
+        pool_position.vaults_initialized = u8::from(self.vaults_initialized);
+        pool_position.version = PoolPosition::VERSION;
+        Ok(())
+    }
+}
@@ -1,7 +1,15 @@
 pub mod investor;
+pub mod ladder_range;
+pub mod legacy;
+pub mod pending_config_change;
 pub mod pool_position;
+pub mod protocol_config;
 pub mod tick_array;
 
 pub use investor::*;
+pub use ladder_range::*;
+pub use legacy::*;
+pub use pending_config_change::*;
 pub use pool_position::*; 
+pub use protocol_config::*;
 pub use tick_array::*;
@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{ BPS_DENOMINATOR, MIN_TIMELOCK_SECONDS };
+use crate::errors::ErrorCode;
+use crate::state::{ PoolPositionConfig, PositionRoles };
+
+/// A change of a `PoolPositionConfig` queued behind its timelock
+#[account]
+#[derive(InitSpace)]
+pub struct PendingConfigChange {
+    pub bump: u8,
+
+    pub pool_position_config_key: Pubkey,
+
+    /// Position of the change in the queue of the config, part of the address
+    pub change_id: u64,
+
+    /// Paid the rent of the account, refunded when the change executes or is cancelled
+    pub proposer: Pubkey,
+
+    pub change: ConfigChange,
+
+    pub queued_at: i64,
+
+    /// First time the change can execute
+    pub executable_at: i64,
+}
+
+/// The mutable fields of a `PoolPositionConfig`, the pause excepted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ConfigChange {
+    /// Liquidity caps, zero for no cap, and minimum deposit
+    DepositLimits {
+        max_total_liquidity: u128,
+        max_investor_liquidity: u128,
+        min_deposit: u64,
+    },
+    /// Lockup of deposits and penalty of exits during it, zero making the lockup strict
+    Lockup {
+        lockup_seconds: i64,
+        early_exit_penalty_bps: u16,
+    },
+    /// Merkle root of the investors allowed in the position, anyone when `None`
+    AllowlistRoot {
+        allowlist_root: Option<[u8; 32]>,
+    },
+    /// Proposes `new_manager`, who still has to `accept_manager`; proposing the current manager
+    /// withdraws the proposal
+    PendingManager {
+        new_manager: Pubkey,
+    },
+    /// Strategist, guardian and fee recipient, queued by the guardian
+    Roles {
+        roles: PositionRoles,
+    },
+    /// Delay of the changes queued afterwards, at least `MIN_TIMELOCK_SECONDS`
+    Timelock {
+        timelock_seconds: i64,
+    },
+}
+
+impl PendingConfigChange {
+    pub const LEN: usize = 8 + PendingConfigChange::INIT_SPACE;
+
+    /// Seed to derive account address and signature
+    pub const PENDING_CONFIG_CHANGE_SEED: &'static str = "pending_config_change:";
+}
+
+impl ConfigChange {
+    /// The key allowed to queue the change: the guardian for roles, the manager otherwise
+    pub fn proposer(&self, config: &PoolPositionConfig) -> Pubkey {
+        match self {
+            ConfigChange::Roles { .. } => config.roles.guardian,
+            _ => config.manager_key,
+        }
+    }
+
+    /// Rejects invalid values when queued, so that the queue only holds applicable changes
+    pub fn validate(&self) -> Result<()> {
+        match *self {
+            ConfigChange::Lockup { lockup_seconds, early_exit_penalty_bps } => {
+                require!(lockup_seconds >= 0, ErrorCode::InvalidLockup);
+                require!(
+                    u64::from(early_exit_penalty_bps) <= BPS_DENOMINATOR,
+                    ErrorCode::InvalidEarlyExitPenalty
+                );
+            }
+            ConfigChange::Timelock { timelock_seconds } => {
+                require!(timelock_seconds >= MIN_TIMELOCK_SECONDS, ErrorCode::InvalidTimelock);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Writes the change to `config`. Caps already exceeded only block further increases, a
+    /// lockup applies to every investor from its `deposited_at`, and investor accounts created
+    /// under a previous allowlist are kept.
+    pub fn apply(&self, config: &mut PoolPositionConfig) {
+        match *self {
+            ConfigChange::DepositLimits {
+                max_total_liquidity,
+                max_investor_liquidity,
+                min_deposit,
+            } => {
+                config.max_total_liquidity = max_total_liquidity;
+                config.max_investor_liquidity = max_investor_liquidity;
+                config.min_deposit = min_deposit;
+            }
+            ConfigChange::Lockup { lockup_seconds, early_exit_penalty_bps } => {
+                config.lockup_seconds = lockup_seconds;
+                config.early_exit_penalty_bps = early_exit_penalty_bps;
+            }
+            ConfigChange::AllowlistRoot { allowlist_root } => {
+                config.allowlist_root = allowlist_root;
+            }
+            ConfigChange::PendingManager { new_manager } => {
+                let withdrawn = new_manager == config.manager_key;
+                config.pending_manager_key = (!withdrawn).then_some(new_manager);
+            }
+            ConfigChange::Roles { roles } => {
+                config.roles = roles;
+            }
+            ConfigChange::Timelock { timelock_seconds } => {
+                config.timelock_seconds = timelock_seconds;
+            }
+        }
+    }
+}
@@ -1,4 +1,10 @@
 use anchor_lang::prelude::*;
+use bytemuck::{ Pod, Zeroable };
+
+use crate::constants::NAV_SNAPSHOTS_LEN;
+use crate::dex::Dex;
+use crate::errors::ErrorCode;
+use crate::state::Investor;
 
 #[account]
 #[derive(InitSpace)]
@@ -28,25 +34,106 @@ pub struct PoolPositionConfig {
 
     #[max_len(32)]
     pub name: String,
+
+    /// Cap of the liquidity of the position, zero for no cap
+    pub max_total_liquidity: u128,
+
+    /// Cap of the liquidity of each investor, zero for no cap
+    pub max_investor_liquidity: u128,
+
+    /// Minimum amount of a deposit in the stable mint
+    pub min_deposit: u64,
+
+    /// Merkle root of the investors allowed to create an investor account, anyone when `None`
+    pub allowlist_root: Option<[u8; 32]>,
+
+    /// Time the liquidity of an investor stays locked after its deposit
+    pub lockup_seconds: i64,
+
+    /// Share of the withdrawn tokens withheld during the lockup and paid to the remaining
+    /// investors; zero makes the lockup strict
+    pub early_exit_penalty_bps: u16,
+
+    /// Keys of the operational roles, changed through the guardian
+    pub roles: PositionRoles,
+
+    /// Set by the guardian, blocks new deposits while withdrawals stay open
+    pub paused: bool,
+
+    /// Delay between queuing a config change and executing it
+    pub timelock_seconds: i64,
+
+    /// Id of the next queued config change
+    pub next_config_change_id: u64,
+
+    /// DEX holding the liquidity of the position
+    pub dex: Dex,
+
+    /// Manager proposed by a queued `ConfigChange::PendingManager`, until `accept_manager`
+    pub pending_manager_key: Option<Pubkey>,
+
+    /// Layout version, `PoolPositionConfig::VERSION` once created or migrated
+    pub version: u8,
+
+    /// Room for the fields of later versions
+    pub reserved: [u8; 64],
+}
+
+/// Keys holding the operational roles of a pool position, all the manager at creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace)]
+pub struct PositionRoles {
+    /// Hot key running the position operations, such as NAV snapshots
+    pub strategist: Pubkey,
+
+    /// Cold key that pauses the position and assigns the roles
+    pub guardian: Pubkey,
+
+    /// Receives the fees earned by the liquidity of the manager
+    pub fee_recipient: Pubkey,
+}
+
+impl PositionRoles {
+    /// Hands the roles still held by `previous` over to `new`
+    pub fn rotate(&mut self, previous: Pubkey, new: Pubkey) {
+        for key in [&mut self.strategist, &mut self.guardian, &mut self.fee_recipient] {
+            if *key == previous {
+                *key = new;
+            }
+        }
+    }
 }
 
 impl PoolPositionConfig {
     pub const LEN: usize = 8 + PoolPositionConfig::INIT_SPACE;
 
+    /// Version of the current layout. Version 0 is the unversioned `PoolPositionConfigV0`.
+    pub const VERSION: u8 = 1;
+
     /// Seed to derive account address and signature
     pub const POOL_POSITION_CONFIG_SEED: &'static str = "pool_position_config:";
+
+    /// Owner of the accounts the fees of `investor` are paid to
+    pub fn fees_recipient(&self, investor: &Investor) -> Pubkey {
+        if investor.is_manager != 0 { self.roles.fee_recipient } else { investor.authority }
+    }
 }
 
-#[account]
-#[derive(InitSpace)]
+/// Zero-copy: the fields are ordered by alignment so that the layout has no padding
+#[account(zero_copy)]
+#[derive(Debug)]
 pub struct PoolPosition {
-    pub bump: u8,
+    pub fees_index0: u128,
 
-    pub pool_position_nft_account_bump: u8,
+    pub fees_index1: u128,
 
-    pub pool_position_config_key: Pubkey,
+    pub liquidity: u128,
 
-    pub manager_key: Pubkey,
+    /// Ring of the latest NAV snapshots, the newest at `nav_snapshot_index`
+    pub nav_snapshots: [NavSnapshot; NAV_SNAPSHOTS_LEN],
+
+    pub created_at: u64,
+
+    pub pool_position_config_key: Pubkey,
 
     pub pool_position_nft_key: Pubkey,
 
@@ -54,22 +141,61 @@ pub struct PoolPosition {
 
     pub position_nft_account_key: Pubkey,
 
-    #[max_len(32)]
-    pub name: String,
+    /// UTF-8 name of the position padded with zeros, see `PoolPosition::name`
+    pub name: [u8; 32],
 
-    pub fees_index0: u128,
+    pub bump: u8,
 
-    pub fees_index1: u128,
+    pub pool_position_nft_account_bump: u8,
 
-    pub liquidity: u128,
+    /// Non-zero once the vaults are created
+    pub vaults_initialized: u8,
 
-    pub vaults_initialized: bool,
+    pub nav_snapshot_index: u8,
 
-    pub created_at: u64,
+    /// Number of `LadderRange`s opened besides the core range
+    pub ladder_ranges: u8,
+
+    /// Layout version, `PoolPosition::VERSION` once created or migrated
+    pub version: u8,
+
+    pub padding: [u8; 2],
+
+    /// Room for the fields of later versions
+    pub reserved: [u8; 128],
+}
+
+/// NAV of a pool position in its stable mint at a point in time
+#[repr(C)]
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    InitSpace,
+    Pod,
+    Zeroable
+)]
+pub struct NavSnapshot {
+    pub timestamp: i64,
+
+    pub nav: u64,
+
+    pub liquidity: u128,
+
+    /// `nav` per unit of `liquidity`, Q64.64
+    pub nav_per_liquidity_x64: u128,
 }
 
 impl PoolPosition {
-    pub const LEN: usize = 8 + PoolPosition::INIT_SPACE;
+    pub const LEN: usize = 8 + std::mem::size_of::<PoolPosition>();
+
+    /// Version of the current layout. Version 0 is the Borsh `PoolPositionV0`.
+    pub const VERSION: u8 = 1;
 
     /// Seed to derive account address and signature
     pub const POOL_POSITION_SEED: &'static str = "pool_position:";
@@ -83,4 +209,42 @@ impl PoolPosition {
     pub const POOL_POSITION_FEES_VAULT_0_SEED: &'static str = "pool_pos_fees_vault_0:";
 
     pub const POOL_POSITION_FEES_VAULT_1_SEED: &'static str = "pool_pos_fees_vault_1:";
+
+    /// The name of the position, empty for positions created before it was recorded
+    pub fn name(&self) -> &str {
+        let len = self.name.iter().position(|byte| *byte == 0).unwrap_or(self.name.len());
+        std::str::from_utf8(&self.name[..len]).unwrap_or_default()
+    }
+
+    /// Records `name`, at most 32 bytes as it seeds the position config
+    pub fn set_name(&mut self, name: &str) -> Result<()> {
+        require!(name.len() <= self.name.len(), ErrorCode::NameTooLong);
+        self.name = [0; 32];
+        self.name[..name.len()].copy_from_slice(name.as_bytes());
+        Ok(())
+    }
+
+    /// The newest NAV snapshot, all zero before the first one
+    pub fn latest_nav_snapshot(&self) -> NavSnapshot {
+        self.nav_snapshots[usize::from(self.nav_snapshot_index)]
+    }
+
+    /// Records `snapshot` over the oldest one, at most once every `min_interval_seconds`
+    pub fn push_nav_snapshot(
+        &mut self,
+        snapshot: NavSnapshot,
+        min_interval_seconds: i64
+    ) -> Result<()> {
+        let latest = self.latest_nav_snapshot();
+        if latest.timestamp != 0 {
+            require!(
+                snapshot.timestamp >= latest.timestamp + min_interval_seconds,
+                ErrorCode::NavSnapshotTooEarly
+            );
+            self.nav_snapshot_index = ((usize::from(self.nav_snapshot_index) + 1) %
+                NAV_SNAPSHOTS_LEN) as u8;
+        }
+        self.nav_snapshots[usize::from(self.nav_snapshot_index)] = snapshot;
+        Ok(())
+    }
 }
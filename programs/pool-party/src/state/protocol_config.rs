@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+/// Settings shared by every pool position. Holds the CLMM programs positions may use, so a single
+/// build of the program serves localnet, devnet, mainnet and their forks.
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    pub bump: u8,
+
+    /// Signer of the protocol config changes, the upgrade authority of the program at creation
+    pub admin: Pubkey,
+
+    /// Raydium CLMM programs the contexts accept, as deployed on the cluster
+    #[max_len(4)]
+    pub clmm_programs: Vec<Pubkey>,
+}
+
+impl ProtocolConfig {
+    pub const LEN: usize = 8 + ProtocolConfig::INIT_SPACE;
+
+    /// Seed to derive account address
+    pub const PROTOCOL_CONFIG_SEED: &'static str = "protocol_config:";
+
+    /// Maximum length of `clmm_programs`, as allocated by its `max_len`
+    pub const MAX_CLMM_PROGRAMS: usize = 4;
+
+    pub fn is_clmm_program(&self, program_id: &Pubkey) -> bool {
+        self.clmm_programs.contains(program_id)
+    }
+}
@@ -0,0 +1,349 @@
+//! State-machine model of the fee accounting of a pool position shared by several investors.
+//!
+//! Random sequences of deposits, fee accruals, claims and withdrawals are replayed on a
+//! `PoolPosition` and its `Investor`s with the same update sequences as `increase_liquidity`,
+//! `collect_fees` and `decrease_liquidity`, through the real `fees_indexes`, `calculate_fees` and
+//! `accrue_investor_fees`.
+//! The fees vaults must always hold what the investors can claim, no more than rounding dust.
+
+use anchor_lang::prelude::Pubkey;
+use pool_party::instructions::{ accrue_investor_fees, calculate_fees, fees_indexes };
+use pool_party::constants::NAV_SNAPSHOTS_LEN;
+use pool_party::state::{ Investor, NavSnapshot, PoolPosition };
+use quickcheck::{ quickcheck, Arbitrary, Gen };
+
+const INVESTORS: usize = 4;
+
+/// Multiplier of the fee indexes, see `fees_indexes`
+const FEES_INDEX_MULTIPLIER: u128 = 10_000_000_000;
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    /// `increase_liquidity` of an investor
+    Deposit { investor: usize, liquidity: u128 },
+    /// Fees accrued by the Raydium position, owed until the next harvest
+    Accrue { amount0: u64, amount1: u64 },
+    /// `collect_fees` of an investor
+    Claim { investor: usize },
+    /// Removal of a share of an investor liquidity, in 1/256, after settling its fees as any
+    /// withdrawal has to
+    Withdraw { investor: usize, share: u8 },
+    /// `decrease_liquidity` during the lockup, the penalty being credited to the other investors
+    EarlyExit { investor: usize, share: u8, penalty0: u64, penalty1: u64 },
+}
+
+impl Arbitrary for Op {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let investor = usize::arbitrary(g) % INVESTORS;
+        match u8::arbitrary(g) % 5 {
+            0 => {
+                // Mostly realistic liquidity, sometimes large enough to round the index to zero
+                let liquidity = match u8::arbitrary(g) % 4 {
+                    0 => u128::from(u64::arbitrary(g)),
+                    _ => u128::from(u32::arbitrary(g)) + 1,
+                };
+                Op::Deposit { investor, liquidity }
+            }
+            1 => {
+                let amount0 = u64::from(u32::arbitrary(g));
+                let amount1 = u64::from(u16::arbitrary(g));
+                Op::Accrue { amount0, amount1 }
+            }
+            2 => Op::Claim { investor },
+            3 => Op::Withdraw { investor, share: u8::arbitrary(g) },
+            _ => {
+                let penalty0 = u64::from(u16::arbitrary(g));
+                let penalty1 = u64::from(u16::arbitrary(g));
+                Op::EarlyExit { investor, share: u8::arbitrary(g), penalty0, penalty1 }
+            }
+        }
+    }
+}
+
+/// Token amounts of both tokens of the pool
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Amounts(u64, u64);
+
+struct Model {
+    pool_position: PoolPosition,
+    investors: Vec<Investor>,
+    /// Fees owed by Raydium and not harvested yet
+    owed: Amounts,
+    /// Fees and early exit penalties paid into the fees vaults so far
+    harvested: Amounts,
+    /// Fees paid to the investors so far
+    paid: Amounts,
+    fees_vault: Amounts,
+    /// Upper bound of the fees lost to rounding so far
+    max_rounding_loss: u128,
+}
+
+impl Model {
+    fn new() -> Self {
+        let mut pool_position = PoolPosition {
+            fees_index0: 0,
+            fees_index1: 0,
+            liquidity: 0,
+            nav_snapshots: [NavSnapshot::default(); NAV_SNAPSHOTS_LEN],
+            created_at: 0,
+            pool_position_config_key: Pubkey::default(),
+            pool_position_nft_key: Pubkey::default(),
+            position_nft_mint_key: Pubkey::default(),
+            position_nft_account_key: Pubkey::default(),
+            name: [0; 32],
+            bump: 0,
+            pool_position_nft_account_bump: 0,
+            vaults_initialized: 1,
+            nav_snapshot_index: 0,
+            ladder_ranges: 0,
+            version: PoolPosition::VERSION,
+            padding: [0; 2],
+            reserved: [0; 128],
+        };
+        pool_position.set_name("model").unwrap();
+        let investor = Investor {
+            init_liquidity: 0,
+            liquidity: 0,
+            fees_index0: 0,
+            fees_index1: 0,
+            fees_earned0: 0,
+            fees_earned1: 0,
+            deposited_at: 0,
+            authority: Pubkey::default(),
+            pool_position_key: Pubkey::default(),
+            bump: 0,
+            is_manager: 0,
+            version: Investor::VERSION,
+            padding: [0; 5],
+            reserved: [0; 64],
+        };
+        Model {
+            pool_position,
+            investors: vec![investor; INVESTORS],
+            owed: Amounts::default(),
+            harvested: Amounts::default(),
+            paid: Amounts::default(),
+            fees_vault: Amounts::default(),
+            max_rounding_loss: 0,
+        }
+    }
+
+    /// Moves the owed fees into the fees vaults, as the `decrease_liquidity_v2` CPI does
+    fn harvest(&mut self) -> Amounts {
+        let owed = std::mem::take(&mut self.owed);
+        self.fees_vault = Amounts(self.fees_vault.0 + owed.0, self.fees_vault.1 + owed.1);
+        self.harvested = Amounts(self.harvested.0 + owed.0, self.harvested.1 + owed.1);
+        if owed != Amounts::default() {
+            // The index is rounded down by less than one unit, worth liquidity / multiplier
+            self.max_rounding_loss += self.pool_position.liquidity.div_ceil(FEES_INDEX_MULTIPLIER);
+        }
+        owed
+    }
+
+    /// Harvests the owed fees and settles the investor at the updated indexes, like
+    /// `increase_liquidity` before it adds liquidity
+    fn settle(&mut self, investor: usize) {
+        let owed = self.harvest();
+        let (fees_index0, fees_index1) = accrue_investor_fees(
+            &self.pool_position,
+            &mut self.investors[investor],
+            self.pool_position.liquidity,
+            owed.0,
+            owed.1
+        );
+        self.pool_position.fees_index0 = fees_index0;
+        self.pool_position.fees_index1 = fees_index1;
+        self.max_rounding_loss += 1;
+    }
+
+    fn apply(&mut self, op: Op) {
+        match op {
+            Op::Deposit { investor, liquidity } => {
+                self.settle(investor);
+                self.investors[investor].liquidity += liquidity;
+                self.pool_position.liquidity += liquidity;
+            }
+            // Raydium only accrues fees to a position with liquidity
+            Op::Accrue { amount0, amount1 } if self.pool_position.liquidity > 0 => {
+                self.owed = Amounts(self.owed.0 + amount0, self.owed.1 + amount1);
+            }
+            Op::Accrue { .. } => {}
+            Op::Claim { investor } => self.claim(investor),
+            Op::Withdraw { investor, share } => {
+                self.settle(investor);
+                self.remove_liquidity(investor, share);
+            }
+            Op::EarlyExit { investor, share, penalty0, penalty1 } => {
+                self.settle(investor);
+                self.remove_liquidity(investor, share);
+                self.redistribute_penalty(investor, Amounts(penalty0, penalty1));
+            }
+        }
+    }
+
+    fn remove_liquidity(&mut self, investor: usize, share: u8) {
+        let liquidity = (self.investors[investor].liquidity * u128::from(share)) / 256;
+        self.investors[investor].liquidity -= liquidity;
+        self.pool_position.liquidity -= liquidity;
+    }
+
+    /// Same update sequence as `decrease_liquidity` once the investor is settled and its
+    /// liquidity removed
+    fn redistribute_penalty(&mut self, investor: usize, penalty: Amounts) {
+        let other_liquidity = self.pool_position.liquidity - self.investors[investor].liquidity;
+        if other_liquidity == 0 {
+            return;
+        }
+        self.fees_vault = Amounts(self.fees_vault.0 + penalty.0, self.fees_vault.1 + penalty.1);
+        self.harvested = Amounts(self.harvested.0 + penalty.0, self.harvested.1 + penalty.1);
+        let (penalty_index0, penalty_index1) = fees_indexes(other_liquidity, penalty.0, penalty.1);
+        self.pool_position.fees_index0 += penalty_index0;
+        self.pool_position.fees_index1 += penalty_index1;
+        self.investors[investor].fees_index0 = self.pool_position.fees_index0;
+        self.investors[investor].fees_index1 = self.pool_position.fees_index1;
+        self.max_rounding_loss += other_liquidity.div_ceil(FEES_INDEX_MULTIPLIER);
+    }
+
+    /// Same update sequence as `collect_fees`
+    fn claim(&mut self, investor: usize) {
+        let owed = self.harvest();
+        let (fees_index0, fees_index1) = fees_indexes(
+            self.pool_position.liquidity,
+            owed.0,
+            owed.1
+        );
+        let fees_index0 = self.pool_position.fees_index0 + fees_index0;
+        let fees_index1 = self.pool_position.fees_index1 + fees_index1;
+
+        let investor_account = &mut self.investors[investor];
+        let fees_earned0 =
+            investor_account.fees_earned0 +
+            calculate_fees(investor_account.liquidity, fees_index0, investor_account.fees_index0);
+        let fees_earned1 =
+            investor_account.fees_earned1 +
+            calculate_fees(investor_account.liquidity, fees_index1, investor_account.fees_index1);
+        investor_account.fees_earned0 = 0;
+        investor_account.fees_earned1 = 0;
+        investor_account.fees_index0 = fees_index0;
+        investor_account.fees_index1 = fees_index1;
+        self.pool_position.fees_index0 = fees_index0;
+        self.pool_position.fees_index1 = fees_index1;
+        self.max_rounding_loss += 1;
+
+        // A transfer out of the fees vault fails rather than going negative
+        self.fees_vault = Amounts(
+            self.fees_vault.0.checked_sub(fees_earned0).expect("fees vault 0 overdrawn"),
+            self.fees_vault.1.checked_sub(fees_earned1).expect("fees vault 1 overdrawn")
+        );
+        self.paid = Amounts(self.paid.0 + fees_earned0, self.paid.1 + fees_earned1);
+    }
+
+    /// Fees each investor could claim right now from the harvested fees
+    fn claimable(&self) -> Amounts {
+        self.investors.iter().fold(Amounts::default(), |total, investor| {
+            Amounts(
+                total.0 +
+                    investor.fees_earned0 +
+                    calculate_fees(
+                        investor.liquidity,
+                        self.pool_position.fees_index0,
+                        investor.fees_index0
+                    ),
+                total.1 +
+                    investor.fees_earned1 +
+                    calculate_fees(
+                        investor.liquidity,
+                        self.pool_position.fees_index1,
+                        investor.fees_index1
+                    )
+            )
+        })
+    }
+
+    fn check_invariants(&self) -> bool {
+        let total_liquidity: u128 = self.investors
+            .iter()
+            .map(|investor| investor.liquidity)
+            .sum();
+        let claimable = self.claimable();
+        // Floors of the claims not settled yet are not counted in `max_rounding_loss`
+        let max_dust = self.max_rounding_loss + (INVESTORS as u128);
+
+        total_liquidity == self.pool_position.liquidity &&
+            self.paid.0 + self.fees_vault.0 == self.harvested.0 &&
+            self.paid.1 + self.fees_vault.1 == self.harvested.1 &&
+            claimable.0 <= self.fees_vault.0 &&
+            claimable.1 <= self.fees_vault.1 &&
+            u128::from(self.fees_vault.0 - claimable.0) <= max_dust &&
+            u128::from(self.fees_vault.1 - claimable.1) <= max_dust
+    }
+}
+
+quickcheck! {
+    fn fees_vaults_cover_every_claim(ops: Vec<Op>) -> bool {
+        let mut model = Model::new();
+        ops.into_iter().all(|op| {
+            model.apply(op);
+            model.check_invariants()
+        })
+    }
+}
+
+quickcheck! {
+    fn claiming_everything_leaves_only_rounding_dust(ops: Vec<Op>) -> bool {
+        let mut model = Model::new();
+        for op in ops {
+            model.apply(op);
+        }
+        for investor in 0..INVESTORS {
+            model.claim(investor);
+        }
+
+        let unpaid0 = u128::from(model.harvested.0 - model.paid.0);
+        let unpaid1 = u128::from(model.harvested.1 - model.paid.1);
+        model.claimable() == Amounts::default() &&
+            unpaid0 <= model.max_rounding_loss &&
+            unpaid1 <= model.max_rounding_loss
+    }
+}
+
+#[test]
+fn late_investor_is_not_paid_fees_accrued_before_its_deposit() {
+    let mut model = Model::new();
+    model.apply(Op::Deposit { investor: 0, liquidity: 1_000_000 });
+    model.apply(Op::Accrue { amount0: 1_000, amount1: 2_000 });
+    model.apply(Op::Deposit { investor: 1, liquidity: 1_000_000 });
+    model.claim(1);
+    assert_eq!(model.paid, Amounts(0, 0));
+
+    model.claim(0);
+    assert_eq!(model.paid, Amounts(1_000, 2_000));
+    assert!(model.check_invariants());
+}
+
+#[test]
+fn added_liquidity_is_not_paid_fees_harvested_by_its_deposit() {
+    let mut model = Model::new();
+    model.apply(Op::Deposit { investor: 0, liquidity: 1_000_000 });
+    model.apply(Op::Accrue { amount0: 1_000, amount1: 2_000 });
+    model.apply(Op::Deposit { investor: 0, liquidity: 1_000_000 });
+    model.claim(0);
+    assert_eq!(model.paid, Amounts(1_000, 2_000));
+    assert!(model.check_invariants());
+}
+
+#[test]
+fn early_exit_penalty_goes_to_the_remaining_investors() {
+    let mut model = Model::new();
+    model.apply(Op::Deposit { investor: 0, liquidity: 1_000_000 });
+    model.apply(Op::Deposit { investor: 1, liquidity: 3_000_000 });
+    model.apply(Op::Deposit { investor: 2, liquidity: 1_000_000 });
+    model.apply(Op::EarlyExit { investor: 2, share: 128, penalty0: 4_000, penalty1: 8_000 });
+
+    for investor in 0..INVESTORS {
+        model.claim(investor);
+    }
+    assert_eq!(model.investors[2].liquidity, 500_000);
+    assert_eq!(model.paid, Amounts(4_000, 8_000));
+    assert!(model.check_invariants());
+}